@@ -7,6 +7,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .nth(1)
         .unwrap_or_else(|| "Cargo.toml".to_string());
 
+    // Like sha1sum, "-" reads from standard input
+    if file_path == "-" {
+        println!("SHA-1: {}", sha_file_hashing::hash_stdin()?);
+        return Ok(());
+    }
+
     let path = Path::new(&file_path);
 
     // Check if file exists
@@ -22,7 +28,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Validate the hash
     println!("\nValidating hash...");
-    let is_valid = path.validate(&hash)?;
+    let is_valid = path.validate(hash.to_hex())?;
     println!("Valid: {}", is_valid);
 
     Ok(())
@@ -1,5 +1,5 @@
-use sha_file_hashing::Hashable;
-use std::path::PathBuf;
+use sha_file_hashing::hash_paths;
+use std::path::Path;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
@@ -16,12 +16,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut success_count = 0;
     let mut error_count = 0;
 
-    for file_path in &args[1..] {
-        let path = PathBuf::from(file_path);
+    let results = hash_paths(&args[1..]);
 
+    for file_path in &args[1..] {
         print!("{:<50} ", file_path);
 
-        match path.hash() {
+        match &results[Path::new(file_path)] {
             Ok(hash) => {
                 println!("{}", hash);
                 success_count += 1;
@@ -1,6 +1,7 @@
-use sha_file_hashing::Hashable;
+use sha_file_hashing::{
+    ChecksumEntry, ChecksumFile, FileOutcome, HashAlgorithm, Hashable, Manifest, verify_manifest,
+};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
 use std::path::Path;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -33,7 +34,7 @@ fn create_checksums(files: &[String]) -> Result<(), Box<dyn std::error::Error>>
     }
 
     let checksum_file = "checksums.sha1";
-    let mut output = File::create(checksum_file)?;
+    let mut checksums = ChecksumFile::new();
 
     println!("Creating checksums file: {}\n", checksum_file);
 
@@ -42,7 +43,7 @@ fn create_checksums(files: &[String]) -> Result<(), Box<dyn std::error::Error>>
 
         match path.hash() {
             Ok(hash) => {
-                writeln!(output, "{}  {}", hash, file_path)?;
+                checksums.push(ChecksumEntry::new(hash, file_path));
                 println!("{:<50} OK", file_path);
             }
             Err(e) => {
@@ -51,65 +52,38 @@ fn create_checksums(files: &[String]) -> Result<(), Box<dyn std::error::Error>>
         }
     }
 
+    checksums.write_to(File::create(checksum_file)?)?;
     println!("\nChecksums written to: {}", checksum_file);
     Ok(())
 }
 
 fn verify_checksums(checksum_file: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::open(checksum_file)?;
-    let reader = BufReader::new(file);
+    let checksums = ChecksumFile::read(checksum_file)?;
 
     println!("Verifying checksums from: {}\n", checksum_file);
 
-    let mut total = 0;
-    let mut passed = 0;
-    let mut failed = 0;
-    let mut missing = 0;
-
-    for line in reader.lines() {
-        let line = line?;
-        let parts: Vec<&str> = line.splitn(2, "  ").collect();
-
-        if parts.len() != 2 {
-            eprintln!("Warning: Invalid line format: {}", line);
-            continue;
-        }
-
-        let expected_hash = parts[0];
-        let file_path = parts[1];
-        let path = Path::new(file_path);
-
-        total += 1;
-
-        if !path.exists() {
-            println!("{:<50} MISSING", file_path);
-            missing += 1;
-            continue;
-        }
-
-        match path.validate(expected_hash) {
-            Ok(true) => {
-                println!("{:<50} OK", file_path);
-                passed += 1;
-            }
-            Ok(false) => {
-                println!("{:<50} FAILED", file_path);
-                failed += 1;
-            }
-            Err(e) => {
-                println!("{:<50} ERROR: {}", file_path, e);
-                failed += 1;
-            }
+    let mut manifest = Manifest::new(HashAlgorithm::Sha1);
+    for entry in checksums.entries() {
+        manifest.insert(&entry.path, entry.digest.clone());
+    }
+    let report = verify_manifest(&manifest, ".");
+
+    for (path, outcome) in report.iter() {
+        match outcome {
+            FileOutcome::Ok => println!("{:<50} OK", path.display()),
+            FileOutcome::Mismatch { .. } => println!("{:<50} FAILED", path.display()),
+            FileOutcome::Missing => println!("{:<50} MISSING", path.display()),
+            FileOutcome::IoError(e) => println!("{:<50} ERROR: {}", path.display(), e),
         }
     }
 
     println!("\nResults:");
-    println!("  Total:   {}", total);
-    println!("  Passed:  {}", passed);
-    println!("  Failed:  {}", failed);
-    println!("  Missing: {}", missing);
+    println!("  Total:   {}", report.len());
+    println!("  Passed:  {}", report.passed());
+    println!("  Failed:  {}", report.mismatched() + report.io_errors());
+    println!("  Missing: {}", report.missing());
 
-    if failed > 0 || missing > 0 {
+    if !report.is_ok() {
         std::process::exit(1);
     }
 
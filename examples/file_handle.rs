@@ -21,7 +21,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let file = File::open(&file_path)?;
 
     println!("\nValidating hash...");
-    let is_valid = file.validate(&hash)?;
+    let is_valid = file.validate(hash.to_hex())?;
     println!("Valid: {}", is_valid);
 
     Ok(())
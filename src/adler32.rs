@@ -0,0 +1,139 @@
+//! Adler-32 checksums.
+//!
+//! Adler-32 is the checksum used in the zlib stream trailer. It is much cheaper than SHA-1
+//! and is a good fit for quick corruption checks, but it is **not** a cryptographic hash and
+//! must not be used where tampering is a concern.
+//!
+//! The functions in this module mirror the crate-level SHA-1 API and return the checksum as
+//! a 4-byte [`Digest`] (big-endian, matching the zlib trailer).
+//!
+//! ```no_run
+//! use sha_file_hashing::adler32;
+//!
+//! let checksum = adler32::hash_file_from_path("payload.bin").unwrap();
+//! assert!(adler32::validate_file_from_path("payload.bin", checksum.to_hex()).unwrap());
+//! ```
+
+use crate::{Digest, HashAlgorithm, SHAError, hash_file_with_algorithm};
+use digest::consts::U4;
+use digest::{FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update};
+use std::fs::File;
+use std::path::Path;
+
+/// Streaming Adler-32 state.
+///
+/// Implements the RustCrypto [`Update`] and [`FixedOutput`] traits so it can be driven by the
+/// same machinery as the cryptographic hashers; the 4-byte output is the big-endian checksum.
+#[derive(Debug, Clone, Default)]
+pub struct Adler32(adler2::Adler32);
+
+impl Adler32 {
+    /// Creates a new Adler-32 state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the checksum of the data processed so far.
+    pub fn checksum(&self) -> u32 {
+        self.0.checksum()
+    }
+}
+
+impl Update for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.write_slice(data);
+    }
+}
+
+impl OutputSizeUser for Adler32 {
+    type OutputSize = U4;
+}
+
+impl FixedOutput for Adler32 {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.checksum().to_be_bytes());
+    }
+}
+
+impl Reset for Adler32 {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl FixedOutputReset for Adler32 {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.checksum().to_be_bytes());
+        self.reset();
+    }
+}
+
+/// Computes the Adler-32 checksum of a `File` handle.
+pub fn hash_file(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Adler32)
+}
+
+/// Computes the Adler-32 checksum of the file at `path`.
+pub fn hash_file_from_path(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file(File::open(path.as_ref())?)
+}
+
+/// Validates a `File` handle against an expected Adler-32 checksum (case-insensitive).
+pub fn validate_file(file: File, checksum: impl AsRef<str>) -> bool {
+    hash_file(file)
+        .and_then(|computed| computed.matches_hex(checksum.as_ref()))
+        .unwrap_or(false)
+}
+
+/// Validates the file at `path` against an expected Adler-32 checksum (case-insensitive).
+pub fn validate_file_from_path(
+    path: impl AsRef<Path>,
+    checksum: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    let computed = hash_file_from_path(path)?;
+    computed.matches_hex(checksum.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_adler32_known_value() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Wikipedia").unwrap();
+        temp_file.flush().unwrap();
+
+        let checksum = hash_file_from_path(temp_file.path()).unwrap();
+        assert_eq!(checksum, "11e60398");
+    }
+
+    #[test]
+    fn test_adler32_empty() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        let file = File::open(temp_file.path()).unwrap();
+        assert_eq!(hash_file(file).unwrap(), "00000001");
+    }
+
+    #[test]
+    fn test_adler32_validate_case_insensitive() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Wikipedia").unwrap();
+        temp_file.flush().unwrap();
+
+        assert!(validate_file_from_path(temp_file.path(), "11E60398").unwrap());
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(!validate_file(file, "00000001"));
+    }
+
+    #[test]
+    fn test_adler32_not_found() {
+        match hash_file_from_path("nonexistent_file_12345.txt") {
+            Err(SHAError::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("Expected IO error"),
+        }
+    }
+}
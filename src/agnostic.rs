@@ -0,0 +1,129 @@
+//! Runtime-agnostic asynchronous hashing.
+//!
+//! Available with the `async-std` or `smol` feature. These futures do not depend on any
+//! particular executor: the read loop is handed to the [`blocking`] thread pool, the same pool
+//! async-std and smol use for their own file I/O, and the future simply waits for the result.
+//! Tokio applications should prefer the `tokio` feature, which uses Tokio's own pool.
+//!
+//! ```no_run
+//! use sha_file_hashing::agnostic;
+//!
+//! # async fn run() -> Result<(), sha_file_hashing::SHAError> {
+//! let hash = agnostic::hash_file_async("upload.bin").await?;
+//! assert!(agnostic::validate_file_async("upload.bin", hash.to_hex()).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::options::DEFAULT_BUFFER_SIZE;
+use crate::output::declared_algorithm;
+use crate::{Digest, HashAlgorithm, Hasher, HasherOptions, SHAError};
+use futures_lite::{AsyncRead, AsyncReadExt};
+use std::path::Path;
+
+/// Computes the SHA-1 hash of the file at `path` off the async threads.
+pub async fn hash_file_async(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file_async_with_algorithm(path, HashAlgorithm::Sha1).await
+}
+
+/// Computes the hash of the file at `path` with `algorithm` off the async threads.
+pub async fn hash_file_async_with_algorithm(
+    path: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let path = path.as_ref().to_path_buf();
+    let options = HasherOptions::new().algorithm(algorithm);
+    blocking::unblock(move || options.hash_file_from_path(path)).await
+}
+
+/// Validates the file at `path` against an expected SHA-1 hash off the async threads.
+pub async fn validate_file_async(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    validate_file_async_with_algorithm(path, hash, HashAlgorithm::Sha1).await
+}
+
+/// Validates the file at `path` against `hash` computed with `algorithm`, off the async
+/// threads.
+pub async fn validate_file_async_with_algorithm(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+    algorithm: HashAlgorithm,
+) -> Result<bool, SHAError> {
+    let hash = hash.as_ref();
+    hash_file_async_with_algorithm(path, declared_algorithm(hash, algorithm)?)
+        .await?
+        .matches_hex(hash)
+}
+
+/// Computes the SHA-1 hash of everything read from a [`futures_io`]-style async `reader`.
+///
+/// This is the reader trait used by async-std, smol and the `futures` crate. Data is hashed as
+/// it arrives, so a download can be verified while it is consumed.
+///
+/// [`futures_io`]: https://docs.rs/futures-io
+pub async fn hash_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Digest, SHAError> {
+    hash_async_reader_with_algorithm(reader, HashAlgorithm::Sha1).await
+}
+
+/// Computes the hash of everything read from an async `reader` with `algorithm`.
+pub async fn hash_async_reader_with_algorithm<R: AsyncRead + Unpin>(
+    mut reader: R,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer).await? {
+            0 => return Ok(hasher.finalize()),
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_hash_file_async_matches_sync() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+        temp_file.flush().unwrap();
+
+        block_on(async {
+            let hash = hash_file_async(temp_file.path()).await.unwrap();
+            assert_eq!(hash, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+            assert!(
+                validate_file_async_with_algorithm(
+                    temp_file.path(),
+                    "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f",
+                    HashAlgorithm::Sha256
+                )
+                .await
+                .unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn test_hash_async_reader() {
+        let reader = futures_lite::io::Cursor::new(b"test data".to_vec());
+        assert_eq!(
+            block_on(hash_async_reader(reader)).unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+    }
+
+    #[test]
+    fn test_hash_file_async_not_found() {
+        match block_on(hash_file_async("nonexistent_file_12345.txt")) {
+            Err(SHAError::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("Expected IO error"),
+        }
+    }
+}
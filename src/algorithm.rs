@@ -0,0 +1,306 @@
+use crate::SHAError;
+use crate::adler32::Adler32;
+use crate::crc64::{Crc64Ecma, Crc64Nvme, Crc64Xz};
+use crate::fingerprint::Fnv1a64;
+use digest::DynDigest;
+use std::fmt;
+use std::str::FromStr;
+
+/// Hash algorithms that can be selected at runtime.
+///
+/// Every hashing entry point in the crate has a variant that accepts a `HashAlgorithm`, and the
+/// `Hashable` trait exposes [`hash_with`](crate::Hashable::hash_with) and
+/// [`validate_with`](crate::Hashable::validate_with). The plain functions keep using
+/// [`HashAlgorithm::Sha1`], which is also the [`Default`].
+///
+/// Algorithms can be parsed from their names, which makes it easy to take the choice from a
+/// command-line argument or config file. Parsing is case-insensitive and ignores `-` and `_`,
+/// so `"sha256"`, `"SHA-256"` and `"sha_256"` are all accepted.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::HashAlgorithm;
+///
+/// let algorithm: HashAlgorithm = "SHA-256".parse().unwrap();
+/// assert_eq!(algorithm, HashAlgorithm::Sha256);
+/// assert_eq!(algorithm.to_string(), "sha256");
+/// assert_eq!(algorithm.output_len(), 32);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum HashAlgorithm {
+    /// MD5 (broken for security purposes; provided for compatibility).
+    Md5,
+    #[default]
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    /// Adler-32 checksum (not cryptographic).
+    Adler32,
+    /// CRC-64/XZ checksum (not cryptographic).
+    Crc64Xz,
+    /// CRC-64/ECMA-182 checksum (not cryptographic).
+    Crc64Ecma,
+    /// CRC-64/NVME checksum (not cryptographic).
+    Crc64Nvme,
+    /// 64-bit FNV-1a fingerprint (not cryptographic).
+    Fnv1a64,
+    /// eDonkey2000 chunked MD4 (broken for security purposes; requires the `ed2k` feature).
+    #[cfg(feature = "ed2k")]
+    Ed2k,
+    /// Ethereum-style Keccak-256 (requires the `keccak` feature).
+    #[cfg(feature = "keccak")]
+    Keccak256,
+    /// SHAKE128 with a 32-byte output (requires the `shake` feature).
+    #[cfg(feature = "shake")]
+    Shake128,
+    /// SHAKE256 with a 64-byte output (requires the `shake` feature).
+    #[cfg(feature = "shake")]
+    Shake256,
+    /// SM3 (requires the `sm3` feature).
+    #[cfg(feature = "sm3")]
+    Sm3,
+    /// GOST R 34.11-2012 Streebog-256 (requires the `streebog` feature).
+    #[cfg(feature = "streebog")]
+    Streebog256,
+    /// GOST R 34.11-2012 Streebog-512 (requires the `streebog` feature).
+    #[cfg(feature = "streebog")]
+    Streebog512,
+}
+
+impl HashAlgorithm {
+    /// Every algorithm enabled in this build.
+    pub const ALL: &'static [HashAlgorithm] = &[
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha224,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha384,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Adler32,
+        HashAlgorithm::Crc64Xz,
+        HashAlgorithm::Crc64Ecma,
+        HashAlgorithm::Crc64Nvme,
+        HashAlgorithm::Fnv1a64,
+        #[cfg(feature = "ed2k")]
+        HashAlgorithm::Ed2k,
+        #[cfg(feature = "keccak")]
+        HashAlgorithm::Keccak256,
+        #[cfg(feature = "shake")]
+        HashAlgorithm::Shake128,
+        #[cfg(feature = "shake")]
+        HashAlgorithm::Shake256,
+        #[cfg(feature = "sm3")]
+        HashAlgorithm::Sm3,
+        #[cfg(feature = "streebog")]
+        HashAlgorithm::Streebog256,
+        #[cfg(feature = "streebog")]
+        HashAlgorithm::Streebog512,
+    ];
+
+    /// Returns the canonical lowercase name, as accepted by [`FromStr`] and used by [`Display`](fmt::Display).
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha224 => "sha224",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Adler32 => "adler32",
+            HashAlgorithm::Crc64Xz => "crc64xz",
+            HashAlgorithm::Crc64Ecma => "crc64ecma",
+            HashAlgorithm::Crc64Nvme => "crc64nvme",
+            HashAlgorithm::Fnv1a64 => "fnv1a64",
+            #[cfg(feature = "ed2k")]
+            HashAlgorithm::Ed2k => "ed2k",
+            #[cfg(feature = "keccak")]
+            HashAlgorithm::Keccak256 => "keccak256",
+            #[cfg(feature = "shake")]
+            HashAlgorithm::Shake128 => "shake128",
+            #[cfg(feature = "shake")]
+            HashAlgorithm::Shake256 => "shake256",
+            #[cfg(feature = "sm3")]
+            HashAlgorithm::Sm3 => "sm3",
+            #[cfg(feature = "streebog")]
+            HashAlgorithm::Streebog256 => "streebog256",
+            #[cfg(feature = "streebog")]
+            HashAlgorithm::Streebog512 => "streebog512",
+        }
+    }
+
+    /// Returns the digest length in bytes. The hex encoding is twice as long.
+    pub fn output_len(&self) -> usize {
+        self.hasher().output_size()
+    }
+
+    /// Returns `true` if this CPU has instructions that the backend for this algorithm uses.
+    ///
+    /// The `sha1` and `sha2` crates pick their backend at runtime, so no feature flag is needed:
+    /// SHA-1 and SHA-224/256 use the SHA extensions (SHA-NI on x86, the `sha2` extension on
+    /// ARMv8), while SHA-384/512 use AVX2 on x86 and the `sha3` extension on ARMv8. Every other
+    /// algorithm runs portable code and reports `false`.
+    ///
+    /// This mirrors the default backend selection; builds that force a backend through the
+    /// `sha1_backend` or `sha2_backend` cfg flags may differ.
+    pub fn is_hardware_accelerated(&self) -> bool {
+        match self {
+            HashAlgorithm::Sha1 | HashAlgorithm::Sha224 | HashAlgorithm::Sha256 => {
+                cpu_has_sha256_extensions()
+            }
+            HashAlgorithm::Sha384 | HashAlgorithm::Sha512 => cpu_has_sha512_extensions(),
+            _ => false,
+        }
+    }
+
+    /// Creates a fresh type-erased hasher for this algorithm.
+    pub(crate) fn hasher(&self) -> Box<dyn DynDigest> {
+        match self {
+            HashAlgorithm::Md5 => Box::new(md5::Md5::default()),
+            HashAlgorithm::Sha1 => Box::new(sha1::Sha1::default()),
+            HashAlgorithm::Sha224 => Box::new(sha2::Sha224::default()),
+            HashAlgorithm::Sha256 => Box::new(sha2::Sha256::default()),
+            HashAlgorithm::Sha384 => Box::new(sha2::Sha384::default()),
+            HashAlgorithm::Sha512 => Box::new(sha2::Sha512::default()),
+            HashAlgorithm::Adler32 => Box::new(Adler32::default()),
+            HashAlgorithm::Crc64Xz => Box::new(Crc64Xz::default()),
+            HashAlgorithm::Crc64Ecma => Box::new(Crc64Ecma::default()),
+            HashAlgorithm::Crc64Nvme => Box::new(Crc64Nvme::default()),
+            HashAlgorithm::Fnv1a64 => Box::new(Fnv1a64::default()),
+            #[cfg(feature = "ed2k")]
+            HashAlgorithm::Ed2k => Box::new(crate::ed2k::Ed2k::default()),
+            #[cfg(feature = "keccak")]
+            HashAlgorithm::Keccak256 => Box::new(sha3::Keccak256::default()),
+            #[cfg(feature = "shake")]
+            HashAlgorithm::Shake128 => Box::new(digest::XofFixedWrapper::<
+                shake::Shake128,
+                digest::consts::U32,
+            >::default()),
+            #[cfg(feature = "shake")]
+            HashAlgorithm::Shake256 => Box::new(digest::XofFixedWrapper::<
+                shake::Shake256,
+                digest::consts::U64,
+            >::default()),
+            #[cfg(feature = "sm3")]
+            HashAlgorithm::Sm3 => Box::new(sm3::Sm3::default()),
+            #[cfg(feature = "streebog")]
+            HashAlgorithm::Streebog256 => Box::new(streebog::Streebog256::default()),
+            #[cfg(feature = "streebog")]
+            HashAlgorithm::Streebog512 => Box::new(streebog::Streebog512::default()),
+        }
+    }
+}
+
+fn cpu_has_sha256_extensions() -> bool {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    return std::arch::is_x86_feature_detected!("sha")
+        && std::arch::is_x86_feature_detected!("sse2")
+        && std::arch::is_x86_feature_detected!("ssse3")
+        && std::arch::is_x86_feature_detected!("sse4.1");
+    #[cfg(target_arch = "aarch64")]
+    return std::arch::is_aarch64_feature_detected!("sha2");
+    #[allow(unreachable_code)]
+    false
+}
+
+fn cpu_has_sha512_extensions() -> bool {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    return std::arch::is_x86_feature_detected!("avx2");
+    #[cfg(target_arch = "aarch64")]
+    return std::arch::is_aarch64_feature_detected!("sha3");
+    #[allow(unreachable_code)]
+    false
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = SHAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        HashAlgorithm::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.name() == normalized)
+            .ok_or_else(|| SHAError::UnsupportedAlgorithm(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_algorithm_names() {
+        assert_eq!(
+            "sha1".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha1
+        );
+        assert_eq!(
+            "SHA-256".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha256
+        );
+        assert_eq!(
+            "sha_512".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Sha512
+        );
+        assert_eq!(
+            "Adler-32".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Adler32
+        );
+        assert_eq!(
+            "CRC64-NVME".parse::<HashAlgorithm>().unwrap(),
+            HashAlgorithm::Crc64Nvme
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_algorithm() {
+        match "whirlpool".parse::<HashAlgorithm>() {
+            Err(SHAError::UnsupportedAlgorithm(name)) => assert_eq!(name, "whirlpool"),
+            _ => panic!("Expected UnsupportedAlgorithm error"),
+        }
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for algorithm in HashAlgorithm::ALL {
+            assert_eq!(
+                algorithm.to_string().parse::<HashAlgorithm>().unwrap(),
+                *algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_len() {
+        assert_eq!(HashAlgorithm::Md5.output_len(), 16);
+        assert_eq!(HashAlgorithm::Sha1.output_len(), 20);
+        assert_eq!(HashAlgorithm::Sha256.output_len(), 32);
+        assert_eq!(HashAlgorithm::Sha512.output_len(), 64);
+        assert_eq!(HashAlgorithm::Adler32.output_len(), 4);
+        assert_eq!(HashAlgorithm::Crc64Xz.output_len(), 8);
+    }
+
+    #[test]
+    fn test_hardware_acceleration_groups() {
+        // SHA-1 and SHA-256 share the same CPU extension on every supported architecture
+        assert_eq!(
+            HashAlgorithm::Sha1.is_hardware_accelerated(),
+            HashAlgorithm::Sha256.is_hardware_accelerated()
+        );
+        assert!(!HashAlgorithm::Md5.is_hardware_accelerated());
+        assert!(!HashAlgorithm::Crc64Xz.is_hardware_accelerated());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{Digest, HasherOptions, SHAError, hash_file_async_with_options};
+use futures_core::Stream;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::{Semaphore, mpsc};
+
+/// A stream of per-file results from [`hash_dir_async`], in completion order.
+///
+/// Implements [`futures_core::Stream`], and [`next`](DirHashStream::next) can be awaited
+/// directly without pulling in a stream extension crate. The stream ends once the whole tree
+/// has been walked and every file hashed.
+#[derive(Debug)]
+pub struct DirHashStream {
+    receiver: mpsc::Receiver<(PathBuf, Result<Digest, SHAError>)>,
+}
+
+impl DirHashStream {
+    /// Waits for the next file to finish hashing.
+    pub async fn next(&mut self) -> Option<(PathBuf, Result<Digest, SHAError>)> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for DirHashStream {
+    type Item = (PathBuf, Result<Digest, SHAError>);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
+
+/// Recursively hashes every regular file under `root`, at most `concurrency` at a time.
+///
+/// Results arrive as files complete, not in directory order. A directory that cannot be read
+/// yields an error under its own path and the walk carries on with the rest of the tree.
+/// Symbolic links are not followed, and entries that are neither files nor directories are
+/// skipped. Dropping the stream stops the walk; files already being hashed run to completion
+/// unless `options` carries a [`CancelToken`](crate::CancelToken) that is cancelled.
+///
+/// Must be called from within a Tokio runtime, since the walk runs as a spawned task.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HasherOptions, hash_dir_async};
+///
+/// # async fn run() {
+/// let mut results = hash_dir_async("uploads", HasherOptions::new(), 8);
+/// while let Some((path, result)) = results.next().await {
+///     match result {
+///         Ok(hash) => println!("{}  {}", hash, path.display()),
+///         Err(e) => eprintln!("{}: {}", path.display(), e),
+///     }
+/// }
+/// # }
+/// ```
+pub fn hash_dir_async(
+    root: impl AsRef<Path>,
+    options: HasherOptions,
+    concurrency: usize,
+) -> DirHashStream {
+    let concurrency = concurrency.max(1);
+    let (sender, receiver) = mpsc::channel(concurrency);
+    tokio::spawn(walk(
+        root.as_ref().to_path_buf(),
+        options,
+        concurrency,
+        sender,
+    ));
+    DirHashStream { receiver }
+}
+
+async fn walk(
+    root: PathBuf,
+    options: HasherOptions,
+    concurrency: usize,
+    sender: mpsc::Sender<(PathBuf, Result<Digest, SHAError>)>,
+) {
+    let permits = Arc::new(Semaphore::new(concurrency));
+    let mut pending = vec![root];
+
+    while let Some(dir) = pending.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                if sender.send((dir, Err(e.into()))).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        loop {
+            if sender.is_closed() {
+                return;
+            }
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = sender.send((dir.clone(), Err(e.into()))).await;
+                    break;
+                }
+            };
+            let path = entry.path();
+            match entry.file_type().await {
+                Ok(file_type) if file_type.is_dir() => pending.push(path),
+                Ok(file_type) if file_type.is_file() => {
+                    let Ok(permit) = permits.clone().acquire_owned().await else {
+                        return;
+                    };
+                    let sender = sender.clone();
+                    let options = options.clone();
+                    tokio::spawn(async move {
+                        let result = hash_file_async_with_options(&path, options).await;
+                        let _ = sender.send((path, result)).await;
+                        drop(permit);
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    let _ = sender.send((path, Err(e.into()))).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_hash_dir_async_walks_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(temp_dir.path().join("top.txt"), b"test data").unwrap();
+        std::fs::write(nested.join("deep.txt"), b"Hello, World!").unwrap();
+        for i in 0..10 {
+            std::fs::write(nested.join(format!("{}.bin", i)), [i as u8]).unwrap();
+        }
+
+        let mut stream = hash_dir_async(temp_dir.path(), HasherOptions::new(), 3);
+        let mut results = HashMap::new();
+        while let Some((path, result)) = stream.next().await {
+            results.insert(path, result.unwrap());
+        }
+
+        assert_eq!(results.len(), 12);
+        assert_eq!(
+            results[&temp_dir.path().join("top.txt")],
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+        assert_eq!(
+            results[&nested.join("deep.txt")],
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hash_dir_async_missing_root() {
+        let mut stream = hash_dir_async("nonexistent_dir_12345", HasherOptions::new(), 2);
+        let (path, result) = stream.next().await.unwrap();
+        assert_eq!(path, Path::new("nonexistent_dir_12345"));
+        assert!(matches!(result, Err(SHAError::IO(_))));
+        assert!(stream.next().await.is_none());
+    }
+}
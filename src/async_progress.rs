@@ -0,0 +1,167 @@
+use crate::{Digest, HasherOptions, SHAError, read_chunks_with};
+use futures_core::Stream;
+use std::fs::File;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Number of events buffered before the hashing thread waits for the consumer.
+const EVENT_BUFFER: usize = 32;
+
+/// One step in hashing a file, yielded by [`hash_file_events`].
+#[derive(Debug)]
+pub enum HashEvent {
+    /// The file was opened; `len` is its size in bytes, for sizing a progress bar.
+    Started { len: u64 },
+    /// `bytes` is the total hashed so far, reported after each buffer.
+    Progress { bytes: u64 },
+    /// Hashing completed. Always the last event of a successful hash.
+    Finished { digest: Digest },
+    /// Hashing failed or was cancelled. Always the last event of a failed hash.
+    Error(SHAError),
+}
+
+/// The events of a single hash started by [`hash_file_events`].
+///
+/// Implements [`futures_core::Stream`]; [`next`](HashEventStream::next) can also be awaited
+/// directly. The stream ends after [`HashEvent::Finished`] or [`HashEvent::Error`].
+#[derive(Debug)]
+pub struct HashEventStream {
+    receiver: mpsc::Receiver<HashEvent>,
+}
+
+impl HashEventStream {
+    /// Waits for the next event.
+    pub async fn next(&mut self) -> Option<HashEvent> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for HashEventStream {
+    type Item = HashEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().receiver.poll_recv(cx)
+    }
+}
+
+/// Hashes the file at `path`, reporting progress as a stream of [`HashEvent`]s.
+///
+/// The read loop runs on Tokio's blocking pool and emits one [`HashEvent::Progress`] per
+/// buffer read; set a fixed [`buffer_size`](HasherOptions::buffer_size) to control how often.
+/// A consumer that falls behind slows the hash down rather than letting events pile up, and
+/// dropping the stream stops the hash.
+///
+/// Must be called from within a Tokio runtime.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashEvent, HasherOptions, hash_file_events};
+///
+/// # async fn run() {
+/// let mut events = hash_file_events("disk.img", HasherOptions::new().buffer_size(1 << 20));
+/// let mut total = 0;
+/// while let Some(event) = events.next().await {
+///     match event {
+///         HashEvent::Started { len } => total = len,
+///         HashEvent::Progress { bytes } => println!("{}/{}", bytes, total),
+///         HashEvent::Finished { digest } => println!("{}", digest),
+///         HashEvent::Error(e) => eprintln!("{}", e),
+///     }
+/// }
+/// # }
+/// ```
+pub fn hash_file_events(path: impl AsRef<Path>, options: HasherOptions) -> HashEventStream {
+    let path = path.as_ref().to_path_buf();
+    let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        let event = match hash_with_events(&path, &options, &sender) {
+            Ok(digest) => HashEvent::Finished { digest },
+            Err(e) => HashEvent::Error(e),
+        };
+        let _ = sender.blocking_send(event);
+    });
+    HashEventStream { receiver }
+}
+
+fn hash_with_events(
+    path: &Path,
+    options: &HasherOptions,
+    sender: &mpsc::Sender<HashEvent>,
+) -> Result<Digest, SHAError> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if sender.blocking_send(HashEvent::Started { len }).is_err() {
+        return Err(SHAError::Cancelled);
+    }
+
+    let mut hasher = options.hasher();
+    let mut bytes = 0;
+    let flow = read_chunks_with(file, options.buffer_size_for(len), |chunk| {
+        if options.is_cancelled() {
+            return ControlFlow::Break(());
+        }
+        hasher.update(chunk);
+        bytes += chunk.len() as u64;
+        match sender.blocking_send(HashEvent::Progress { bytes }) {
+            Ok(()) => ControlFlow::Continue(()),
+            // The stream was dropped, so nobody is waiting for the digest
+            Err(_) => ControlFlow::Break(()),
+        }
+    })?;
+    match flow {
+        ControlFlow::Continue(()) => Ok(hasher.finalize()),
+        ControlFlow::Break(()) => Err(SHAError::Cancelled),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashAlgorithm;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_hash_file_events_order() {
+        let data = vec![b'p'; 10_000];
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&data).unwrap();
+        temp_file.flush().unwrap();
+
+        let options = HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha256)
+            .buffer_size(4096);
+        let mut events = hash_file_events(temp_file.path(), options.clone());
+
+        assert!(matches!(
+            events.next().await,
+            Some(HashEvent::Started { len: 10_000 })
+        ));
+        let mut progress = Vec::new();
+        let digest = loop {
+            match events.next().await.unwrap() {
+                HashEvent::Progress { bytes } => progress.push(bytes),
+                HashEvent::Finished { digest } => break digest,
+                event => panic!("Unexpected event {:?}", event),
+            }
+        };
+        assert_eq!(progress, [4096, 8192, 10_000]);
+        assert_eq!(digest, options.hash_reader(&data[..]).unwrap());
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_hash_file_events_not_found() {
+        let mut events = hash_file_events("nonexistent_file_12345.txt", HasherOptions::new());
+        match events.next().await {
+            Some(HashEvent::Error(SHAError::IO(e))) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotFound)
+            }
+            event => panic!("Expected IO error, got {:?}", event),
+        }
+        assert!(events.next().await.is_none());
+    }
+}
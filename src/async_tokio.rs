@@ -0,0 +1,273 @@
+//! Asynchronous hashing on the Tokio runtime.
+//!
+//! Available with the `tokio` feature. Files are opened through [`tokio::fs`] and the hashing
+//! loop itself runs on Tokio's blocking thread pool via [`spawn_blocking`], so hashing a large
+//! upload never stalls the async worker threads.
+//!
+//! [`spawn_blocking`]: tokio::task::spawn_blocking
+
+use crate::options::DEFAULT_BUFFER_SIZE;
+use crate::output::declared_algorithm;
+use crate::{Digest, HashAlgorithm, Hasher, HasherOptions, SHAError};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Asynchronous counterpart of [`Hashable`](crate::Hashable).
+///
+/// Implemented for [`Path`] and [`PathBuf`], which hash the file contents, and for an already
+/// open [`tokio::fs::File`]. The returned futures are `Send`, so they can be spawned onto a
+/// multi-threaded runtime.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::AsyncHashable;
+/// use std::path::Path;
+///
+/// # async fn run() -> Result<(), sha_file_hashing::SHAError> {
+/// let hash = Path::new("upload.bin").hash_async().await?;
+/// assert!(Path::new("upload.bin").validate_async(hash.to_hex()).await?);
+/// # Ok(())
+/// # }
+/// ```
+pub trait AsyncHashable {
+    /// Computes the SHA-1 hash of the data.
+    fn hash_async(&self) -> impl Future<Output = Result<Digest, SHAError>> + Send {
+        self.hash_with_async(HashAlgorithm::Sha1)
+    }
+
+    /// Validates the data against an expected SHA-1 hash (case-insensitive).
+    fn validate_async(
+        &self,
+        hash: impl AsRef<str>,
+    ) -> impl Future<Output = Result<bool, SHAError>> + Send {
+        self.validate_with_async(hash, HashAlgorithm::Sha1)
+    }
+
+    /// Computes the hash of the data with `algorithm`.
+    fn hash_with_async(
+        &self,
+        algorithm: HashAlgorithm,
+    ) -> impl Future<Output = Result<Digest, SHAError>> + Send;
+
+    /// Validates the data against `hash` computed with `algorithm` (case-insensitive).
+    fn validate_with_async(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> impl Future<Output = Result<bool, SHAError>> + Send {
+        let expected = hash.as_ref().to_string();
+        let algorithm = declared_algorithm(&expected, algorithm);
+        let hashed = algorithm.map(|algorithm| self.hash_with_async(algorithm));
+        async move { hashed?.await?.matches_hex(&expected) }
+    }
+}
+
+impl AsyncHashable for Path {
+    fn hash_with_async(
+        &self,
+        algorithm: HashAlgorithm,
+    ) -> impl Future<Output = Result<Digest, SHAError>> + Send {
+        hash_file_async_with_algorithm(self.to_path_buf(), algorithm)
+    }
+}
+
+impl AsyncHashable for PathBuf {
+    fn hash_with_async(
+        &self,
+        algorithm: HashAlgorithm,
+    ) -> impl Future<Output = Result<Digest, SHAError>> + Send {
+        self.as_path().hash_with_async(algorithm)
+    }
+}
+
+/// Hashes an open handle from its current position, like the [`File`](std::fs::File) impl of
+/// [`Hashable`](crate::Hashable).
+///
+/// The handle is cloned first, so the original stays usable, but the clone shares its cursor:
+/// after hashing, the original is positioned at EOF.
+impl AsyncHashable for tokio::fs::File {
+    async fn hash_with_async(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        let file = self.try_clone().await?;
+        hash_tokio_file(file, HasherOptions::new().algorithm(algorithm)).await
+    }
+}
+
+/// Computes the SHA-1 hash of the file at `path` without blocking the runtime.
+///
+/// # Examples
+/// ```no_run
+/// # async fn run() -> Result<(), sha_file_hashing::SHAError> {
+/// let hash = sha_file_hashing::hash_file_async("upload.bin").await?;
+/// println!("{}", hash);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn hash_file_async(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file_async_with_algorithm(path, HashAlgorithm::Sha1).await
+}
+
+/// Computes the hash of the file at `path` with `algorithm` without blocking the runtime.
+pub async fn hash_file_async_with_algorithm(
+    path: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    hash_file_async_with_options(path, HasherOptions::new().algorithm(algorithm)).await
+}
+
+/// Computes the hash of the file at `path` with fully configured `options`.
+///
+/// A [`CancelToken`](crate::CancelToken) in `options` is the way to stop the hash early:
+/// dropping the future does not stop the blocking read loop, but cancelling the token does.
+pub async fn hash_file_async_with_options(
+    path: impl AsRef<Path>,
+    options: HasherOptions,
+) -> Result<Digest, SHAError> {
+    let file = tokio::fs::File::open(path.as_ref()).await?;
+    hash_tokio_file(file, options).await
+}
+
+/// Validates the file at `path` against an expected SHA-1 hash without blocking the runtime.
+pub async fn validate_file_async(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    validate_file_async_with_algorithm(path, hash, HashAlgorithm::Sha1).await
+}
+
+/// Validates the file at `path` against `hash` computed with `algorithm`, without blocking the
+/// runtime.
+pub async fn validate_file_async_with_algorithm(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+    algorithm: HashAlgorithm,
+) -> Result<bool, SHAError> {
+    let hash = hash.as_ref();
+    hash_file_async_with_algorithm(path, declared_algorithm(hash, algorithm)?)
+        .await?
+        .matches_hex(hash)
+}
+
+/// Computes the SHA-1 hash of everything read from an async `reader`.
+///
+/// The data is hashed chunk by chunk as it arrives, which suits verify-on-download flows: pass
+/// the response body (for example a reqwest stream adapted with `tokio_util::io::StreamReader`)
+/// and compare the digest once the transfer completes.
+///
+/// # Examples
+/// ```
+/// # async fn run() {
+/// let body: &[u8] = b"Hello, World!";
+/// let hash = sha_file_hashing::hash_async_reader(body).await.unwrap();
+/// assert_eq!(hash, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+/// # }
+/// ```
+pub async fn hash_async_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Digest, SHAError> {
+    hash_async_reader_with_algorithm(reader, HashAlgorithm::Sha1).await
+}
+
+/// Computes the hash of everything read from an async `reader` with `algorithm`.
+pub async fn hash_async_reader_with_algorithm<R: AsyncRead + Unpin>(
+    mut reader: R,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer).await? {
+            0 => return Ok(hasher.finalize()),
+            n => hasher.update(&buffer[..n]),
+        }
+    }
+}
+
+/// Hashes an open Tokio file on the blocking pool.
+pub(crate) async fn hash_tokio_file(
+    file: tokio::fs::File,
+    options: HasherOptions,
+) -> Result<Digest, SHAError> {
+    let file = file.into_std().await;
+    tokio::task::spawn_blocking(move || options.hash_file(file))
+        .await
+        .map_err(|e| SHAError::IO(std::io::Error::other(e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_hash_file_async_matches_sync() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+        temp_file.flush().unwrap();
+
+        let hash = hash_file_async(temp_file.path()).await.unwrap();
+        assert_eq!(hash, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+        assert!(
+            validate_file_async(temp_file.path(), hash.to_hex())
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            temp_file
+                .path()
+                .hash_with_async(HashAlgorithm::Sha256)
+                .await
+                .unwrap(),
+            crate::hash_file_from_path_with_algorithm(temp_file.path(), HashAlgorithm::Sha256)
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tokio_file_hashable() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"test data").unwrap();
+        temp_file.flush().unwrap();
+
+        let file = tokio::fs::File::open(temp_file.path()).await.unwrap();
+        assert_eq!(
+            file.hash_async().await.unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+
+        let file = tokio::fs::File::open(temp_file.path()).await.unwrap();
+        assert!(
+            file.validate_with_async(
+                "916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9",
+                HashAlgorithm::Sha256
+            )
+            .await
+            .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_reader() {
+        let data = vec![b'r'; 20000];
+        assert_eq!(
+            hash_async_reader_with_algorithm(&data[..], HashAlgorithm::Sha256)
+                .await
+                .unwrap(),
+            crate::hash_reader_with_algorithm(&data[..], HashAlgorithm::Sha256).unwrap()
+        );
+
+        let chained = (&b"Hello, "[..]).chain(&b"World!"[..]);
+        assert_eq!(
+            hash_async_reader(chained).await.unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hash_file_async_not_found() {
+        let path = PathBuf::from("nonexistent_file_12345.txt");
+        match path.hash_async().await {
+            Err(SHAError::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("Expected IO error"),
+        }
+    }
+}
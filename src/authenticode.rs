@@ -0,0 +1,217 @@
+//! The Authenticode hash of a Windows PE image, as signatures and security catalogs record it.
+
+use crate::{Digest, HashAlgorithm, Hasher, HasherOptions, SHAError, read_chunks};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// The optional header magic of a PE32 image.
+const PE32_MAGIC: u16 = 0x10b;
+/// The optional header magic of a PE32+ (64-bit) image.
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+/// The index of the certificate table among the data directories.
+const CERTIFICATE_TABLE: u32 = 4;
+
+/// Computes the Authenticode hash of the PE image (`.exe`, `.dll`, `.sys`, ...) at `path`.
+///
+/// This is the digest a signature's `SpcIndirectDataContent` and a `.cat` catalog list for the
+/// image: the whole file except the `CheckSum` field of the optional header, the certificate
+/// table entry among the data directories, and the certificate table itself, so it does not
+/// change when the image is signed. Signatures and catalogs use SHA-1 or SHA-256.
+///
+/// # Errors
+/// Fails with an [`io::ErrorKind::InvalidData`] error if the file is not a PE image or its
+/// certificate table lies outside it.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashAlgorithm, authenticode_hash};
+///
+/// let digest = authenticode_hash("C:/Windows/System32/notepad.exe", HashAlgorithm::Sha256).unwrap();
+/// println!("{}", digest.to_hex().to_uppercase());
+/// ```
+pub fn authenticode_hash(
+    path: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let path = path.as_ref();
+    hash_image(path, algorithm).map_err(|e| e.at_path(path))
+}
+
+fn hash_image(path: &Path, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let skipped = skipped_ranges(&mut file, len)?;
+
+    let mut hasher = Hasher::new(algorithm);
+    let mut position = 0;
+    for (start, skip) in skipped.into_iter().chain([(len, 0)]) {
+        file.seek(SeekFrom::Start(position))?;
+        read_chunks(
+            &HasherOptions::new(),
+            (&mut file).take(start - position),
+            |chunk| hasher.update(chunk),
+        )?;
+        position = start + skip;
+    }
+    Ok(hasher.finalize())
+}
+
+/// Returns the `(offset, length)` of each region left out of the hash, in file order.
+fn skipped_ranges(file: &mut File, len: u64) -> io::Result<Vec<(u64, u64)>> {
+    let mut dos_header = [0u8; 64];
+    read_at(file, 0, &mut dos_header)?;
+    if &dos_header[..2] != b"MZ" {
+        return Err(not_pe("no MZ signature"));
+    }
+    let pe_offset = u64::from(u32_at(&dos_header, 0x3c));
+
+    let mut headers = [0u8; 24];
+    read_at(file, pe_offset, &mut headers)?;
+    if &headers[..4] != b"PE\0\0" {
+        return Err(not_pe("no PE signature"));
+    }
+    let optional_size = usize::from(u16_at(&headers, 20));
+    let optional_offset = pe_offset + 24;
+    let mut optional = vec![0u8; optional_size];
+    read_at(file, optional_offset, &mut optional)?;
+
+    // The CheckSum field sits at the same place in both formats; the data directories follow
+    // the fields whose width differs
+    let directories = match optional.get(..2).map(|magic| u16_at(magic, 0)) {
+        Some(PE32_MAGIC) => 92,
+        Some(PE32_PLUS_MAGIC) => 108,
+        _ => return Err(not_pe("unknown optional header magic")),
+    };
+    if optional_size < directories + 4 {
+        return Err(not_pe("optional header too short"));
+    }
+    let mut skipped = vec![(optional_offset + 64, 4)];
+    let directory_count = u32_at(&optional, directories);
+    let entry = directories + 4 + CERTIFICATE_TABLE as usize * 8;
+    if directory_count > CERTIFICATE_TABLE && optional_size >= entry + 8 {
+        skipped.push((optional_offset + entry as u64, 8));
+        // Unlike the other directories, the certificate table is located by file offset
+        let table_offset = u64::from(u32_at(&optional, entry));
+        let table_len = u64::from(u32_at(&optional, entry + 4));
+        if table_len > 0 {
+            let headers_end = optional_offset + optional_size as u64;
+            if table_offset < headers_end || table_offset + table_len > len {
+                return Err(not_pe("certificate table outside the image"));
+            }
+            skipped.push((table_offset, table_len));
+        }
+    }
+    Ok(skipped)
+}
+
+fn read_at(file: &mut File, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buffer).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => not_pe("truncated headers"),
+        _ => e,
+    })
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().expect("four bytes"))
+}
+
+fn not_pe(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("not a PE image: {}", reason),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const PE_OFFSET: usize = 0x80;
+    const OPTIONAL: usize = PE_OFFSET + 24;
+    const CHECKSUM: usize = OPTIONAL + 64;
+    const CERT_ENTRY: usize = OPTIONAL + 112 + 4 * 8;
+
+    /// A PE32+ image of 0x400 bytes with 16 data directories and a certificate table of
+    /// `cert_len` bytes appended.
+    fn image(cert_len: u32) -> Vec<u8> {
+        let mut image: Vec<u8> = (0..0x400u32).map(|i| (i % 251) as u8).collect();
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&(PE_OFFSET as u32).to_le_bytes());
+        image[PE_OFFSET..PE_OFFSET + 4].copy_from_slice(b"PE\0\0");
+        image[PE_OFFSET + 20..PE_OFFSET + 22].copy_from_slice(&240u16.to_le_bytes());
+        image[OPTIONAL..OPTIONAL + 2].copy_from_slice(&PE32_PLUS_MAGIC.to_le_bytes());
+        image[OPTIONAL + 108..OPTIONAL + 112].copy_from_slice(&16u32.to_le_bytes());
+        let (offset, len) = if cert_len > 0 {
+            (0x400u32, cert_len)
+        } else {
+            (0, 0)
+        };
+        image[CERT_ENTRY..CERT_ENTRY + 4].copy_from_slice(&offset.to_le_bytes());
+        image[CERT_ENTRY + 4..CERT_ENTRY + 8].copy_from_slice(&len.to_le_bytes());
+        image.extend((0..cert_len).map(|i| (i * 7) as u8));
+        image
+    }
+
+    #[test]
+    fn test_authenticode_hash_skips_signature_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.exe");
+        let unsigned = image(0);
+        fs::write(&path, &unsigned).unwrap();
+        let digest = authenticode_hash(&path, HashAlgorithm::Sha256).unwrap();
+
+        let mut expected = Hasher::new(HashAlgorithm::Sha256);
+        expected.update(&unsigned[..CHECKSUM]);
+        expected.update(&unsigned[CHECKSUM + 4..CERT_ENTRY]);
+        expected.update(&unsigned[CERT_ENTRY + 8..]);
+        assert_eq!(digest, expected.finalize());
+
+        // Signing fills in the checksum, the directory entry and the table
+        let mut signed = image(40);
+        signed[CHECKSUM..CHECKSUM + 4].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        fs::write(&path, &signed).unwrap();
+        assert_eq!(
+            authenticode_hash(&path, HashAlgorithm::Sha256).unwrap(),
+            digest
+        );
+
+        signed[0x200] ^= 1;
+        fs::write(&path, &signed).unwrap();
+        assert_ne!(
+            authenticode_hash(&path, HashAlgorithm::Sha256).unwrap(),
+            digest
+        );
+    }
+
+    #[test]
+    fn test_authenticode_hash_rejects_non_pe() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.exe");
+        let mut bad_table = image(40);
+        bad_table[CERT_ENTRY + 4..CERT_ENTRY + 8].copy_from_slice(&400u32.to_le_bytes());
+        let mut bad_magic = image(0);
+        bad_magic[OPTIONAL] = 0;
+        for data in [
+            b"not a PE image".to_vec(),
+            image(0)[..0x90].to_vec(),
+            bad_table,
+            bad_magic,
+        ] {
+            fs::write(&path, &data).unwrap();
+            match authenticode_hash(&path, HashAlgorithm::Sha1) {
+                Err(SHAError::File { source, .. }) => {
+                    assert_eq!(source.kind(), io::ErrorKind::InvalidData)
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+    }
+}
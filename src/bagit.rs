@@ -0,0 +1,488 @@
+//! BagIt packages, as specified by RFC 8493.
+//!
+//! A bag is a directory holding the payload under `data/`, a `bagit.txt` declaration, one
+//! `manifest-<algorithm>.txt` per algorithm listing every payload file, and
+//! `tagmanifest-<algorithm>.txt` files covering the other metadata files.
+
+use crate::dir::portable_path;
+use crate::report::{VerificationReport, verify_manifest};
+use crate::sfv::{SECONDS_PER_DAY, civil_from_days};
+use crate::{Digest, DirOptions, HashAlgorithm, HasherOptions, Manifest, SHAError, SymlinkPolicy};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+const DECLARATION: &str = "bagit.txt";
+const BAG_INFO: &str = "bag-info.txt";
+const PAYLOAD: &str = "data";
+
+/// A BagIt bag rooted at a directory.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{Bag, HashAlgorithm};
+///
+/// // The payload is already in accession/data
+/// Bag::create("accession", &[HashAlgorithm::Sha256]).unwrap();
+///
+/// let report = Bag::open("accession").unwrap().validate().unwrap();
+/// assert!(report.is_valid());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bag {
+    root: PathBuf,
+}
+
+impl Bag {
+    /// Makes `root` a bag around the payload already in `root/data`, writing `bagit.txt`,
+    /// `bag-info.txt` and a payload and tag manifest for each of `algorithms`.
+    ///
+    /// Existing manifests are replaced, so an updated payload can be re-bagged in place. Other
+    /// fields of an existing `bag-info.txt` are kept; `Bagging-Date` and `Payload-Oxum` are
+    /// rewritten. Symbolic links in the payload are followed.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidBag`] if `algorithms` is empty, and an I/O error if
+    /// `root/data` does not exist or cannot be read.
+    pub fn create(root: impl AsRef<Path>, algorithms: &[HashAlgorithm]) -> Result<Bag, SHAError> {
+        let bag = Bag {
+            root: root.as_ref().to_path_buf(),
+        };
+        if algorithms.is_empty() {
+            return Err(SHAError::InvalidBag(
+                "at least one manifest algorithm is required".into(),
+            ));
+        }
+        let payload = bag.payload_dir();
+        if !payload.is_dir() {
+            let source = io::Error::new(io::ErrorKind::NotFound, "payload directory not found");
+            return Err(SHAError::File {
+                path: payload,
+                source,
+            });
+        }
+        for (name, _) in bag.manifest_files("manifest-")? {
+            bag.remove(&name)?;
+        }
+        for (name, _) in bag.manifest_files("tagmanifest-")? {
+            bag.remove(&name)?;
+        }
+
+        let mut tag_files = vec![DECLARATION.to_string(), BAG_INFO.to_string()];
+        let mut oxum = None;
+        for &algorithm in algorithms {
+            let manifest = payload_options(algorithm).hash_dir(&payload)?;
+            if oxum.is_none() {
+                oxum = Some(bag.payload_oxum(&manifest)?);
+            }
+            let name = format!("manifest-{}.txt", algorithm);
+            let lines = manifest.iter().map(|(path, digest)| {
+                let path = format!("{}/{}", PAYLOAD, portable_path(path));
+                (path, digest.clone())
+            });
+            bag.write_manifest(&name, lines)?;
+            tag_files.push(name);
+        }
+        bag.write(
+            DECLARATION,
+            "BagIt-Version: 1.0\nTag-File-Character-Encoding: UTF-8\n",
+        )?;
+        bag.write_bag_info(oxum.unwrap_or_default())?;
+
+        for &algorithm in algorithms {
+            let options = HasherOptions::new().algorithm(algorithm);
+            let mut lines = Vec::with_capacity(tag_files.len());
+            for name in &tag_files {
+                lines.push((
+                    name.clone(),
+                    options.hash_file_from_path(bag.root.join(name))?,
+                ));
+            }
+            bag.write_manifest(&format!("tagmanifest-{}.txt", algorithm), lines)?;
+        }
+        Ok(bag)
+    }
+
+    /// Opens an existing bag, checking that `root` holds a `bagit.txt` declaration.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidBag`] if the declaration has no `BagIt-Version`.
+    pub fn open(root: impl AsRef<Path>) -> Result<Bag, SHAError> {
+        let bag = Bag {
+            root: root.as_ref().to_path_buf(),
+        };
+        let declaration = bag.read(DECLARATION)?;
+        if !declaration.lines().any(|line| {
+            line.trim_start_matches('\u{feff}')
+                .starts_with("BagIt-Version:")
+        }) {
+            return Err(SHAError::InvalidBag(format!(
+                "{} has no BagIt-Version",
+                DECLARATION
+            )));
+        }
+        Ok(bag)
+    }
+
+    /// Returns the bag's root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the directory holding the payload, `data` under the root.
+    pub fn payload_dir(&self) -> PathBuf {
+        self.root.join(PAYLOAD)
+    }
+
+    /// Returns the algorithms of the payload manifests this crate can check, in name order.
+    pub fn algorithms(&self) -> Result<Vec<HashAlgorithm>, SHAError> {
+        let manifests = self.manifest_files("manifest-")?;
+        Ok(manifests
+            .into_iter()
+            .map(|(_, algorithm)| algorithm)
+            .collect())
+    }
+
+    /// Checks every payload and tag manifest, and that every payload file is listed in each
+    /// payload manifest.
+    ///
+    /// Manifests for algorithms this crate does not support are ignored.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidBag`] if there is no supported payload manifest or a manifest
+    /// line is malformed or names a path outside the bag.
+    pub fn validate(&self) -> Result<BagReport, SHAError> {
+        let manifests = self.manifest_files("manifest-")?;
+        if manifests.is_empty() {
+            return Err(SHAError::InvalidBag("no supported payload manifest".into()));
+        }
+        let files = payload_options(HashAlgorithm::default()).list_files(&self.payload_dir())?;
+        let files: Vec<PathBuf> = files
+            .iter()
+            .map(|path| Path::new(PAYLOAD).join(path))
+            .collect();
+
+        let mut report = BagReport::default();
+        let mut unlisted = BTreeSet::new();
+        for (name, algorithm) in manifests {
+            let manifest = self.read_manifest(&name, algorithm, true)?;
+            unlisted.extend(
+                files
+                    .iter()
+                    .filter(|path| manifest.get(path).is_none())
+                    .cloned(),
+            );
+            report
+                .payload
+                .extend(verify_manifest(&manifest, &self.root));
+        }
+        for (name, algorithm) in self.manifest_files("tagmanifest-")? {
+            let manifest = self.read_manifest(&name, algorithm, false)?;
+            report.tags.extend(verify_manifest(&manifest, &self.root));
+        }
+        report.unlisted = unlisted.into_iter().collect();
+        Ok(report)
+    }
+
+    /// Lists the `<prefix><algorithm>.txt` files in the root with a supported algorithm, sorted
+    /// by name.
+    fn manifest_files(&self, prefix: &str) -> Result<Vec<(String, HashAlgorithm)>, SHAError> {
+        let entries =
+            fs::read_dir(&self.root).map_err(|e| SHAError::from(e).at_path(&self.root))?;
+        let mut manifests = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| SHAError::from(e).at_path(&self.root))?;
+            let Ok(name) = entry.file_name().into_string() else {
+                continue;
+            };
+            let algorithm = name
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(".txt"))
+                .and_then(|algorithm| algorithm.parse().ok());
+            if let Some(algorithm) = algorithm {
+                manifests.push((name, algorithm));
+            }
+        }
+        manifests.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(manifests)
+    }
+
+    /// Parses the manifest `name` into digests keyed by path relative to the root.
+    fn read_manifest(
+        &self,
+        name: &str,
+        algorithm: HashAlgorithm,
+        payload: bool,
+    ) -> Result<Manifest, SHAError> {
+        let text = self.read(name)?;
+        let mut manifest = Manifest::new(algorithm);
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                SHAError::InvalidBag(format!("{} line {}: {}", name, index + 1, reason))
+            };
+            let (hex, path) = line
+                .split_once([' ', '\t'])
+                .ok_or_else(|| invalid("expected a digest and a path"))?;
+            let digest = Digest::parse(hex, algorithm).map_err(|e| invalid(&e.to_string()))?;
+            let path = PathBuf::from(decode_path(path.trim_start_matches([' ', '\t'])));
+            let inside = path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+            if !inside || (payload && !path.starts_with(PAYLOAD)) {
+                return Err(invalid("path is outside the payload"));
+            }
+            manifest.insert(path, digest);
+        }
+        Ok(manifest)
+    }
+
+    fn write_manifest(
+        &self,
+        name: &str,
+        lines: impl IntoIterator<Item = (String, Digest)>,
+    ) -> Result<(), SHAError> {
+        let mut text = String::new();
+        for (path, digest) in lines {
+            text.push_str(&format!("{}  {}\n", digest, encode_path(&path)));
+        }
+        self.write(name, &text)
+    }
+
+    /// Writes `bag-info.txt`, keeping any fields other than the ones computed here.
+    fn write_bag_info(&self, oxum: (u64, usize)) -> Result<(), SHAError> {
+        let existing = match fs::read_to_string(self.root.join(BAG_INFO)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(SHAError::from(e).at_path(&self.root.join(BAG_INFO))),
+        };
+        let mut text = String::new();
+        let mut keep = true;
+        for line in existing.lines() {
+            // Indented lines continue the previous field
+            if !line.starts_with([' ', '\t']) {
+                let label = line.split(':').next().unwrap_or_default().trim();
+                keep = !label.eq_ignore_ascii_case("Bagging-Date")
+                    && !label.eq_ignore_ascii_case("Payload-Oxum");
+            }
+            if keep {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+        let days = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() / SECONDS_PER_DAY);
+        let (year, month, day) = civil_from_days(days as i64);
+        text.push_str(&format!(
+            "Bagging-Date: {:04}-{:02}-{:02}\nPayload-Oxum: {}.{}\n",
+            year, month, day, oxum.0, oxum.1
+        ));
+        self.write(BAG_INFO, &text)
+    }
+
+    /// Returns the total size and number of payload files, as recorded in `Payload-Oxum`.
+    fn payload_oxum(&self, manifest: &Manifest) -> Result<(u64, usize), SHAError> {
+        let mut octets = 0;
+        for (path, _) in manifest.iter() {
+            octets += match manifest.stamp(path) {
+                Some(stamp) => stamp.len,
+                None => {
+                    let path = self.payload_dir().join(path);
+                    fs::metadata(&path)
+                        .map_err(|e| SHAError::from(e).at_path(&path))?
+                        .len()
+                }
+            };
+        }
+        Ok((octets, manifest.len()))
+    }
+
+    fn read(&self, name: &str) -> Result<String, SHAError> {
+        let path = self.root.join(name);
+        fs::read_to_string(&path).map_err(|e| SHAError::from(e).at_path(&path))
+    }
+
+    fn write(&self, name: &str, text: &str) -> Result<(), SHAError> {
+        let path = self.root.join(name);
+        fs::write(&path, text).map_err(|e| SHAError::from(e).at_path(&path))
+    }
+
+    fn remove(&self, name: &str) -> Result<(), SHAError> {
+        let path = self.root.join(name);
+        fs::remove_file(&path).map_err(|e| SHAError::from(e).at_path(&path))
+    }
+}
+
+/// Walk settings for a bag payload: every file, links followed.
+fn payload_options(algorithm: HashAlgorithm) -> DirOptions {
+    DirOptions::new()
+        .hasher(HasherOptions::new().algorithm(algorithm))
+        .hash_ignore(false)
+        .symlinks(SymlinkPolicy::Follow)
+}
+
+/// Percent-encodes the characters RFC 8493 requires in manifest paths.
+fn encode_path(path: &str) -> String {
+    path.replace('%', "%25")
+        .replace('\n', "%0A")
+        .replace('\r', "%0D")
+}
+
+fn decode_path(path: &str) -> String {
+    let mut decoded = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(index) = rest.find('%') {
+        decoded.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 3).unwrap_or_default();
+        let replacement = match escape.to_ascii_uppercase().as_str() {
+            "25" => Some('%'),
+            "0A" => Some('\n'),
+            "0D" => Some('\r'),
+            _ => None,
+        };
+        match replacement {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[index + 3..];
+            }
+            None => {
+                decoded.push('%');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// The outcome of [`Bag::validate`].
+#[derive(Debug, Default)]
+pub struct BagReport {
+    payload: VerificationReport,
+    tags: VerificationReport,
+    unlisted: Vec<PathBuf>,
+}
+
+impl BagReport {
+    /// Returns the outcome for each entry of each payload manifest, paths relative to the bag
+    /// root.
+    pub fn payload(&self) -> &VerificationReport {
+        &self.payload
+    }
+
+    /// Returns the outcome for each entry of each tag manifest.
+    pub fn tags(&self) -> &VerificationReport {
+        &self.tags
+    }
+
+    /// Returns payload files missing from at least one payload manifest, in path order.
+    pub fn unlisted(&self) -> &[PathBuf] {
+        &self.unlisted
+    }
+
+    /// Returns `true` if the bag is complete and every file matched.
+    pub fn is_valid(&self) -> bool {
+        self.payload.is_ok() && self.tags.is_ok() && self.unlisted.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_bag() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let data = temp_dir.path().join("data");
+        fs::create_dir_all(data.join("sub")).unwrap();
+        fs::write(data.join("a.txt"), b"test data").unwrap();
+        fs::write(data.join("sub").join("b.txt"), b"Hello, World!").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_create_and_validate_bag() {
+        let temp_dir = sample_bag();
+        fs::write(
+            temp_dir.path().join(BAG_INFO),
+            "Source-Organization: Archive\n",
+        )
+        .unwrap();
+        let algorithms = [HashAlgorithm::Sha256, HashAlgorithm::Sha512];
+        let bag = Bag::create(temp_dir.path(), &algorithms).unwrap();
+
+        let manifest = fs::read_to_string(temp_dir.path().join("manifest-sha256.txt")).unwrap();
+        assert_eq!(
+            manifest,
+            "916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9  data/a.txt\n\
+             dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f  data/sub/b.txt\n"
+        );
+        let info = fs::read_to_string(temp_dir.path().join(BAG_INFO)).unwrap();
+        assert!(info.starts_with("Source-Organization: Archive\nBagging-Date: "));
+        assert!(info.ends_with("Payload-Oxum: 22.2\n"));
+        let tags = fs::read_to_string(temp_dir.path().join("tagmanifest-sha256.txt")).unwrap();
+        assert_eq!(tags.lines().count(), 4);
+
+        let bag = Bag::open(bag.root()).unwrap();
+        assert_eq!(bag.algorithms().unwrap(), algorithms);
+        let report = bag.validate().unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.payload().len(), 4);
+        assert_eq!(report.tags().len(), 8);
+    }
+
+    #[test]
+    fn test_validate_reports_changes() {
+        let temp_dir = sample_bag();
+        let bag = Bag::create(temp_dir.path(), &[HashAlgorithm::Sha256]).unwrap();
+        fs::write(bag.payload_dir().join("a.txt"), b"tampered").unwrap();
+        fs::remove_file(bag.payload_dir().join("sub").join("b.txt")).unwrap();
+        fs::write(bag.payload_dir().join("new.txt"), b"").unwrap();
+        fs::write(temp_dir.path().join(BAG_INFO), "Payload-Oxum: 0.0\n").unwrap();
+
+        let report = bag.validate().unwrap();
+        assert!(!report.is_valid());
+        assert_eq!(report.payload().mismatched(), 1);
+        assert_eq!(report.payload().missing(), 1);
+        assert_eq!(report.unlisted(), [Path::new("data").join("new.txt")]);
+        assert_eq!(report.tags().mismatched(), 1);
+    }
+
+    #[test]
+    fn test_invalid_bags() {
+        let temp_dir = sample_bag();
+        assert!(matches!(
+            Bag::create(temp_dir.path(), &[]),
+            Err(SHAError::InvalidBag(_))
+        ));
+        assert!(matches!(
+            Bag::open(temp_dir.path()),
+            Err(SHAError::File { .. })
+        ));
+
+        let bag = Bag::create(temp_dir.path(), &[HashAlgorithm::Md5]).unwrap();
+        let d41d = "d41d8cd98f00b204e9800998ecf8427e";
+        for manifest in [
+            format!("{}  ../outside\n", d41d),
+            format!("{}  bag-info.txt\n", d41d),
+            format!("{}\n", d41d),
+        ] {
+            fs::write(temp_dir.path().join("manifest-md5.txt"), manifest).unwrap();
+            assert!(matches!(bag.validate(), Err(SHAError::InvalidBag(_))));
+        }
+    }
+
+    #[test]
+    fn test_percent_encoded_paths() {
+        let name = "100%\r\nsure";
+        assert_eq!(encode_path(name), "100%25%0D%0Asure");
+        assert_eq!(decode_path(&encode_path(name)), name);
+        assert_eq!(decode_path("50% %2x %0a%"), "50% %2x \n%");
+    }
+}
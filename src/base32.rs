@@ -0,0 +1,161 @@
+//! RFC 4648 base32, used for file names in some content-addressed stores and for onion-style
+//! identifiers, where hex is too long and base64 is not safe on case-insensitive file systems.
+
+use crate::{Digest, HashAlgorithm, SHAError};
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Returns the number of base32 characters for `len` bytes, without padding.
+fn encoded_len(len: usize) -> usize {
+    (len * 8).div_ceil(5)
+}
+
+/// Encodes `bytes` as uppercase RFC 4648 base32, padded with `=` to a multiple of eight
+/// characters if `padded` is set.
+pub(crate) fn encode(bytes: &[u8], padded: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u16, 0);
+    for &byte in bytes {
+        buffer = buffer << 8 | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(char::from(ALPHABET[usize::from(buffer >> bits & 0x1f)]));
+        }
+    }
+    if bits > 0 {
+        out.push(char::from(
+            ALPHABET[usize::from(buffer << (5 - bits) & 0x1f)],
+        ));
+    }
+    if padded {
+        while !out.len().is_multiple_of(8) {
+            out.push('=');
+        }
+    }
+    out
+}
+
+impl Digest {
+    /// Encodes the digest as RFC 4648 base32: uppercase, padded with `=`.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::Digest;
+    ///
+    /// let digest = Digest::from_bytes(b"foobar".to_vec());
+    /// assert_eq!(digest.to_base32(), "MZXW6YTBOI======");
+    /// assert_eq!(digest.to_base32_unpadded().to_lowercase(), "mzxw6ytboi");
+    /// ```
+    pub fn to_base32(&self) -> String {
+        encode(self.as_bytes(), true)
+    }
+
+    /// Like [`to_base32`](Digest::to_base32), without the `=` padding, as file names and
+    /// onion-style identifiers use it.
+    pub fn to_base32_unpadded(&self) -> String {
+        encode(self.as_bytes(), false)
+    }
+
+    /// Parses a base32 digest computed with `algorithm`, in either case and with or without
+    /// padding.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidDigest`] if `s` has the wrong length for `algorithm`, contains
+    /// a character outside the base32 alphabet, or encodes bits beyond the digest.
+    pub fn parse_base32(s: &str, algorithm: HashAlgorithm) -> Result<Self, SHAError> {
+        let len = algorithm.output_len();
+        let unpadded = s.trim_end_matches('=');
+        if unpadded.len() != encoded_len(len) {
+            return Err(SHAError::InvalidDigest(format!(
+                "a base32 {} digest has {} characters, found {}",
+                algorithm,
+                encoded_len(len),
+                unpadded.len()
+            )));
+        }
+        let mut bytes = Vec::with_capacity(len);
+        let (mut buffer, mut bits) = (0u16, 0);
+        for c in unpadded.bytes() {
+            let Some(digit) = ALPHABET
+                .iter()
+                .position(|&known| known == c.to_ascii_uppercase())
+            else {
+                return Err(SHAError::InvalidDigest(format!(
+                    "{:?} is not a base32 character",
+                    char::from(c)
+                )));
+            };
+            buffer = buffer << 5 | digit as u16;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+            }
+        }
+        if buffer & ((1 << bits) - 1) != 0 {
+            return Err(SHAError::InvalidDigest(format!(
+                "base32 digest {} has bits beyond its length",
+                s
+            )));
+        }
+        Ok(Digest::from_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+
+    #[test]
+    fn test_base32_rfc4648_vectors() {
+        for (input, expected) in [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ] {
+            let digest = Digest::from_bytes(input.as_bytes().to_vec());
+            assert_eq!(digest.to_base32(), expected);
+            assert_eq!(digest.to_base32_unpadded(), expected.trim_end_matches('='));
+        }
+    }
+
+    #[test]
+    fn test_parse_base32() {
+        for algorithm in HashAlgorithm::ALL.iter().copied() {
+            let digest = "test data".hash_with(algorithm).unwrap();
+            for encoded in [
+                digest.to_base32(),
+                digest.to_base32_unpadded().to_lowercase(),
+            ] {
+                assert_eq!(Digest::parse_base32(&encoded, algorithm).unwrap(), digest);
+            }
+        }
+
+        // The SHA-1 of "abc"; 32 characters hold 160 bits exactly
+        let sha1 = "VGMT4NSHA2AWVOR6EVYXQUGCNSONBWE5";
+        assert_eq!(
+            Digest::parse_base32(sha1, HashAlgorithm::Sha1).unwrap(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        let md5 = Digest::from_bytes(vec![0xff; 16]).to_base32_unpadded();
+        for invalid in [&sha1[1..], "VGMT4NSHA2AWVOR6EVYXQUGCNSONBWE1"] {
+            assert!(matches!(
+                Digest::parse_base32(invalid, HashAlgorithm::Sha1),
+                Err(SHAError::InvalidDigest(_))
+            ));
+        }
+        // 26 characters hold 130 bits, and the last two must be zero
+        let beyond = format!("{}7", &md5[..25]);
+        assert!(matches!(
+            Digest::parse_base32(&beyond, HashAlgorithm::Md5),
+            Err(SHAError::InvalidDigest(_))
+        ));
+        assert!(Digest::parse_base32(&md5, HashAlgorithm::Md5).is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+//! Bencoding, the serialization of BitTorrent metainfo files.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Nesting deeper than this is rejected rather than risking the stack on hostile input.
+const MAX_DEPTH: usize = 64;
+
+/// A bencoded value. Dictionaries keep their keys sorted, as encoding requires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Value {
+    /// Returns the value as a string of bytes.
+    pub(crate) fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an integer.
+    pub(crate) fn int(&self) -> Option<i64> {
+        match self {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a list.
+    pub(crate) fn list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a dictionary.
+    pub(crate) fn dict(&self) -> Option<&BTreeMap<Vec<u8>, Value>> {
+        match self {
+            Value::Dict(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Appends the encoding of the value to `out`.
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Int(n) => out.extend_from_slice(format!("i{}e", n).as_bytes()),
+            Value::Bytes(bytes) => encode_bytes(bytes, out),
+            Value::List(items) => {
+                out.push(b'l');
+                items.iter().for_each(|item| item.encode(out));
+                out.push(b'e');
+            }
+            Value::Dict(entries) => {
+                out.push(b'd');
+                for (key, value) in entries {
+                    encode_bytes(key, out);
+                    value.encode(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+/// Builds a dictionary value from `(key, value)` pairs.
+pub(crate) fn dict<'a>(entries: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+    let entries = entries.into_iter();
+    Value::Dict(entries.map(|(key, value)| (key.into(), value)).collect())
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// A top-level dictionary with the byte range each value was read from.
+pub(crate) type SpannedDict = BTreeMap<Vec<u8>, (Value, Range<usize>)>;
+
+/// Decodes a top-level dictionary, returning each value with the byte range it was read from.
+///
+/// The ranges let a torrent's infohash be taken over the `info` dictionary exactly as written,
+/// including keys this crate does not know.
+pub(crate) fn decode_dict(data: &[u8]) -> Result<SpannedDict, String> {
+    let mut decoder = Decoder { data, pos: 0 };
+    decoder.expect(b'd')?;
+    let mut entries = BTreeMap::new();
+    while decoder.peek()? != b'e' {
+        let key = decoder.bytes()?;
+        let start = decoder.pos;
+        let value = decoder.value(1)?;
+        entries.insert(key, (value, start..decoder.pos));
+    }
+    decoder.pos += 1;
+    if decoder.pos != data.len() {
+        return Err("trailing data after the dictionary".to_string());
+    }
+    Ok(entries)
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn peek(&self) -> Result<u8, String> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| "unexpected end of data".to_string())
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        match self.peek()? {
+            found if found == byte => {
+                self.pos += 1;
+                Ok(())
+            }
+            found => Err(format!(
+                "expected {:?} at byte {}, found {:?}",
+                byte as char, self.pos, found as char
+            )),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err("values are nested too deeply".to_string());
+        }
+        match self.peek()? {
+            b'i' => {
+                self.pos += 1;
+                let n = self.digits(b'e')?;
+                let n = n.parse().map_err(|_| format!("invalid integer {:?}", n))?;
+                Ok(Value::Int(n))
+            }
+            b'l' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.peek()? != b'e' {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::List(items))
+            }
+            b'd' => {
+                self.pos += 1;
+                let mut entries = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let key = self.bytes()?;
+                    entries.insert(key, self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::Dict(entries))
+            }
+            _ => self.bytes().map(Value::Bytes),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.digits(b':')?;
+        let len: usize = len
+            .parse()
+            .map_err(|_| format!("invalid string length {:?}", len))?;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "string runs past the end of data".to_string())?;
+        let bytes = self.data[self.pos..end].to_vec();
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Reads up to `terminator`, which is consumed.
+    fn digits(&mut self, terminator: u8) -> Result<String, String> {
+        let start = self.pos;
+        while self.peek()? != terminator {
+            self.pos += 1;
+        }
+        let digits = String::from_utf8_lossy(&self.data[start..self.pos]).into_owned();
+        self.pos += 1;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bencode_round_trip() {
+        let value = dict([
+            ("name", Value::Bytes(b"spam".to_vec())),
+            ("list", Value::List(vec![Value::Int(-3), Value::Int(42)])),
+        ]);
+        let mut encoded = Vec::new();
+        value.encode(&mut encoded);
+        assert_eq!(encoded, b"d4:listli-3ei42ee4:name4:spame");
+
+        let decoded = decode_dict(&encoded).unwrap();
+        let (name, range) = &decoded[b"name".as_slice()];
+        assert_eq!(name.bytes(), Some(b"spam".as_slice()));
+        assert_eq!(&encoded[range.clone()], b"4:spam");
+
+        for bad in [
+            &b"d4:name"[..],
+            b"d4:namei1x",
+            b"d9:name",
+            b"de extra",
+            b"l",
+        ] {
+            assert!(decode_dict(bad).is_err(), "{:?}", bad);
+        }
+        let deep = format!("d1:a{}{}e", "l".repeat(100), "e".repeat(100));
+        assert!(decode_dict(deep.as_bytes()).is_err());
+    }
+}
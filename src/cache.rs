@@ -0,0 +1,360 @@
+use crate::{Digest, DirOptions, FileStamp, HashAlgorithm, Manifest, SHAError};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::ops::Bound;
+use std::path::{self, Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// First line of a cache file. A file starting with anything else is treated as empty.
+const HEADER: &str = "sha_file_hashing cache v1";
+
+/// A digest remembered for one file and algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cached {
+    algorithm: HashAlgorithm,
+    stamp: FileStamp,
+    digest: Digest,
+}
+
+/// An on-disk record of file digests, so repeated directory hashing only reads files whose size
+/// or modification time changed.
+///
+/// Entries are keyed by absolute path and algorithm. [`DirOptions::hash_dir_cached`] reuses
+/// them and records what it hashed; nothing is written to disk until [`save`](HashCache::save).
+/// The cache is only an optimization, so a file that is missing, from another version or
+/// partly unreadable as text just starts out empty or loses the bad lines.
+///
+/// Like [`DirOptions::quick_check`], a file rewritten without changing its size or modification
+/// time keeps its old digest. Call [`invalidate`](HashCache::invalidate) to force a re-hash.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{DirOptions, HashCache};
+///
+/// let mut cache = HashCache::open(".hash-cache").unwrap();
+/// let manifest = DirOptions::new().hash_dir_cached("archive", &mut cache).unwrap();
+/// cache.save().unwrap();
+/// println!("{} files", manifest.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct HashCache {
+    path: PathBuf,
+    entries: BTreeMap<PathBuf, Vec<Cached>>,
+}
+
+impl HashCache {
+    /// Loads the cache stored at `path`, or starts an empty one if the file does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref().to_path_buf();
+        let mut cache = Self {
+            path,
+            entries: BTreeMap::new(),
+        };
+        let text = match fs::read_to_string(&cache.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => return Ok(cache),
+            Err(e) => return Err(SHAError::from(e).at_path(&cache.path)),
+        };
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Ok(cache);
+        }
+        for (path, cached) in lines.filter_map(parse_line) {
+            cache.insert(path, cached);
+        }
+        Ok(cache)
+    }
+
+    /// Writes the cache back to the file it was opened from.
+    ///
+    /// The file is replaced atomically, so an interrupted save leaves the previous cache intact.
+    /// Files whose path is not valid UTF-8 or contains a line break are not saved.
+    pub fn save(&self) -> Result<(), SHAError> {
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+        self.write(&temp)
+            .and_then(|()| fs::rename(&temp, &self.path))
+            .map_err(|e| SHAError::from(e).at_path(&self.path))
+    }
+
+    /// Returns the file the cache is stored in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of files with at least one cached digest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forgets the digests of the file at `path`, or of everything below it if it is a
+    /// directory, so they are hashed again next time.
+    pub fn invalidate(&mut self, path: impl AsRef<Path>) {
+        let Ok(path) = path::absolute(path.as_ref()) else {
+            return;
+        };
+        let stale: Vec<PathBuf> = self.under(&path).map(|(path, _)| path.clone()).collect();
+        for path in stale {
+            self.entries.remove(&path);
+        }
+    }
+
+    /// Forgets every cached digest.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Iterates over the entries for `root` and every path below it.
+    fn under<'a>(&'a self, root: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a Vec<Cached>)> {
+        // Paths order by component, so everything below `root` directly follows it
+        self.entries
+            .range::<Path, _>((Bound::Included(root), Bound::Unbounded))
+            .take_while(move |(path, _)| path.starts_with(root))
+    }
+
+    fn insert(&mut self, path: PathBuf, cached: Cached) {
+        let digests = self.entries.entry(path).or_default();
+        digests.retain(|known| known.algorithm != cached.algorithm);
+        digests.push(cached);
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", HEADER)?;
+        for (path, digests) in &self.entries {
+            let Some(name) = path.to_str().filter(|name| !name.contains(['\n', '\r'])) else {
+                continue;
+            };
+            for cached in digests {
+                let Ok(modified) = cached.stamp.modified.duration_since(UNIX_EPOCH) else {
+                    continue;
+                };
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    cached.algorithm,
+                    cached.stamp.len,
+                    modified.as_secs(),
+                    modified.subsec_nanos(),
+                    cached.digest,
+                    name
+                )?;
+            }
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()
+    }
+}
+
+/// Parses one `algorithm, len, seconds, nanoseconds, digest, path` line, tab separated.
+fn parse_line(line: &str) -> Option<(PathBuf, Cached)> {
+    let mut fields = line.splitn(6, '\t');
+    let algorithm: HashAlgorithm = fields.next()?.parse().ok()?;
+    let len = fields.next()?.parse().ok()?;
+    let secs = fields.next()?.parse().ok()?;
+    let nanos = fields.next()?.parse().ok()?;
+    let digest = Digest::parse(fields.next()?, algorithm).ok()?;
+    let path = PathBuf::from(fields.next()?);
+    let modified = SystemTime::UNIX_EPOCH.checked_add(Duration::new(secs, nanos))?;
+    let stamp = FileStamp { len, modified };
+    Some((
+        path,
+        Cached {
+            algorithm,
+            stamp,
+            digest,
+        },
+    ))
+}
+
+/// The cache being filled in by a checkpointed [`DirOptions::hash_dir_cached`].
+struct Checkpoint<'a> {
+    cache: &'a mut HashCache,
+    saved: Instant,
+    error: Option<SHAError>,
+}
+
+impl DirOptions {
+    /// Hashes every file under `root` like [`hash_dir`](DirOptions::hash_dir), reusing digests
+    /// from `cache` for files whose size and modification time are unchanged.
+    ///
+    /// Afterwards the cache holds exactly the files found under `root` for this algorithm:
+    /// digests of files that have since been deleted or filtered out are dropped. With
+    /// [`checkpoint_every`](DirOptions::checkpoint_every), each newly hashed file is added to
+    /// the cache as it is found and the cache is saved at that interval, so an interrupted run
+    /// can resume where the last save left off.
+    ///
+    /// # Errors
+    /// Fails like [`hash_dir`](DirOptions::hash_dir). If a checkpoint cannot be saved, hashing
+    /// still runs to the end and the first save error is returned instead of the manifest.
+    pub fn hash_dir_cached(
+        &self,
+        root: impl AsRef<Path>,
+        cache: &mut HashCache,
+    ) -> Result<Manifest, SHAError> {
+        let root = root.as_ref();
+        let root = path::absolute(root).map_err(|e| SHAError::from(e).at_path(root))?;
+        let algorithm = self.hasher_options().selected_algorithm();
+
+        let mut previous = Manifest::new(algorithm);
+        for (path, digests) in cache.under(&root) {
+            let relative = path.strip_prefix(&root).unwrap_or(path);
+            if let Some(cached) = digests.iter().find(|cached| cached.algorithm == algorithm) {
+                previous.insert_with_stamp(relative, cached.digest.clone(), Some(cached.stamp));
+            }
+        }
+        let manifest = match self.checkpoint_interval() {
+            None => self.hash_dir_reusing(&root, Some(&previous))?,
+            Some(interval) => {
+                let checkpoint = Mutex::new(Checkpoint {
+                    cache: &mut *cache,
+                    saved: Instant::now(),
+                    error: None,
+                });
+                let observe = |relative: &Path, stamp: Option<&FileStamp>, digest: &Digest| {
+                    let Some(&stamp) = stamp else {
+                        return;
+                    };
+                    let mut checkpoint = checkpoint.lock().unwrap_or_else(PoisonError::into_inner);
+                    let cached = Cached {
+                        algorithm,
+                        stamp,
+                        digest: digest.clone(),
+                    };
+                    checkpoint.cache.insert(root.join(relative), cached);
+                    if checkpoint.saved.elapsed() >= interval {
+                        let saved = checkpoint.cache.save();
+                        checkpoint.saved = Instant::now();
+                        if let Err(e) = saved {
+                            checkpoint.error.get_or_insert(e);
+                        }
+                    }
+                };
+                let manifest = self.hash_dir_observed(&root, Some(&previous), &observe)?;
+                let checkpoint = checkpoint
+                    .into_inner()
+                    .unwrap_or_else(PoisonError::into_inner);
+                if let Some(e) = checkpoint.error {
+                    return Err(e);
+                }
+                manifest
+            }
+        };
+
+        for (relative, _) in previous.iter() {
+            if let Some(digests) = cache.entries.get_mut(&root.join(relative)) {
+                digests.retain(|cached| cached.algorithm != algorithm);
+                if digests.is_empty() {
+                    cache.entries.remove(&root.join(relative));
+                }
+            }
+        }
+        for (relative, digest) in manifest.iter() {
+            if let Some(&stamp) = manifest.stamp(relative) {
+                let digest = digest.clone();
+                let cached = Cached {
+                    algorithm,
+                    stamp,
+                    digest,
+                };
+                cache.insert(root.join(relative), cached);
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hash_dir_cached_reuses_and_invalidates() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("tree");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), b"test data").unwrap();
+        fs::write(root.join("sub").join("b.txt"), b"Hello, World!").unwrap();
+        let cache_path = temp_dir.path().join("cache");
+
+        let options = DirOptions::new();
+        let mut cache = HashCache::open(&cache_path).unwrap();
+        assert!(cache.is_empty());
+        let first = options.hash_dir_cached(&root, &mut cache).unwrap();
+        assert_eq!(first, options.hash_dir(&root).unwrap());
+        assert_eq!(cache.len(), 2);
+        cache.save().unwrap();
+
+        // A rewrite that keeps the size and mtime is served from the reloaded cache
+        let a = root.join("a.txt");
+        let modified = fs::metadata(&a).unwrap().modified().unwrap();
+        fs::write(&a, b"TEST DATA").unwrap();
+        File::options()
+            .write(true)
+            .open(&a)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        fs::remove_file(root.join("sub").join("b.txt")).unwrap();
+
+        let mut cache = HashCache::open(&cache_path).unwrap();
+        assert_eq!(cache.len(), 2);
+        let cached = options.hash_dir_cached(&root, &mut cache).unwrap();
+        assert_eq!(cached.get("a.txt"), first.get("a.txt"));
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate(&root);
+        assert!(cache.is_empty());
+        let fresh = options.hash_dir_cached(&root, &mut cache).unwrap();
+        assert_eq!(
+            *fresh.get("a.txt").unwrap(),
+            crate::hash_file_from_path(&a).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_dir_cached_checkpoints() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("tree");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), b"test data").unwrap();
+        fs::write(root.join("sub").join("b.txt"), b"Hello, World!").unwrap();
+        let cache_path = temp_dir.path().join("cache");
+
+        // Saved as the walk goes, before the caller saves anything
+        for threads in [1, 2] {
+            let options = DirOptions::new()
+                .threads(threads)
+                .checkpoint_every(Duration::ZERO);
+            let mut cache = HashCache::open(&cache_path).unwrap();
+            let manifest = options.hash_dir_cached(&root, &mut cache).unwrap();
+            assert_eq!(manifest, options.hash_dir(&root).unwrap());
+            assert_eq!(HashCache::open(&cache_path).unwrap().len(), 2);
+            fs::remove_file(&cache_path).unwrap();
+        }
+
+        let options = DirOptions::new().checkpoint_every(Duration::ZERO);
+        let mut cache = HashCache::open(temp_dir.path().join("missing/cache")).unwrap();
+        assert!(options.hash_dir_cached(&root, &mut cache).is_err());
+    }
+
+    #[test]
+    fn test_cache_ignores_foreign_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache");
+        fs::write(&cache_path, b"not a cache\n").unwrap();
+        assert!(HashCache::open(&cache_path).unwrap().is_empty());
+
+        fs::write(&cache_path, format!("{}\nsha1\tbad line\n", HEADER)).unwrap();
+        assert!(HashCache::open(&cache_path).unwrap().is_empty());
+    }
+}
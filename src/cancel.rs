@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A handle for aborting a hash that is already running.
+///
+/// Attach a token with [`HasherOptions::cancel_token`](crate::HasherOptions::cancel_token) and
+/// call [`cancel`](CancelToken::cancel) from any thread, for example a GUI's cancel button or
+/// a server's request timeout. The token is checked between buffer reads, so the hash stops
+/// within one read and returns [`SHAError::Cancelled`](crate::SHAError::Cancelled).
+///
+/// Clones share the same flag. Once cancelled, a token stays cancelled.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::{CancelToken, HasherOptions, SHAError};
+/// use std::io::Cursor;
+///
+/// let token = CancelToken::new();
+/// let options = HasherOptions::new().cancel_token(token.clone());
+///
+/// token.cancel();
+/// let result = options.hash_reader(Cursor::new(vec![0u8; 1 << 20]));
+/// assert!(matches!(result, Err(SHAError::Cancelled)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of every hash using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` once [`cancel`](CancelToken::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Tokens are equal when they share the same flag.
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_state() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert_eq!(token, clone);
+        assert_ne!(token, CancelToken::new());
+    }
+}
@@ -0,0 +1,226 @@
+//! `Cargo.lock` checksums of registry crates, enabled by the `toml` feature.
+
+use crate::report::{FileOutcome, VerificationReport};
+use crate::{Digest, HashAlgorithm, HasherOptions, SHAError};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A registry package pinned by a `Cargo.lock`, with the SHA-256 of its `.crate` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedCrate {
+    /// The package name.
+    pub name: String,
+    /// The exact version.
+    pub version: String,
+    /// Where the package comes from, such as
+    /// `registry+https://github.com/rust-lang/crates.io-index`.
+    pub source: String,
+    /// The SHA-256 digest of the `.crate` tarball.
+    pub checksum: Digest,
+}
+
+impl LockedCrate {
+    /// Returns the name Cargo gives the downloaded tarball, `<name>-<version>.crate`.
+    pub fn file_name(&self) -> String {
+        format!("{}-{}.crate", self.name, self.version)
+    }
+}
+
+/// The registry packages of a `Cargo.lock` and their checksums.
+///
+/// Path and git dependencies have no checksum and are left out.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::CargoLock;
+///
+/// let lock = CargoLock::read("Cargo.lock").unwrap();
+/// let cache = "/home/me/.cargo/registry/cache/index.crates.io-1949cf8c6b5b557f";
+/// let report = lock.verify(cache);
+/// println!("{} of {} crates OK", report.passed(), report.len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoLock {
+    crates: Vec<LockedCrate>,
+}
+
+#[derive(Deserialize)]
+struct LockRepr {
+    #[serde(default)]
+    package: Vec<PackageRepr>,
+    #[serde(default)]
+    metadata: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Deserialize)]
+struct PackageRepr {
+    name: String,
+    version: String,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    checksum: Option<String>,
+}
+
+impl CargoLock {
+    /// Reads a `Cargo.lock` file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        text.parse()
+    }
+
+    /// Returns the registry packages in lockfile order.
+    pub fn crates(&self) -> &[LockedCrate] {
+        &self.crates
+    }
+
+    /// Checks the `<name>-<version>.crate` file of every package in `dir`, a registry cache
+    /// directory such as `~/.cargo/registry/cache/<registry>`.
+    ///
+    /// Outcomes are reported under the tarball file names. Cargo keeps a cache directory per
+    /// registry, so packages from another registry are reported as missing.
+    pub fn verify(&self, dir: impl AsRef<Path>) -> VerificationReport {
+        let dir = dir.as_ref();
+        let options = HasherOptions::new().algorithm(HashAlgorithm::Sha256);
+        let mut report = VerificationReport::default();
+        for package in &self.crates {
+            let file_name = package.file_name();
+            let outcome = match options.hash_file_from_path(dir.join(&file_name)) {
+                Ok(actual) if actual == package.checksum => FileOutcome::Ok,
+                Ok(actual) => FileOutcome::Mismatch {
+                    expected: package.checksum.clone(),
+                    actual,
+                },
+                Err(e) => FileOutcome::from_error(e),
+            };
+            report.push(file_name, outcome);
+        }
+        report
+    }
+}
+
+impl std::str::FromStr for CargoLock {
+    type Err = SHAError;
+
+    /// Parses a lockfile of any version, including version 1 files that keep checksums in a
+    /// `[metadata]` table.
+    ///
+    /// Fails with [`SHAError::InvalidManifest`] on malformed TOML or checksums.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |e: String| SHAError::InvalidManifest(e);
+        let repr: LockRepr = toml::from_str(s).map_err(|e| invalid(e.to_string()))?;
+        let mut crates = Vec::new();
+        for package in repr.package {
+            let Some(source) = package.source else {
+                continue;
+            };
+            // Version 1 lockfiles key checksums by `checksum <name> <version> (<source>)`
+            let key = format!("checksum {} {} ({})", package.name, package.version, source);
+            let checksum = match (package.checksum, repr.metadata.get(&key)) {
+                (Some(checksum), _) => checksum,
+                (None, Some(toml::Value::String(checksum))) => checksum.clone(),
+                _ => continue,
+            };
+            // Cargo writes a placeholder for checksums it could not obtain
+            if checksum == "<none>" {
+                continue;
+            }
+            let checksum = Digest::parse(&checksum, HashAlgorithm::Sha256)
+                .map_err(|e| invalid(format!("{} {}: {}", package.name, package.version, e)))?;
+            crates.push(LockedCrate {
+                name: package.name,
+                version: package.version,
+                source,
+                checksum,
+            });
+        }
+        Ok(Self { crates })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+    // SHA-256 of "abc"
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn test_cargo_lock_verify() {
+        let lock = format!(
+            r#"# This file is automatically @generated by Cargo.
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["abc", "gone"]
+
+[[package]]
+name = "abc"
+version = "1.0.0"
+source = "{source}"
+checksum = "{abc}"
+
+[[package]]
+name = "gone"
+version = "2.0.0"
+source = "{source}"
+checksum = "{abc}"
+
+[[package]]
+name = "forked"
+version = "0.3.0"
+source = "git+https://example.org/forked#0123456789abcdef"
+"#,
+            source = CRATES_IO,
+            abc = ABC
+        );
+        let lock: CargoLock = lock.parse().unwrap();
+        let names: Vec<_> = lock.crates().iter().map(LockedCrate::file_name).collect();
+        assert_eq!(names, ["abc-1.0.0.crate", "gone-2.0.0.crate"]);
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("abc-1.0.0.crate"), "abc").unwrap();
+        let report = lock.verify(temp_dir.path());
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.missing(), 1);
+
+        fs::write(temp_dir.path().join("gone-2.0.0.crate"), "abd").unwrap();
+        assert_eq!(lock.verify(temp_dir.path()).mismatched(), 1);
+    }
+
+    #[test]
+    fn test_cargo_lock_v1_metadata() {
+        let lock = format!(
+            r#"[[package]]
+name = "abc"
+version = "1.0.0"
+source = "{source}"
+
+[metadata]
+"checksum abc 1.0.0 ({source})" = "{abc}"
+"#,
+            source = CRATES_IO,
+            abc = ABC
+        );
+        let lock: CargoLock = lock.parse().unwrap();
+        assert_eq!(lock.crates().len(), 1);
+        assert_eq!(lock.crates()[0].checksum, ABC);
+
+        let bad = format!(
+            "[[package]]\nname = \"a\"\nversion = \"1\"\nsource = \"{}\"\nchecksum = \"abc\"\n",
+            CRATES_IO
+        );
+        assert!(matches!(
+            bad.parse::<CargoLock>(),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        assert!("[[package]".parse::<CargoLock>().is_err());
+    }
+}
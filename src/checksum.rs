@@ -0,0 +1,455 @@
+use crate::dir::portable_path;
+use crate::{Digest, HashAlgorithm, Manifest, SHAError};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// One file listed in a [`ChecksumFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumEntry {
+    /// The expected digest.
+    pub digest: Digest,
+    /// The file, exactly as written in the checksum file.
+    pub path: PathBuf,
+    /// Whether the file was hashed in binary mode, marked with `*` before the path. The digest
+    /// is the same either way on every platform this crate supports.
+    pub binary: bool,
+    /// The algorithm named by a BSD-style line. GNU-style lines do not name one.
+    pub algorithm: Option<HashAlgorithm>,
+}
+
+impl ChecksumEntry {
+    /// Creates a text-mode entry for `path`.
+    pub fn new(digest: Digest, path: impl Into<PathBuf>) -> Self {
+        Self {
+            digest,
+            path: path.into(),
+            binary: false,
+            algorithm: None,
+        }
+    }
+
+    /// Returns the algorithm to name in a BSD-style line: the recorded one, or else the
+    /// SHA-1, SHA-2 or MD5 algorithm with the digest's length, as `shasum` assumes.
+    fn tag(&self) -> Option<HashAlgorithm> {
+        const BY_LENGTH: [HashAlgorithm; 6] = [
+            HashAlgorithm::Md5,
+            HashAlgorithm::Sha1,
+            HashAlgorithm::Sha224,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Sha384,
+            HashAlgorithm::Sha512,
+        ];
+        self.algorithm.or_else(|| {
+            BY_LENGTH
+                .into_iter()
+                .find(|algorithm| algorithm.output_len() == self.digest.len())
+        })
+    }
+}
+
+/// The line layout a [`ChecksumFile`] is written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ChecksumFormat {
+    /// `<hex>  <path>`, as written by GNU `sha256sum`.
+    #[default]
+    Gnu,
+    /// `SHA256 (<path>) = <hex>`, as written by `shasum --tag`, `sha256sum --tag` and the BSD
+    /// and macOS `md5` and `sha256` tools.
+    Bsd,
+}
+
+/// A line of a [`ChecksumFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumLine {
+    /// A file and its digest.
+    Entry(ChecksumEntry),
+    /// A line starting with `#`, stored without the `#`.
+    Comment(String),
+}
+
+/// A checksum list in the format of GNU coreutils `sha1sum`, `sha256sum` and `md5sum`, or the
+/// BSD-style format of `shasum --tag` and macOS `md5`.
+///
+/// A GNU entry line is a hex digest, a space, a mode marker (a space for text mode or `*` for
+/// binary mode) and the file path. A BSD entry line is an algorithm tag, the path in
+/// parentheses, ` = ` and the hex digest. Both kinds may be mixed when parsing, and the file is
+/// written in the [`format`](ChecksumFile::format) of its first entry unless changed with
+/// [`set_format`](ChecksumFile::set_format). Comment lines start with `#` and are kept in
+/// place, so a file can be read, amended and written back. Blank lines are skipped.
+///
+/// Files written on any platform parse the same: a leading byte order mark is ignored and lines
+/// may end in `\r\n`. As in GNU coreutils, a line starting with `\` has an escaped path, in
+/// which `\\`, `\n` and `\r` stand for a backslash, a newline and a carriage return; paths
+/// containing any of those are written that way too.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{ChecksumFile, Hashable};
+/// use std::path::Path;
+///
+/// let sums = ChecksumFile::read("SHA1SUMS").unwrap();
+/// for entry in sums.entries() {
+///     let ok = entry.path.validate(entry.digest.to_hex()).unwrap_or(false);
+///     println!("{}: {}", entry.path.display(), if ok { "OK" } else { "FAILED" });
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChecksumFile {
+    lines: Vec<ChecksumLine>,
+    format: ChecksumFormat,
+}
+
+impl ChecksumFile {
+    /// Creates an empty checksum file.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads and parses the checksum file at `path`.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::File`] if the file cannot be read, or
+    /// [`SHAError::InvalidChecksumLine`] for the first line that is not an entry or a comment.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        text.parse()
+    }
+
+    /// Returns the format the file is written in.
+    pub fn format(&self) -> ChecksumFormat {
+        self.format
+    }
+
+    /// Sets the format the file is written in.
+    ///
+    /// A BSD-style line needs an algorithm tag. Entries without a recorded
+    /// [`algorithm`](ChecksumEntry::algorithm) are tagged by digest length as MD5, SHA-1 or
+    /// SHA-2, and entries of any other length are written GNU style instead.
+    pub fn set_format(&mut self, format: ChecksumFormat) {
+        self.format = format;
+    }
+
+    /// Returns every line, entries and comments, in file order.
+    pub fn lines(&self) -> &[ChecksumLine] {
+        &self.lines
+    }
+
+    /// Iterates over the entries, skipping comments.
+    pub fn entries(&self) -> impl Iterator<Item = &ChecksumEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            ChecksumLine::Entry(entry) => Some(entry),
+            ChecksumLine::Comment(_) => None,
+        })
+    }
+
+    /// Appends an entry.
+    pub fn push(&mut self, entry: ChecksumEntry) {
+        self.lines.push(ChecksumLine::Entry(entry));
+    }
+
+    /// Appends a comment line. `text` is written after a `#`.
+    pub fn push_comment(&mut self, text: impl Into<String>) {
+        self.lines.push(ChecksumLine::Comment(text.into()));
+    }
+
+    /// Writes the checksum file to `writer`, one `\n`-terminated line per entry or comment.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{}", self)
+    }
+}
+
+impl FromStr for ChecksumFile {
+    type Err = SHAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut file = ChecksumFile::new();
+        let mut first = true;
+        let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+        for (index, line) in s.lines().enumerate() {
+            if let Some(comment) = line.strip_prefix('#') {
+                file.push_comment(comment);
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (escaped, body) = match line.strip_prefix('\\') {
+                Some(body) => (true, body),
+                None => (false, line),
+            };
+            let (entry, format) = parse_gnu(body)
+                .map(|entry| (entry, ChecksumFormat::Gnu))
+                .or_else(|| parse_bsd(body).map(|entry| (entry, ChecksumFormat::Bsd)))
+                .and_then(|(mut entry, format)| {
+                    if escaped {
+                        entry.path = PathBuf::from(unescape(&entry.path.to_string_lossy())?);
+                    }
+                    Some((entry, format))
+                })
+                .ok_or_else(|| SHAError::InvalidChecksumLine {
+                    line: index + 1,
+                    text: line.to_string(),
+                })?;
+            if first {
+                file.format = format;
+                first = false;
+            }
+            file.push(entry);
+        }
+        Ok(file)
+    }
+}
+
+/// Parses `<hex> <marker><path>`, where the marker is a space or `*`.
+fn parse_gnu(line: &str) -> Option<ChecksumEntry> {
+    let (hex, rest) = line.split_once(' ')?;
+    let digest = hex.parse().ok()?;
+    let (binary, path) = match rest.as_bytes().first()? {
+        b' ' => (false, &rest[1..]),
+        b'*' => (true, &rest[1..]),
+        _ => return None,
+    };
+    if path.is_empty() {
+        return None;
+    }
+    Some(ChecksumEntry {
+        digest,
+        path: PathBuf::from(path),
+        binary,
+        algorithm: None,
+    })
+}
+
+/// Parses `<tag> (<path>) = <hex>`. An unknown tag leaves the algorithm unset.
+fn parse_bsd(line: &str) -> Option<ChecksumEntry> {
+    let (tag, rest) = line.split_once(" (")?;
+    let (path, hex) = rest.rsplit_once(") = ")?;
+    if tag.is_empty() || tag.contains(' ') || path.is_empty() {
+        return None;
+    }
+    let algorithm = tag.parse::<HashAlgorithm>().ok();
+    let digest = match algorithm {
+        Some(algorithm) => Digest::parse(hex, algorithm).ok()?,
+        None => hex.parse().ok()?,
+    };
+    Some(ChecksumEntry {
+        digest,
+        path: PathBuf::from(path),
+        binary: false,
+        algorithm,
+    })
+}
+
+/// Reverses the escaping of a GNU path: `\\`, `\n` and `\r` are the only escapes.
+fn unescape(path: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(unescaped)
+}
+
+impl fmt::Display for ChecksumFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                ChecksumLine::Entry(entry) => {
+                    let mut path = entry.path.to_string_lossy();
+                    if path.contains(['\\', '\n', '\r']) {
+                        let escaped = path
+                            .replace('\\', "\\\\")
+                            .replace('\n', "\\n")
+                            .replace('\r', "\\r");
+                        path = escaped.into();
+                        f.write_str("\\")?;
+                    }
+                    match (self.format, entry.tag()) {
+                        (ChecksumFormat::Bsd, Some(tag)) => writeln!(
+                            f,
+                            "{} ({}) = {}",
+                            tag.name().to_ascii_uppercase(),
+                            path,
+                            entry.digest
+                        )?,
+                        _ => {
+                            let marker = if entry.binary { '*' } else { ' ' };
+                            writeln!(f, "{} {}{}", entry.digest, marker, path)?
+                        }
+                    }
+                }
+                ChecksumLine::Comment(text) => writeln!(f, "#{}", text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<&Manifest> for ChecksumFile {
+    /// Lists every file in the manifest in path order, with `/`-separated relative paths.
+    fn from(manifest: &Manifest) -> Self {
+        let mut file = ChecksumFile::new();
+        for (path, digest) in manifest.iter() {
+            file.push(ChecksumEntry::new(digest.clone(), portable_path(path)));
+        }
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUMS: &str = "\
+# Release 1.0
+f48dd853820860816c75d54d0f584dc863327a7c  docs/readme.txt
+0a0a9f2a6772942557ab5355d76af442f8f65e01 *bin/app.exe
+
+da39a3ee5e6b4b0d3255bfef95601890afd80709  name with  spaces
+";
+
+    #[test]
+    fn test_parse_checksum_file() {
+        let file: ChecksumFile = SUMS.parse().unwrap();
+        assert_eq!(file.lines().len(), 4);
+        assert_eq!(
+            file.lines()[0],
+            ChecksumLine::Comment(" Release 1.0".to_string())
+        );
+
+        let entries: Vec<_> = file.entries().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].path, Path::new("docs/readme.txt"));
+        assert!(!entries[0].binary);
+        assert_eq!(
+            entries[0].digest,
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+        assert_eq!(entries[1].path, Path::new("bin/app.exe"));
+        assert!(entries[1].binary);
+        assert_eq!(entries[2].path, Path::new("name with  spaces"));
+    }
+
+    #[test]
+    fn test_checksum_file_round_trip() {
+        let file: ChecksumFile = SUMS.parse().unwrap();
+        let mut out = Vec::new();
+        file.write_to(&mut out).unwrap();
+        // Only the blank line is lost
+        assert_eq!(String::from_utf8(out).unwrap(), SUMS.replace("\n\n", "\n"));
+    }
+
+    #[test]
+    fn test_bsd_checksum_lines() {
+        let text = "\
+SHA1 (docs/readme.txt) = f48dd853820860816c75d54d0f584dc863327a7c
+MD5 (a (1).txt) = d41d8cd98f00b204e9800998ecf8427e
+";
+        let file: ChecksumFile = text.parse().unwrap();
+        assert_eq!(file.format(), ChecksumFormat::Bsd);
+        let entries: Vec<_> = file.entries().collect();
+        assert_eq!(entries[0].algorithm, Some(HashAlgorithm::Sha1));
+        assert_eq!(entries[0].path, Path::new("docs/readme.txt"));
+        assert_eq!(entries[1].algorithm, Some(HashAlgorithm::Md5));
+        assert_eq!(entries[1].path, Path::new("a (1).txt"));
+        assert_eq!(file.to_string(), text);
+
+        // A digest of the wrong length for the tag is rejected
+        assert!(
+            "SHA256 (x) = d41d8cd98f00b204e9800998ecf8427e"
+                .parse::<ChecksumFile>()
+                .is_err()
+        );
+
+        // GNU entries are tagged by length when converted
+        let mut file: ChecksumFile = SUMS.parse().unwrap();
+        assert_eq!(file.format(), ChecksumFormat::Gnu);
+        file.set_format(ChecksumFormat::Bsd);
+        assert!(
+            file.to_string()
+                .contains("SHA1 (bin/app.exe) = 0a0a9f2a6772942557ab5355d76af442f8f65e01\n")
+        );
+    }
+
+    #[test]
+    fn test_escaped_paths_and_line_endings() {
+        let digest = "da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        let text = format!(
+            "\u{feff}# Windows\r\n{d}  a.txt\r\n\\{d}  two\\nlines\\\\x\r\n\\SHA1 (c\\rr) = {d}\r\n",
+            d = digest
+        );
+        let file: ChecksumFile = text.parse().unwrap();
+        assert_eq!(
+            file.lines()[0],
+            ChecksumLine::Comment(" Windows".to_string())
+        );
+        let paths: Vec<_> = file.entries().map(|entry| entry.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("a.txt"),
+                PathBuf::from("two\nlines\\x"),
+                PathBuf::from("c\rr")
+            ]
+        );
+
+        // Escaped paths are written the way GNU sha1sum writes them
+        let mut file = ChecksumFile::new();
+        file.push(ChecksumEntry::new(digest.parse().unwrap(), "two\nlines\\x"));
+        file.push(ChecksumEntry::new(digest.parse().unwrap(), "dir\\file"));
+        let written = file.to_string();
+        assert_eq!(
+            written,
+            format!("\\{d}  two\\nlines\\\\x\n\\{d}  dir\\\\file\n", d = digest)
+        );
+        assert_eq!(written.parse::<ChecksumFile>().unwrap(), file);
+
+        // Unescaped lines keep their backslashes, and unknown escapes are rejected
+        let file: ChecksumFile = format!("{}  dir\\file", digest).parse().unwrap();
+        assert_eq!(file.entries().next().unwrap().path, Path::new("dir\\file"));
+        assert!(
+            format!("\\{}  bad\\t", digest)
+                .parse::<ChecksumFile>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_checksum_lines() {
+        for text in [
+            "xyz  file",
+            "abcd",
+            "abcd file",
+            "abcd  ",
+            "# ok\nabcd\tfile",
+        ] {
+            assert!(
+                matches!(
+                    text.parse::<ChecksumFile>(),
+                    Err(SHAError::InvalidChecksumLine { .. })
+                ),
+                "{:?}",
+                text
+            );
+        }
+        match "# ok\nbad".parse::<ChecksumFile>() {
+            Err(SHAError::InvalidChecksumLine { line, text }) => {
+                assert_eq!(line, 2);
+                assert_eq!(text, "bad");
+            }
+            other => panic!("Expected InvalidChecksumLine, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Checksums that cloud object stores report for uploaded data, so an upload can be checked
+//! against the store without downloading it again.
+
+use crate::{
+    Digest, HashAlgorithm, Hasher, HasherOptions, SHAError, hash_file_from_path_with_algorithm,
+    read_chunks,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// The leaf size of a Glacier tree hash.
+const GLACIER_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Computes the ETag Amazon S3 reports for the file at `path` uploaded in parts of
+/// `part_size` bytes: the MD5 of the parts' MD5 digests laid end to end, followed by `-` and
+/// the number of parts.
+///
+/// `part_size` must match the uploader's; the AWS CLI uses 8 MiB unless
+/// `multipart_chunksize` says otherwise. An empty file counts as one empty part. Objects
+/// uploaded with a single `PutObject` have the plain MD5 as their ETag instead, and objects
+/// encrypted with SSE-KMS have an ETag that is not derived from their contents at all.
+///
+/// # Errors
+/// Fails with an [`io::ErrorKind::InvalidInput`] error if `part_size` is zero.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::s3_etag;
+///
+/// let etag = s3_etag("backup.tar", 8 * 1024 * 1024).unwrap();
+/// println!("\"{}\"", etag);
+/// ```
+pub fn s3_etag(path: impl AsRef<Path>, part_size: u64) -> Result<String, SHAError> {
+    let path = path.as_ref();
+    let parts = hash_parts(path, part_size, HashAlgorithm::Md5)?;
+    let mut hasher = Hasher::new(HashAlgorithm::Md5);
+    parts.iter().for_each(|part| hasher.update(part.as_bytes()));
+    Ok(format!("{}-{}", hasher.finalize(), parts.len()))
+}
+
+/// Computes the SHA-256 tree hash of the file at `path`, as Amazon S3 Glacier expects in the
+/// `x-amz-sha256-tree-hash` header of an archive upload.
+///
+/// The file is split into 1 MiB chunks whose SHA-256 digests are hashed together in pairs,
+/// level by level, carrying an unpaired digest up to the next level unchanged. A file of at
+/// most 1 MiB has its plain SHA-256 as its tree hash.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::glacier_tree_hash;
+///
+/// let tree_hash = glacier_tree_hash("archive.tar").unwrap();
+/// println!("x-amz-sha256-tree-hash: {}", tree_hash);
+/// ```
+pub fn glacier_tree_hash(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    let mut level = hash_parts(path.as_ref(), GLACIER_CHUNK_SIZE, HashAlgorithm::Sha256)?;
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+                    hasher.update(left.as_bytes());
+                    hasher.update(right.as_bytes());
+                    hasher.finalize()
+                }
+                [single] => single.clone(),
+                _ => unreachable!("chunks(2) yields one or two digests"),
+            })
+            .collect();
+    }
+    Ok(level.pop().expect("a file has at least one part"))
+}
+
+/// Computes the `Content-MD5` of the file at `path`: its MD5 digest in base64, as Azure Blob
+/// Storage stores it for a blob and S3 checks it on upload.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::azure_content_md5;
+///
+/// let content_md5 = azure_content_md5("report.pdf").unwrap();
+/// println!("Content-MD5: {}", content_md5);
+/// ```
+pub fn azure_content_md5(path: impl AsRef<Path>) -> Result<String, SHAError> {
+    let path = path.as_ref();
+    let digest = hash_file_from_path_with_algorithm(path, HashAlgorithm::Md5)
+        .map_err(|e| e.at_path(path))?;
+    Ok(STANDARD.encode(digest.as_bytes()))
+}
+
+/// Computes the `x-ms-content-crc64` of the file at `path`, the checksum Azure Storage
+/// accepts in place of `Content-MD5` for block uploads.
+///
+/// Azure's CRC-64 is CRC-64/NVME ([`HashAlgorithm::Crc64Nvme`]), sent as the base64 of its
+/// little-endian bytes rather than the big-endian bytes a [`Digest`] holds.
+pub fn azure_content_crc64(path: impl AsRef<Path>) -> Result<String, SHAError> {
+    let path = path.as_ref();
+    let digest = hash_file_from_path_with_algorithm(path, HashAlgorithm::Crc64Nvme)
+        .map_err(|e| e.at_path(path))?;
+    let mut bytes = digest.into_bytes();
+    bytes.reverse();
+    Ok(STANDARD.encode(bytes))
+}
+
+/// Hashes the file at `path` in consecutive parts of `part_size` bytes, the last one possibly
+/// shorter. An empty file yields the digest of one empty part.
+fn hash_parts(
+    path: &Path,
+    part_size: u64,
+    algorithm: HashAlgorithm,
+) -> Result<Vec<Digest>, SHAError> {
+    if part_size == 0 {
+        let e = io::Error::new(io::ErrorKind::InvalidInput, "part size must not be zero");
+        return Err(SHAError::from(e).at_path(path));
+    }
+    let file = File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    let mut parts = Vec::new();
+    let mut hasher = Hasher::new(algorithm);
+    let mut filled = 0u64;
+    read_chunks(&HasherOptions::new(), file, |mut chunk| {
+        while !chunk.is_empty() {
+            let take = chunk.len().min((part_size - filled) as usize);
+            hasher.update(&chunk[..take]);
+            filled += take as u64;
+            if filled == part_size {
+                parts.push(hasher.finalize_reset());
+                filled = 0;
+            }
+            chunk = &chunk[take..];
+        }
+    })
+    .map_err(|e| e.at_path(path))?;
+    if filled > 0 || parts.is_empty() {
+        parts.push(hasher.finalize());
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_s3_etag() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("object");
+        fs::write(&path, "abcdefghij").unwrap();
+        // Computed independently with Python's hashlib
+        assert_eq!(
+            s3_etag(&path, 4).unwrap(),
+            "446feba4c1b5cc7ad93bf4d44a0e36ac-3"
+        );
+        assert_eq!(
+            s3_etag(&path, 8 * 1024 * 1024).unwrap(),
+            "65a9594be77c5d3a826f3e43195d1cf3-1"
+        );
+        assert_eq!(s3_etag(&path, 5).unwrap().rsplit_once('-').unwrap().1, "2");
+
+        fs::write(&path, "").unwrap();
+        assert_eq!(
+            s3_etag(&path, 4).unwrap(),
+            "59adb24ef3cdbe0297f05b395827453f-1"
+        );
+        assert!(matches!(s3_etag(&path, 0), Err(SHAError::File { .. })));
+    }
+
+    #[test]
+    fn test_glacier_tree_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("archive");
+        // Three chunks, so the last digest is carried up a level unpaired
+        let data: Vec<u8> = (0..5 * GLACIER_CHUNK_SIZE / 2)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        fs::write(&path, &data).unwrap();
+        // Computed independently with Python's hashlib
+        assert_eq!(
+            glacier_tree_hash(&path).unwrap(),
+            "9b2474f7359f1464535323bfcd3254903843f74cd482d4b35b8e039bcc88c5bf"
+        );
+
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            glacier_tree_hash(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        fs::write(&path, "").unwrap();
+        assert_eq!(
+            glacier_tree_hash(&path).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_azure_checksums() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("blob");
+        fs::write(&path, "123456789").unwrap();
+        assert_eq!(
+            azure_content_md5(&path).unwrap(),
+            "JfnnlDI7RTiF9RgfG2JNCw=="
+        );
+        // The CRC-64/NVME check value 0xae8b14860a799888, little-endian
+        assert_eq!(azure_content_crc64(&path).unwrap(), "iJh5CoYUi64=");
+        assert!(matches!(
+            azure_content_md5(temp_dir.path().join("missing")),
+            Err(SHAError::File { .. })
+        ));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::dir::{is_contained, portable_path};
+use crate::{Digest, HashAlgorithm, Manifest, SHAError};
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+impl Manifest {
+    /// Writes the manifest as CSV with a `path,size,<algorithm>` header and one row per file.
+    ///
+    /// Paths use `/` separators and are quoted when they contain a comma, quote or line break,
+    /// as spreadsheets expect. The size column is empty for entries hashed without a
+    /// [`FileStamp`](crate::FileStamp).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::DirOptions;
+    ///
+    /// let manifest = DirOptions::new().hash_dir("release").unwrap();
+    /// manifest.write_csv(std::fs::File::create("release.csv").unwrap()).unwrap();
+    /// ```
+    pub fn write_csv(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(self.to_csv().as_bytes())
+    }
+
+    /// Renders the manifest as CSV; see [`write_csv`](Manifest::write_csv).
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("path,size,{}\r\n", self.algorithm());
+        for (path, digest) in self.iter() {
+            let size = self.stamp(path).map(|stamp| stamp.len.to_string());
+            let _ = write!(
+                csv,
+                "{},{},{}\r\n",
+                quote(&portable_path(path)),
+                size.unwrap_or_default(),
+                digest
+            );
+        }
+        csv
+    }
+
+    /// Parses CSV written by [`to_csv`](Manifest::to_csv).
+    ///
+    /// The header's last column names the algorithm, and a leading byte order mark as saved by
+    /// spreadsheets is skipped. Sizes are checked to be numbers but not kept, since a size alone
+    /// does not make a [`FileStamp`](crate::FileStamp).
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] for a missing header, a row without three columns,
+    /// a digest of the wrong length or a path that is absolute or contains `..`, and
+    /// [`SHAError::UnsupportedAlgorithm`] if the header names an unknown algorithm.
+    pub fn from_csv(csv: &str) -> Result<Self, SHAError> {
+        let csv = csv.strip_prefix('\u{feff}').unwrap_or(csv);
+        let mut rows = Rows { rest: csv, line: 1 };
+        let header = rows.next().transpose()?.unwrap_or_default();
+        let algorithm = match header.as_slice() {
+            [path, size, algorithm] if path == "path" && size == "size" => {
+                algorithm.parse::<HashAlgorithm>()?
+            }
+            _ => return Err(invalid(1, "expected a path,size,<algorithm> header")),
+        };
+        let mut manifest = Manifest::new(algorithm);
+        loop {
+            let line = rows.line;
+            let Some(row) = rows.next().transpose()? else {
+                break;
+            };
+            match row.as_slice() {
+                [empty] if empty.is_empty() => {}
+                [path, size, digest] => {
+                    if !size.is_empty() && size.parse::<u64>().is_err() {
+                        return Err(invalid(line, "size is not a number"));
+                    }
+                    let path = PathBuf::from(path);
+                    if !is_contained(&path) {
+                        return Err(invalid(line, "path is outside the manifest root"));
+                    }
+                    let digest = Digest::parse(digest, algorithm)
+                        .map_err(|e| invalid(line, &e.to_string()))?;
+                    manifest.insert(path, digest);
+                }
+                _ => return Err(invalid(line, "expected three columns")),
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+fn invalid(line: usize, message: &str) -> SHAError {
+    SHAError::InvalidManifest(format!("CSV line {}: {}", line, message))
+}
+
+/// Quotes a field if it would otherwise be split or misread.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits RFC 4180 CSV into rows of fields, tracking line numbers across quoted line breaks.
+struct Rows<'a> {
+    rest: &'a str,
+    line: usize,
+}
+
+impl Iterator for Rows<'_> {
+    type Item = Result<Vec<String>, SHAError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let start = self.line;
+        let mut fields = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = self.rest.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let field = fields.last_mut().expect("fields is never empty");
+            match c {
+                '"' if quoted => {
+                    if chars.next_if(|&(_, c)| c == '"').is_some() {
+                        field.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                '"' if field.is_empty() => quoted = true,
+                '\n' if quoted => {
+                    self.line += 1;
+                    field.push(c);
+                }
+                _ if quoted => field.push(c),
+                ',' => fields.push(String::new()),
+                '\n' => {
+                    self.rest = &self.rest[i + 1..];
+                    self.line += 1;
+                    return Some(Ok(fields));
+                }
+                '\r' if chars.peek().is_some_and(|&(_, c)| c == '\n') => {}
+                _ => field.push(c),
+            }
+        }
+        self.rest = "";
+        if quoted {
+            return Some(Err(invalid(start, "unterminated quoted field")));
+        }
+        Some(Ok(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DirOptions;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_manifest_csv_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), b"test data").unwrap();
+        fs::write(temp_dir.path().join("b, \"c\".txt"), b"Hello, World!").unwrap();
+
+        let manifest = DirOptions::new().hash_dir(temp_dir.path()).unwrap();
+        let csv = manifest.to_csv();
+        assert_eq!(
+            csv,
+            "path,size,sha1\r\n\
+             a.txt,9,f48dd853820860816c75d54d0f584dc863327a7c\r\n\
+             \"b, \"\"c\"\".txt\",13,0a0a9f2a6772942557ab5355d76af442f8f65e01\r\n"
+        );
+
+        let parsed = Manifest::from_csv(&csv).unwrap();
+        assert_eq!(parsed.algorithm(), HashAlgorithm::Sha1);
+        assert!(parsed.iter().eq(manifest.iter()));
+    }
+
+    #[test]
+    fn test_from_csv_errors() {
+        let md5 = "d41d8cd98f00b204e9800998ecf8427e";
+        let manifest = Manifest::from_csv(&format!("\u{feff}path,size,md5\n\"a\nb\",,{}\n\n", md5));
+        assert_eq!(manifest.unwrap().get("a\nb").unwrap().to_hex(), md5);
+
+        for (csv, line) in [
+            ("", 1),
+            ("name,digest\n", 1),
+            ("path,size,md5\na,1\n", 2),
+            ("path,size,md5\n\"a\nb\",x,00\n", 2),
+            ("path,size,md5\na,1,00ff\n", 2),
+            ("path,size,md5\n\"a,1,00ff\n", 2),
+            ("path,size,md5\n../a,,d41d8cd98f00b204e9800998ecf8427e\n", 2),
+            ("path,size,md5\n/a,,d41d8cd98f00b204e9800998ecf8427e\n", 2),
+        ] {
+            match Manifest::from_csv(csv) {
+                Err(SHAError::InvalidManifest(message)) => {
+                    assert!(
+                        message.starts_with(&format!("CSV line {}:", line)),
+                        "{}",
+                        message
+                    )
+                }
+                other => panic!("{:?} parsed as {:?}", csv, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,457 @@
+//! Checksums in Debian archive indexes: the `Release` file of a suite, which lists its
+//! `Packages` indexes, and the `Packages` indexes, which list the `.deb` files in the pool.
+
+use crate::report::{FileOutcome, VerificationReport};
+use crate::{Digest, HashAlgorithm, HasherOptions, SHAError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// The checksum fields of archive indexes, strongest first. `Release` files spell the MD5
+/// field `MD5Sum` and `Packages` files `MD5sum`; field names are case-insensitive.
+const HASH_FIELDS: [(&str, HashAlgorithm); 4] = [
+    ("sha512", HashAlgorithm::Sha512),
+    ("sha256", HashAlgorithm::Sha256),
+    ("sha1", HashAlgorithm::Sha1),
+    ("md5sum", HashAlgorithm::Md5),
+];
+
+/// A file listed in a Debian archive index, with the strongest checksum given for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebianFile {
+    /// The path, relative to the suite directory for a `Release` file and to the archive root
+    /// for a `Packages` file.
+    pub path: PathBuf,
+    /// The length in bytes.
+    pub size: u64,
+    /// The algorithm of [`digest`](Self::digest).
+    pub algorithm: HashAlgorithm,
+    /// The expected digest.
+    pub digest: Digest,
+}
+
+/// The `Release` (or signed `InRelease`) file of a suite, such as `dists/bookworm/Release`.
+///
+/// The OpenPGP signature of an `InRelease` file is skipped, not checked.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::DebianRelease;
+///
+/// let release = DebianRelease::read("/srv/mirror/debian/dists/bookworm/InRelease").unwrap();
+/// let report = release.verify("/srv/mirror/debian/dists/bookworm");
+/// println!("{} of {} indexes OK", report.passed(), report.len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebianRelease {
+    suite: Option<String>,
+    codename: Option<String>,
+    files: Vec<DebianFile>,
+}
+
+impl DebianRelease {
+    /// Reads a `Release` or `InRelease` file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        read_index(path.as_ref())
+    }
+
+    /// Returns the `Suite` field, such as `stable`.
+    pub fn suite(&self) -> Option<&str> {
+        self.suite.as_deref()
+    }
+
+    /// Returns the `Codename` field, such as `bookworm`.
+    pub fn codename(&self) -> Option<&str> {
+        self.codename.as_deref()
+    }
+
+    /// Returns the listed files, sorted by path.
+    pub fn files(&self) -> &[DebianFile] {
+        &self.files
+    }
+
+    /// Checks every listed file under `suite_dir`, the directory holding the `Release` file.
+    ///
+    /// `Release` files list every index the archive can produce, and mirrors commonly keep only
+    /// the compressed forms, so some files are usually reported as missing.
+    pub fn verify(&self, suite_dir: impl AsRef<Path>) -> VerificationReport {
+        verify_files(suite_dir.as_ref(), &self.files)
+    }
+}
+
+impl FromStr for DebianRelease {
+    type Err = SHAError;
+
+    /// Parses a `Release` file, or an `InRelease` file with its signature stripped.
+    ///
+    /// Fails with [`SHAError::InvalidManifest`] on malformed fields, checksums or paths.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let paragraphs = paragraphs(&strip_signature(s))?;
+        let Some(fields) = paragraphs.into_iter().next() else {
+            return Ok(Self::default());
+        };
+        // Each path is listed once per algorithm; keep the strongest
+        let mut files = BTreeMap::new();
+        for (field, algorithm) in HASH_FIELDS.iter().rev() {
+            let Some(value) = fields.get(*field) else {
+                continue;
+            };
+            for line in value.lines().filter(|line| !line.trim().is_empty()) {
+                let mut parts = line.split_whitespace();
+                let (Some(hex), Some(size), Some(path), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(invalid(format!("malformed {} line {:?}", field, line)));
+                };
+                let file = debian_file(path, size, *algorithm, hex)?;
+                files.insert(file.path.clone(), file);
+            }
+        }
+        Ok(Self {
+            suite: fields.get("suite").cloned(),
+            codename: fields.get("codename").cloned(),
+            files: files.into_values().collect(),
+        })
+    }
+}
+
+/// A binary package listed in a `Packages` index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebianPackage {
+    /// The `Package` field.
+    pub name: String,
+    /// The `Version` field.
+    pub version: String,
+    /// The `.deb` file, from the `Filename`, `Size` and checksum fields.
+    pub file: DebianFile,
+}
+
+/// An uncompressed `Packages` index, such as `dists/bookworm/main/binary-amd64/Packages`.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::DebianPackages;
+///
+/// let index = "/srv/mirror/debian/dists/bookworm/main/binary-amd64/Packages";
+/// let report = DebianPackages::read(index).unwrap().verify("/srv/mirror/debian");
+/// for (path, outcome) in report.iter().filter(|(_, outcome)| !outcome.is_ok()) {
+///     println!("{}: {:?}", path.display(), outcome);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebianPackages {
+    packages: Vec<DebianPackage>,
+}
+
+impl DebianPackages {
+    /// Reads a `Packages` file. Compressed indexes must be decompressed first.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        read_index(path.as_ref())
+    }
+
+    /// Returns the packages in index order.
+    pub fn packages(&self) -> &[DebianPackage] {
+        &self.packages
+    }
+
+    /// Checks the `.deb` of every package under `archive_root`, the directory holding `dists`
+    /// and `pool`.
+    pub fn verify(&self, archive_root: impl AsRef<Path>) -> VerificationReport {
+        let files: Vec<_> = self.packages.iter().map(|p| p.file.clone()).collect();
+        verify_files(archive_root.as_ref(), &files)
+    }
+}
+
+impl FromStr for DebianPackages {
+    type Err = SHAError;
+
+    /// Parses a `Packages` file.
+    ///
+    /// Fails with [`SHAError::InvalidManifest`] if a stanza lacks its `Filename`, `Size` or a
+    /// checksum, or has a malformed one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut packages = Vec::new();
+        for fields in paragraphs(s)? {
+            let field = |name: &str| {
+                fields
+                    .get(name)
+                    .ok_or_else(|| invalid(format!("a package has no {} field", name)))
+            };
+            let name = field("package")?.clone();
+            let (hash_field, algorithm) = HASH_FIELDS
+                .iter()
+                .find(|(hash_field, _)| fields.contains_key(*hash_field))
+                .ok_or_else(|| invalid(format!("package {} has no checksum", name)))?;
+            let file = debian_file(
+                field("filename")?,
+                field("size")?,
+                *algorithm,
+                &fields[*hash_field],
+            )?;
+            packages.push(DebianPackage {
+                version: field("version").cloned().unwrap_or_default(),
+                name,
+                file,
+            });
+        }
+        Ok(Self { packages })
+    }
+}
+
+fn invalid(message: String) -> SHAError {
+    SHAError::InvalidManifest(message)
+}
+
+fn read_index<T: FromStr<Err = SHAError>>(path: &Path) -> Result<T, SHAError> {
+    let text = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    text.parse()
+}
+
+fn debian_file(
+    path: &str,
+    size: &str,
+    algorithm: HashAlgorithm,
+    hex: &str,
+) -> Result<DebianFile, SHAError> {
+    let relative = PathBuf::from(path);
+    if path.is_empty()
+        || !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(invalid(format!("unsafe path {:?}", path)));
+    }
+    Ok(DebianFile {
+        size: size
+            .trim()
+            .parse()
+            .map_err(|_| invalid(format!("{}: bad size {:?}", path, size)))?,
+        algorithm,
+        digest: Digest::parse(hex.trim(), algorithm)
+            .map_err(|e| invalid(format!("{}: {}", path, e)))?,
+        path: relative,
+    })
+}
+
+fn verify_files(root: &Path, files: &[DebianFile]) -> VerificationReport {
+    let mut report = VerificationReport::default();
+    for file in files {
+        let outcome = match HasherOptions::new()
+            .algorithm(file.algorithm)
+            .hash_file_from_path(root.join(&file.path))
+        {
+            Ok(actual) if actual == file.digest => FileOutcome::Ok,
+            Ok(actual) => FileOutcome::Mismatch {
+                expected: file.digest.clone(),
+                actual,
+            },
+            Err(e) => FileOutcome::from_error(e),
+        };
+        report.push(&file.path, outcome);
+    }
+    report
+}
+
+/// Returns the signed text of a clearsigned `InRelease` file, or `text` itself if it is not
+/// clearsigned.
+fn strip_signature(text: &str) -> String {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim_end) != Some("-----BEGIN PGP SIGNED MESSAGE-----") {
+        return text.to_string();
+    }
+    // Skip the armor headers, such as `Hash: SHA512`, up to the blank line
+    lines
+        .by_ref()
+        .take_while(|line| !line.trim().is_empty())
+        .for_each(drop);
+    let mut signed = String::new();
+    for line in lines.take_while(|line| !line.starts_with("-----BEGIN PGP SIGNATURE-----")) {
+        signed.push_str(line.strip_prefix("- ").unwrap_or(line));
+        signed.push('\n');
+    }
+    signed
+}
+
+/// Splits deb822 `text` into paragraphs of fields keyed by lowercase name. Continuation lines
+/// are joined to their field's value with newlines.
+fn paragraphs(text: &str) -> Result<Vec<BTreeMap<String, String>>, SHAError> {
+    let mut paragraphs = Vec::new();
+    let mut fields = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                paragraphs.push(std::mem::take(&mut fields));
+            }
+            current = None;
+        } else if line.starts_with('#') {
+            continue;
+        } else if line.starts_with([' ', '\t']) {
+            let value: &mut String = current
+                .as_ref()
+                .and_then(|name| fields.get_mut(name))
+                .ok_or_else(|| invalid(format!("continuation line outside a field: {:?}", line)))?;
+            value.push('\n');
+            value.push_str(line.trim());
+        } else {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("malformed field {:?}", line)))?;
+            let name = name.trim().to_ascii_lowercase();
+            fields.insert(name.clone(), value.trim().to_string());
+            current = Some(name);
+        }
+    }
+    if !fields.is_empty() {
+        paragraphs.push(fields);
+    }
+    Ok(paragraphs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn release() -> String {
+        format!(
+            "Origin: Debian\n\
+             Suite: stable\n\
+             Codename: bookworm\n\
+             Components: main\n\
+             Description: Debian 12\n \
+             .\n \
+             Continued description\n\
+             MD5Sum:\n \
+             {md5}                3 main/binary-amd64/Packages\n \
+             {md5}                3 main/binary-amd64/Packages.xz\n\
+             SHA256:\n \
+             {sha256}        3 main/binary-amd64/Packages\n \
+             {sha256}        3 main/binary-amd64/Packages.xz\n \
+             {sha256}        3 main/i18n/Translation-en\n",
+            md5 = MD5,
+            sha256 = SHA256
+        )
+    }
+
+    #[test]
+    fn test_debian_release_verify() {
+        let release: DebianRelease = release().parse().unwrap();
+        assert_eq!(release.suite(), Some("stable"));
+        assert_eq!(release.codename(), Some("bookworm"));
+        assert_eq!(release.files().len(), 3);
+        assert!(
+            release
+                .files()
+                .iter()
+                .all(|file| file.algorithm == HashAlgorithm::Sha256 && file.size == 3)
+        );
+
+        let temp_dir = TempDir::new().unwrap();
+        let suite = temp_dir.path();
+        fs::create_dir_all(suite.join("main/binary-amd64")).unwrap();
+        fs::write(suite.join("main/binary-amd64/Packages"), "abc").unwrap();
+        fs::write(suite.join("main/binary-amd64/Packages.xz"), "abd").unwrap();
+        let report = release.verify(suite);
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.mismatched(), 1);
+        assert_eq!(report.missing(), 1);
+    }
+
+    #[test]
+    fn test_debian_inrelease_signature_stripped() {
+        let signed = format!(
+            "-----BEGIN PGP SIGNED MESSAGE-----\n\
+             Hash: SHA512\n\
+             \n\
+             {}\
+             -----BEGIN PGP SIGNATURE-----\n\
+             \n\
+             iQIzBAEBCgAdFiEE\n\
+             -----END PGP SIGNATURE-----\n",
+            release()
+        );
+        assert_eq!(
+            signed.parse::<DebianRelease>().unwrap(),
+            release().parse::<DebianRelease>().unwrap()
+        );
+        // Dash-escaped lines are unescaped
+        assert_eq!(
+            strip_signature("-----BEGIN PGP SIGNED MESSAGE-----\n\n- -x\nSuite: a\n"),
+            "-x\nSuite: a\n"
+        );
+    }
+
+    #[test]
+    fn test_debian_packages_verify() {
+        let index = format!(
+            "Package: abc\n\
+             Version: 1.0-1\n\
+             Filename: pool/main/a/abc/abc_1.0-1_amd64.deb\n\
+             Size: 3\n\
+             MD5sum: {md5}\n\
+             SHA256: {sha256}\n\
+             \n\
+             Package: old\n\
+             Version: 0.1\n\
+             Filename: pool/main/o/old/old_0.1_all.deb\n\
+             Size: 3\n\
+             SHA1: {sha1}\n",
+            md5 = MD5,
+            sha1 = SHA1,
+            sha256 = SHA256
+        );
+        let packages: DebianPackages = index.parse().unwrap();
+        assert_eq!(packages.packages().len(), 2);
+        assert_eq!(packages.packages()[0].name, "abc");
+        assert_eq!(packages.packages()[0].version, "1.0-1");
+        assert_eq!(packages.packages()[0].file.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(packages.packages()[1].file.algorithm, HashAlgorithm::Sha1);
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("pool/main/a/abc")).unwrap();
+        fs::write(root.join("pool/main/a/abc/abc_1.0-1_amd64.deb"), "abc").unwrap();
+        let report = packages.verify(root);
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.missing(), 1);
+    }
+
+    #[test]
+    fn test_debian_index_errors() {
+        for bad in [
+            "Package: a\nFilename: pool/a.deb\nSize: 3\n".to_string(),
+            format!("Package: a\nFilename: ../a.deb\nSize: 3\nSHA1: {}\n", SHA1),
+            format!(
+                "Package: a\nFilename: pool/a.deb\nSize: x\nSHA1: {}\n",
+                SHA1
+            ),
+            format!("Package: a\nSize: 3\nSHA1: {}\n", SHA1),
+            "Package: a\nFilename: pool/a.deb\nSize: 3\nSHA1: a999\n".to_string(),
+            " orphan continuation\n".to_string(),
+        ] {
+            assert!(
+                matches!(
+                    bad.parse::<DebianPackages>(),
+                    Err(SHAError::InvalidManifest(_))
+                ),
+                "{}",
+                bad
+            );
+        }
+        assert!(
+            "SHA256:\n abc main/Packages\n"
+                .parse::<DebianRelease>()
+                .is_err()
+        );
+        assert_eq!(
+            "".parse::<DebianRelease>().unwrap(),
+            DebianRelease::default()
+        );
+    }
+}
@@ -0,0 +1,1603 @@
+use crate::{Digest, HashAlgorithm, Hasher, HasherOptions, SHAError, SymlinkPolicy};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Name of the crate-specific ignore file, using `.gitignore` syntax.
+pub const HASHIGNORE_FILE: &str = ".hashignore";
+
+/// The digests of every file under a directory, keyed by path relative to that directory.
+///
+/// Entries are kept sorted by path, so iterating a manifest is deterministic regardless of the
+/// order the filesystem listed the files in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    algorithm: HashAlgorithm,
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+/// One file in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    digest: Digest,
+    stamp: Option<FileStamp>,
+}
+
+/// The size and modification time of a file, recorded when it was hashed.
+///
+/// [`DirOptions::hash_dir`] stores a stamp with every regular file, so a later
+/// [`quick_check`](DirOptions::quick_check) can tell which files may have changed without
+/// reading them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileStamp {
+    /// The file length in bytes.
+    pub len: u64,
+    /// The last modification time.
+    pub modified: SystemTime,
+}
+
+impl FileStamp {
+    /// Reads the stamp of the file at `path`, following symbolic links.
+    ///
+    /// Returns `None` if the platform does not report modification times.
+    pub fn of(path: impl AsRef<Path>) -> io::Result<Option<FileStamp>> {
+        Ok(Self::from_metadata(&fs::metadata(path)?))
+    }
+
+    /// Builds a stamp from metadata already in hand.
+    pub fn from_metadata(metadata: &fs::Metadata) -> Option<FileStamp> {
+        Some(FileStamp {
+            len: metadata.len(),
+            modified: metadata.modified().ok()?,
+        })
+    }
+}
+
+impl Manifest {
+    /// Creates an empty manifest of `algorithm` digests.
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the algorithm every digest in this manifest was computed with.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the digest recorded for the relative `path`.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&Digest> {
+        self.entries.get(path.as_ref()).map(|entry| &entry.digest)
+    }
+
+    /// Returns the size and modification time recorded for the relative `path`, if any.
+    pub fn stamp(&self, path: impl AsRef<Path>) -> Option<&FileStamp> {
+        self.entries.get(path.as_ref())?.stamp.as_ref()
+    }
+
+    /// Records `digest` for the relative `path`, returning the digest it replaces.
+    pub fn insert(&mut self, path: impl Into<PathBuf>, digest: Digest) -> Option<Digest> {
+        self.insert_with_stamp(path, digest, None)
+    }
+
+    /// Records `digest` along with the file's size and modification time.
+    pub fn insert_with_stamp(
+        &mut self,
+        path: impl Into<PathBuf>,
+        digest: Digest,
+        stamp: Option<FileStamp>,
+    ) -> Option<Digest> {
+        let entry = Entry { digest, stamp };
+        Some(self.entries.insert(path.into(), entry)?.digest)
+    }
+
+    /// Removes the entry for `path`, returning its digest.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<Digest> {
+        Some(self.entries.remove(path.as_ref())?.digest)
+    }
+
+    /// Returns the number of files in the manifest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the manifest has no files.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the entries in path order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &Digest)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), &entry.digest))
+    }
+
+    /// Combines every entry into one digest identifying the whole tree.
+    ///
+    /// For each entry in path order, the relative path with `/` separators, a NUL byte and the
+    /// raw file digest are fed to a fresh hasher of the manifest's algorithm. The result only
+    /// depends on paths and contents, not on timestamps, permissions or the platform, so two
+    /// deployments can be compared with a single value. Empty directories do not contribute.
+    pub fn tree_digest(&self) -> Digest {
+        let mut hasher = Hasher::new(self.algorithm);
+        for (path, digest) in self.iter() {
+            hasher.update(portable_path(path));
+            hasher.update([0]);
+            hasher.update(digest.as_bytes());
+        }
+        hasher.finalize()
+    }
+
+    /// Re-hashes only the files under `base_dir` that were added or whose size or modification
+    /// time changed, and drops the entries of deleted files, returning what changed.
+    ///
+    /// This keeps a manifest of a large archive current without reading every file again. Use
+    /// [`DirOptions::update`] to filter the walk the way the manifest was first built.
+    ///
+    /// # Errors
+    /// Fails like [`hash_dir`](DirOptions::hash_dir), leaving the manifest unchanged.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::{DirOptions, hash_dir};
+    ///
+    /// let mut manifest = hash_dir("archive", &DirOptions::new()).unwrap();
+    /// // ... later, after files were added and edited
+    /// let diff = manifest.update("archive").unwrap();
+    /// println!("{} new, {} changed", diff.added().len(), diff.modified().len());
+    /// ```
+    pub fn update(&mut self, base_dir: impl AsRef<Path>) -> Result<DirDiff, SHAError> {
+        DirOptions::new().update(base_dir, self)
+    }
+
+    /// Compares this manifest, taken as the old state, with `other` as the new one, keeping the
+    /// digests on both sides.
+    ///
+    /// Nothing is re-hashed, so two releases can be audited from their manifests alone.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if the manifests use different algorithms, since
+    /// every shared file would then look modified.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{HashAlgorithm, Hashable, Manifest};
+    ///
+    /// let mut old = Manifest::new(HashAlgorithm::Sha1);
+    /// old.insert("app.bin", "1.0".hash().unwrap());
+    /// old.insert("README", "docs".hash().unwrap());
+    /// let mut new = old.clone();
+    /// new.insert("app.bin", "1.1".hash().unwrap());
+    ///
+    /// let diff = old.diff(&new).unwrap();
+    /// assert_eq!(diff.modified()[0].path, std::path::Path::new("app.bin"));
+    /// assert_eq!(diff.modified()[0].old, "1.0".hash().unwrap());
+    /// ```
+    pub fn diff(&self, other: &Manifest) -> Result<DirDiff, SHAError> {
+        if self.algorithm() != other.algorithm() {
+            return Err(SHAError::InvalidManifest(format!(
+                "cannot compare a {} manifest with a {} one",
+                self.algorithm(),
+                other.algorithm()
+            )));
+        }
+        let mut diff = DirDiff::default();
+        for (path, old) in self.iter() {
+            match other.get(path) {
+                None => diff.removed.push((path.to_path_buf(), old.clone())),
+                Some(new) if new != old => diff.modified.push(DigestChange {
+                    path: path.to_path_buf(),
+                    old: old.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.added = other
+            .iter()
+            .filter(|(path, _)| self.get(path).is_none())
+            .map(|(path, digest)| (path.to_path_buf(), digest.clone()))
+            .collect();
+        Ok(diff)
+    }
+}
+
+/// The files that differ between two directory trees, each list in path order.
+///
+/// Returned by [`diff_dirs`] and [`Manifest::diff`]. Paths are relative to the roots compared,
+/// and each change keeps the digests involved. The [`Display`](fmt::Display) form lists one
+/// change per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirDiff {
+    added: Vec<(PathBuf, Digest)>,
+    removed: Vec<(PathBuf, Digest)>,
+    modified: Vec<DigestChange>,
+    stamp_changed: Vec<PathBuf>,
+}
+
+/// A file whose digest differs between two trees or manifests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestChange {
+    /// The path relative to the root compared.
+    pub path: PathBuf,
+    /// The digest on the old side.
+    pub old: Digest,
+    /// The digest on the new side.
+    pub new: Digest,
+}
+
+impl DirDiff {
+    /// Returns the files only present in the new tree, with their digests.
+    pub fn added(&self) -> &[(PathBuf, Digest)] {
+        &self.added
+    }
+
+    /// Returns the files only present in the old tree, with the digests they had.
+    pub fn removed(&self) -> &[(PathBuf, Digest)] {
+        &self.removed
+    }
+
+    /// Returns the files present in both trees with different contents.
+    pub fn modified(&self) -> &[DigestChange] {
+        &self.modified
+    }
+
+    /// Returns the files with unchanged contents whose size or modification time differs from
+    /// the manifest. Only [`DirOptions::verify`] with
+    /// [`check_stamps`](DirOptions::check_stamps) fills this in.
+    pub fn stamp_changed(&self) -> &[PathBuf] {
+        &self.stamp_changed
+    }
+
+    /// Returns `true` if both trees hold the same files with the same contents, and with the
+    /// same stamps when those were checked.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.stamp_changed.is_empty()
+    }
+}
+
+/// Writes one line per change, in path order: `+ <digest>  <path>` for added files,
+/// `- <digest>  <path>` for removed ones, `~ <old> -> <new>  <path>` for modified ones and
+/// `= <path>` for files whose stamp alone changed.
+impl fmt::Display for DirDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines: Vec<(&Path, String)> = Vec::new();
+        lines.extend(
+            self.added
+                .iter()
+                .map(|(path, digest)| (path.as_path(), format!("+ {}  ", digest))),
+        );
+        lines.extend(
+            self.removed
+                .iter()
+                .map(|(path, digest)| (path.as_path(), format!("- {}  ", digest))),
+        );
+        lines.extend(self.modified.iter().map(|change| {
+            let line = format!("~ {} -> {}  ", change.old, change.new);
+            (change.path.as_path(), line)
+        }));
+        lines.extend(
+            self.stamp_changed
+                .iter()
+                .map(|path| (path.as_path(), "= ".to_string())),
+        );
+        lines.sort_by(|a, b| a.0.cmp(b.0));
+        for (path, line) in lines {
+            writeln!(f, "{}{}", line, portable_path(path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether `path` stays under the directory it is joined to: relative, with no `.` or
+/// `..` components.
+pub(crate) fn is_contained(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Renders a path with `/` separators on every platform.
+///
+/// A rooted path keeps a single leading `/`, after any Windows prefix such as `C:`.
+pub(crate) fn portable_path(path: &Path) -> String {
+    let mut portable = String::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) => portable.push_str(&prefix.as_os_str().to_string_lossy()),
+            Component::RootDir => portable.push('/'),
+            other => {
+                if !portable.is_empty() && !portable.ends_with('/') {
+                    portable.push('/');
+                }
+                portable.push_str(&other.as_os_str().to_string_lossy());
+            }
+        }
+    }
+    portable
+}
+
+/// Configuration for walking and hashing a directory tree.
+///
+/// Every regular file below the root is hashed with the [`HasherOptions`] given to
+/// [`hasher`](DirOptions::hasher). Symbolic links are skipped unless
+/// [`symlinks`](DirOptions::symlinks) says otherwise.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{DirOptions, HashAlgorithm, HasherOptions};
+///
+/// let options =
+///     DirOptions::new().hasher(HasherOptions::new().algorithm(HashAlgorithm::Sha256));
+/// let manifest = options.hash_dir("release").unwrap();
+/// for (path, digest) in manifest.iter() {
+///     println!("{}  {}", digest, path.display());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirOptions {
+    hasher: HasherOptions,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    git_ignore: bool,
+    hash_ignore: bool,
+    symlinks: SymlinkPolicy,
+    hidden: bool,
+    threads: usize,
+    quick_check: bool,
+    check_stamps: bool,
+    checkpoint: Option<Duration>,
+}
+
+impl Default for DirOptions {
+    fn default() -> Self {
+        Self {
+            hasher: HasherOptions::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            git_ignore: false,
+            hash_ignore: true,
+            symlinks: SymlinkPolicy::Skip,
+            hidden: true,
+            threads: 1,
+            quick_check: false,
+            check_stamps: false,
+            checkpoint: None,
+        }
+    }
+}
+
+impl DirOptions {
+    /// Creates options hashing every file with SHA-1.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how each file is hashed.
+    pub fn hasher(mut self, hasher: HasherOptions) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Returns the options each file is hashed with.
+    pub fn hasher_options(&self) -> &HasherOptions {
+        &self.hasher
+    }
+
+    /// Only hashes files whose relative path matches the glob `pattern`.
+    ///
+    /// Patterns are matched against `/`-separated paths relative to the root. `*` does not
+    /// cross directories, so `*.jar` only matches the top level and `**/*.jar` matches at any
+    /// depth. Once any include pattern is set, files matching none of them are skipped.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Skips files and directories whose relative path matches the glob `pattern`.
+    ///
+    /// A directory matching `pattern`, such as `target` for the pattern `target/**`, is not
+    /// walked at all. Exclusions win over [`include`](Self::include).
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Adds `pattern` as an include, or as an exclude when it starts with `!`.
+    ///
+    /// This accepts lists like `["**/*.jar", "!target/**"]` straight from a config file.
+    pub fn filter(self, pattern: impl AsRef<str>) -> Self {
+        let pattern = pattern.as_ref();
+        match pattern.strip_prefix('!') {
+            Some(excluded) => self.exclude(excluded),
+            None => self.include(pattern),
+        }
+    }
+
+    /// Skips files ignored by `.gitignore` files in the tree, and the `.git` directory itself.
+    ///
+    /// Off by default. Each `.gitignore` applies to its own directory and everything below,
+    /// with deeper files taking precedence, as in git. Global and `.git/info/exclude` rules are
+    /// not read, so the result does not depend on the machine doing the hashing.
+    pub fn git_ignore(mut self, enabled: bool) -> Self {
+        self.git_ignore = enabled;
+        self
+    }
+
+    /// Skips files ignored by [`.hashignore`](HASHIGNORE_FILE) files in the tree.
+    ///
+    /// On by default. `.hashignore` uses `.gitignore` syntax and takes precedence over a
+    /// `.gitignore` in the same directory, so it can both add exclusions and re-include files
+    /// git ignores.
+    pub fn hash_ignore(mut self, enabled: bool) -> Self {
+        self.hash_ignore = enabled;
+        self
+    }
+
+    /// Sets how symbolic links found during the walk are handled. Links are skipped by default.
+    ///
+    /// This replaces the [`HasherOptions::symlinks`] setting for everything below the root.
+    /// With [`SymlinkPolicy::Follow`], a link to a directory is walked as if it were one, and a
+    /// link back into a directory currently being walked fails with
+    /// [`SHAError::SymlinkLoop`] instead of recursing forever. With
+    /// [`SymlinkPolicy::HashTargetPath`], the manifest records the digest of each link's target
+    /// path, for links to files and directories alike.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Sets whether hidden files and directories are hashed. On by default.
+    ///
+    /// An entry is hidden if its name starts with `.`. On Windows only, entries with the hidden
+    /// or system attribute are also hidden, so with `hidden(false)` the same tree can give a
+    /// different manifest on Windows than on Unix. Keep the default for manifests that must
+    /// match across platforms. Ignore files are still read when hidden entries are left out.
+    pub fn hidden(mut self, enabled: bool) -> Self {
+        self.hidden = enabled;
+        self
+    }
+
+    /// Sets how many threads walk and hash the tree. A `threads` of zero uses one per CPU.
+    ///
+    /// The default of one lists the whole tree, then hashes each file in turn. With more
+    /// threads, listing directories and hashing files are tasks taken from one shared queue, so
+    /// enumerating a large tree on a slow network share overlaps with hashing what has been
+    /// found so far. The manifest is the same either way.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Makes [`verify`](DirOptions::verify) trust files whose size and modification time match
+    /// the stamp stored in the manifest, instead of hashing them again. Off by default.
+    ///
+    /// Re-verifying a large tree where few files changed then only reads the changed ones. The
+    /// trade-off is that a file rewritten without changing its size or modification time, such
+    /// as by tampering that restores the timestamp, is not detected.
+    pub fn quick_check(mut self, enabled: bool) -> Self {
+        self.quick_check = enabled;
+        self
+    }
+
+    /// Makes [`verify`](DirOptions::verify) also compare each file's size and modification time
+    /// with the [`FileStamp`] recorded in the manifest.
+    ///
+    /// Files whose contents match but whose stamp does not are listed in
+    /// [`DirDiff::stamp_changed`], so a file touched or restored from elsewhere fails the check.
+    /// Entries recorded without a stamp are not affected. Off by default, since copying a tree
+    /// without preserving timestamps changes every stamp.
+    pub fn check_stamps(mut self, enabled: bool) -> Self {
+        self.check_stamps = enabled;
+        self
+    }
+
+    /// Makes [`hash_dir_cached`](DirOptions::hash_dir_cached) save the cache whenever
+    /// `interval` has passed since the last save, not only when the caller does.
+    ///
+    /// If a long run is interrupted, running it again with the same cache file skips every file
+    /// hashed before the last checkpoint whose size and modification time are unchanged.
+    pub fn checkpoint_every(mut self, interval: Duration) -> Self {
+        self.checkpoint = Some(interval);
+        self
+    }
+
+    /// Returns the interval set with [`checkpoint_every`](DirOptions::checkpoint_every).
+    pub(crate) fn checkpoint_interval(&self) -> Option<Duration> {
+        self.checkpoint
+    }
+
+    /// Hashes every file under `root` into a [`Manifest`] of relative paths.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidPattern`] if a filter is not a valid glob. Otherwise stops at
+    /// the first directory or file that cannot be read, returning [`SHAError::File`] with its
+    /// path. With more than one [`thread`](DirOptions::threads), which failure is reported
+    /// first can vary between runs.
+    pub fn hash_dir(&self, root: impl AsRef<Path>) -> Result<Manifest, SHAError> {
+        self.hash_dir_reusing(root.as_ref(), None)
+    }
+
+    /// Checks the tree under `root` against `expected`, returning what changed going from the
+    /// manifest to the tree. An empty [`DirDiff`] means the tree matches.
+    ///
+    /// Files are hashed with the manifest's algorithm, whatever the hasher is set to. With
+    /// [`quick_check`](DirOptions::quick_check), files whose stamp is unchanged are not read.
+    ///
+    /// # Errors
+    /// Fails like [`hash_dir`](DirOptions::hash_dir) if the tree cannot be walked or read.
+    pub fn verify(&self, root: impl AsRef<Path>, expected: &Manifest) -> Result<DirDiff, SHAError> {
+        let mut options = self.clone();
+        options.hasher = options.hasher.algorithm(expected.algorithm());
+        let previous = self.quick_check.then_some(expected);
+        let actual = options.hash_dir_reusing(root.as_ref(), previous)?;
+        let mut diff = expected.diff(&actual)?;
+        if self.check_stamps {
+            diff.stamp_changed = expected
+                .entries
+                .iter()
+                .filter(|(path, entry)| {
+                    entry.stamp.is_some()
+                        && actual.entries.get(*path).is_some_and(|found| {
+                            found.digest == entry.digest && found.stamp != entry.stamp
+                        })
+                })
+                .map(|(path, _)| path.clone())
+                .collect();
+        }
+        Ok(diff)
+    }
+
+    /// Brings `manifest` up to date with the tree under `root`, returning what changed.
+    ///
+    /// Files whose size and modification time match the manifest keep their digest without
+    /// being read, whether or not [`quick_check`](DirOptions::quick_check) is set; new or
+    /// changed files are hashed with the manifest's algorithm, and entries for files no longer
+    /// in the tree are dropped. Entries without a [`FileStamp`] are always re-hashed.
+    ///
+    /// # Errors
+    /// Fails like [`hash_dir`](DirOptions::hash_dir), leaving `manifest` unchanged.
+    pub fn update(
+        &self,
+        root: impl AsRef<Path>,
+        manifest: &mut Manifest,
+    ) -> Result<DirDiff, SHAError> {
+        let mut options = self.clone();
+        options.hasher = options.hasher.algorithm(manifest.algorithm());
+        let updated = options.hash_dir_reusing(root.as_ref(), Some(manifest))?;
+        let diff = manifest.diff(&updated)?;
+        *manifest = updated;
+        Ok(diff)
+    }
+
+    /// Hashes `root`, taking the digest from `previous` for files whose stamp it matches.
+    pub(crate) fn hash_dir_reusing(
+        &self,
+        root: &Path,
+        previous: Option<&Manifest>,
+    ) -> Result<Manifest, SHAError> {
+        self.hash_dir_observed(root, previous, &|_, _, _| {})
+    }
+
+    /// Like [`hash_dir_reusing`](DirOptions::hash_dir_reusing), passing each file's stamp and
+    /// digest to `observe` as soon as it is known, from whichever thread found it.
+    pub(crate) fn hash_dir_observed(
+        &self,
+        root: &Path,
+        previous: Option<&Manifest>,
+        observe: &(dyn Fn(&Path, Option<&FileStamp>, &Digest) + Sync),
+    ) -> Result<Manifest, SHAError> {
+        let mut manifest = Manifest::new(self.hasher.selected_algorithm());
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, usize::from),
+            n => n,
+        };
+        if threads > 1 {
+            self.hash_parallel(root, threads, previous, observe, &mut manifest)?;
+            return Ok(manifest);
+        }
+        for entry in self.walk(root)? {
+            let (relative, entry) = self.hash_entry(root, entry, previous)?;
+            observe(&relative, entry.stamp.as_ref(), &entry.digest);
+            manifest.entries.insert(relative, entry);
+        }
+        Ok(manifest)
+    }
+
+    /// Hashes the whole tree under `root` into a single digest.
+    ///
+    /// See [`Manifest::tree_digest`] for exactly what is hashed.
+    pub fn hash_tree(&self, root: impl AsRef<Path>) -> Result<Digest, SHAError> {
+        Ok(self.hash_dir(root)?.tree_digest())
+    }
+
+    /// Hashes the trees under `old` and `new` and reports which files were added, removed or
+    /// modified going from one to the other.
+    pub fn diff_dirs(
+        &self,
+        old: impl AsRef<Path>,
+        new: impl AsRef<Path>,
+    ) -> Result<DirDiff, SHAError> {
+        self.hash_dir(old)?.diff(&self.hash_dir(new)?)
+    }
+
+    /// Lists every file under `root` that passes the filters, by relative path.
+    fn walk(&self, root: &Path) -> Result<Vec<WalkEntry>, SHAError> {
+        let filter = Filter::new(&self.include, &self.exclude)?;
+        let mut files = Vec::new();
+        let mut pending = vec![self.root_dir(root)?];
+        while let Some(parent) = pending.pop() {
+            let (dirs, entries) = self.list(root, &filter, parent)?;
+            pending.extend(dirs);
+            files.extend(entries);
+        }
+        Ok(files)
+    }
+
+    /// Lists the relative path of every file under `root` that would be hashed, without
+    /// reading any of them.
+    pub(crate) fn list_files(&self, root: &Path) -> Result<Vec<PathBuf>, SHAError> {
+        let files = self.walk(root)?.into_iter().map(|entry| match entry {
+            WalkEntry::File(relative) | WalkEntry::Link(relative) => relative,
+        });
+        Ok(files.collect())
+    }
+
+    /// Walks and hashes `root` on `threads` threads sharing one queue of directories to list
+    /// and files to hash.
+    fn hash_parallel(
+        &self,
+        root: &Path,
+        threads: usize,
+        previous: Option<&Manifest>,
+        observe: &(dyn Fn(&Path, Option<&FileStamp>, &Digest) + Sync),
+        manifest: &mut Manifest,
+    ) -> Result<(), SHAError> {
+        let filter = Filter::new(&self.include, &self.exclude)?;
+        let queue = WorkQueue::new(Task::List(self.root_dir(root)?));
+        let digests = thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| scope.spawn(|| self.work(root, &filter, previous, observe, &queue)))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| match worker.join() {
+                    Ok(digests) => digests,
+                    Err(panic) => std::panic::resume_unwind(panic),
+                })
+                .collect::<Vec<_>>()
+        });
+        if let Some(e) = queue.into_error() {
+            return Err(e);
+        }
+        manifest.entries.extend(digests);
+        Ok(())
+    }
+
+    /// Runs tasks from `queue` until it is drained or a task fails, returning the digests of
+    /// the files this thread hashed.
+    fn work(
+        &self,
+        root: &Path,
+        filter: &Filter,
+        previous: Option<&Manifest>,
+        observe: &(dyn Fn(&Path, Option<&FileStamp>, &Digest) + Sync),
+        queue: &WorkQueue,
+    ) -> Vec<(PathBuf, Entry)> {
+        let mut digests = Vec::new();
+        while let Some((task, running)) = queue.take() {
+            let result = match task {
+                Task::List(dir) => self.list(root, filter, dir).map(|(dirs, files)| {
+                    // Files go on top, so hashing keeps pace with listing
+                    let mut tasks: Vec<Task> = dirs.into_iter().map(Task::List).collect();
+                    tasks.extend(files.into_iter().map(Task::Hash));
+                    tasks
+                }),
+                Task::Hash(entry) => self.hash_entry(root, entry, previous).map(|digest| {
+                    observe(&digest.0, digest.1.stamp.as_ref(), &digest.1.digest);
+                    digests.push(digest);
+                    Vec::new()
+                }),
+            };
+            running.finish(result);
+        }
+        digests
+    }
+
+    /// Returns the starting point of a walk of `root`.
+    fn root_dir(&self, root: &Path) -> Result<PendingDir, SHAError> {
+        Ok(PendingDir {
+            relative: PathBuf::new(),
+            rules: None,
+            ancestors: match self.symlinks {
+                SymlinkPolicy::Follow => Some(Ancestors::push(None, root)?),
+                _ => None,
+            },
+        })
+    }
+
+    /// Lists one directory, returning its subdirectories to walk and the files in it to hash.
+    fn list(
+        &self,
+        root: &Path,
+        filter: &Filter,
+        parent: PendingDir,
+    ) -> Result<(Vec<PendingDir>, Vec<WalkEntry>), SHAError> {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        let dir = root.join(&parent.relative);
+        let rules = self.ignore_rules(&dir, parent.rules)?;
+        let entries = fs::read_dir(&dir).map_err(|e| SHAError::from(e).at_path(&dir))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| SHAError::from(e).at_path(&dir))?;
+            if !self.hidden && is_hidden(&entry) {
+                continue;
+            }
+            let path = entry.path();
+            let relative = parent.relative.join(entry.file_name());
+            let mut file_type = entry
+                .file_type()
+                .map_err(|e| SHAError::from(e).at_path(&path))?;
+            let matched = portable_path(&relative);
+            let included = |is_dir| {
+                filter.includes_file(&matched) && !IgnoreRules::ignores(&rules, &path, is_dir)
+            };
+
+            if file_type.is_symlink() {
+                match self.symlinks {
+                    SymlinkPolicy::Skip => continue,
+                    SymlinkPolicy::Error => return Err(SHAError::Symlink(path)),
+                    SymlinkPolicy::HashTargetPath => {
+                        if included(false) {
+                            files.push(WalkEntry::Link(relative));
+                        }
+                        continue;
+                    }
+                    SymlinkPolicy::Follow => {
+                        file_type = fs::metadata(&path)
+                            .map_err(|e| SHAError::from(e).at_path(&path))?
+                            .file_type();
+                    }
+                }
+            }
+
+            if file_type.is_dir() {
+                let is_git_dir = self.git_ignore && entry.file_name() == ".git";
+                if is_git_dir
+                    || filter.skips_dir(&matched)
+                    || IgnoreRules::ignores(&rules, &path, true)
+                {
+                    continue;
+                }
+                let ancestors = match &parent.ancestors {
+                    Some(ancestors) => Some(Ancestors::push(Some(ancestors.clone()), &path)?),
+                    None => None,
+                };
+                dirs.push(PendingDir {
+                    relative,
+                    rules: rules.clone(),
+                    ancestors,
+                });
+            } else if file_type.is_file() && included(false) {
+                files.push(WalkEntry::File(relative));
+            }
+        }
+        Ok((dirs, files))
+    }
+
+    /// Hashes one file found by the walk, returning its relative path and manifest entry.
+    ///
+    /// A file whose stamp matches its entry in `previous` keeps that digest without being read.
+    fn hash_entry(
+        &self,
+        root: &Path,
+        entry: WalkEntry,
+        previous: Option<&Manifest>,
+    ) -> Result<(PathBuf, Entry), SHAError> {
+        match entry {
+            WalkEntry::File(relative) => {
+                let path = root.join(&relative);
+                // Taken before reading, so a write during hashing shows up as a change next time
+                let stamp = FileStamp::of(&path).map_err(|e| SHAError::from(e).at_path(&path))?;
+                if let Some(known) = previous.and_then(|manifest| manifest.entries.get(&relative))
+                    && stamp.is_some()
+                    && known.stamp == stamp
+                {
+                    return Ok((relative, known.clone()));
+                }
+                let digest = self
+                    .hasher
+                    .hash_resolved(&path)
+                    .map_err(|e| e.at_path(&path))?;
+                Ok((relative, Entry { digest, stamp }))
+            }
+            WalkEntry::Link(relative) => {
+                let path = root.join(&relative);
+                let digest = self
+                    .hasher
+                    .hash_link_target(&path)
+                    .map_err(|e| e.at_path(&path))?;
+                let stamp = None;
+                Ok((relative, Entry { digest, stamp }))
+            }
+        }
+    }
+
+    /// Adds the ignore files in `dir`, if any, on top of the rules inherited from its parents.
+    fn ignore_rules(
+        &self,
+        dir: &Path,
+        parent: Option<Arc<IgnoreRules>>,
+    ) -> Result<Option<Arc<IgnoreRules>>, SHAError> {
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        let names = [
+            (self.git_ignore, ".gitignore"),
+            (self.hash_ignore, HASHIGNORE_FILE),
+        ];
+        for (enabled, name) in names {
+            let path = dir.join(name);
+            if enabled && path.is_file() {
+                // Invalid lines are skipped, as git does; only unreadable files are errors
+                if let Some(ignore::Error::Io(e)) = builder.add(&path) {
+                    return Err(SHAError::File { path, source: e });
+                }
+                found = true;
+            }
+        }
+        if !found {
+            return Ok(parent);
+        }
+        let matcher = builder
+            .build()
+            .map_err(|e| SHAError::InvalidPattern(e.to_string()))?;
+        Ok(Some(Arc::new(IgnoreRules { matcher, parent })))
+    }
+}
+
+/// Returns whether `entry` is a dotfile, or on Windows has the hidden or system attribute.
+fn is_hidden(entry: &fs::DirEntry) -> bool {
+    if entry.file_name().as_encoded_bytes().starts_with(b".") {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+        // The attributes come from the directory listing, so this does not touch the file
+        if let Ok(metadata) = entry.metadata() {
+            return metadata.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM)
+                != 0;
+        }
+    }
+    false
+}
+
+/// A file found by [`DirOptions::walk`], relative to the root.
+enum WalkEntry {
+    /// A regular file, or a followed link to one.
+    File(PathBuf),
+    /// A symbolic link whose target path is hashed.
+    Link(PathBuf),
+}
+
+/// A unit of work in a parallel walk.
+enum Task {
+    List(PendingDir),
+    Hash(WalkEntry),
+}
+
+/// The tasks of a parallel walk, shared by its threads.
+///
+/// A thread waits for work while the queue is empty but other threads are still running tasks
+/// that may add more. Once nothing is queued or running, or a task has failed or panicked,
+/// every thread stops.
+struct WorkQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+struct QueueState {
+    tasks: Vec<Task>,
+    running: usize,
+    error: Option<SHAError>,
+    /// Set when a task panics; the panic itself reaches the caller through `join`.
+    panicked: bool,
+}
+
+impl WorkQueue {
+    fn new(task: Task) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                tasks: vec![task],
+                running: 0,
+                error: None,
+                panicked: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Waits for the next task, or returns `None` once the walk is over.
+    ///
+    /// The task counts as running until the returned guard is finished or dropped, so a task
+    /// that panics still lets the other threads stop.
+    fn take(&self) -> Option<(Task, Running<'_>)> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if state.error.is_some() || state.panicked {
+                return None;
+            }
+            if let Some(task) = state.tasks.pop() {
+                state.running += 1;
+                return Some((task, Running { queue: self }));
+            }
+            if state.running == 0 {
+                return None;
+            }
+            state = self
+                .changed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Returns the first failure, if any.
+    fn into_error(self) -> Option<SHAError> {
+        self.state
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .error
+    }
+}
+
+/// A task taken from a [`WorkQueue`] that has not finished yet.
+struct Running<'a> {
+    queue: &'a WorkQueue,
+}
+
+impl Running<'_> {
+    /// Records the outcome of the task.
+    fn finish(self, result: Result<Vec<Task>, SHAError>) {
+        let mut state = self
+            .queue
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match result {
+            Ok(tasks) => state.tasks.extend(tasks),
+            Err(e) => {
+                state.error.get_or_insert(e);
+            }
+        }
+    }
+}
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        let mut state = self
+            .queue
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.running -= 1;
+        state.panicked |= thread::panicking();
+        self.queue.changed.notify_all();
+    }
+}
+
+/// A directory waiting to be listed.
+struct PendingDir {
+    relative: PathBuf,
+    rules: Option<Arc<IgnoreRules>>,
+    /// Only tracked while following links, to detect loops.
+    ancestors: Option<Arc<Ancestors>>,
+}
+
+/// The canonical paths of a directory and everything above it in the walk.
+struct Ancestors {
+    path: PathBuf,
+    parent: Option<Arc<Ancestors>>,
+}
+
+impl Ancestors {
+    /// Adds the directory at `path` below `parent`, failing if it is already one of them.
+    fn push(parent: Option<Arc<Ancestors>>, path: &Path) -> Result<Arc<Ancestors>, SHAError> {
+        let canonical = fs::canonicalize(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        let mut current = parent.as_deref();
+        while let Some(ancestor) = current {
+            if ancestor.path == canonical {
+                return Err(SHAError::SymlinkLoop(path.to_path_buf()));
+            }
+            current = ancestor.parent.as_deref();
+        }
+        Ok(Arc::new(Ancestors {
+            path: canonical,
+            parent,
+        }))
+    }
+}
+
+/// The ignore files in effect for one directory, innermost first.
+struct IgnoreRules {
+    matcher: Gitignore,
+    parent: Option<Arc<IgnoreRules>>,
+}
+
+impl IgnoreRules {
+    /// Returns `true` if the innermost rule matching `path` ignores it.
+    fn ignores(rules: &Option<Arc<IgnoreRules>>, path: &Path, is_dir: bool) -> bool {
+        let mut current = rules.as_deref();
+        while let Some(rules) = current {
+            let matched = rules.matcher.matched(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+            current = rules.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// Compiled include and exclude globs of a [`DirOptions`].
+struct Filter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl Filter {
+    fn new(include: &[String], exclude: &[String]) -> Result<Self, SHAError> {
+        Ok(Self {
+            include: match include {
+                [] => None,
+                patterns => Some(compile(patterns)?),
+            },
+            exclude: compile(exclude)?,
+        })
+    }
+
+    fn skips_dir(&self, relative: &str) -> bool {
+        // `target/**` matches `target/` but not `target` itself
+        self.exclude.is_match(relative) || self.exclude.is_match(format!("{}/", relative))
+    }
+
+    fn includes_file(&self, relative: &str) -> bool {
+        !self.exclude.is_match(relative)
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(relative))
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<GlobSet, SHAError> {
+    let mut set = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| SHAError::InvalidPattern(e.to_string()))?;
+        set.add(glob);
+    }
+    set.build()
+        .map_err(|e| SHAError::InvalidPattern(e.to_string()))
+}
+
+/// Hashes every file under `root` into a [`Manifest`] of relative paths.
+///
+/// Shorthand for [`DirOptions::hash_dir`].
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{DirOptions, hash_dir};
+///
+/// let manifest = hash_dir("assets", &DirOptions::new()).unwrap();
+/// println!("{} files", manifest.len());
+/// ```
+pub fn hash_dir(root: impl AsRef<Path>, options: &DirOptions) -> Result<Manifest, SHAError> {
+    options.hash_dir(root)
+}
+
+/// Computes one SHA-1 digest of the directory tree under `root`.
+///
+/// Use [`DirOptions::hash_tree`] for other algorithms. See [`Manifest::tree_digest`] for
+/// exactly what is hashed.
+///
+/// # Examples
+/// ```no_run
+/// let before = sha_file_hashing::hash_tree("deploy/blue").unwrap();
+/// let after = sha_file_hashing::hash_tree("deploy/green").unwrap();
+/// println!("identical: {}", before == after);
+/// ```
+pub fn hash_tree(root: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    DirOptions::new().hash_tree(root)
+}
+
+/// Hashes two directory trees with SHA-1 and reports the files added, removed and modified
+/// going from `old` to `new`.
+///
+/// Use [`DirOptions::diff_dirs`] to choose the algorithm or filter the walk.
+///
+/// # Examples
+/// ```no_run
+/// let diff = sha_file_hashing::diff_dirs("release/1.0", "release/1.1").unwrap();
+/// for change in diff.modified() {
+///     println!("M {}", change.path.display());
+/// }
+/// ```
+pub fn diff_dirs(old: impl AsRef<Path>, new: impl AsRef<Path>) -> Result<DirDiff, SHAError> {
+    DirOptions::new().diff_dirs(old, new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    /// The added, removed and modified paths of `diff`.
+    fn diff_paths(diff: &DirDiff) -> [Vec<&Path>; 3] {
+        [
+            diff.added()
+                .iter()
+                .map(|(path, _)| path.as_path())
+                .collect(),
+            diff.removed()
+                .iter()
+                .map(|(path, _)| path.as_path())
+                .collect(),
+            diff.modified()
+                .iter()
+                .map(|change| change.path.as_path())
+                .collect(),
+        ]
+    }
+
+    fn create_tree() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(temp_dir.path().join("empty")).unwrap();
+        fs::write(temp_dir.path().join("top.txt"), b"test data").unwrap();
+        fs::write(nested.join("deep.txt"), b"Hello, World!").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_hash_dir_relative_paths() {
+        let temp_dir = create_tree();
+        let manifest = hash_dir(temp_dir.path(), &DirOptions::new()).unwrap();
+
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest.algorithm(), HashAlgorithm::Sha1);
+        assert_eq!(
+            *manifest.get("top.txt").unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+        assert_eq!(
+            *manifest
+                .get(Path::new("a").join("b").join("deep.txt"))
+                .unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+
+        let paths: Vec<&Path> = manifest.iter().map(|(path, _)| path).collect();
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+    }
+
+    #[test]
+    fn test_hash_tree_is_stable() {
+        let first = create_tree();
+        let second = create_tree();
+        let digest = hash_tree(first.path()).unwrap();
+        assert_eq!(digest, hash_tree(second.path()).unwrap());
+
+        let mut expected = Hasher::new(HashAlgorithm::Sha1);
+        expected.update(b"a/b/deep.txt\0");
+        expected.update(crate::hash_file_from_path(second.path().join("a/b/deep.txt")).unwrap());
+        expected.update(b"top.txt\0");
+        expected.update(crate::hash_file_from_path(second.path().join("top.txt")).unwrap());
+        assert_eq!(digest, expected.finalize());
+
+        // Renaming a file changes the digest even though the contents are the same
+        fs::rename(
+            second.path().join("top.txt"),
+            second.path().join("renamed.txt"),
+        )
+        .unwrap();
+        assert_ne!(digest, hash_tree(second.path()).unwrap());
+    }
+
+    #[test]
+    fn test_hash_dir_filters() {
+        let temp_dir = create_tree();
+        let target = temp_dir.path().join("target");
+        fs::create_dir_all(target.join("lib")).unwrap();
+        fs::write(temp_dir.path().join("app.jar"), b"app").unwrap();
+        fs::write(temp_dir.path().join("a").join("dep.jar"), b"dep").unwrap();
+        fs::write(target.join("lib").join("built.jar"), b"built").unwrap();
+
+        let manifest = DirOptions::new()
+            .filter("**/*.jar")
+            .filter("!target/**")
+            .hash_dir(temp_dir.path())
+            .unwrap();
+        let paths: Vec<&Path> = manifest.iter().map(|(path, _)| path).collect();
+        assert_eq!(paths, [Path::new("a/dep.jar"), Path::new("app.jar")]);
+
+        // `*` stays within one directory level
+        let manifest = DirOptions::new()
+            .include("*.txt")
+            .hash_dir(temp_dir.path())
+            .unwrap();
+        assert_eq!(manifest.len(), 1);
+        assert!(manifest.get("top.txt").is_some());
+
+        assert!(matches!(
+            DirOptions::new().exclude("[").hash_dir(temp_dir.path()),
+            Err(SHAError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_hash_dir_ignore_files() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git").join("HEAD"), b"ref").unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::write(root.join("build").join("out.o"), b"obj").unwrap();
+        fs::write(root.join("a").join("scratch.tmp"), b"tmp").unwrap();
+        fs::write(root.join("a").join("keep.tmp"), b"keep").unwrap();
+        fs::write(root.join(".gitignore"), b"build/\n*.tmp\n").unwrap();
+        fs::write(root.join("a").join(".hashignore"), b"!keep.tmp\nb/\n").unwrap();
+
+        let paths = |options: DirOptions| -> Vec<String> {
+            let manifest = options.hash_dir(root).unwrap();
+            manifest
+                .iter()
+                .map(|(path, _)| portable_path(path))
+                .collect()
+        };
+
+        // `.hashignore` is honored by default, `.gitignore` only on request
+        assert_eq!(
+            paths(DirOptions::new()),
+            [
+                ".git/HEAD",
+                ".gitignore",
+                "a/.hashignore",
+                "a/keep.tmp",
+                "a/scratch.tmp",
+                "build/out.o",
+                "top.txt"
+            ]
+        );
+        assert_eq!(
+            paths(DirOptions::new().git_ignore(true)),
+            [".gitignore", "a/.hashignore", "a/keep.tmp", "top.txt"]
+        );
+        assert_eq!(paths(DirOptions::new().hash_ignore(false)).len(), 8);
+
+        // Ignore files still apply when hidden entries are left out
+        assert_eq!(
+            paths(DirOptions::new().git_ignore(true).hidden(false)),
+            ["a/keep.tmp", "top.txt"]
+        );
+        assert_eq!(
+            paths(DirOptions::new().hidden(false)),
+            ["a/keep.tmp", "a/scratch.tmp", "build/out.o", "top.txt"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hash_dir_symlink_policies() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        symlink("top.txt", root.join("link.txt")).unwrap();
+        symlink("a", root.join("alias")).unwrap();
+
+        let paths = |policy| -> Vec<String> {
+            let manifest = DirOptions::new().symlinks(policy).hash_dir(root).unwrap();
+            manifest
+                .iter()
+                .map(|(path, _)| portable_path(path))
+                .collect()
+        };
+        assert_eq!(paths(SymlinkPolicy::Skip), ["a/b/deep.txt", "top.txt"]);
+        assert_eq!(
+            paths(SymlinkPolicy::Follow),
+            ["a/b/deep.txt", "alias/b/deep.txt", "link.txt", "top.txt"]
+        );
+
+        let manifest = DirOptions::new()
+            .symlinks(SymlinkPolicy::HashTargetPath)
+            .hash_dir(root)
+            .unwrap();
+        assert_eq!(manifest.len(), 4);
+        assert_eq!(
+            manifest.get("alias").unwrap(),
+            &HasherOptions::new().hash_reader(&b"a"[..]).unwrap()
+        );
+
+        assert!(matches!(
+            DirOptions::new()
+                .symlinks(SymlinkPolicy::Error)
+                .hash_dir(root),
+            Err(SHAError::Symlink(_))
+        ));
+
+        // A link back up the tree is reported rather than walked forever
+        symlink("..", root.join("a").join("up")).unwrap();
+        assert!(matches!(
+            DirOptions::new()
+                .symlinks(SymlinkPolicy::Follow)
+                .hash_dir(root),
+            Err(SHAError::SymlinkLoop(_))
+        ));
+    }
+
+    #[test]
+    fn test_hash_dir_parallel_matches_serial() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        for i in 0..20 {
+            let dir = root.join(format!("dir{}", i % 5));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(format!("file{}.txt", i)), format!("data {}", i)).unwrap();
+        }
+
+        let serial = DirOptions::new().hash_dir(root).unwrap();
+        assert_eq!(serial.len(), 22);
+        assert_eq!(DirOptions::new().threads(4).hash_dir(root).unwrap(), serial);
+        assert_eq!(DirOptions::new().threads(0).hash_dir(root).unwrap(), serial);
+
+        assert!(matches!(
+            DirOptions::new()
+                .threads(4)
+                .hash_dir("nonexistent_dir_12345"),
+            Err(SHAError::File { .. })
+        ));
+    }
+
+    #[test]
+    fn test_work_queue_panic_stops_walk() {
+        let root = DirOptions::new().root_dir(Path::new(".")).unwrap();
+        let queue = WorkQueue::new(Task::List(root));
+        let worker = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _task = queue.take();
+                    panic!("task failed");
+                })
+                .join()
+        });
+        assert!(worker.is_err());
+        // Without the running task being released, this would wait forever
+        assert!(queue.take().is_none());
+    }
+
+    #[test]
+    fn test_diff_dirs() {
+        let old = create_tree();
+        let new = create_tree();
+        fs::write(new.path().join("top.txt"), b"changed").unwrap();
+        fs::write(new.path().join("a").join("added.txt"), b"new").unwrap();
+        fs::remove_file(new.path().join("a").join("b").join("deep.txt")).unwrap();
+
+        let diff = diff_dirs(old.path(), new.path()).unwrap();
+        assert_eq!(
+            diff_paths(&diff),
+            [
+                [Path::new("a/added.txt")],
+                [Path::new("a/b/deep.txt")],
+                [Path::new("top.txt")]
+            ]
+        );
+        assert!(!diff.is_empty());
+
+        assert!(diff_dirs(old.path(), old.path()).unwrap().is_empty());
+    }
+
+    fn md5_manifest(entries: &[(&str, &str)]) -> Manifest {
+        let mut manifest = Manifest::new(HashAlgorithm::Md5);
+        for (path, contents) in entries {
+            let digest = contents.hash_with(HashAlgorithm::Md5).unwrap();
+            manifest.insert(*path, digest);
+        }
+        manifest
+    }
+
+    #[test]
+    fn test_manifest_diff_keeps_digests() {
+        let old = md5_manifest(&[("a", "same"), ("b", "old"), ("c", "gone")]);
+        let new = md5_manifest(&[("a", "same"), ("b", "new"), ("d/e", "fresh")]);
+        let diff = old.diff(&new).unwrap();
+        let md5 = |contents: &str| contents.hash_with(HashAlgorithm::Md5).unwrap();
+
+        assert_eq!(diff.added(), [(PathBuf::from("d/e"), md5("fresh"))]);
+        assert_eq!(diff.removed(), [(PathBuf::from("c"), md5("gone"))]);
+        assert_eq!(
+            diff.modified(),
+            [DigestChange {
+                path: PathBuf::from("b"),
+                old: md5("old"),
+                new: md5("new"),
+            }]
+        );
+        assert_eq!(
+            diff.to_string(),
+            format!(
+                "~ {} -> {}  b\n- {}  c\n+ {}  d/e\n",
+                md5("old"),
+                md5("new"),
+                md5("gone"),
+                md5("fresh")
+            )
+        );
+        assert!(old.diff(&old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_manifest_diff_rejects_mixed_algorithms() {
+        let old = md5_manifest(&[("a", "same")]);
+        let new = Manifest::new(HashAlgorithm::Sha256);
+        assert!(matches!(old.diff(&new), Err(SHAError::InvalidManifest(_))));
+    }
+
+    #[test]
+    fn test_verify_quick_check() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        let top = root.join("top.txt");
+        let manifest = DirOptions::new()
+            .hasher(HasherOptions::new().algorithm(HashAlgorithm::Sha256))
+            .hash_dir(root)
+            .unwrap();
+        let stamp = *manifest.stamp("top.txt").unwrap();
+        assert_eq!(stamp.len, 9);
+
+        // Verification uses the manifest's algorithm, not the hasher's default
+        assert!(
+            DirOptions::new()
+                .verify(root, &manifest)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Same size and restored mtime: only a full check notices
+        fs::write(&top, b"TEST DATA").unwrap();
+        File::options()
+            .write(true)
+            .open(&top)
+            .unwrap()
+            .set_modified(stamp.modified)
+            .unwrap();
+        let quick = DirOptions::new().quick_check(true);
+        assert!(quick.verify(root, &manifest).unwrap().is_empty());
+        let diff = DirOptions::new().verify(root, &manifest).unwrap();
+        assert_eq!(diff_paths(&diff)[2], [Path::new("top.txt")]);
+
+        fs::write(&top, b"short").unwrap();
+        let diff = quick.verify(root, &manifest).unwrap();
+        assert_eq!(diff_paths(&diff)[2], [Path::new("top.txt")]);
+    }
+
+    #[test]
+    fn test_verify_check_stamps() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        let top = root.join("top.txt");
+        let mut manifest = hash_dir(root, &DirOptions::new()).unwrap();
+        manifest.insert("unstamped.txt", manifest.get("top.txt").unwrap().clone());
+        fs::copy(&top, root.join("unstamped.txt")).unwrap();
+
+        let check = DirOptions::new().check_stamps(true);
+        assert!(check.verify(root, &manifest).unwrap().is_empty());
+
+        // Same contents, new timestamp
+        let stamp = *manifest.stamp("top.txt").unwrap();
+        let touched = stamp.modified + std::time::Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&top)
+            .unwrap()
+            .set_modified(touched)
+            .unwrap();
+        assert!(
+            DirOptions::new()
+                .verify(root, &manifest)
+                .unwrap()
+                .is_empty()
+        );
+        for options in [check.clone(), check.quick_check(true)] {
+            let diff = options.verify(root, &manifest).unwrap();
+            assert_eq!(diff.stamp_changed(), [Path::new("top.txt")]);
+            assert!(diff.modified().is_empty());
+            assert!(!diff.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_manifest_update() {
+        let temp_dir = create_tree();
+        let root = temp_dir.path();
+        let top = root.join("top.txt");
+        let mut manifest = DirOptions::new()
+            .hasher(HasherOptions::new().algorithm(HashAlgorithm::Md5))
+            .hash_dir(root)
+            .unwrap();
+        let stamp = *manifest.stamp("top.txt").unwrap();
+        assert!(manifest.update(root).unwrap().is_empty());
+
+        // A rewrite that keeps the stamp is trusted, as with quick_check
+        fs::write(&top, b"TEST DATA").unwrap();
+        File::options()
+            .write(true)
+            .open(&top)
+            .unwrap()
+            .set_modified(stamp.modified)
+            .unwrap();
+        assert!(manifest.update(root).unwrap().is_empty());
+
+        fs::write(&top, b"changed").unwrap();
+        fs::write(root.join("new.txt"), b"new").unwrap();
+        fs::remove_file(root.join("a/b/deep.txt")).unwrap();
+        let diff = manifest.update(root).unwrap();
+        assert_eq!(
+            diff_paths(&diff),
+            [
+                [Path::new("new.txt")],
+                [Path::new("a/b/deep.txt")],
+                [Path::new("top.txt")]
+            ]
+        );
+        assert_eq!(manifest.algorithm(), HashAlgorithm::Md5);
+        assert_eq!(
+            manifest,
+            DirOptions::new()
+                .hasher(HasherOptions::new().algorithm(HashAlgorithm::Md5))
+                .hash_dir(root)
+                .unwrap()
+        );
+
+        let before = manifest.clone();
+        assert!(manifest.update(root.join("missing")).is_err());
+        assert_eq!(manifest, before);
+    }
+
+    #[test]
+    fn test_hash_dir_missing_root() {
+        let missing = Path::new("nonexistent_dir_12345");
+        match hash_dir(missing, &DirOptions::new()) {
+            Err(SHAError::File { path, source }) => {
+                assert_eq!(path, missing);
+                assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+            }
+            other => panic!("Expected File error, got {:?}", other),
+        }
+    }
+}
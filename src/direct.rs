@@ -0,0 +1,127 @@
+use crate::{HasherOptions, SHAError};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Buffer address and length alignment for unbuffered reads. Covers both 512-byte and 4KiB
+/// logical sectors.
+const ALIGNMENT: usize = 4096;
+
+/// An access pattern hint for [`advise`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Advice {
+    Sequential,
+    DontNeed,
+}
+
+/// Passes `advice` about the whole of `file` to the kernel where `posix_fadvise` exists.
+///
+/// Hints are best effort: a failure only means the kernel ignores them, so it is not reported.
+pub(crate) fn advise(file: &File, advice: Advice) {
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    {
+        use std::os::fd::AsRawFd;
+
+        let advice = match advice {
+            Advice::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+            Advice::DontNeed => libc::POSIX_FADV_DONTNEED,
+        };
+        // SAFETY: `posix_fadvise` only reads the descriptor, which `file` keeps open
+        unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, advice) };
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    let _ = (file, advice);
+}
+
+/// Opens `path` bypassing the page cache, or returns `None` if the platform or filesystem
+/// does not support it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn open(path: &Path) -> io::Result<Option<File>> {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    match OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(path)
+    {
+        Ok(file) => Ok(Some(file)),
+        // tmpfs and some FUSE filesystems reject O_DIRECT outright
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn open(path: &Path) -> io::Result<Option<File>> {
+    use std::os::fd::AsRawFd;
+
+    let file = File::open(path)?;
+    // SAFETY: `fcntl` with F_NOCACHE only toggles a flag on a descriptor we own
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } == -1 {
+        return Ok(None);
+    }
+    Ok(Some(file))
+}
+
+#[cfg(windows)]
+pub(crate) fn open(path: &Path) -> io::Result<Option<File>> {
+    use std::fs::OpenOptions;
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_FLAG_NO_BUFFERING: u32 = 0x2000_0000;
+    const ERROR_INVALID_PARAMETER: i32 = 87;
+
+    match OpenOptions::new()
+        .read(true)
+        .custom_flags(FILE_FLAG_NO_BUFFERING)
+        .open(path)
+    {
+        Ok(file) => Ok(Some(file)),
+        Err(e) if e.raw_os_error() == Some(ERROR_INVALID_PARAMETER) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    windows
+)))]
+pub(crate) fn open(_path: &Path) -> io::Result<Option<File>> {
+    Ok(None)
+}
+
+/// Hands the contents of a file returned by [`open`], from offset zero, to `update`.
+///
+/// Unbuffered reads must use an aligned buffer and aligned lengths, so the configured buffer
+/// size is rounded up to [`ALIGNMENT`]. Only the final read may be short, so the loop stops
+/// once the length reported up front has been read.
+pub(crate) fn read(
+    options: &HasherOptions,
+    mut file: &File,
+    mut update: impl FnMut(&[u8]),
+) -> Result<(), SHAError> {
+    let len = file.metadata()?.len();
+    let size = options.buffer_size_for(len).next_multiple_of(ALIGNMENT);
+    let mut storage = vec![0u8; size + ALIGNMENT];
+    let start = storage.as_ptr().align_offset(ALIGNMENT);
+    let buffer = &mut storage[start..start + size];
+
+    let mut total = 0u64;
+    while total < len {
+        if options.is_cancelled() {
+            return Err(SHAError::Cancelled);
+        }
+        let n = match file.read(buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        update(&buffer[..n]);
+        total += n as u64;
+    }
+    Ok(())
+}
@@ -0,0 +1,277 @@
+//! eDonkey2000 (ed2k) hashes.
+//!
+//! Available with the `ed2k` feature. A file is split into chunks of 9,728,000 bytes and each
+//! chunk is hashed with MD4; a file shorter than one chunk is identified by its MD4 alone, and
+//! a longer one by the MD4 of the chunk hashes laid end to end. Like RHash and eMule, a file
+//! that fills its last chunk exactly also lists the hash of an empty chunk after it.
+//!
+//! The hash appears in `ed2k://|file|name|size|hash|/` links, which [`Ed2kLink`] reads and
+//! writes. MD4 is broken, so an ed2k match only shows a file was not corrupted by accident.
+//!
+//! ```no_run
+//! use sha_file_hashing::ed2k::{self, Ed2kLink};
+//!
+//! let digest = ed2k::hash_file_from_path("archive.iso").unwrap();
+//! let link: Ed2kLink = "ed2k://|file|archive.iso|734003200|31d6cfe0d16ae931b73c59d7e0c089c0|/"
+//!     .parse()
+//!     .unwrap();
+//! println!("intact: {}", link.verify("archive.iso").unwrap());
+//! ```
+
+use crate::{Digest, HashAlgorithm, SHAError, hash_file_with_algorithm};
+use digest::consts::U16;
+use digest::{Digest as _, FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update};
+use md4::Md4;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The length of an ed2k chunk in bytes.
+pub const CHUNK_SIZE: u64 = 9_728_000;
+
+/// Streaming ed2k state.
+///
+/// Implements the RustCrypto [`Update`] and [`FixedOutput`] traits so it can be driven by the
+/// same machinery as the other hashers.
+#[derive(Debug, Clone, Default)]
+pub struct Ed2k {
+    chunk: Md4,
+    filled: u64,
+    /// Hashes the MD4 of each full chunk, once there is more than one chunk.
+    chunk_hashes: Md4,
+    full_chunks: u64,
+}
+
+impl Ed2k {
+    /// Creates a new ed2k state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn finish(&mut self) -> Output<Md4> {
+        let last = self.chunk.finalize_reset();
+        match self.full_chunks {
+            0 => last,
+            _ => {
+                Update::update(&mut self.chunk_hashes, &last);
+                self.chunk_hashes.finalize_reset()
+            }
+        }
+    }
+}
+
+impl Update for Ed2k {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = data.len().min((CHUNK_SIZE - self.filled) as usize);
+            Update::update(&mut self.chunk, &data[..take]);
+            self.filled += take as u64;
+            data = &data[take..];
+            if self.filled == CHUNK_SIZE {
+                let hash = self.chunk.finalize_reset();
+                Update::update(&mut self.chunk_hashes, &hash);
+                self.full_chunks += 1;
+                self.filled = 0;
+            }
+        }
+    }
+}
+
+impl OutputSizeUser for Ed2k {
+    type OutputSize = U16;
+}
+
+impl FixedOutput for Ed2k {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.finish());
+    }
+}
+
+impl Reset for Ed2k {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl FixedOutputReset for Ed2k {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.finish());
+        self.reset();
+    }
+}
+
+/// Computes the ed2k hash of a `File` handle.
+pub fn hash_file(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Ed2k)
+}
+
+/// Computes the ed2k hash of the file at `path`.
+pub fn hash_file_from_path(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file(File::open(path.as_ref())?)
+}
+
+/// Validates a `File` handle against an expected ed2k hash (case-insensitive).
+pub fn validate_file(file: File, hash: impl AsRef<str>) -> bool {
+    hash_file(file)
+        .and_then(|computed| computed.matches_hex(hash.as_ref()))
+        .unwrap_or(false)
+}
+
+/// Validates the file at `path` against an expected ed2k hash (case-insensitive).
+pub fn validate_file_from_path(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    hash_file_from_path(path)?.matches_hex(hash.as_ref())
+}
+
+/// An `ed2k://|file|<name>|<size>|<hash>|/` link.
+///
+/// Fields after the hash, such as `h=` AICH hashes and `s=` sources, are accepted and dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ed2kLink {
+    /// The file name, percent-decoded.
+    pub name: String,
+    /// The file length in bytes.
+    pub size: u64,
+    /// The ed2k hash.
+    pub hash: Digest,
+}
+
+impl Ed2kLink {
+    /// Builds the link for the file at `path`, named after its last component.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let size = fs::metadata(path)
+            .map_err(|e| SHAError::from(e).at_path(path))?
+            .len();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let hash = hash_file_from_path(path).map_err(|e| e.at_path(path))?;
+        Ok(Self { name, size, hash })
+    }
+
+    /// Checks that the file at `path` has the link's size and hash. The name is not compared.
+    pub fn verify(&self, path: impl AsRef<Path>) -> Result<bool, SHAError> {
+        let path = path.as_ref();
+        let size = fs::metadata(path)
+            .map_err(|e| SHAError::from(e).at_path(path))?
+            .len();
+        Ok(size == self.size
+            && hash_file_from_path(path).map_err(|e| e.at_path(path))? == self.hash)
+    }
+}
+
+impl FromStr for Ed2kLink {
+    type Err = SHAError;
+
+    /// Parses a link, failing with [`SHAError::InvalidDigest`] if it is not an ed2k file link.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SHAError::InvalidDigest(format!("not an ed2k file link: {}", s));
+        let rest = s.trim().strip_prefix("ed2k://|file|").ok_or_else(invalid)?;
+        let mut fields = rest.split('|');
+        let (Some(name), Some(size), Some(hash)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            name: percent_decode(name).ok_or_else(invalid)?,
+            size: size.parse().map_err(|_| invalid())?,
+            hash: Digest::parse(hash, HashAlgorithm::Ed2k)?,
+        })
+    }
+}
+
+impl fmt::Display for Ed2kLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ed2k://|file|")?;
+        for byte in self.name.bytes() {
+            match byte {
+                b'|' | b'%' | b' ' | 0..0x20 | 0x7f.. => write!(f, "%{:02X}", byte)?,
+                _ => write!(f, "{}", byte as char)?,
+            }
+        }
+        write!(f, "|{}|{}|/", self.size, self.hash.to_hex().to_uppercase())
+    }
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let hex = std::str::from_utf8(rest.get(..2)?).ok()?;
+        bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        rest = &rest[2..];
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ed2k_known_values() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("abc");
+        fs::write(&path, "abc").unwrap();
+        // A file shorter than one chunk hashes to its MD4
+        assert_eq!(
+            hash_file_from_path(&path).unwrap(),
+            "a448017aaf21d8525fc10ae87aa6729d"
+        );
+
+        // Computed independently with a Python MD4
+        let chunk: Vec<u8> = (0..CHUNK_SIZE).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &chunk).unwrap();
+        assert_eq!(
+            hash_file_from_path(&path).unwrap(),
+            "22155255a2ed92712ccd01ad0eb9e8cb"
+        );
+        let mut longer = chunk;
+        longer.extend_from_slice(b"xyz");
+        fs::write(&path, &longer).unwrap();
+        assert!(validate_file_from_path(&path, "A0D21DE2CB8BB89E8290405E0F2A7DF9").unwrap());
+        assert!(!validate_file(File::open(&path).unwrap(), "00"));
+    }
+
+    #[test]
+    fn test_ed2k_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a|b c.txt");
+        fs::write(&path, "abc").unwrap();
+
+        let link = Ed2kLink::from_path(&path).unwrap();
+        let text = "ed2k://|file|a%7Cb%20c.txt|3|A448017AAF21D8525FC10AE87AA6729D|/";
+        assert_eq!(link.to_string(), text);
+        assert_eq!(text.parse::<Ed2kLink>().unwrap(), link);
+        assert!(link.verify(&path).unwrap());
+
+        let with_sources: Ed2kLink =
+            "ed2k://|file|a.txt|3|a448017aaf21d8525fc10ae87aa6729d|h=AAAA|/|sources,1.2.3.4:4662|/"
+                .parse()
+                .unwrap();
+        assert_eq!(with_sources.name, "a.txt");
+        assert!(with_sources.verify(&path).unwrap());
+        fs::write(&path, "abd").unwrap();
+        assert!(!with_sources.verify(&path).unwrap());
+
+        for bad in [
+            "ed2k://|server|1.2.3.4|4661|/",
+            "ed2k://|file|a.txt|x|a448017aaf21d8525fc10ae87aa6729d|/",
+            "ed2k://|file|a.txt|3|a448|/",
+            "ed2k://|file|a%zz|3|a448017aaf21d8525fc10ae87aa6729d|/",
+        ] {
+            assert!(bad.parse::<Ed2kLink>().is_err(), "{}", bad);
+        }
+    }
+}
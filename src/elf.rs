@@ -0,0 +1,369 @@
+//! GNU build-id notes of ELF binaries, the identifiers debuginfod and symbol servers index
+//! debug information by.
+
+use crate::{Digest, HashAlgorithm, Hasher, HasherOptions, SHAError, read_chunks};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// The program header type of a note segment.
+const PT_NOTE: u32 = 4;
+/// The section header type of a note section.
+const SHT_NOTE: u32 = 7;
+/// The note type of a GNU build-id.
+const NT_GNU_BUILD_ID: u32 = 3;
+/// Notes larger than this are not read.
+const MAX_NOTES_LEN: u64 = 1024 * 1024;
+
+/// Returns the GNU build-id of the ELF binary at `path`, or `None` if it has no build-id note.
+///
+/// The note is looked for in the note segments and, for objects without program headers such
+/// as separate debug files, in the note sections. The id is usually a 20-byte SHA-1, but
+/// linkers also produce 16-byte MD5 or UUID ids and 8-byte fast hashes.
+///
+/// # Errors
+/// Fails with an [`io::ErrorKind::InvalidData`] error if the file is not an ELF binary or its
+/// headers are malformed.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::elf_build_id;
+///
+/// if let Some(id) = elf_build_id("target/release/app").unwrap() {
+///     let hex = id.to_hex();
+///     println!("/usr/lib/debug/.build-id/{}/{}.debug", &hex[..2], &hex[2..]);
+/// }
+/// ```
+pub fn elf_build_id(path: impl AsRef<Path>) -> Result<Option<Digest>, SHAError> {
+    let path = path.as_ref();
+    find_build_id(path)
+        .map(|found| found.map(|(_, id)| Digest::from_bytes(id)))
+        .map_err(|e| SHAError::from(e).at_path(path))
+}
+
+/// Checks the build-id of the ELF binary at `path` against the file's own content hash: the
+/// SHA-1 (or, for a 16-byte id, MD5) of the whole file with the id zeroed.
+///
+/// This is how `gold` computes `--build-id=sha1` and `--build-id=md5`, so for its output a
+/// mismatch means the binary changed after linking. Other linkers hash only parts of their
+/// output, or hash it differently, so their build-ids never match; and `strip` changes the
+/// file without changing the id.
+///
+/// # Errors
+/// Fails with an [`io::ErrorKind::InvalidData`] error if the file is not an ELF binary or has
+/// no build-id, and with [`SHAError::UnsupportedAlgorithm`] if the id has neither length.
+pub fn verify_elf_build_id(path: impl AsRef<Path>) -> Result<bool, SHAError> {
+    let path = path.as_ref();
+    let (offset, id) = find_build_id(path)
+        .and_then(|found| found.ok_or_else(|| invalid("no GNU build-id note")))
+        .map_err(|e| SHAError::from(e).at_path(path))?;
+    let algorithm = match id.len() {
+        20 => HashAlgorithm::Sha1,
+        16 => HashAlgorithm::Md5,
+        len => {
+            return Err(SHAError::UnsupportedAlgorithm(format!(
+                "no content hash yields a {}-byte build-id",
+                len
+            )));
+        }
+    };
+    let content =
+        hash_zeroed(path, algorithm, offset, id.len() as u64).map_err(|e| e.at_path(path))?;
+    Ok(content.as_bytes() == id.as_slice())
+}
+
+/// Hashes the file at `path` with the `len` bytes at `offset` replaced by zeros.
+fn hash_zeroed(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    offset: u64,
+    len: u64,
+) -> Result<Digest, SHAError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Hasher::new(algorithm);
+    read_chunks(&HasherOptions::new(), (&mut file).take(offset), |chunk| {
+        hasher.update(chunk)
+    })?;
+    hasher.update(vec![0u8; len as usize]);
+    file.seek(SeekFrom::Start(offset + len))?;
+    read_chunks(&HasherOptions::new(), file, |chunk| hasher.update(chunk))?;
+    Ok(hasher.finalize())
+}
+
+/// The class and byte order of an ELF file, read from its identification bytes.
+struct Elf {
+    file: File,
+    is_64: bool,
+    big_endian: bool,
+}
+
+impl Elf {
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut ident = [0u8; 16];
+        file.read_exact(&mut ident)
+            .map_err(|_| invalid("file too short"))?;
+        if &ident[..4] != b"\x7fELF" {
+            return Err(invalid("no ELF signature"));
+        }
+        let is_64 = match ident[4] {
+            1 => false,
+            2 => true,
+            _ => return Err(invalid("unknown class")),
+        };
+        let big_endian = match ident[5] {
+            1 => false,
+            2 => true,
+            _ => return Err(invalid("unknown byte order")),
+        };
+        Ok(Self {
+            file,
+            is_64,
+            big_endian,
+        })
+    }
+
+    fn read(&mut self, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+        if len > MAX_NOTES_LEN {
+            return Err(invalid("header table or note too large"));
+        }
+        let mut buffer = vec![0u8; len as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file
+            .read_exact(&mut buffer)
+            .map_err(|_| invalid("truncated file"))?;
+        Ok(buffer)
+    }
+
+    fn u16(&self, bytes: &[u8], at: usize) -> u16 {
+        let bytes = [bytes[at], bytes[at + 1]];
+        match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        }
+    }
+
+    fn u32(&self, bytes: &[u8], at: usize) -> u32 {
+        let bytes = bytes[at..at + 4].try_into().expect("four bytes");
+        match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        }
+    }
+
+    /// Reads an address or offset, four bytes wide in 32-bit files and eight in 64-bit ones.
+    fn word(&self, bytes: &[u8], at: usize) -> u64 {
+        if !self.is_64 {
+            return u64::from(self.u32(bytes, at));
+        }
+        let bytes = bytes[at..at + 8].try_into().expect("eight bytes");
+        match self.big_endian {
+            true => u64::from_be_bytes(bytes),
+            false => u64::from_le_bytes(bytes),
+        }
+    }
+
+    /// Returns the `(offset, length, alignment)` of every note segment, or of every note
+    /// section if there are no program headers.
+    fn note_regions(&mut self) -> io::Result<Vec<(u64, u64, u64)>> {
+        let header = self.read(0, if self.is_64 { 64 } else { 52 })?;
+        // Field offsets of the ELF header, program headers and section headers
+        let (phoff, shoff, phentsize) = if self.is_64 {
+            (32, 40, 54)
+        } else {
+            (28, 32, 42)
+        };
+        let (p_offset, p_filesz, p_align) = if self.is_64 { (8, 32, 48) } else { (4, 16, 28) };
+        let (sh_offset, sh_size, sh_align) = if self.is_64 {
+            (24, 32, 48)
+        } else {
+            (16, 20, 32)
+        };
+
+        let mut regions = Vec::new();
+        let table = |offset: usize, min_size: u64| {
+            let entry_size = u64::from(self.u16(&header, offset));
+            let count = u64::from(self.u16(&header, offset + 2));
+            if count > 0 && entry_size < min_size {
+                return Err(invalid("header table entries too small"));
+            }
+            Ok((entry_size, count))
+        };
+        let (entry_size, count) = table(phentsize, if self.is_64 { 56 } else { 32 })?;
+        let phoff = self.word(&header, phoff);
+        if phoff != 0 && count > 0 {
+            let headers = self.read(phoff, entry_size * count)?;
+            for entry in headers.chunks_exact(entry_size as usize) {
+                if self.u32(entry, 0) == PT_NOTE {
+                    regions.push((
+                        self.word(entry, p_offset),
+                        self.word(entry, p_filesz),
+                        self.word(entry, p_align),
+                    ));
+                }
+            }
+            return Ok(regions);
+        }
+        let (entry_size, count) = table(phentsize + 4, if self.is_64 { 64 } else { 40 })?;
+        let shoff = self.word(&header, shoff);
+        if shoff != 0 && count > 0 {
+            let headers = self.read(shoff, entry_size * count)?;
+            for entry in headers.chunks_exact(entry_size as usize) {
+                if self.u32(entry, 4) == SHT_NOTE {
+                    regions.push((
+                        self.word(entry, sh_offset),
+                        self.word(entry, sh_size),
+                        self.word(entry, sh_align),
+                    ));
+                }
+            }
+        }
+        Ok(regions)
+    }
+}
+
+/// Returns the file offset and bytes of the build-id, if the file has one.
+fn find_build_id(path: &Path) -> io::Result<Option<(u64, Vec<u8>)>> {
+    let mut elf = Elf::open(path)?;
+    for (offset, len, align) in elf.note_regions()? {
+        let notes = elf.read(offset, len)?;
+        // The name and descriptor of a note start on four-byte boundaries, or eight-byte ones
+        // in segments aligned to eight
+        let pad = |n: usize| match align {
+            8 => n.next_multiple_of(8),
+            _ => n.next_multiple_of(4),
+        };
+        let mut at = 0;
+        while at + 12 <= notes.len() {
+            let name_len = elf.u32(&notes, at) as usize;
+            let desc_len = elf.u32(&notes, at + 4) as usize;
+            let kind = elf.u32(&notes, at + 8);
+            let name_at = at + 12;
+            let desc_at = pad(name_at + name_len);
+            let end = desc_at + desc_len;
+            if end > notes.len() {
+                return Err(invalid("truncated note"));
+            }
+            if kind == NT_GNU_BUILD_ID && &notes[name_at..name_at + name_len] == b"GNU\0" {
+                let id = notes[desc_at..end].to_vec();
+                return Ok(Some((offset + desc_at as u64, id)));
+            }
+            at = pad(end);
+        }
+    }
+    Ok(None)
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("not a valid ELF binary: {}", reason),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Appends a GNU build-id note holding `id` to `out`.
+    fn note(out: &mut Vec<u8>, id: &[u8], big_endian: bool) {
+        let word = |n: u32| match big_endian {
+            true => n.to_be_bytes(),
+            false => n.to_le_bytes(),
+        };
+        out.extend(word(4));
+        out.extend(word(id.len() as u32));
+        out.extend(word(NT_GNU_BUILD_ID));
+        out.extend(b"GNU\0");
+        out.extend(id);
+    }
+
+    /// A little-endian ELF64 executable with one note segment aligned to eight: a GNU
+    /// property note, then the build-id note.
+    fn elf64(id: &[u8]) -> Vec<u8> {
+        let mut elf = vec![0u8; 64 + 56];
+        elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        elf[32..40].copy_from_slice(&64u64.to_le_bytes());
+        elf[54..56].copy_from_slice(&56u16.to_le_bytes());
+        elf[56..58].copy_from_slice(&1u16.to_le_bytes());
+        let notes_at = elf.len();
+        elf.extend(4u32.to_le_bytes());
+        elf.extend(16u32.to_le_bytes());
+        elf.extend(5u32.to_le_bytes());
+        elf.extend(b"GNU\0");
+        elf.extend([0u8; 16]);
+        note(&mut elf, id, false);
+        let notes_len = elf.len() - notes_at;
+        elf.extend(b"program text");
+
+        let phdr = &mut elf[64..120];
+        phdr[..4].copy_from_slice(&PT_NOTE.to_le_bytes());
+        phdr[8..16].copy_from_slice(&(notes_at as u64).to_le_bytes());
+        phdr[32..40].copy_from_slice(&(notes_len as u64).to_le_bytes());
+        phdr[48..56].copy_from_slice(&8u64.to_le_bytes());
+        elf
+    }
+
+    #[test]
+    fn test_elf_build_id_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app");
+        let zeroed = elf64(&[0u8; 20]);
+        let mut content = Hasher::new(HashAlgorithm::Sha1);
+        content.update(&zeroed);
+        let id = content.finalize();
+
+        fs::write(&path, elf64(id.as_bytes())).unwrap();
+        assert_eq!(elf_build_id(&path).unwrap(), Some(id.clone()));
+        assert!(verify_elf_build_id(&path).unwrap());
+
+        let mut modified = elf64(id.as_bytes());
+        *modified.last_mut().unwrap() ^= 1;
+        fs::write(&path, &modified).unwrap();
+        assert_eq!(elf_build_id(&path).unwrap(), Some(id));
+        assert!(!verify_elf_build_id(&path).unwrap());
+
+        fs::write(&path, elf64(&[0xab; 8])).unwrap();
+        assert!(matches!(
+            verify_elf_build_id(&path),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_elf_build_id_from_sections() {
+        // A big-endian ELF32 object with no program headers and the note in a section
+        let mut elf = vec![0u8; 52];
+        elf[..6].copy_from_slice(b"\x7fELF\x01\x02");
+        note(&mut elf, &[0x12, 0x34, 0x56, 0x78], true);
+        let shoff = elf.len();
+        elf.extend([0u8; 40]);
+        let mut section = vec![0u8; 40];
+        section[4..8].copy_from_slice(&SHT_NOTE.to_be_bytes());
+        section[16..20].copy_from_slice(&52u32.to_be_bytes());
+        section[20..24].copy_from_slice(&20u32.to_be_bytes());
+        elf.extend(section);
+        elf[32..36].copy_from_slice(&(shoff as u32).to_be_bytes());
+        elf[46..48].copy_from_slice(&40u16.to_be_bytes());
+        elf[48..50].copy_from_slice(&2u16.to_be_bytes());
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.debug");
+        fs::write(&path, &elf).unwrap();
+        assert_eq!(elf_build_id(&path).unwrap().unwrap(), "12345678");
+
+        // Without the section table there is nowhere to look
+        elf[48..50].copy_from_slice(&0u16.to_be_bytes());
+        fs::write(&path, &elf).unwrap();
+        assert_eq!(elf_build_id(&path).unwrap(), None);
+        assert!(matches!(
+            verify_elf_build_id(&path),
+            Err(SHAError::File { .. })
+        ));
+
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        assert!(matches!(elf_build_id(&path), Err(SHAError::File { .. })));
+    }
+}
@@ -0,0 +1,295 @@
+//! Fast fingerprints for indexing and change detection.
+//!
+//! Fingerprints are returned as integers rather than hex strings, since they are typically
+//! used directly as keys in hash maps and dedupe indexes.
+
+use crate::{HasherOptions, SHAError, read_chunks};
+use digest::consts::U8;
+use digest::{FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update};
+use siphasher::sip::SipHasher24;
+use std::fs::File;
+use std::hash::Hasher;
+use std::path::Path;
+
+/// Computes a keyed SipHash-2-4 fingerprint of the file at `path`.
+///
+/// SipHash is a keyed PRF: without the secret 128-bit `key`, an adversary cannot craft files
+/// that collide in an index, which makes it suitable for dedupe tables exposed to untrusted
+/// input. It is much faster than a cryptographic digest but its 64-bit output is too short to
+/// serve as a content identifier on its own.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::siphash_file;
+///
+/// let key = [7u8; 16];
+/// let fingerprint = siphash_file("upload.bin", &key).unwrap();
+/// println!("{:016x}", fingerprint);
+/// ```
+pub fn siphash_file(path: impl AsRef<Path>, key: &[u8; 16]) -> Result<u64, SHAError> {
+    let mut hasher = SipHasher24::new_with_key(key);
+    read_chunks(&HasherOptions::new(), File::open(path.as_ref())?, |chunk| {
+        hasher.write(chunk)
+    })?;
+    Ok(hasher.finish())
+}
+
+/// Computes the 64-bit FNV-1a hash of the file at `path`.
+///
+/// FNV-1a is about as cheap as a hash can be, which makes it a good fit for build-system change
+/// detection where collisions only cost a spurious rebuild. It is also available as
+/// [`HashAlgorithm::Fnv1a64`](crate::HashAlgorithm::Fnv1a64), which yields the same value as a
+/// big-endian [`Digest`](crate::Digest).
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::fnv1a_file;
+///
+/// let fingerprint = fnv1a_file("src/main.rs").unwrap();
+/// println!("{:016x}", fingerprint);
+/// ```
+pub fn fnv1a_file(path: impl AsRef<Path>) -> Result<u64, SHAError> {
+    let mut hasher = Fnv1a64::default();
+    read_chunks(&HasherOptions::new(), File::open(path.as_ref())?, |chunk| {
+        hasher.write(chunk)
+    })?;
+    Ok(hasher.0)
+}
+
+/// Computes the CurseForge fingerprint of the file at `path`.
+///
+/// This is the value the CurseForge API uses to match mod jars and resource packs: a 32-bit
+/// MurmurHash2 (seed 1) over the file contents with all tab, line feed, carriage return and
+/// space bytes removed. MurmurHash2 mixes the input length into its initial state, so the file
+/// is read twice: once to count the significant bytes and once to hash them.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::curseforge_fingerprint;
+///
+/// let fingerprint = curseforge_fingerprint("mods/jei.jar").unwrap();
+/// println!("{}", fingerprint);
+/// ```
+pub fn curseforge_fingerprint(path: impl AsRef<Path>) -> Result<u32, SHAError> {
+    let path = path.as_ref();
+
+    let mut len: u32 = 0;
+    read_chunks(&HasherOptions::new(), File::open(path)?, |chunk| {
+        let significant = chunk
+            .iter()
+            .filter(|b| !is_curseforge_whitespace(**b))
+            .count();
+        len = len.wrapping_add(significant as u32);
+    })?;
+
+    let mut hasher = Murmur2::new(1, len);
+    read_chunks(&HasherOptions::new(), File::open(path)?, |chunk| {
+        for byte in chunk.iter().copied() {
+            if !is_curseforge_whitespace(byte) {
+                hasher.push(byte);
+            }
+        }
+    })?;
+    Ok(hasher.finish())
+}
+
+fn is_curseforge_whitespace(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\r' | b' ')
+}
+
+const MURMUR2_M: u32 = 0x5bd1_e995;
+
+/// Streaming 32-bit MurmurHash2 for inputs whose length is known up front.
+struct Murmur2 {
+    h: u32,
+    block: [u8; 4],
+    filled: usize,
+}
+
+impl Murmur2 {
+    fn new(seed: u32, len: u32) -> Self {
+        Self {
+            h: seed ^ len,
+            block: [0; 4],
+            filled: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.block[self.filled] = byte;
+        self.filled += 1;
+        if self.filled == 4 {
+            let mut k = u32::from_le_bytes(self.block);
+            k = k.wrapping_mul(MURMUR2_M);
+            k ^= k >> 24;
+            k = k.wrapping_mul(MURMUR2_M);
+            self.h = self.h.wrapping_mul(MURMUR2_M) ^ k;
+            self.filled = 0;
+        }
+    }
+
+    fn finish(mut self) -> u32 {
+        let tail = &self.block[..self.filled];
+        if !tail.is_empty() {
+            for (i, byte) in tail.iter().enumerate() {
+                self.h ^= u32::from(*byte) << (8 * i);
+            }
+            self.h = self.h.wrapping_mul(MURMUR2_M);
+        }
+        self.h ^= self.h >> 13;
+        self.h = self.h.wrapping_mul(MURMUR2_M);
+        self.h ^ (self.h >> 15)
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Streaming 64-bit FNV-1a state.
+#[derive(Debug, Clone)]
+pub(crate) struct Fnv1a64(u64);
+
+impl Fnv1a64 {
+    fn write(&mut self, data: &[u8]) {
+        for byte in data {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+impl Default for Fnv1a64 {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Update for Fnv1a64 {
+    fn update(&mut self, data: &[u8]) {
+        self.write(data);
+    }
+}
+
+impl OutputSizeUser for Fnv1a64 {
+    type OutputSize = U8;
+}
+
+impl FixedOutput for Fnv1a64 {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.0.to_be_bytes());
+    }
+}
+
+impl Reset for Fnv1a64 {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl FixedOutputReset for Fnv1a64 {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        out.copy_from_slice(&self.0.to_be_bytes());
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn reference_key() -> [u8; 16] {
+        std::array::from_fn(|i| i as u8)
+    }
+
+    #[test]
+    fn test_siphash_reference_vector() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        let fingerprint = siphash_file(temp_file.path(), &reference_key()).unwrap();
+        assert_eq!(fingerprint, 0x726fdb47dd0e0e31);
+    }
+
+    #[test]
+    fn test_siphash_depends_on_key() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&vec![b'A'; 20000]).unwrap();
+        temp_file.flush().unwrap();
+
+        let a = siphash_file(temp_file.path(), &reference_key()).unwrap();
+        let b = siphash_file(temp_file.path(), &[0u8; 16]).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a, siphash_file(temp_file.path(), &reference_key()).unwrap());
+    }
+
+    #[test]
+    fn test_curseforge_empty_file() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        assert_eq!(
+            curseforge_fingerprint(temp_file.path()).unwrap(),
+            1540447798
+        );
+    }
+
+    #[test]
+    fn test_curseforge_ignores_whitespace() {
+        let mut compact = NamedTempFile::new().unwrap();
+        compact.write_all(b"abcdefg").unwrap();
+        compact.flush().unwrap();
+
+        let mut spaced = NamedTempFile::new().unwrap();
+        spaced.write_all(b" a\tb\r\nc d\n\nefg ").unwrap();
+        spaced.flush().unwrap();
+
+        assert_eq!(
+            curseforge_fingerprint(compact.path()).unwrap(),
+            curseforge_fingerprint(spaced.path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_curseforge_matches_murmur2_across_chunks() {
+        let data: Vec<u8> = (0..20001u32).map(|i| (i % 251) as u8).collect();
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&data).unwrap();
+        temp_file.flush().unwrap();
+
+        let significant: Vec<u8> = data
+            .into_iter()
+            .filter(|b| !is_curseforge_whitespace(*b))
+            .collect();
+        let mut reference = Murmur2::new(1, significant.len() as u32);
+        significant.iter().for_each(|b| reference.push(*b));
+
+        assert_eq!(
+            curseforge_fingerprint(temp_file.path()).unwrap(),
+            reference.finish()
+        );
+    }
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        assert_eq!(fnv1a_file(temp_file.path()).unwrap(), FNV_OFFSET_BASIS);
+
+        temp_file.write_all(b"foobar").unwrap();
+        temp_file.flush().unwrap();
+        assert_eq!(fnv1a_file(temp_file.path()).unwrap(), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_fnv1a_algorithm_matches_fingerprint() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"foobar").unwrap();
+        temp_file.flush().unwrap();
+
+        let hex = crate::hash_file_from_path_with_algorithm(
+            temp_file.path(),
+            crate::HashAlgorithm::Fnv1a64,
+        )
+        .unwrap();
+        assert_eq!(hex, "85944171f73967e8");
+    }
+}
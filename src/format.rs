@@ -0,0 +1,106 @@
+use crate::Digest;
+use std::fmt::Write;
+
+/// How [`Digest::format`] writes a digest as hex: the letter case and an optional separator
+/// between bytes.
+///
+/// The default is the plain lowercase hex `Display` prints. Other tools display digests in
+/// their own way: `openssl dgst -c` separates bytes with colons, `openssl x509 -fingerprint`
+/// does too in uppercase, and older `certutil -hashfile` separates them with spaces.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::{HashAlgorithm, Hashable, OutputFormat};
+///
+/// let digest = "abc".hash_with(HashAlgorithm::Md5).unwrap();
+/// let openssl = OutputFormat::new().uppercase(true).separator(':');
+/// assert_eq!(
+///     digest.format(&openssl),
+///     "90:01:50:98:3C:D2:4F:B0:D6:96:3F:7D:28:E1:7F:72"
+/// );
+/// assert_eq!(digest.format(&OutputFormat::new()), digest.to_hex());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct OutputFormat {
+    uppercase: bool,
+    separator: Option<char>,
+}
+
+impl OutputFormat {
+    /// Creates the default format, lowercase hex with no separators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the hex digits `A` to `F` in uppercase.
+    pub fn uppercase(mut self, enabled: bool) -> Self {
+        self.uppercase = enabled;
+        self
+    }
+
+    /// Writes `separator` between bytes, as in `ab:cd:ef`.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+}
+
+impl Digest {
+    /// Formats the digest as hex in the given [`OutputFormat`].
+    pub fn format(&self, format: &OutputFormat) -> String {
+        let mut out = String::with_capacity(self.len() * 3);
+        for (i, byte) in self.as_bytes().iter().enumerate() {
+            if let Some(separator) = format.separator
+                && i > 0
+            {
+                out.push(separator);
+            }
+            let _ = match format.uppercase {
+                true => write!(out, "{:02X}", byte),
+                false => write!(out, "{:02x}", byte),
+            };
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HashAlgorithm, Hashable};
+
+    #[test]
+    fn test_output_formats() {
+        let digest = Digest::from_bytes(vec![0xab, 0x0c, 0xef]);
+        for (format, expected) in [
+            (OutputFormat::new(), "ab0cef"),
+            (OutputFormat::new().uppercase(true), "AB0CEF"),
+            (OutputFormat::new().separator(':'), "ab:0c:ef"),
+            (
+                OutputFormat::new().separator(' ').uppercase(true),
+                "AB 0C EF",
+            ),
+        ] {
+            assert_eq!(digest.format(&format), expected);
+        }
+        assert_eq!(
+            Digest::from_bytes(Vec::new()).format(&OutputFormat::new().separator(':')),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_hash_formatted() {
+        let format = OutputFormat::new().uppercase(true).separator(':');
+        assert_eq!(
+            "abc".hash_formatted(&format).unwrap(),
+            "A9:99:3E:36:47:06:81:6A:BA:3E:25:71:78:50:C2:6C:9C:D0:D8:9D"
+        );
+        assert_eq!(
+            "abc"
+                .hash_formatted_with(&format, HashAlgorithm::Md5)
+                .unwrap(),
+            "90:01:50:98:3C:D2:4F:B0:D6:96:3F:7D:28:E1:7F:72"
+        );
+    }
+}
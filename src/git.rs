@@ -0,0 +1,284 @@
+//! Git object IDs, as computed by `git hash-object` and `git write-tree`.
+//!
+//! Git hashes an object's type and length before its contents, so a blob ID is not the plain
+//! SHA-1 of the file. These functions reproduce the IDs without a repository or a `git`
+//! binary, for comparing working-tree files against the objects a commit records.
+
+use crate::{
+    Digest, DirOptions, HashAlgorithm, Hasher, HasherOptions, SHAError, SymlinkPolicy, read_chunks,
+};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path};
+
+/// Computes the git blob ID of the file at `path`, matching `git hash-object <path>` in a
+/// SHA-1 repository.
+///
+/// The file is hashed as stored; git applies no filters to it unless `.gitattributes` asks for
+/// line-ending conversion or other clean filters.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::hash_git_blob;
+///
+/// let id = hash_git_blob("src/main.rs").unwrap();
+/// println!("{}", id);
+/// ```
+pub fn hash_git_blob(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_git_blob_with(path, HashAlgorithm::Sha1)
+}
+
+/// Computes the git blob ID of the file at `path` with `algorithm`, such as
+/// [`HashAlgorithm::Sha256`] for repositories created with `--object-format=sha256`.
+pub fn hash_git_blob_with(
+    path: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    let len = file
+        .metadata()
+        .map_err(|e| SHAError::from(e).at_path(path))?
+        .len();
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(format!("blob {}\0", len));
+    let mut read = 0u64;
+    read_chunks(&HasherOptions::new(), file, |chunk| {
+        read += chunk.len() as u64;
+        hasher.update(chunk);
+    })
+    .map_err(|e| e.at_path(path))?;
+    // The header already committed to the length
+    if read != len {
+        let e = io::Error::other("file changed size while it was hashed");
+        return Err(SHAError::from(e).at_path(path));
+    }
+    Ok(hasher.finalize())
+}
+
+/// Computes the git tree ID of the directory `dir`, matching `git write-tree` after
+/// `git add -A` in a SHA-1 repository.
+///
+/// Files ignored by `.gitignore` and `.git` directories are left out, executable files and
+/// symbolic links get the modes git gives them, and directories without files are dropped, as
+/// git cannot store them. A file committed despite matching `.gitignore` is missed, so compare
+/// against `git ls-files` if the tree does not match.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::hash_git_tree;
+///
+/// // From `git rev-parse v1.0^{tree}`
+/// let expected = "cfd7f7c7fdd0dc4cf9ad2cc00ba6f80a425b34a5";
+/// println!("checkout matches: {}", hash_git_tree(".").unwrap() == expected);
+/// ```
+pub fn hash_git_tree(dir: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    DirOptions::new()
+        .git_ignore(true)
+        .hash_ignore(false)
+        .symlinks(SymlinkPolicy::HashTargetPath)
+        .hash_git_tree(dir)
+}
+
+impl DirOptions {
+    /// Hashes the files under `root` that pass these options into a git tree object ID.
+    ///
+    /// Blob IDs use the configured algorithm, which should be [`HashAlgorithm::Sha1`] or
+    /// [`HashAlgorithm::Sha256`] for the object format of the repository. Symbolic links are
+    /// recorded as git records them only under [`SymlinkPolicy::HashTargetPath`].
+    /// [`hash_git_tree`] sets these options up the way git selects files.
+    pub fn hash_git_tree(&self, root: impl AsRef<Path>) -> Result<Digest, SHAError> {
+        let root = root.as_ref();
+        let algorithm = self.hasher_options().selected_algorithm();
+        let mut tree = Tree::default();
+        for relative in self.list_files(root)? {
+            let path = root.join(&relative);
+            let metadata =
+                fs::symlink_metadata(&path).map_err(|e| SHAError::from(e).at_path(&path))?;
+            let blob = match metadata.is_symlink() {
+                true => {
+                    let target =
+                        fs::read_link(&path).map_err(|e| SHAError::from(e).at_path(&path))?;
+                    let target = target.into_os_string();
+                    Node::Blob(LINK_MODE, blob_id(algorithm, target.as_encoded_bytes()))
+                }
+                false => Node::Blob(file_mode(&metadata), hash_git_blob_with(&path, algorithm)?),
+            };
+            tree.insert(&relative, blob);
+        }
+        Ok(tree.id(algorithm))
+    }
+}
+
+const FILE_MODE: &str = "100644";
+const EXECUTABLE_MODE: &str = "100755";
+const LINK_MODE: &str = "120000";
+const TREE_MODE: &str = "40000";
+
+/// A directory being assembled into a tree object, keyed by entry name.
+#[derive(Default)]
+struct Tree {
+    entries: BTreeMap<OsString, Node>,
+}
+
+enum Node {
+    Blob(&'static str, Digest),
+    Tree(Tree),
+}
+
+impl Tree {
+    /// Adds the blob at `relative`, creating the directories above it.
+    fn insert(&mut self, relative: &Path, blob: Node) {
+        let mut names: Vec<_> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_os_string()),
+                _ => None,
+            })
+            .collect();
+        let Some(file_name) = names.pop() else {
+            return;
+        };
+        let mut tree = self;
+        for name in names {
+            let node = tree
+                .entries
+                .entry(name)
+                .or_insert_with(|| Node::Tree(Tree::default()));
+            tree = match node {
+                Node::Tree(subtree) => subtree,
+                Node::Blob(..) => return,
+            };
+        }
+        tree.entries.insert(file_name, blob);
+    }
+
+    /// Hashes the tree object, hashing subtrees first.
+    fn id(&self, algorithm: HashAlgorithm) -> Digest {
+        let mut entries: Vec<(Vec<u8>, &str, Digest)> = self
+            .entries
+            .iter()
+            .map(|(name, node)| match node {
+                Node::Blob(mode, id) => (name.as_encoded_bytes().to_vec(), *mode, id.clone()),
+                Node::Tree(tree) => {
+                    // Git orders a directory as if its name ended in `/`
+                    let mut key = name.as_encoded_bytes().to_vec();
+                    key.push(b'/');
+                    (key, TREE_MODE, tree.id(algorithm))
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut body = Vec::new();
+        for (key, mode, id) in &entries {
+            let name = match *mode {
+                TREE_MODE => &key[..key.len() - 1],
+                _ => &key[..],
+            };
+            body.extend_from_slice(mode.as_bytes());
+            body.push(b' ');
+            body.extend_from_slice(name);
+            body.push(0);
+            body.extend_from_slice(id.as_bytes());
+        }
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(format!("tree {}\0", body.len()));
+        hasher.update(body);
+        hasher.finalize()
+    }
+}
+
+/// Hashes `data` as a git blob.
+fn blob_id(algorithm: HashAlgorithm, data: &[u8]) -> Digest {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(format!("blob {}\0", data.len()));
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Returns the mode git records for a regular file: executable if its owner may execute it.
+fn file_mode(metadata: &fs::Metadata) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if metadata.permissions().mode() & 0o100 != 0 {
+            return EXECUTABLE_MODE;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    FILE_MODE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hash_git_blob() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hello.txt");
+        fs::write(&path, "hello\n").unwrap();
+        // From `git hash-object`
+        assert_eq!(
+            hash_git_blob(&path).unwrap(),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+        assert_eq!(
+            hash_git_blob_with(&path, HashAlgorithm::Sha256).unwrap(),
+            "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4"
+        );
+
+        let empty = temp_dir.path().join("empty");
+        fs::write(&empty, "").unwrap();
+        assert_eq!(
+            hash_git_blob(&empty).unwrap(),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+        assert!(matches!(
+            hash_git_blob(temp_dir.path().join("missing")),
+            Err(SHAError::File { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hash_git_tree() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(root.join("a.txt"), "top\n").unwrap();
+        fs::write(root.join("a/b.txt"), "nested\n").unwrap();
+        fs::write(root.join("a-b"), "dash\n").unwrap();
+        fs::write(root.join("run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(root.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("a.txt", root.join("link")).unwrap();
+        fs::write(root.join(".gitignore"), "ignored.log\n").unwrap();
+        fs::write(root.join("ignored.log"), "x").unwrap();
+
+        // From `git add -A && git write-tree`
+        assert_eq!(
+            hash_git_tree(root).unwrap(),
+            "cfd7f7c7fdd0dc4cf9ad2cc00ba6f80a425b34a5"
+        );
+        assert_eq!(
+            hash_git_tree(root.join("a")).unwrap(),
+            "bd6bc799012984c0083beb6c9448d3ea68c214cf"
+        );
+        // The empty tree
+        assert_eq!(
+            hash_git_tree(root.join("empty")).unwrap(),
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+        );
+    }
+}
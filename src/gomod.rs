@@ -0,0 +1,152 @@
+//! Go module hashes, as recorded in `go.sum` files.
+//!
+//! These implement the `h1:` algorithm of Go's `golang.org/x/mod/sumdb/dirhash` package: every
+//! file is hashed with SHA-256, the sorted `"<hex>  <name>\n"` lines are hashed again with
+//! SHA-256, and the result is base64 encoded after an `h1:` prefix.
+
+use crate::dir::portable_path;
+use crate::{Digest, DirOptions, HashAlgorithm, Hasher, HasherOptions, SHAError, SymlinkPolicy};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io;
+use std::path::Path;
+
+/// Computes the Go `h1:` hash of the directory `dir`, with file names under `prefix`.
+///
+/// For a module, `prefix` is `<module path>@<version>`, which is how files are named inside
+/// the module zip, so this matches the hash `go` records for the extracted module. An empty
+/// `prefix` names files by their path relative to `dir`. Every file is included, hidden files
+/// and ignore files too, as in Go. Symbolic links are rejected with [`SHAError::Symlink`],
+/// since module zips cannot contain them.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::go_dirhash;
+///
+/// let go_sum_line = "rsc.io/quote v1.5.2 h1:w5fcysjrx7yqtD/aO+QwRjYZOKnaM9Uh2b40tElTs3Y=";
+/// let expected = go_sum_line.rsplit(' ').next().unwrap();
+/// let hash = go_dirhash("quote", "rsc.io/quote@v1.5.2").unwrap();
+/// println!("matches go.sum: {}", hash == expected);
+/// ```
+pub fn go_dirhash(dir: impl AsRef<Path>, prefix: &str) -> Result<String, SHAError> {
+    let manifest = DirOptions::new()
+        .hasher(HasherOptions::new().algorithm(HashAlgorithm::Sha256))
+        .hash_ignore(false)
+        .symlinks(SymlinkPolicy::Error)
+        .hash_dir(dir)?;
+    let files = manifest.iter().map(|(path, digest)| {
+        let name = portable_path(path);
+        match prefix {
+            "" => (name, digest.clone()),
+            prefix => (format!("{}/{}", prefix, name), digest.clone()),
+        }
+    });
+    hash1(files.collect())
+}
+
+/// Computes the Go `h1:` hash of the files in the zip archive at `path`, such as a module zip
+/// downloaded from a module proxy.
+///
+/// Files are named exactly as stored in the archive.
+#[cfg(feature = "zip")]
+pub fn go_ziphash(path: impl AsRef<Path>) -> Result<String, SHAError> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    let mut archive = zip::ZipArchive::new(io::BufReader::new(file)).map_err(io::Error::from)?;
+    let options = HasherOptions::new().algorithm(HashAlgorithm::Sha256);
+    let mut files = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(io::Error::from)?;
+        let name = entry.name().map_err(io::Error::from)?.into_owned();
+        files.push((name, options.hash_reader(entry)?));
+    }
+    hash1(files)
+}
+
+/// Combines named SHA-256 file digests into an `h1:` hash.
+fn hash1(mut files: Vec<(String, Digest)>) -> Result<String, SHAError> {
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+    for (name, digest) in &files {
+        // The line format could not be parsed back unambiguously
+        if name.contains('\n') {
+            return Err(SHAError::File {
+                path: name.into(),
+                source: io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "file names with newlines are not supported",
+                ),
+            });
+        }
+        hasher.update(format!("{}  {}\n", digest, name));
+    }
+    Ok(format!(
+        "h1:{}",
+        STANDARD.encode(hasher.finalize().as_bytes())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn sha256(data: &str) -> Digest {
+        HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha256)
+            .hash_reader(data.as_bytes())
+            .unwrap()
+    }
+
+    // Mirrors TestHashDir in Go's dirhash package
+    #[test]
+    fn test_go_dirhash() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("xyz"), "data for xyz").unwrap();
+        fs::write(temp_dir.path().join("abc"), "data for abc").unwrap();
+
+        let summary = format!(
+            "{}  prefix/abc\n{}  prefix/xyz\n",
+            sha256("data for abc"),
+            sha256("data for xyz")
+        );
+        let expected = format!("h1:{}", STANDARD.encode(sha256(&summary).as_bytes()));
+        assert_eq!(go_dirhash(temp_dir.path(), "prefix").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_hash1_rejects_newlines() {
+        let files = vec![("a\nb".to_string(), sha256(""))];
+        assert!(matches!(hash1(files), Err(SHAError::File { .. })));
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_go_ziphash_matches_dirhash() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let temp_dir = TempDir::new().unwrap();
+        let module = temp_dir.path().join("module");
+        fs::create_dir_all(module.join("sub")).unwrap();
+        fs::write(module.join("go.mod"), "module example.com/m\n").unwrap();
+        fs::write(module.join("sub").join("a.go"), "package sub\n").unwrap();
+
+        let zip_path = temp_dir.path().join("m.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        for (name, data) in [
+            ("example.com/m@v1.0.0/sub/a.go", "package sub\n"),
+            ("example.com/m@v1.0.0/go.mod", "module example.com/m\n"),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        assert_eq!(
+            go_ziphash(&zip_path).unwrap(),
+            go_dirhash(&module, "example.com/m@v1.0.0").unwrap()
+        );
+    }
+}
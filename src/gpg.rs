@@ -0,0 +1,284 @@
+//! OpenPGP verification of detached signatures over checksum files, as published next to
+//! `SHA256SUMS` by most Linux distributions and release pipelines.
+//!
+//! A `SHA256SUMS` file without its signature only proves that files match whatever list the
+//! download came with. [`ChecksumFile::read_gpg_verified`] first checks the `.asc`, `.sig` or
+//! `.gpg` signature beside the file against a [`Keyring`] of trusted certificates, exported
+//! with `gpg --export`. Verification uses [Sequoia](https://sequoia-pgp.org/) and its standard
+//! policy, so signatures over SHA-1 or made with revoked or expired keys are rejected.
+//!
+//! # Examples
+//! ```no_run
+//! use sha_file_hashing::ChecksumFile;
+//! use sha_file_hashing::gpg::Keyring;
+//!
+//! let keyring = Keyring::read("release-keys.asc").unwrap();
+//! let sums = ChecksumFile::read_gpg_verified("SHA256SUMS", &keyring).unwrap();
+//! ```
+
+use crate::{ChecksumFile, SHAError};
+use openpgp::cert::{Cert, CertParser};
+use openpgp::parse::Parse;
+use openpgp::parse::stream::{
+    DetachedVerifierBuilder, MessageLayer, MessageStructure, VerificationHelper,
+};
+use openpgp::policy::StandardPolicy;
+use openpgp::{Fingerprint, KeyHandle};
+use sequoia_openpgp as openpgp;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Extensions tried for the detached signature of a file, in order.
+const SIGNATURE_EXTENSIONS: [&str; 3] = ["asc", "sig", "gpg"];
+
+/// A set of trusted OpenPGP certificates (public keys).
+///
+/// Reads armored or binary keyrings holding any number of certificates, such as the output of
+/// `gpg --export --armor`.
+#[derive(Debug, Clone)]
+pub struct Keyring {
+    certs: Vec<Cert>,
+}
+
+impl Keyring {
+    /// Reads a keyring file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parses an armored or binary keyring.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidKey`] if the data is not OpenPGP or holds no certificates.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SHAError> {
+        let certs = CertParser::from_bytes(bytes)
+            .and_then(|parser| parser.collect::<openpgp::Result<Vec<_>>>())
+            .map_err(|e| SHAError::InvalidKey(e.to_string()))?;
+        if certs.is_empty() {
+            return Err(SHAError::InvalidKey(
+                "keyring holds no certificates".to_string(),
+            ));
+        }
+        Ok(Self { certs })
+    }
+
+    /// Returns the fingerprints of the certificates, in hex like `gpg --fingerprint` shows them.
+    pub fn fingerprints(&self) -> impl Iterator<Item = String> + '_ {
+        self.certs.iter().map(|cert| cert.fingerprint().to_hex())
+    }
+
+    /// Checks a detached signature over `data`, returning the fingerprint of the certificate
+    /// that made it.
+    ///
+    /// `signature` may be armored (`.asc`) or binary (`.sig`, `.gpg`). If it holds several
+    /// signatures, one valid signature from a certificate in the keyring is enough.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidSignature`] if the signature is malformed, was made by a key
+    /// outside the keyring, or does not match `data`.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<String, SHAError> {
+        let policy = StandardPolicy::new();
+        let helper = Helper {
+            certs: &self.certs,
+            signer: None,
+            errors: Vec::new(),
+        };
+        let mut verifier = DetachedVerifierBuilder::from_bytes(signature)
+            .and_then(|builder| builder.with_policy(&policy, None, helper))
+            .map_err(|e| SHAError::InvalidSignature(e.to_string()))?;
+        let result = verifier.verify_bytes(data);
+        let helper = verifier.into_helper();
+        match (result, helper.signer) {
+            (Ok(()), Some(signer)) => Ok(signer.to_hex()),
+            (result, _) => Err(SHAError::InvalidSignature(match helper.errors.is_empty() {
+                true => result.err().map_or_else(String::new, |e| e.to_string()),
+                false => helper.errors.join("; "),
+            })),
+        }
+    }
+
+    /// Checks the file at `path` against the first of `<path>.asc`, `<path>.sig` and
+    /// `<path>.gpg` that exists, returning the signer's fingerprint.
+    pub fn verify_file(&self, path: impl AsRef<Path>) -> Result<String, SHAError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        self.verify(&data, &read_signature(path)?)
+    }
+}
+
+impl FromStr for Keyring {
+    type Err = SHAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(s.as_bytes())
+    }
+}
+
+impl ChecksumFile {
+    /// Reads the checksum file at `path` after checking its detached signature, the first of
+    /// `<path>.asc`, `<path>.sig` and `<path>.gpg` that exists, against `keyring`.
+    ///
+    /// The bytes checked are the bytes parsed, so the file cannot change in between.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidSignature`] if there is no signature or it does not verify,
+    /// and otherwise fails like [`read`](ChecksumFile::read).
+    pub fn read_gpg_verified(path: impl AsRef<Path>, keyring: &Keyring) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        keyring.verify(&data, &read_signature(path)?)?;
+        let text = String::from_utf8(data).map_err(|e| {
+            let e = std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+            SHAError::from(e).at_path(path)
+        })?;
+        text.parse()
+    }
+}
+
+/// Hands the keyring to Sequoia and records which certificate signed.
+struct Helper<'a> {
+    certs: &'a [Cert],
+    signer: Option<Fingerprint>,
+    errors: Vec<String>,
+}
+
+impl VerificationHelper for Helper<'_> {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> openpgp::Result<Vec<Cert>> {
+        Ok(self.certs.to_vec())
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> openpgp::Result<()> {
+        for layer in structure {
+            let MessageLayer::SignatureGroup { results } = layer else {
+                continue;
+            };
+            for result in results {
+                match result {
+                    Ok(good) => {
+                        self.signer = Some(good.ka.cert().fingerprint());
+                        return Ok(());
+                    }
+                    Err(e) => self.errors.push(e.to_string()),
+                }
+            }
+        }
+        if self.errors.is_empty() {
+            self.errors.push("no signature found".to_string());
+        }
+        Err(openpgp::Error::InvalidOperation(self.errors.join("; ")).into())
+    }
+}
+
+fn read_signature(path: &Path) -> Result<Vec<u8>, SHAError> {
+    for extension in SIGNATURE_EXTENSIONS {
+        let signature_path = signature_path(path, extension);
+        match fs::read(&signature_path) {
+            Ok(signature) => return Ok(signature),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(SHAError::from(e).at_path(&signature_path)),
+        }
+    }
+    Err(SHAError::InvalidSignature(format!(
+        "no .asc, .sig or .gpg signature for {}",
+        path.display()
+    )))
+}
+
+/// Returns `<path>.<extension>`, keeping any extension `path` already has.
+fn signature_path(path: &Path, extension: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openpgp::cert::CertBuilder;
+    use openpgp::serialize::Serialize;
+    use tempfile::TempDir;
+
+    // An Ed25519 key and a signature over SUMS made by GnuPG
+    const PUBLIC_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEas8IFhYJKwYBBAHaRw8BAQdAB3YfnLxpSv5jxzAmdK1KmJQw/xMbYvR2z7xU
+HA9t4BG0JVJlbGVhc2UgU2lnbmluZyA8cmVsZWFzZUBleGFtcGxlLm9yZz6IkAQT
+FggAOBYhBE0zIkMDMojdMfEsrGb57Z+NZ3VHBQJqzwgWAhsDBQsJCAcCBhUKCQgL
+AgQWAgMBAh4BAheAAAoJEGb57Z+NZ3VHoxgA/j9LcSEFKAvRaVgzTOsNUJhAqE7x
+t5SGXEEq0NtQp7QsAQC18+/QRqVviytcrKIENR29iWmye9VouhqKB1p3PBThCg==
+=pJiB
+-----END PGP PUBLIC KEY BLOCK-----
+";
+    const SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----
+
+iHUEABYIAB0WIQRNMyJDAzKI3THxLKxm+e2fjWd1RwUCas8IFgAKCRBm+e2fjWd1
+R2yZAQDYVi+eJFNiQgiOzeT4C4wubU/u8ZAmvQUB74woCXR2MwD+O7ADsA2uakS9
+y/sBPcIEXx55/qYkKhUJGkVyeJVP1A8=
+=HlOe
+-----END PGP SIGNATURE-----
+";
+    const SUMS: &str =
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.txt\n";
+    const FINGERPRINT: &str = "4D332243033288DD31F12CAC66F9ED9F8D677547";
+
+    #[test]
+    fn test_verify_gpg_signature() {
+        let keyring: Keyring = PUBLIC_KEY.parse().unwrap();
+        assert_eq!(keyring.fingerprints().collect::<Vec<_>>(), [FINGERPRINT]);
+        assert_eq!(
+            keyring
+                .verify(SUMS.as_bytes(), SIGNATURE.as_bytes())
+                .unwrap(),
+            FINGERPRINT
+        );
+
+        let tampered = SUMS.replace("empty.txt", "other.txt");
+        assert!(matches!(
+            keyring.verify(tampered.as_bytes(), SIGNATURE.as_bytes()),
+            Err(SHAError::InvalidSignature(_))
+        ));
+        assert!(keyring.verify(SUMS.as_bytes(), b"not a signature").is_err());
+
+        // A keyring without the signing key
+        let (other, _) = CertBuilder::general_purpose(Some("Other <other@example.org>"))
+            .generate()
+            .unwrap();
+        let mut exported = Vec::new();
+        other.serialize(&mut exported).unwrap();
+        let other = Keyring::from_bytes(&exported).unwrap();
+        assert!(matches!(
+            other.verify(SUMS.as_bytes(), SIGNATURE.as_bytes()),
+            Err(SHAError::InvalidSignature(_))
+        ));
+
+        assert!(matches!(
+            "".parse::<Keyring>(),
+            Err(SHAError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_gpg_verified() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("SHA256SUMS");
+        let keyring: Keyring = PUBLIC_KEY.parse().unwrap();
+        fs::write(&path, SUMS).unwrap();
+        assert!(matches!(
+            ChecksumFile::read_gpg_verified(&path, &keyring),
+            Err(SHAError::InvalidSignature(_))
+        ));
+
+        fs::write(signature_path(&path, "sig"), SIGNATURE).unwrap();
+        let sums = ChecksumFile::read_gpg_verified(&path, &keyring).unwrap();
+        assert_eq!(sums.to_string(), SUMS);
+        assert_eq!(keyring.verify_file(&path).unwrap(), FINGERPRINT);
+
+        fs::write(&path, SUMS.replace("e3b0", "0000")).unwrap();
+        assert!(ChecksumFile::read_gpg_verified(&path, &keyring).is_err());
+    }
+}
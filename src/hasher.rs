@@ -0,0 +1,226 @@
+use crate::options::DEFAULT_BUFFER_SIZE;
+use crate::{Digest, HashAlgorithm, SHAError};
+use digest::DynDigest;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// An incremental hasher fed with [`update`](Hasher::update) calls.
+///
+/// Useful when the data passes through the program in chunks anyway, for example while it is
+/// being written to disk, so the digest is ready without reading the file back. `Hasher` also
+/// implements [`Write`], so it can sit behind [`io::copy`] or a tee-style writer.
+///
+/// A `Hasher` can also hash whole files with [`hash_path`](Hasher::hash_path). Its state and
+/// read buffer are reused from one file to the next, so a loop over hundreds of thousands of
+/// small files makes no allocations per file beyond what opening it costs.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::{HashAlgorithm, Hasher};
+///
+/// let mut hasher = Hasher::new(HashAlgorithm::Sha1);
+/// hasher.update(b"Hello, ");
+/// hasher.update(b"World!");
+/// assert_eq!(
+///     hasher.finalize().to_hex(),
+///     "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+/// );
+/// ```
+pub struct Hasher {
+    algorithm: HashAlgorithm,
+    state: Box<dyn DynDigest>,
+    buffer: Vec<u8>,
+}
+
+impl Hasher {
+    /// Creates a hasher for `algorithm` with no data processed.
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            state: algorithm.hasher(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Creates a hasher for `algorithm` around an existing state, such as one with a custom
+    /// output length.
+    #[cfg(feature = "shake")]
+    pub(crate) fn from_state(algorithm: HashAlgorithm, state: Box<dyn DynDigest>) -> Self {
+        Self {
+            algorithm,
+            state,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the algorithm this hasher computes.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Feeds `data` into the hash.
+    pub fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.state.update(data.as_ref());
+    }
+
+    /// Consumes the hasher and returns the digest of everything fed to it.
+    pub fn finalize(self) -> Digest {
+        Digest::from(self.state.finalize())
+    }
+
+    /// Returns the digest so far and resets the hasher for reuse.
+    pub fn finalize_reset(&mut self) -> Digest {
+        Digest::from(self.state.finalize_reset())
+    }
+
+    /// Discards all data processed so far.
+    pub fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    /// Hashes the file at `path`, reusing this hasher's state and read buffer.
+    ///
+    /// Any data fed with [`update`](Hasher::update) beforehand is discarded, and the hasher is
+    /// left reset afterwards, even on error, ready for the next file.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::{HashAlgorithm, Hasher};
+    ///
+    /// let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+    /// for path in ["a.txt", "b.txt", "c.txt"] {
+    ///     println!("{}  {}", hasher.hash_path(path).unwrap(), path);
+    /// }
+    /// ```
+    pub fn hash_path(&mut self, path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+        let file = File::open(path.as_ref())?;
+        self.hash_reader(file)
+    }
+
+    /// Hashes everything read from `reader`, reusing this hasher's state and read buffer.
+    ///
+    /// Like [`hash_path`](Hasher::hash_path), earlier data is discarded and the hasher is left
+    /// reset.
+    pub fn hash_reader(&mut self, mut reader: impl Read) -> Result<Digest, SHAError> {
+        self.reset();
+        if self.buffer.is_empty() {
+            self.buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+        }
+        loop {
+            match reader.read(&mut self.buffer) {
+                Ok(0) => return Ok(self.finalize_reset()),
+                Ok(n) => self.state.update(&self.buffer[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.reset();
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+}
+
+impl Default for Hasher {
+    /// Creates a SHA-1 hasher.
+    fn default() -> Self {
+        Self::new(HashAlgorithm::default())
+    }
+}
+
+impl Clone for Hasher {
+    fn clone(&self) -> Self {
+        Self {
+            algorithm: self.algorithm,
+            state: self.state.box_clone(),
+            // The read buffer holds no state worth copying
+            buffer: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for Hasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hasher")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+
+    #[test]
+    fn test_incremental_matches_one_shot() {
+        let data = vec![b'x'; 20000];
+        let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+        for chunk in data.chunks(777) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.algorithm(), HashAlgorithm::Sha256);
+        assert_eq!(
+            hasher.finalize(),
+            data.hash_with(HashAlgorithm::Sha256).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_finalize_reset_and_clone() {
+        let mut hasher = Hasher::default();
+        hasher.update("test data");
+        let snapshot = hasher.clone();
+
+        let first = hasher.finalize_reset();
+        assert_eq!(first, "f48dd853820860816c75d54d0f584dc863327a7c");
+        assert_eq!(snapshot.finalize(), first);
+        assert_eq!(
+            hasher.finalize(),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+    }
+
+    #[test]
+    fn test_hash_path_reuses_hasher() {
+        use std::io::Write;
+        use tempfile::NamedTempFile;
+
+        let mut hasher = Hasher::new(HashAlgorithm::Sha1);
+        hasher.update("stale data");
+        for content in [&b"test data"[..], b"", &[b'z'; 300_000]] {
+            let mut temp_file = NamedTempFile::new().unwrap();
+            temp_file.write_all(content).unwrap();
+            temp_file.flush().unwrap();
+            assert_eq!(
+                hasher.hash_path(temp_file.path()).unwrap(),
+                content.hash().unwrap()
+            );
+        }
+
+        assert!(hasher.hash_path("nonexistent_file_12345.txt").is_err());
+        assert_eq!(
+            hasher.hash_reader(&b"Hello, World!"[..]).unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+    }
+
+    #[test]
+    fn test_write_via_io_copy() {
+        let mut hasher = Hasher::new(HashAlgorithm::Md5);
+        io::copy(&mut &b"Hello, World!"[..], &mut hasher).unwrap();
+        assert_eq!(hasher.finalize(), "65a8e27d8879283831b664bd8b7f0ad4");
+    }
+}
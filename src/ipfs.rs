@@ -0,0 +1,293 @@
+//! IPFS content identifiers, as `ipfs add --cid-version 1` computes them.
+//!
+//! Files are split into 256 KiB chunks stored as raw blocks, which are linked into a balanced
+//! UnixFS DAG of dag-pb nodes holding at most 174 links each. A file that fits in one chunk is
+//! identified by its raw block alone.
+
+use crate::multihash::write_varint;
+use crate::{Digest, HashAlgorithm, SHAError, base32};
+use sha2::Digest as _;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// The size of the chunks Kubo's default `size-262144` chunker cuts.
+const CHUNK_SIZE: usize = 256 * 1024;
+/// The most links Kubo puts in one node of a balanced DAG.
+const MAX_LINKS: usize = 174;
+
+const CODEC_RAW: u64 = 0x55;
+const CODEC_DAG_PB: u64 = 0x70;
+
+/// Computes the CIDv1 IPFS would assign the file at `path`, in its usual base32 form.
+///
+/// The result matches `ipfs add --cid-version 1` with the default chunker and balanced layout,
+/// so content can be checked against a CID before or after it is published. Other import
+/// settings, such as `--trickle` or a different `--chunker`, lead to different CIDs.
+///
+/// # Errors
+/// Returns [`SHAError::File`] if the file cannot be read.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::ipfs_cid;
+///
+/// let cid = ipfs_cid("site.tar").unwrap();
+/// println!("https://ipfs.io/ipfs/{}", cid);
+/// ```
+pub fn ipfs_cid(path: impl AsRef<Path>) -> Result<String, SHAError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    ipfs_cid_reader(file).map_err(|e| e.at_path(path))
+}
+
+/// Computes the CIDv1 of everything read from `reader`; see [`ipfs_cid`].
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::ipfs_cid_reader;
+///
+/// let cid = ipfs_cid_reader(std::io::empty()).unwrap();
+/// assert_eq!(cid, "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+/// ```
+pub fn ipfs_cid_reader(mut reader: impl Read) -> Result<String, SHAError> {
+    let mut builder = DagBuilder::default();
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    loop {
+        let len = read_chunk(&mut reader, &mut chunk)?;
+        if len == 0 && !builder.levels.is_empty() {
+            break;
+        }
+        builder.push(
+            0,
+            Link {
+                cid: cid(CODEC_RAW, &chunk[..len]),
+                tsize: len as u64,
+                file_size: len as u64,
+            },
+        );
+        if len < CHUNK_SIZE {
+            break;
+        }
+    }
+    Ok(format!("b{}", multibase_base32(&builder.finish().cid)))
+}
+
+/// Fills `chunk` from `reader`, returning less than its length only at the end of the input.
+fn read_chunk(reader: &mut impl Read, chunk: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < chunk.len() {
+        match reader.read(&mut chunk[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(len)
+}
+
+/// A block as its parent links to it.
+struct Link {
+    /// The binary CID of the block.
+    cid: Vec<u8>,
+    /// The total size of the block and every block below it.
+    tsize: u64,
+    /// The number of file bytes the block covers.
+    file_size: u64,
+}
+
+/// Builds the balanced DAG bottom-up while the file streams in, keeping only the links of the
+/// nodes not yet full at each depth.
+#[derive(Default)]
+struct DagBuilder {
+    levels: Vec<Vec<Link>>,
+}
+
+impl DagBuilder {
+    fn push(&mut self, depth: usize, link: Link) {
+        if self.levels.len() == depth {
+            self.levels.push(Vec::with_capacity(MAX_LINKS));
+        }
+        if self.levels[depth].len() == MAX_LINKS {
+            let full = std::mem::take(&mut self.levels[depth]);
+            self.push(depth + 1, node(full));
+        }
+        self.levels[depth].push(link);
+    }
+
+    fn finish(mut self) -> Link {
+        for depth in 0.. {
+            let links = std::mem::take(&mut self.levels[depth]);
+            if depth + 1 == self.levels.len() && links.len() == 1 {
+                return links.into_iter().next().expect("one link");
+            }
+            self.push(depth + 1, node(links));
+        }
+        unreachable!()
+    }
+}
+
+/// Encodes a UnixFS file node over `links` as dag-pb.
+fn node(links: Vec<Link>) -> Link {
+    let file_size = links.iter().map(|link| link.file_size).sum();
+    // UnixFS Data { Type: File, filesize, blocksizes }
+    let mut data = vec![0x08, 0x02, 0x18];
+    write_varint(&mut data, file_size);
+    for link in &links {
+        data.push(0x20);
+        write_varint(&mut data, link.file_size);
+    }
+
+    // PBNode { Links, Data }, with every link's Name present but empty, as Kubo writes them
+    let mut block = Vec::new();
+    for link in &links {
+        let mut encoded = vec![0x0a];
+        write_varint(&mut encoded, link.cid.len() as u64);
+        encoded.extend_from_slice(&link.cid);
+        encoded.extend_from_slice(&[0x12, 0x00, 0x18]);
+        write_varint(&mut encoded, link.tsize);
+        block.push(0x12);
+        write_varint(&mut block, encoded.len() as u64);
+        block.extend_from_slice(&encoded);
+    }
+    block.push(0x0a);
+    write_varint(&mut block, data.len() as u64);
+    block.extend_from_slice(&data);
+
+    Link {
+        cid: cid(CODEC_DAG_PB, &block),
+        tsize: block.len() as u64 + links.iter().map(|link| link.tsize).sum::<u64>(),
+        file_size,
+    }
+}
+
+/// Returns the binary CIDv1 of a block, addressed by its SHA-256 digest.
+fn cid(codec: u64, block: &[u8]) -> Vec<u8> {
+    let digest = Digest::from_bytes(sha2::Sha256::digest(block).to_vec());
+    let mut cid = vec![0x01];
+    write_varint(&mut cid, codec);
+    cid.extend(
+        digest
+            .to_multihash(HashAlgorithm::Sha256)
+            .expect("SHA-256 has a multihash code"),
+    );
+    cid
+}
+
+/// Encodes `bytes` as lowercase, unpadded RFC 4648 base32, the multibase `b` form.
+fn multibase_base32(bytes: &[u8]) -> String {
+    base32::encode(bytes, false).to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::iter::Peekable;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_single_chunk_is_raw_block() {
+        let data = vec![7u8; CHUNK_SIZE];
+        let cid = ipfs_cid_reader(Cursor::new(&data)).unwrap();
+        assert!(cid.starts_with("bafkrei"), "{}", cid);
+        assert_eq!(
+            cid,
+            format!("b{}", multibase_base32(&super::cid(CODEC_RAW, &data)))
+        );
+
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &data).unwrap();
+        assert_eq!(ipfs_cid(file.path()).unwrap(), cid);
+    }
+
+    #[test]
+    fn test_multiple_chunks_build_dag() {
+        let cid = ipfs_cid_reader(Cursor::new(vec![7u8; CHUNK_SIZE + 1])).unwrap();
+        assert!(cid.starts_with("bafybei"), "{}", cid);
+
+        // Two raw leaves under one node, whose sizes include the leaves
+        let mut builder = DagBuilder::default();
+        for len in [3, 1] {
+            builder.push(
+                0,
+                Link {
+                    cid: super::cid(CODEC_RAW, &vec![0; len]),
+                    tsize: len as u64,
+                    file_size: len as u64,
+                },
+            );
+        }
+        let root = builder.finish();
+        assert_eq!(root.file_size, 4);
+        assert_eq!(root.tsize, 2 * 44 + 10 + 4);
+    }
+
+    /// Kubo's recursive balanced layout, to check the streaming builder against.
+    fn layout(leaves: &mut Peekable<impl Iterator<Item = Link>>) -> Link {
+        fn fill(
+            leaves: &mut Peekable<impl Iterator<Item = Link>>,
+            mut links: Vec<Link>,
+            depth: usize,
+        ) -> Link {
+            while links.len() < MAX_LINKS && leaves.peek().is_some() {
+                let child = match depth {
+                    1 => leaves.next().unwrap(),
+                    _ => fill(leaves, Vec::new(), depth - 1),
+                };
+                links.push(child);
+            }
+            node(links)
+        }
+
+        let mut root = leaves.next().unwrap();
+        for depth in 1.. {
+            if leaves.peek().is_none() {
+                break;
+            }
+            root = fill(leaves, vec![root], depth);
+        }
+        root
+    }
+
+    #[test]
+    fn test_balanced_layout() {
+        let leaves = |count: usize| {
+            (0..count).map(|i| {
+                let block = i.to_le_bytes();
+                Link {
+                    cid: super::cid(CODEC_RAW, &block),
+                    tsize: block.len() as u64,
+                    file_size: block.len() as u64,
+                }
+            })
+        };
+        for count in [
+            1,
+            2,
+            MAX_LINKS,
+            MAX_LINKS + 1,
+            MAX_LINKS * MAX_LINKS,
+            MAX_LINKS * MAX_LINKS + 1,
+            MAX_LINKS * MAX_LINKS + MAX_LINKS + 1,
+        ] {
+            let mut builder = DagBuilder::default();
+            for leaf in leaves(count) {
+                builder.push(0, leaf);
+            }
+            let root = builder.finish();
+            let expected = layout(&mut leaves(count).peekable());
+            assert_eq!(root.cid, expected.cid, "{} leaves", count);
+            assert_eq!(root.tsize, expected.tsize);
+            assert_eq!(root.file_size, 8 * count as u64);
+        }
+    }
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(multibase_base32(b""), "");
+        assert_eq!(multibase_base32(b"f"), "my");
+        assert_eq!(multibase_base32(b"foobar"), "mzxw6ytboi");
+    }
+}
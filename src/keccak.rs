@@ -0,0 +1,84 @@
+//! Keccak-256 digests as used by Ethereum.
+//!
+//! Available with the `keccak` feature. This is the original Keccak submission padding
+//! (`0x01`), **not** the FIPS 202 SHA3-256 padding, so the output matches `keccak256` in
+//! Solidity and Ethereum client tooling. The functions mirror the crate-level SHA-1 API and
+//! return the digest as a [`Digest`], whose hex form has no `0x` prefix.
+//!
+//! ```no_run
+//! use sha_file_hashing::keccak;
+//!
+//! let digest = keccak::hash_file_from_path("contract.bin").unwrap();
+//! assert!(keccak::validate_file_from_path("contract.bin", digest.to_hex()).unwrap());
+//! ```
+
+use crate::{Digest, HashAlgorithm, SHAError, hash_file_with_algorithm};
+use std::fs::File;
+use std::path::Path;
+
+pub use sha3::Keccak256;
+
+/// Computes the Keccak-256 digest of a `File` handle.
+pub fn hash_file(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Keccak256)
+}
+
+/// Computes the Keccak-256 digest of the file at `path`.
+pub fn hash_file_from_path(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file(File::open(path.as_ref())?)
+}
+
+/// Validates a `File` handle against an expected Keccak-256 digest (case-insensitive).
+///
+/// A leading `0x` on the expected digest is accepted, since Ethereum tooling usually prints one.
+pub fn validate_file(file: File, hash: impl AsRef<str>) -> bool {
+    hash_file(file)
+        .and_then(|computed| matches(&computed, hash.as_ref()))
+        .unwrap_or(false)
+}
+
+/// Validates the file at `path` against an expected Keccak-256 digest (case-insensitive).
+///
+/// A leading `0x` on the expected digest is accepted, since Ethereum tooling usually prints one.
+pub fn validate_file_from_path(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    matches(&hash_file_from_path(path)?, hash.as_ref())
+}
+
+fn matches(computed: &Digest, expected: &str) -> Result<bool, SHAError> {
+    let expected = expected
+        .strip_prefix("0x")
+        .or_else(|| expected.strip_prefix("0X"))
+        .unwrap_or(expected);
+    computed.matches_hex(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    const EMPTY_KECCAK: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
+    #[test]
+    fn test_keccak_empty() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        assert_eq!(hash_file_from_path(temp_file.path()).unwrap(), EMPTY_KECCAK);
+    }
+
+    #[test]
+    fn test_keccak_validate_with_prefix() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        let prefixed = format!("0x{}", EMPTY_KECCAK.to_uppercase());
+        assert!(validate_file_from_path(temp_file.path(), &prefixed).unwrap());
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(!validate_file(
+            file,
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        ));
+    }
+}
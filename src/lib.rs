@@ -1,15 +1,153 @@
 #![doc = include_str!("../README.md")]
 
-use sha1::{Digest, Sha1};
+pub mod adler32;
+#[cfg(any(feature = "async-std", feature = "smol"))]
+pub mod agnostic;
+mod algorithm;
+#[cfg(feature = "tokio")]
+mod async_dir;
+#[cfg(feature = "tokio")]
+mod async_progress;
+#[cfg(feature = "tokio")]
+mod async_tokio;
+mod authenticode;
+mod bagit;
+mod base32;
+mod bencode;
+mod cache;
+mod cancel;
+#[cfg(feature = "toml")]
+mod cargo;
+mod checksum;
+mod cloud;
+pub mod crc64;
+mod csv;
+mod debian;
+mod dir;
+mod direct;
+#[cfg(feature = "ed2k")]
+pub mod ed2k;
+mod elf;
+mod fingerprint;
+mod format;
+mod git;
+mod gomod;
+#[cfg(feature = "gpg")]
+pub mod gpg;
+mod hasher;
+#[cfg(feature = "ipfs")]
+mod ipfs;
+#[cfg(feature = "keccak")]
+pub mod keccak;
+mod mac;
+mod merge;
+mod merkle;
+#[cfg(feature = "sign")]
+pub mod minisign;
+#[cfg(feature = "json")]
+mod modrinth;
+mod multihash;
+mod ni;
+mod nix;
+#[cfg(feature = "json")]
+mod npm;
+#[cfg(feature = "oci")]
+mod oci;
+mod options;
+mod output;
+mod paths;
+mod pipeline;
+mod pool;
+mod report;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sfv;
+mod sidecar;
+#[cfg(feature = "sm3")]
+pub mod sm3;
+mod source;
+mod sri;
+#[cfg(feature = "streebog")]
+pub mod streebog;
+mod torrent;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+pub mod uring;
+#[cfg(feature = "shake")]
+mod xof;
+
+pub use algorithm::HashAlgorithm;
+#[cfg(feature = "tokio")]
+pub use async_dir::{DirHashStream, hash_dir_async};
+#[cfg(feature = "tokio")]
+pub use async_progress::{HashEvent, HashEventStream, hash_file_events};
+#[cfg(feature = "tokio")]
+pub use async_tokio::{
+    AsyncHashable, hash_async_reader, hash_async_reader_with_algorithm, hash_file_async,
+    hash_file_async_with_algorithm, hash_file_async_with_options, validate_file_async,
+    validate_file_async_with_algorithm,
+};
+pub use authenticode::authenticode_hash;
+pub use bagit::{Bag, BagReport};
+pub use cache::HashCache;
+pub use cancel::CancelToken;
+#[cfg(feature = "toml")]
+pub use cargo::{CargoLock, LockedCrate};
+pub use checksum::{ChecksumEntry, ChecksumFile, ChecksumFormat, ChecksumLine};
+pub use cloud::{azure_content_crc64, azure_content_md5, glacier_tree_hash, s3_etag};
+pub use debian::{DebianFile, DebianPackage, DebianPackages, DebianRelease};
+/// Re-export of the `digest` crate, so [`hash_file_with`] can be used without a version mismatch.
+pub use digest;
+pub use dir::{
+    DigestChange, DirDiff, DirOptions, FileStamp, HASHIGNORE_FILE, Manifest, diff_dirs, hash_dir,
+    hash_tree,
+};
+pub use elf::{elf_build_id, verify_elf_build_id};
+pub use fingerprint::{curseforge_fingerprint, fnv1a_file, siphash_file};
+pub use format::OutputFormat;
+pub use git::{hash_git_blob, hash_git_blob_with, hash_git_tree};
+pub use gomod::go_dirhash;
+#[cfg(feature = "zip")]
+pub use gomod::go_ziphash;
+pub use hasher::Hasher;
+#[cfg(feature = "ipfs")]
+pub use ipfs::{ipfs_cid, ipfs_cid_reader};
+pub use mac::{hmac_file, validate_hmac_file};
+pub use merge::ConflictPolicy;
+pub use merkle::{MerkleNode, MerkleTree};
+#[cfg(feature = "json")]
+pub use modrinth::{MODRINTH_INDEX_FILE, ModrinthFile, ModrinthIndex};
+#[cfg(feature = "json")]
+pub use npm::{LockedPackage, PackageLock};
+#[cfg(feature = "oci")]
+pub use oci::{verify_oci_archive, verify_oci_layout};
+pub use options::{HasherOptions, SymlinkPolicy};
+pub use output::Digest;
+pub use paths::PathOptions;
+pub use pool::{HashJob, HashPool};
+pub use report::{FileOutcome, VerificationReport, verify_manifest};
+pub use sfv::{SfvEntry, SfvFile, SfvInfo, SfvLine};
+pub use sidecar::{read_sidecar, sidecar_path, verify_sidecar, write_sidecar};
+pub use source::HashSource;
+pub use torrent::{TorrentFile, TorrentInfo, TorrentOptions, TorrentVersion};
+
+use output::declared_algorithm;
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Stdin, Write};
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 
+/// Hashing and validation for files and in-memory data.
+///
+/// Implemented for [`Path`], [`PathBuf`] and [`File`], which hash the **file contents**, and for
+/// `[u8]`, `Vec<u8>`, `str` and `String`, which hash the **value itself**. In particular a
+/// string is never interpreted as a path: wrap it in [`Path::new`] to hash the file it names.
+/// Any other [`Read`] source can be wrapped in a [`HashSource`].
 pub trait Hashable {
-    /// Computes and returns the hash of the current object's data as a `String`.
+    /// Computes and returns the hash of the current object's data as a [`Digest`].
     ///
     /// # Returns
-    /// - `Ok(String)`: A `String` representation of the computed hash if the operation is successful.
+    /// - `Ok(Digest)`: The computed hash if the operation is successful.
     /// - `Err(SHAError)`: An error of type `SHAError` if the hashing process fails.
     ///
     /// # Errors
@@ -27,7 +165,9 @@ pub trait Hashable {
     ///     Err(e) => println!("Error occurred: {:?}", e),
     /// }
     /// ```
-    fn hash(&self) -> Result<String, SHAError>;
+    fn hash(&self) -> Result<Digest, SHAError> {
+        self.hash_with(HashAlgorithm::Sha1)
+    }
     ///
     /// Validates the given hash against some internal criteria or expected value.
     ///
@@ -57,27 +197,164 @@ pub trait Hashable {
     /// }
     /// ```
     ///
-    fn validate(&self, hash: impl AsRef<str>) -> Result<bool, SHAError>;
+    fn validate(&self, hash: impl AsRef<str>) -> Result<bool, SHAError> {
+        self.validate_with(hash, HashAlgorithm::Sha1)
+    }
+
+    /// Computes the hash of the current object's data using the given `algorithm`.
+    ///
+    /// [`hash`](Hashable::hash) is equivalent to calling this with [`HashAlgorithm::Sha1`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::{HashAlgorithm, Hashable};
+    /// use std::path::Path;
+    ///
+    /// let algorithm: HashAlgorithm = "sha256".parse().unwrap();
+    /// let hash = Path::new("example.txt").hash_with(algorithm).unwrap();
+    /// assert_eq!(hash.to_hex().len(), 64);
+    /// ```
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError>;
+
+    /// Validates the given hash using the given `algorithm`.
+    ///
+    /// [`validate`](Hashable::validate) is equivalent to calling this with [`HashAlgorithm::Sha1`].
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError>;
+
+    /// Validates the given SHA-1 hash, returning [`SHAError::FailedValidation`] on a mismatch.
+    ///
+    /// This suits verification pipelines, where a mismatch should abort through `?` like any
+    /// other error instead of being checked as a `bool`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::{Hashable, SHAError};
+    /// use std::path::Path;
+    ///
+    /// fn verify(path: &Path, expected: &str) -> Result<(), SHAError> {
+    ///     path.validate_or_err(expected)?;
+    ///     println!("{} OK", path.display());
+    ///     Ok(())
+    /// }
+    /// ```
+    fn validate_or_err(&self, hash: impl AsRef<str>) -> Result<(), SHAError> {
+        self.validate_or_err_with(hash, HashAlgorithm::Sha1)
+    }
+
+    /// Like [`validate_or_err`](Hashable::validate_or_err), using the given `algorithm`.
+    ///
+    /// [`Path`] and [`PathBuf`] name their path in the error. Other inputs are reported as `-`,
+    /// the name `sha1sum` uses for unnamed input.
+    fn validate_or_err_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<(), SHAError> {
+        let hash = hash.as_ref();
+        self.hash_with(declared_algorithm(hash, algorithm)?)?
+            .ensure_matches(hash, Path::new("-"))
+    }
+
+    /// Computes a Subresource Integrity value such as `sha384-<base64>` with SHA-384, the
+    /// algorithm usually recommended for `integrity` attributes.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::Hashable;
+    /// use std::path::Path;
+    ///
+    /// let integrity = Path::new("dist/app.js").to_sri().unwrap();
+    /// println!(r#"<script src="app.js" integrity="{}"></script>"#, integrity);
+    /// ```
+    fn to_sri(&self) -> Result<String, SHAError> {
+        self.to_sri_with(HashAlgorithm::Sha384)
+    }
+
+    /// Like [`to_sri`](Hashable::to_sri), using SHA-256, SHA-384 or SHA-512.
+    ///
+    /// Other algorithms are rejected with [`SHAError::UnsupportedAlgorithm`] before any data is
+    /// read, since browsers do not accept them.
+    fn to_sri_with(&self, algorithm: HashAlgorithm) -> Result<String, SHAError> {
+        sri::check_algorithm(algorithm)?;
+        self.hash_with(algorithm)?.to_sri(algorithm)
+    }
+
+    /// Validates against an SRI `integrity` value, such as `sha384-<base64>`.
+    ///
+    /// As in browsers, the value may list several hashes separated by spaces. Only those of the
+    /// strongest algorithm listed are checked, and any one of them matching is enough; entries
+    /// with other algorithms are ignored.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidDigest`] if `integrity` holds no well-formed SHA-256, SHA-384
+    /// or SHA-512 hash.
+    fn validate_sri(&self, integrity: impl AsRef<str>) -> Result<bool, SHAError> {
+        let (algorithm, expected) = sri::strongest(integrity.as_ref())?;
+        Ok(sri::matches_any(&self.hash_with(algorithm)?, &expected))
+    }
+
+    /// Computes the SHA-1 hash and writes it as hex in the given [`OutputFormat`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::{Hashable, OutputFormat};
+    /// use std::path::Path;
+    ///
+    /// let format = OutputFormat::new().uppercase(true).separator(':');
+    /// println!("{}", Path::new("cert.der").hash_formatted(&format).unwrap());
+    /// ```
+    fn hash_formatted(&self, format: &OutputFormat) -> Result<String, SHAError> {
+        self.hash_formatted_with(format, HashAlgorithm::Sha1)
+    }
+
+    /// Like [`hash_formatted`](Hashable::hash_formatted), using the given `algorithm`.
+    fn hash_formatted_with(
+        &self,
+        format: &OutputFormat,
+        algorithm: HashAlgorithm,
+    ) -> Result<String, SHAError> {
+        Ok(self.hash_with(algorithm)?.format(format))
+    }
 }
 
 impl Hashable for Path {
-    fn hash(&self) -> Result<String, SHAError> {
-        hash_file_from_path(self)
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        hash_file_from_path_with_algorithm(self, algorithm)
     }
 
-    fn validate(&self, hash: impl AsRef<str>) -> Result<bool, SHAError> {
-        validate_file_from_path(self, hash)
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        validate_file_from_path_with_algorithm(self, hash, algorithm)
+    }
+
+    fn validate_or_err_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<(), SHAError> {
+        validate_or_err_with_algorithm(self, hash, algorithm)
     }
 }
 
 impl Hashable for File {
-    fn hash(&self) -> Result<String, SHAError> {
-        hash_file(self.try_clone()?)
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        hash_file_with_algorithm(self.try_clone()?, algorithm)
     }
 
-    fn validate(&self, hash: impl AsRef<str>) -> Result<bool, SHAError> {
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
         if let Ok(file) = self.try_clone() {
-            Ok(validate_file(file, hash))
+            Ok(validate_file_with_algorithm(file, hash, algorithm))
         } else {
             Err(SHAError::IO(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -88,83 +365,518 @@ impl Hashable for File {
 }
 
 impl Hashable for PathBuf {
-    fn hash(&self) -> Result<String, SHAError> {
-        hash_file_from_path(self)
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        hash_file_from_path_with_algorithm(self, algorithm)
     }
-    fn validate(&self, hash: impl AsRef<str>) -> Result<bool, SHAError> {
-        validate_file_from_path(self, hash)
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        validate_file_from_path_with_algorithm(self, hash, algorithm)
+    }
+
+    fn validate_or_err_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<(), SHAError> {
+        validate_or_err_with_algorithm(self, hash, algorithm)
+    }
+}
+
+/// Hashes in-memory bytes, such as a downloaded body or a generated artifact.
+///
+/// Hashing a buffer cannot fail, so these methods always return `Ok`.
+impl Hashable for [u8] {
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        Ok(hash_bytes_with_algorithm(self, algorithm))
+    }
+
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        let hash = hash.as_ref();
+        hash_bytes_with_algorithm(self, declared_algorithm(hash, algorithm)?).matches_hex(hash)
+    }
+}
+
+/// Hashes the vector's contents; see the `[u8]` implementation.
+impl Hashable for Vec<u8> {
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        self.as_slice().hash_with(algorithm)
+    }
+
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        self.as_slice().validate_with(hash, algorithm)
+    }
+}
+
+/// Hashes the UTF-8 bytes of the string itself.
+///
+/// **This hashes the text, not a file named by it.** `"config.toml".hash()` returns the digest
+/// of the ten bytes `config.toml`; to hash a file, go through [`Path`] instead, e.g.
+/// `Path::new("config.toml").hash()`.
+impl Hashable for str {
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        self.as_bytes().hash_with(algorithm)
+    }
+
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        self.as_bytes().validate_with(hash, algorithm)
+    }
+}
+
+/// Hashes the string's UTF-8 content, not a path; see the `str` implementation.
+impl Hashable for String {
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        self.as_str().hash_with(algorithm)
+    }
+
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        self.as_str().validate_with(hash, algorithm)
+    }
+}
+
+/// Hashes standard input until EOF, holding the stdin lock while reading.
+///
+/// As with any stream, hashing consumes the input; see [`hash_stdin`].
+impl Hashable for Stdin {
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        hash_reader_with_algorithm(self.lock(), algorithm)
+    }
+
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        let hash = hash.as_ref();
+        self.hash_with(declared_algorithm(hash, algorithm)?)?
+            .matches_hex(hash)
     }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum SHAError {
-    #[error("SHA validation failed for file: {0}")]
-    FailedValidation(String),
+    /// The computed hash did not match the expected one.
+    ///
+    /// Both hashes are included, so the failure can be reported without hashing the file again.
+    #[error(
+        "SHA validation failed for file: {} (expected {expected}, found {actual})",
+        path.display()
+    )]
+    FailedValidation {
+        /// The file that failed, or `-` for input without a path.
+        path: PathBuf,
+        /// The expected hash, exactly as it was supplied.
+        expected: String,
+        /// The hash that was actually computed.
+        actual: Digest,
+    },
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
+    /// An I/O error while processing one of many files, such as during a directory walk.
+    #[error("IO error for {}: {source}", path.display())]
+    File {
+        /// The file or directory that could not be read.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+    #[error("Unsupported hash algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("Invalid digest: {0}")]
+    InvalidDigest(String),
+    /// A symbolic link was found where the [`SymlinkPolicy`] does not allow one.
+    #[error("Symbolic link not allowed: {}", .0.display())]
+    Symlink(PathBuf),
+    /// Following symbolic links led back into a directory that is already being walked.
+    #[error("Symbolic link loop at: {}", .0.display())]
+    SymlinkLoop(PathBuf),
+    /// A directory filter is not a valid glob pattern.
+    #[error("Invalid glob pattern: {0}")]
+    InvalidPattern(String),
+    /// A line of a [`ChecksumFile`] or [`SfvFile`] is neither an entry nor a comment.
+    #[error("Invalid checksum line {line}: {text}")]
+    InvalidChecksumLine {
+        /// The 1-based line number.
+        line: usize,
+        /// The offending line.
+        text: String,
+    },
+    /// A directory is not a well-formed BagIt [`Bag`], or cannot be made into one.
+    #[error("Invalid BagIt bag: {0}")]
+    InvalidBag(String),
+    /// A serialized [`Manifest`] could not be parsed.
+    #[error("Invalid manifest: {0}")]
+    InvalidManifest(String),
+    /// A signing or verification key could not be parsed, or is encrypted.
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    /// A signature is missing or malformed, was made with another key, or does not match what it
+    /// signs.
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+    /// A `.torrent` file could not be parsed, or torrent options are invalid.
+    #[error("Invalid torrent: {0}")]
+    InvalidTorrent(String),
+    /// Hashing was stopped through a [`CancelToken`].
+    #[error("Hashing was cancelled")]
+    Cancelled,
+}
+
+impl SHAError {
+    /// Attaches `path` to a bare I/O error; other errors are returned unchanged.
+    pub(crate) fn at_path(self, path: &Path) -> Self {
+        match self {
+            SHAError::IO(source) => SHAError::File {
+                path: path.to_path_buf(),
+                source,
+            },
+            other => other,
+        }
+    }
 }
 
 pub fn validate_file_from_path(
     path: impl AsRef<Path>,
     hash: impl AsRef<str>,
 ) -> Result<bool, SHAError> {
-    let Ok(file) = File::open(path.as_ref()) else {
-        return Err(SHAError::IO(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "File not found",
-        )));
-    };
-    Ok(validate_file(file, hash))
+    validate_file_from_path_with_algorithm(path, hash, HashAlgorithm::Sha1)
 }
 
-pub fn hash_file_from_path(path: impl AsRef<Path>) -> Result<String, SHAError> {
-    let path = path.as_ref();
-    if !path.exists() {
-        Err(SHAError::IO(std::io::Error::new(
-            std::io::ErrorKind::NotFound,
-            "File not found",
-        )))
-    } else {
-        let file = File::open(path)?;
-        let computed = hash_file(file)?;
-        Ok(computed)
-    }
+pub fn hash_file_from_path(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file_from_path_with_algorithm(path, HashAlgorithm::Sha1)
 }
 
 pub fn validate_file(file: File, hash: impl AsRef<str>) -> bool {
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha1::new();
-    let mut buffer = [0u8; 8192];
+    validate_file_with_algorithm(file, hash, HashAlgorithm::Sha1)
+}
 
-    loop {
-        match reader.read(&mut buffer) {
-            Ok(0) => break,
-            Ok(n) => hasher.update(&buffer[..n]),
-            Err(_) => return false,
-        }
-    }
+pub fn hash_file(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Sha1)
+}
+
+/// Validates the file at `path` against a SHA-1 `hash`, failing on a mismatch.
+///
+/// Returns [`SHAError::FailedValidation`] naming `path` when the hash does not match, so
+/// verification steps can be chained with `?`.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::validate_or_err;
+///
+/// fn main() -> Result<(), sha_file_hashing::SHAError> {
+///     validate_or_err("release.tar.gz", "da39a3ee5e6b4b0d3255bfef95601890afd80709")?;
+///     Ok(())
+/// }
+/// ```
+pub fn validate_or_err(path: impl AsRef<Path>, hash: impl AsRef<str>) -> Result<(), SHAError> {
+    validate_or_err_with_algorithm(path, hash, HashAlgorithm::Sha1)
+}
+
+/// Validates the file at `path` against `hash` computed with `algorithm`, failing on a mismatch.
+///
+/// See [`validate_or_err`] for details.
+pub fn validate_or_err_with_algorithm(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+    algorithm: HashAlgorithm,
+) -> Result<(), SHAError> {
+    HasherOptions::new()
+        .algorithm(algorithm)
+        .validate_or_err(path, hash)
+}
+
+/// Validates the file at `path` against `hash` computed with `algorithm` (case-insensitive).
+pub fn validate_file_from_path_with_algorithm(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+    algorithm: HashAlgorithm,
+) -> Result<bool, SHAError> {
+    HasherOptions::new()
+        .algorithm(algorithm)
+        .validate_file_from_path(path, hash)
+}
+
+/// Computes the hash of the file at `path` with `algorithm`.
+pub fn hash_file_from_path_with_algorithm(
+    path: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    HasherOptions::new()
+        .algorithm(algorithm)
+        .hash_file_from_path(path)
+}
+
+/// Validates a `File` handle against `hash` computed with `algorithm` (case-insensitive).
+pub fn validate_file_with_algorithm(
+    file: File,
+    hash: impl AsRef<str>,
+    algorithm: HashAlgorithm,
+) -> bool {
+    HasherOptions::new()
+        .algorithm(algorithm)
+        .validate_file(file, hash)
+}
+
+/// Computes the hash of a `File` handle with `algorithm`.
+pub fn hash_file_with_algorithm(file: File, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+    HasherOptions::new().algorithm(algorithm).hash_file(file)
+}
+
+/// Computes the SHA-1 hash of the file at `path` and writes the raw digest bytes to `writer`.
+///
+/// The 20 bytes are written as they are, not as hex, for binary container formats and headers
+/// that embed a digest. The digest is also returned.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::hash_to_writer;
+///
+/// let mut header = b"HDR1".to_vec();
+/// hash_to_writer("payload.bin", &mut header)?;
+/// assert_eq!(header.len(), 24);
+/// # Ok::<(), sha_file_hashing::SHAError>(())
+/// ```
+pub fn hash_to_writer(path: impl AsRef<Path>, writer: &mut impl Write) -> Result<Digest, SHAError> {
+    hash_to_writer_with_algorithm(path, writer, HashAlgorithm::Sha1)
+}
+
+/// Computes the hash of the file at `path` with `algorithm` and writes the raw digest bytes to
+/// `writer`.
+///
+/// See [`hash_to_writer`] for details.
+pub fn hash_to_writer_with_algorithm(
+    path: impl AsRef<Path>,
+    writer: &mut impl Write,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let digest = hash_file_from_path_with_algorithm(path, algorithm)?;
+    writer.write_all(digest.as_bytes())?;
+    Ok(digest)
+}
+
+/// Computes the SHA-1 hash of everything read from `reader` until EOF.
+///
+/// Any [`Read`] source works: network streams, decompressors, pipes or in-memory cursors. The
+/// reader is consumed through the same buffered loop used for files.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::hash_reader;
+/// use std::io::Cursor;
+///
+/// let hash = hash_reader(Cursor::new(b"Hello, World!")).unwrap();
+/// assert_eq!(hash, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+/// ```
+pub fn hash_reader<R: Read>(reader: R) -> Result<Digest, SHAError> {
+    hash_reader_with_algorithm(reader, HashAlgorithm::Sha1)
+}
+
+/// Computes the SHA-1 hash of standard input, reading until EOF.
+///
+/// This backs `somecmd | hash -` style pipelines. Standard input can only be consumed once, so
+/// calling this again after EOF yields the hash of empty input.
+///
+/// # Examples
+/// ```no_run
+/// let hash = sha_file_hashing::hash_stdin().unwrap();
+/// println!("{}  -", hash);
+/// ```
+pub fn hash_stdin() -> Result<Digest, SHAError> {
+    hash_stdin_with_algorithm(HashAlgorithm::Sha1)
+}
+
+/// Computes the hash of standard input with `algorithm`, reading until EOF.
+pub fn hash_stdin_with_algorithm(algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+    std::io::stdin().hash_with(algorithm)
+}
+
+/// Computes the hash of everything read from `reader` with `algorithm`.
+pub fn hash_reader_with_algorithm<R: Read>(
+    reader: R,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    HasherOptions::new()
+        .algorithm(algorithm)
+        .hash_reader(reader)
+}
+
+fn hash_bytes_with_algorithm(bytes: &[u8], algorithm: HashAlgorithm) -> Digest {
+    let mut hasher = algorithm.hasher();
+    hasher.update(bytes);
+    Digest::from(hasher.finalize())
+}
 
-    let result = hasher.finalize();
-    let computed: String = result.iter().map(|b| format!("{:02x}", b)).collect();
-    computed.eq_ignore_ascii_case(hash.as_ref())
+/// Computes several hashes of a `File` handle in a single read pass.
+///
+/// Every chunk read from the file is fed to one hasher per algorithm, so asking for SHA-1,
+/// SHA-256 and MD5 costs one read of the file instead of three. Duplicate algorithms are
+/// only computed once.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashAlgorithm, hash_file_multi};
+/// use std::fs::File;
+///
+/// let file = File::open("release.tar.gz").unwrap();
+/// let hashes = hash_file_multi(
+///     file,
+///     &[HashAlgorithm::Sha1, HashAlgorithm::Sha256, HashAlgorithm::Md5],
+/// )
+/// .unwrap();
+/// println!("sha256: {}", hashes[&HashAlgorithm::Sha256]);
+/// ```
+pub fn hash_file_multi(
+    file: File,
+    algorithms: &[HashAlgorithm],
+) -> Result<HashMap<HashAlgorithm, Digest>, SHAError> {
+    HasherOptions::new().hash_file_multi(file, algorithms)
 }
 
-pub fn hash_file(file: File) -> Result<String, SHAError> {
-    let mut reader = BufReader::new(file);
-    let mut hasher = Sha1::new();
-    let mut buffer = [0u8; 8192];
+/// Computes several hashes of the file at `path` in a single read pass.
+///
+/// See [`hash_file_multi`] for details.
+pub fn hash_file_from_path_multi(
+    path: impl AsRef<Path>,
+    algorithms: &[HashAlgorithm],
+) -> Result<HashMap<HashAlgorithm, Digest>, SHAError> {
+    HasherOptions::new().hash_file_from_path_multi(path, algorithms)
+}
+
+/// Computes the SHA-1 hash of each file in `paths`.
+///
+/// Every path gets its own entry, so one unreadable file does not abort the batch. Use
+/// [`hash_paths_with_algorithm`] for other algorithms or [`HasherOptions::hash_paths`] for
+/// full control.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::hash_paths;
+///
+/// for (path, result) in hash_paths(["a.bin", "b.bin"]) {
+///     match result {
+///         Ok(hash) => println!("{}  {}", hash, path.display()),
+///         Err(e) => eprintln!("{}: {}", path.display(), e),
+///     }
+/// }
+/// ```
+pub fn hash_paths<P: AsRef<Path>>(
+    paths: impl IntoIterator<Item = P>,
+) -> HashMap<PathBuf, Result<Digest, SHAError>> {
+    hash_paths_with_algorithm(paths, HashAlgorithm::Sha1)
+}
+
+/// Computes the hash of each file in `paths` with `algorithm`.
+///
+/// See [`hash_paths`] for details.
+pub fn hash_paths_with_algorithm<P: AsRef<Path>>(
+    paths: impl IntoIterator<Item = P>,
+    algorithm: HashAlgorithm,
+) -> HashMap<PathBuf, Result<Digest, SHAError>> {
+    HasherOptions::new().algorithm(algorithm).hash_paths(paths)
+}
+
+/// Computes the SHA-1 hash of each file in `paths` on `threads` worker threads.
+///
+/// Available with the `rayon` feature; pass zero to use every CPU. See
+/// [`HasherOptions::hash_paths_parallel`] for details and other algorithms.
+///
+/// # Examples
+/// ```no_run
+/// # fn run(paths: Vec<std::path::PathBuf>) -> Result<(), sha_file_hashing::SHAError> {
+/// let results = sha_file_hashing::hash_paths_parallel(&paths, 8)?;
+/// let failed = results.values().filter(|result| result.is_err()).count();
+/// println!("{} of {} files failed", failed, results.len());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "rayon")]
+pub fn hash_paths_parallel<P: AsRef<Path>>(
+    paths: impl IntoIterator<Item = P>,
+    threads: usize,
+) -> Result<HashMap<PathBuf, Result<Digest, SHAError>>, SHAError> {
+    HasherOptions::new().hash_paths_parallel(paths, threads)
+}
+
+/// Computes the hash of a `File` handle with any hasher implementing [`digest::Digest`].
+///
+/// This uses the same buffered reading loop as the rest of the crate, so hashers that are not
+/// covered by [`HashAlgorithm`] (including ones from other crates) can be plugged in directly.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::hash_file_with;
+/// use std::fs::File;
+///
+/// let file = File::open("example.txt").unwrap();
+/// let hash = hash_file_with::<sha2::Sha256>(file).unwrap();
+/// assert_eq!(hash.to_hex().len(), 64);
+/// ```
+pub fn hash_file_with<D: digest::Digest>(file: File) -> Result<Digest, SHAError> {
+    HasherOptions::new().hash_file_with::<D>(file)
+}
+
+/// Computes the hash of the file at `path` with any hasher implementing [`digest::Digest`].
+///
+/// See [`hash_file_with`] for details.
+pub fn hash_file_from_path_with<D: digest::Digest>(
+    path: impl AsRef<Path>,
+) -> Result<Digest, SHAError> {
+    HasherOptions::new().hash_file_from_path_with::<D>(path)
+}
+
+/// Streams `reader` through a buffered read loop sized by `options`, handing each chunk to
+/// `update` and honoring the cancel token.
+///
+/// This is the shared reading loop behind every hashing function in the crate, so all
+/// algorithms get the same memory-bounded behavior regardless of file size.
+pub(crate) fn read_chunks(
+    options: &HasherOptions,
+    reader: impl Read,
+    update: impl FnMut(&[u8]),
+) -> Result<(), SHAError> {
+    options.read_reader(reader, options.selected_buffer_size(), update)
+}
+
+/// Like [`read_chunks`], reading up to `buffer_size` bytes at a time and stopping early when
+/// `update` breaks. Reads interrupted by a signal are retried.
+pub(crate) fn read_chunks_with(
+    reader: impl Read,
+    buffer_size: usize,
+    mut update: impl FnMut(&[u8]) -> ControlFlow<()>,
+) -> std::io::Result<ControlFlow<()>> {
+    let mut reader = BufReader::with_capacity(buffer_size, reader);
+    let mut buffer = vec![0u8; buffer_size];
 
     loop {
         match reader.read(&mut buffer) {
-            Ok(0) => break,
-            Ok(n) => hasher.update(&buffer[..n]),
-            Err(e) => return Err(SHAError::IO(e)),
+            Ok(0) => return Ok(ControlFlow::Continue(())),
+            Ok(n) => {
+                if update(&buffer[..n]).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
     }
-
-    let result = hasher.finalize();
-    let computed: String = result.iter().map(|b| format!("{:02x}", b)).collect();
-    Ok(computed)
 }
 
 #[cfg(test)]
@@ -238,7 +950,7 @@ mod tests {
         temp_file.flush().unwrap();
 
         let hash = hash_file_from_path(temp_file.path()).unwrap();
-        assert_eq!(40, hash.len());
+        assert_eq!(40, hash.to_hex().len());
     }
 
     #[test]
@@ -259,7 +971,7 @@ mod tests {
         temp_file.flush().unwrap();
 
         let hash = hash_file_from_path(temp_file.path()).unwrap();
-        let is_valid = validate_file_from_path(temp_file.path(), &hash).unwrap();
+        let is_valid = validate_file_from_path(temp_file.path(), hash.to_hex()).unwrap();
 
         assert!(is_valid);
     }
@@ -279,8 +991,8 @@ mod tests {
         let path = temp_file.path();
         let hash = path.hash().unwrap();
 
-        assert_eq!(hash.len(), 40);
-        assert!(path.validate(&hash).unwrap());
+        assert_eq!(hash.to_hex().len(), 40);
+        assert!(path.validate(hash.to_hex()).unwrap());
     }
 
     #[test]
@@ -292,8 +1004,8 @@ mod tests {
         let pathbuf = temp_file.path().to_path_buf();
         let hash = pathbuf.hash().unwrap();
 
-        assert_eq!(hash.len(), 40);
-        assert!(pathbuf.validate(&hash).unwrap());
+        assert_eq!(hash.to_hex().len(), 40);
+        assert!(pathbuf.validate(hash.to_hex()).unwrap());
     }
 
     #[test]
@@ -306,13 +1018,365 @@ mod tests {
         let hash = file.hash().unwrap();
 
         let file = File::open(temp_file.path()).unwrap();
-        assert!(file.validate(&hash).unwrap());
+        assert!(file.validate(hash.to_hex()).unwrap());
     }
 
     #[test]
     fn test_sha_error_display() {
-        let error = SHAError::FailedValidation("test.txt".to_string());
-        assert_eq!(error.to_string(), "SHA validation failed for file: test.txt");
+        let error = SHAError::FailedValidation {
+            path: PathBuf::from("test.txt"),
+            expected: "00ff".to_string(),
+            actual: Digest::from(vec![0xab, 0xcd]),
+        };
+        assert_eq!(
+            error.to_string(),
+            "SHA validation failed for file: test.txt (expected 00ff, found abcd)"
+        );
+    }
+
+    #[test]
+    fn test_validate_or_err() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"test data").unwrap();
+        temp_file.flush().unwrap();
+
+        validate_or_err(temp_file.path(), "f48dd853820860816c75d54d0f584dc863327a7c").unwrap();
+        match validate_or_err(temp_file.path(), "0000000000000000000000000000000000000000") {
+            Err(SHAError::FailedValidation {
+                path,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(path, temp_file.path());
+                assert_eq!(expected, "0000000000000000000000000000000000000000");
+                assert_eq!(actual, "f48dd853820860816c75d54d0f584dc863327a7c");
+            }
+            _ => panic!("Expected FailedValidation error"),
+        }
+        assert!(matches!(
+            validate_or_err(
+                "nonexistent_file_12345.txt",
+                "0000000000000000000000000000000000000000"
+            ),
+            Err(SHAError::IO(_))
+        ));
+        assert!(matches!(
+            validate_or_err(temp_file.path(), "not a hash"),
+            Err(SHAError::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_prefixed_hash() {
+        let sha256 = "sha256:916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9";
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"test data").unwrap();
+        temp_file.flush().unwrap();
+
+        // The declared algorithm wins over the SHA-1 default and an explicit one
+        assert!(temp_file.path().validate(sha256).unwrap());
+        assert!(validate_file(temp_file.reopen().unwrap(), sha256));
+        assert!(
+            "test data"
+                .validate_with(sha256, HashAlgorithm::Md5)
+                .unwrap()
+        );
+        assert!(!"other data".validate(sha256).unwrap());
+        validate_or_err(temp_file.path(), sha256).unwrap();
+        match "other data".validate_or_err(sha256) {
+            Err(SHAError::FailedValidation {
+                expected, actual, ..
+            }) => {
+                assert_eq!(expected, sha256);
+                assert_eq!(actual.len(), 32);
+            }
+            _ => panic!("Expected FailedValidation error"),
+        }
+        assert!(matches!(
+            temp_file.path().validate("blake9:00"),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_or_err_trait() {
+        let text = "test data";
+        text.validate_or_err("F48DD853820860816C75D54D0F584DC863327A7C")
+            .unwrap();
+        match text.validate_or_err_with("00000000000000000000000000000000", HashAlgorithm::Md5) {
+            Err(SHAError::FailedValidation { path, actual, .. }) => {
+                assert_eq!(path, Path::new("-"));
+                assert_eq!(actual.len(), 16);
+            }
+            _ => panic!("Expected FailedValidation error"),
+        }
+    }
+
+    #[test]
+    fn test_hash_file_with_algorithm() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"test data").unwrap();
+        temp_file.flush().unwrap();
+
+        let file = File::open(temp_file.path()).unwrap();
+        let hash = hash_file_with_algorithm(file, HashAlgorithm::Sha512).unwrap();
+        assert_eq!(hash.to_hex().len(), 128);
+
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(validate_file_with_algorithm(
+            file,
+            hash.to_hex(),
+            HashAlgorithm::Sha512
+        ));
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(!validate_file_with_algorithm(
+            file,
+            hash.to_hex(),
+            HashAlgorithm::Sha384
+        ));
+    }
+
+    #[test]
+    fn test_file_hashable_with_algorithm() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"file trait test").unwrap();
+        temp_file.flush().unwrap();
+
+        let file = File::open(temp_file.path()).unwrap();
+        let hash = file.hash_with(HashAlgorithm::Sha224).unwrap();
+        assert_eq!(hash.to_hex().len(), 56);
+
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(
+            file.validate_with(hash.to_hex(), HashAlgorithm::Sha224)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_file_with_generic_digest() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+        temp_file.flush().unwrap();
+
+        let file = File::open(temp_file.path()).unwrap();
+        assert_eq!(
+            hash_file_with::<sha1::Sha1>(file).unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+        assert_eq!(
+            hash_file_from_path_with::<sha2::Sha256>(temp_file.path()).unwrap(),
+            hash_file_from_path_with_algorithm(temp_file.path(), HashAlgorithm::Sha256).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_file_multi_matches_single_pass() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&vec![b'Z'; 20000]).unwrap();
+        temp_file.flush().unwrap();
+
+        let algorithms = [
+            HashAlgorithm::Sha1,
+            HashAlgorithm::Sha256,
+            HashAlgorithm::Md5,
+            HashAlgorithm::Sha1,
+        ];
+        let hashes = hash_file_from_path_multi(temp_file.path(), &algorithms).unwrap();
+
+        assert_eq!(hashes.len(), 3);
+        for (algorithm, hash) in &hashes {
+            let expected =
+                hash_file_from_path_with_algorithm(temp_file.path(), *algorithm).unwrap();
+            assert_eq!(hash, &expected);
+        }
+    }
+
+    #[test]
+    fn test_read_chunks_retries_interrupted_reads() {
+        struct Interrupting(bool, std::io::Cursor<&'static [u8]>);
+        impl Read for Interrupting {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.0 = !self.0;
+                if self.0 {
+                    return Err(std::io::ErrorKind::Interrupted.into());
+                }
+                self.1.read(buf)
+            }
+        }
+        let mut data = Vec::new();
+        let reader = Interrupting(false, std::io::Cursor::new(b"test data"));
+        read_chunks(&HasherOptions::new().buffer_size(4), reader, |chunk| {
+            data.extend_from_slice(chunk)
+        })
+        .unwrap();
+        assert_eq!(data, b"test data");
+    }
+
+    #[test]
+    fn test_hash_file_multi_md5_known_value() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        let file = File::open(temp_file.path()).unwrap();
+        let hashes = hash_file_multi(file, &[HashAlgorithm::Md5]).unwrap();
+        assert_eq!(
+            hashes[&HashAlgorithm::Md5],
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+
+    #[test]
+    fn test_bytes_hashable() {
+        let bytes: &[u8] = b"Hello, World!";
+        assert_eq!(
+            bytes.hash().unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+        assert!(
+            bytes
+                .validate("0A0A9F2A6772942557AB5355D76AF442F8F65E01")
+                .unwrap()
+        );
+
+        let vec = bytes.to_vec();
+        assert_eq!(
+            vec.hash_with(HashAlgorithm::Sha256).unwrap(),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+        assert!(
+            !vec.validate("0000000000000000000000000000000000000000")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bytes_match_file_hash() {
+        let data = vec![0x00, 0x01, 0x02, 0xFF, 0xFE, 0xFD];
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&data).unwrap();
+        temp_file.flush().unwrap();
+
+        assert_eq!(data.hash().unwrap(), temp_file.path().hash().unwrap());
+    }
+
+    #[test]
+    fn test_str_hashes_content_not_path() {
+        let text = "Hello, World!";
+        assert_eq!(
+            text.hash().unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+        assert_eq!(
+            text.to_string().hash().unwrap(),
+            text.as_bytes().hash().unwrap()
+        );
+
+        // A string naming an existing file still hashes the string itself
+        let temp_file = NamedTempFile::new().unwrap();
+        let name = temp_file.path().to_str().unwrap();
+        assert_ne!(name.hash().unwrap(), temp_file.path().hash().unwrap());
+        assert_eq!(name.hash().unwrap(), name.as_bytes().hash().unwrap());
+    }
+
+    #[test]
+    fn test_string_validate() {
+        let text = String::from("test data");
+        assert!(
+            text.validate("f48dd853820860816c75d54d0f584dc863327a7c")
+                .unwrap()
+        );
+        assert!(
+            text.validate_with(
+                "916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9",
+                HashAlgorithm::Sha256
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_hash() {
+        let text = "test data";
+        match text.validate("not a hash") {
+            Err(SHAError::InvalidDigest(_)) => {}
+            _ => panic!("Expected InvalidDigest error"),
+        }
+        // Well-formed hex of the wrong length is rejected too, not reported as a mismatch
+        assert!(matches!(
+            text.validate("f48dd853"),
+            Err(SHAError::InvalidDigest(_))
+        ));
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(text.as_bytes()).unwrap();
+        temp_file.flush().unwrap();
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(!validate_file(file, "zz"));
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        let reader = std::io::Cursor::new(b"Hello, World!");
+        assert_eq!(
+            hash_reader(reader).unwrap(),
+            "0a0a9f2a6772942557ab5355d76af442f8f65e01"
+        );
+
+        // Readers composed from several sources hash their concatenation
+        let chained = (&b"Hello, "[..]).chain(&b"World!"[..]);
+        assert_eq!(
+            hash_reader_with_algorithm(chained, HashAlgorithm::Sha256).unwrap(),
+            "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+        );
+    }
+
+    #[test]
+    fn test_hash_to_writer() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut out = b"HDR".to_vec();
+        let digest = hash_to_writer(temp_file.path(), &mut out).unwrap();
+        assert_eq!(&out[..3], b"HDR");
+        assert_eq!(&out[3..], digest.as_bytes());
+        assert_eq!(digest, "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+
+        let mut out = Vec::new();
+        let digest =
+            hash_to_writer_with_algorithm(temp_file.path(), &mut out, HashAlgorithm::Sha256)
+                .unwrap();
+        assert_eq!(out.len(), 32);
+        assert_eq!(Digest::from_bytes(out), digest);
+
+        struct FailingWriter;
+        impl Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        match hash_to_writer(temp_file.path(), &mut FailingWriter) {
+            Err(SHAError::IO(e)) => assert_eq!(e.to_string(), "disk full"),
+            _ => panic!("Expected IO error"),
+        }
+    }
+
+    #[test]
+    fn test_hash_reader_propagates_errors() {
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("connection reset"))
+            }
+        }
+
+        match hash_reader(FailingReader) {
+            Err(SHAError::IO(e)) => assert_eq!(e.to_string(), "connection reset"),
+            _ => panic!("Expected IO error"),
+        }
     }
 
     #[test]
@@ -325,10 +1389,10 @@ mod tests {
         let file = File::open(temp_file.path()).unwrap();
         let hash = hash_file(file).unwrap();
 
-        assert_eq!(hash.len(), 40);
+        assert_eq!(hash.to_hex().len(), 40);
 
         let file = File::open(temp_file.path()).unwrap();
-        assert!(validate_file(file, &hash));
+        assert!(validate_file(file, hash.to_hex()));
     }
 
     #[test]
@@ -342,6 +1406,6 @@ mod tests {
         let hash = hash_file(file).unwrap();
 
         let file = File::open(temp_file.path()).unwrap();
-        assert!(validate_file(file, &hash));
+        assert!(validate_file(file, hash.to_hex()));
     }
 }
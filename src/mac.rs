@@ -0,0 +1,137 @@
+//! Keyed HMAC file authentication.
+
+use crate::{Digest, HashAlgorithm, HasherOptions, SHAError, read_chunks};
+use digest::{KeyInit, Mac};
+use hmac::Hmac;
+use std::fs::File;
+use std::path::Path;
+
+/// Object-safe view of an HMAC instance so the algorithm can be picked at runtime.
+trait DynMac {
+    fn update(&mut self, data: &[u8]);
+    fn finalize(self: Box<Self>) -> Vec<u8>;
+}
+
+impl<M: Mac> DynMac for M {
+    fn update(&mut self, data: &[u8]) {
+        Mac::update(self, data);
+    }
+
+    fn finalize(self: Box<Self>) -> Vec<u8> {
+        Mac::finalize(*self).into_bytes().to_vec()
+    }
+}
+
+fn keyed<M: Mac + KeyInit + 'static>(key: &[u8]) -> Box<dyn DynMac> {
+    Box::new(<M as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length"))
+}
+
+fn new_mac(algorithm: HashAlgorithm, key: &[u8]) -> Result<Box<dyn DynMac>, SHAError> {
+    let mac = match algorithm {
+        HashAlgorithm::Md5 => keyed::<Hmac<md5::Md5>>(key),
+        HashAlgorithm::Sha1 => keyed::<Hmac<sha1::Sha1>>(key),
+        HashAlgorithm::Sha224 => keyed::<Hmac<sha2::Sha224>>(key),
+        HashAlgorithm::Sha256 => keyed::<Hmac<sha2::Sha256>>(key),
+        HashAlgorithm::Sha384 => keyed::<Hmac<sha2::Sha384>>(key),
+        HashAlgorithm::Sha512 => keyed::<Hmac<sha2::Sha512>>(key),
+        #[cfg(feature = "keccak")]
+        HashAlgorithm::Keccak256 => keyed::<hmac::SimpleHmac<sha3::Keccak256>>(key),
+        #[cfg(feature = "sm3")]
+        HashAlgorithm::Sm3 => keyed::<Hmac<sm3::Sm3>>(key),
+        #[cfg(feature = "streebog")]
+        HashAlgorithm::Streebog256 => keyed::<Hmac<streebog::Streebog256>>(key),
+        #[cfg(feature = "streebog")]
+        HashAlgorithm::Streebog512 => keyed::<Hmac<streebog::Streebog512>>(key),
+        // Checksums and XOFs have no HMAC construction.
+        other => return Err(SHAError::UnsupportedAlgorithm(other.to_string())),
+    };
+    Ok(mac)
+}
+
+/// Computes the HMAC of the file at `path` using `key` and the given hash `algorithm`.
+///
+/// Returns the MAC as a [`Digest`]. Only block-based hash functions have an
+/// HMAC construction, so checksums such as [`HashAlgorithm::Adler32`] return
+/// [`SHAError::UnsupportedAlgorithm`].
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashAlgorithm, hmac_file, validate_hmac_file};
+///
+/// let key = b"shared secret";
+/// let mac = hmac_file("transfer.bin", key, HashAlgorithm::Sha256).unwrap();
+/// assert!(validate_hmac_file("transfer.bin", key, mac.to_hex(), HashAlgorithm::Sha256).unwrap());
+/// ```
+pub fn hmac_file(
+    path: impl AsRef<Path>,
+    key: impl AsRef<[u8]>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let mut mac = new_mac(algorithm, key.as_ref())?;
+    read_chunks(&HasherOptions::new(), File::open(path.as_ref())?, |chunk| {
+        mac.update(chunk)
+    })?;
+    Ok(Digest::from(mac.finalize()))
+}
+
+/// Validates the file at `path` against an expected HMAC computed with `key` and `algorithm`.
+///
+/// The comparison is case-insensitive. See [`hmac_file`] for the supported algorithms.
+pub fn validate_hmac_file(
+    path: impl AsRef<Path>,
+    key: impl AsRef<[u8]>,
+    mac: impl AsRef<str>,
+    algorithm: HashAlgorithm,
+) -> Result<bool, SHAError> {
+    hmac_file(path, key, algorithm)?.matches_hex(mac.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_hmac_sha256_known_value() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(b"The quick brown fox jumps over the lazy dog")
+            .unwrap();
+        temp_file.flush().unwrap();
+
+        let mac = hmac_file(temp_file.path(), b"key", HashAlgorithm::Sha256).unwrap();
+        assert_eq!(
+            mac,
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        assert!(
+            validate_hmac_file(
+                temp_file.path(),
+                b"key",
+                mac.to_hex(),
+                HashAlgorithm::Sha256
+            )
+            .unwrap()
+        );
+        assert!(
+            !validate_hmac_file(
+                temp_file.path(),
+                b"other",
+                mac.to_hex(),
+                HashAlgorithm::Sha256
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hmac_unsupported_algorithm() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        match hmac_file(temp_file.path(), b"key", HashAlgorithm::Adler32) {
+            Err(SHAError::UnsupportedAlgorithm(name)) => assert_eq!(name, "adler32"),
+            _ => panic!("Expected UnsupportedAlgorithm error"),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::dir::portable_path;
+use crate::{Manifest, SHAError};
+
+/// What [`Manifest::merge`] does when both manifests list a path with different digests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Fail with [`SHAError::InvalidManifest`], leaving the manifest unchanged.
+    #[default]
+    Error,
+    /// Keep the entry already in the manifest.
+    KeepExisting,
+    /// Take the entry from the manifest being merged in.
+    Replace,
+}
+
+impl Manifest {
+    /// Adds every entry of `other` to this manifest, such as the per-shard manifests of
+    /// parallel workers.
+    ///
+    /// A path listed in both with the same digest is not a conflict; the recorded
+    /// [`FileStamp`](crate::FileStamp) is kept, or taken from `other` if this manifest has none.
+    /// Different digests are resolved by `policy`.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if the manifests use different algorithms, or on
+    /// the first conflict under [`ConflictPolicy::Error`]. Either way nothing is merged.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{ConflictPolicy, HashAlgorithm, Hashable, Manifest};
+    ///
+    /// let shards = ["a", "b", "c"].map(|name| {
+    ///     let mut shard = Manifest::new(HashAlgorithm::Sha1);
+    ///     shard.insert(name, name.hash().unwrap());
+    ///     shard
+    /// });
+    /// let mut merged = Manifest::new(HashAlgorithm::Sha1);
+    /// for shard in shards {
+    ///     merged.merge(shard, ConflictPolicy::Error).unwrap();
+    /// }
+    /// assert_eq!(merged.len(), 3);
+    /// ```
+    pub fn merge(&mut self, other: Manifest, policy: ConflictPolicy) -> Result<(), SHAError> {
+        if self.algorithm() != other.algorithm() {
+            return Err(SHAError::InvalidManifest(format!(
+                "cannot merge a {} manifest into a {} one",
+                other.algorithm(),
+                self.algorithm()
+            )));
+        }
+        if policy == ConflictPolicy::Error
+            && let Some((path, _)) = other
+                .iter()
+                .find(|(path, digest)| self.get(path).is_some_and(|existing| existing != *digest))
+        {
+            return Err(SHAError::InvalidManifest(format!(
+                "conflicting digests for {}",
+                portable_path(path)
+            )));
+        }
+        for (path, digest) in other.iter() {
+            let stamp = other.stamp(path).copied();
+            let keep = match self.get(path) {
+                None => false,
+                // Same contents: only fill in a missing stamp
+                Some(existing) if existing == digest => {
+                    self.stamp(path).is_some() || stamp.is_none()
+                }
+                Some(_) => policy == ConflictPolicy::KeepExisting,
+            };
+            if keep {
+                continue;
+            }
+            self.insert_with_stamp(path, digest.clone(), stamp);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileStamp, HashAlgorithm, Hashable};
+    use std::time::SystemTime;
+
+    fn shard(entries: &[(&str, &str)]) -> Manifest {
+        let mut manifest = Manifest::new(HashAlgorithm::Sha1);
+        for (path, contents) in entries {
+            manifest.insert(*path, contents.hash().unwrap());
+        }
+        manifest
+    }
+
+    #[test]
+    fn test_merge_policies() {
+        let base = shard(&[("a", "1"), ("b", "2")]);
+        let other = shard(&[("b", "changed"), ("c", "3")]);
+
+        let mut merged = base.clone();
+        match merged.merge(other.clone(), ConflictPolicy::Error) {
+            Err(SHAError::InvalidManifest(message)) => assert!(message.ends_with(" b")),
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(merged, base);
+
+        merged
+            .merge(other.clone(), ConflictPolicy::KeepExisting)
+            .unwrap();
+        assert_eq!(merged, shard(&[("a", "1"), ("b", "2"), ("c", "3")]));
+
+        let mut merged = base.clone();
+        merged.merge(other, ConflictPolicy::Replace).unwrap();
+        assert_eq!(merged, shard(&[("a", "1"), ("b", "changed"), ("c", "3")]));
+
+        let other = Manifest::new(HashAlgorithm::Sha256);
+        assert!(merged.merge(other, ConflictPolicy::Replace).is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_stamps() {
+        let stamp = FileStamp {
+            len: 1,
+            modified: SystemTime::UNIX_EPOCH,
+        };
+        let mut stamped = Manifest::new(HashAlgorithm::Sha1);
+        stamped.insert_with_stamp("a", "1".hash().unwrap(), Some(stamp));
+
+        let mut merged = shard(&[("a", "1")]);
+        merged
+            .merge(stamped.clone(), ConflictPolicy::Error)
+            .unwrap();
+        assert_eq!(merged.stamp("a"), Some(&stamp));
+
+        let mut merged = stamped.clone();
+        merged
+            .merge(shard(&[("a", "1")]), ConflictPolicy::Replace)
+            .unwrap();
+        assert_eq!(merged, stamped);
+    }
+}
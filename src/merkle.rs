@@ -0,0 +1,234 @@
+//! Merkle trees over hashed directories.
+//!
+//! A [`MerkleTree`] mirrors the directory structure of a [`Manifest`]: files are leaves holding
+//! their own digests, and every directory is an internal node whose digest covers the names and
+//! digests of its children. Because a directory's digest only depends on what is below it, one
+//! subtree can be re-hashed and checked against a stored tree without touching the rest.
+
+use crate::{Digest, DirOptions, HashAlgorithm, Hasher, Manifest, SHAError};
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+/// Marks a file child in a directory node's hash input.
+const FILE_TAG: u8 = b'f';
+/// Marks a directory child in a directory node's hash input.
+const DIR_TAG: u8 = b'd';
+
+/// One file or directory in a [`MerkleTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleNode {
+    /// A file, holding the digest of its contents.
+    File(Digest),
+    /// A directory, holding its children by name.
+    ///
+    /// For each child in name order, its digest input is a tag byte (`f` for a file, `d` for a
+    /// directory), the name, a NUL byte and the child's raw digest.
+    Dir {
+        digest: Digest,
+        children: BTreeMap<String, MerkleNode>,
+    },
+}
+
+impl MerkleNode {
+    /// Returns the digest of this file or subtree.
+    pub fn digest(&self) -> &Digest {
+        match self {
+            MerkleNode::File(digest) => digest,
+            MerkleNode::Dir { digest, .. } => digest,
+        }
+    }
+
+    /// Returns `true` if this node is a directory.
+    pub fn is_dir(&self) -> bool {
+        matches!(self, MerkleNode::Dir { .. })
+    }
+
+    /// Returns the child called `name`, or `None` for files and missing names.
+    pub fn child(&self, name: &str) -> Option<&MerkleNode> {
+        match self {
+            MerkleNode::File(_) => None,
+            MerkleNode::Dir { children, .. } => children.get(name),
+        }
+    }
+}
+
+/// A Merkle tree of a directory, built by [`DirOptions::merkle_tree`] or
+/// [`Manifest::merkle_tree`].
+///
+/// Empty directories are not part of a [`Manifest`], so they do not appear in the tree either.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::DirOptions;
+///
+/// let options = DirOptions::new();
+/// let tree = options.merkle_tree("release").unwrap();
+///
+/// // Later, check only the assets directory against the stored tree
+/// let expected = tree.get("assets").unwrap().digest();
+/// let actual = options.merkle_tree("release/assets").unwrap();
+/// println!("assets intact: {}", actual.root_digest() == expected);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleTree {
+    algorithm: HashAlgorithm,
+    root: MerkleNode,
+}
+
+impl MerkleTree {
+    /// Returns the algorithm every digest in the tree was computed with.
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the node for the root directory.
+    pub fn root(&self) -> &MerkleNode {
+        &self.root
+    }
+
+    /// Returns the digest identifying the whole tree.
+    pub fn root_digest(&self) -> &Digest {
+        self.root.digest()
+    }
+
+    /// Returns the node for a file or directory by its path relative to the root.
+    ///
+    /// An empty path returns the root.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&MerkleNode> {
+        let mut node = &self.root;
+        for component in path.as_ref().components() {
+            match component {
+                Component::Normal(name) => node = node.child(name.to_str()?)?,
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+        Some(node)
+    }
+}
+
+/// A directory whose digest has not been computed yet.
+#[derive(Default)]
+struct PendingDir {
+    files: BTreeMap<String, Digest>,
+    dirs: BTreeMap<String, PendingDir>,
+}
+
+impl PendingDir {
+    fn insert(&mut self, path: &Path, digest: &Digest) {
+        let names: Vec<String> = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some((file, dirs)) = names.split_last() else {
+            return;
+        };
+        let mut dir = self;
+        for name in dirs {
+            dir = dir.dirs.entry(name.clone()).or_default();
+        }
+        dir.files.insert(file.clone(), digest.clone());
+    }
+
+    fn finish(self, algorithm: HashAlgorithm) -> MerkleNode {
+        let mut children: BTreeMap<String, MerkleNode> = self
+            .dirs
+            .into_iter()
+            .map(|(name, dir)| (name, dir.finish(algorithm)))
+            .collect();
+        children.extend(
+            self.files
+                .into_iter()
+                .map(|(name, digest)| (name, MerkleNode::File(digest))),
+        );
+
+        let mut hasher = Hasher::new(algorithm);
+        for (name, child) in &children {
+            hasher.update([if child.is_dir() { DIR_TAG } else { FILE_TAG }]);
+            hasher.update(name);
+            hasher.update([0]);
+            hasher.update(child.digest().as_bytes());
+        }
+        MerkleNode::Dir {
+            digest: hasher.finalize(),
+            children,
+        }
+    }
+}
+
+impl Manifest {
+    /// Arranges the manifest's entries into a [`MerkleTree`].
+    pub fn merkle_tree(&self) -> MerkleTree {
+        let mut root = PendingDir::default();
+        for (path, digest) in self.iter() {
+            root.insert(path, digest);
+        }
+        MerkleTree {
+            algorithm: self.algorithm(),
+            root: root.finish(self.algorithm()),
+        }
+    }
+}
+
+impl DirOptions {
+    /// Hashes every file under `root` into a [`MerkleTree`].
+    pub fn merkle_tree(&self, root: impl AsRef<Path>) -> Result<MerkleTree, SHAError> {
+        Ok(self.hash_dir(root)?.merkle_tree())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_merkle_tree_structure() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("a").join("b")).unwrap();
+        fs::write(root.join("top.txt"), b"test data").unwrap();
+        fs::write(root.join("a").join("b").join("deep.txt"), b"Hello, World!").unwrap();
+
+        let options = DirOptions::new();
+        let tree = options.merkle_tree(root).unwrap();
+        assert_eq!(
+            *tree.get("top.txt").unwrap(),
+            MerkleNode::File(crate::hash_file_from_path(root.join("top.txt")).unwrap())
+        );
+        assert!(tree.get("a/b").unwrap().is_dir());
+        assert!(tree.get("a/missing").is_none());
+        assert_eq!(tree.get("").unwrap(), tree.root());
+
+        // A subtree hashed on its own has the same digest as its node in the full tree
+        let subtree = options.merkle_tree(root.join("a")).unwrap();
+        assert_eq!(subtree.root_digest(), tree.get("a").unwrap().digest());
+
+        fs::write(root.join("a").join("b").join("deep.txt"), b"changed").unwrap();
+        let changed = options.merkle_tree(root).unwrap();
+        assert_ne!(changed.root_digest(), tree.root_digest());
+        assert_ne!(changed.get("a").unwrap(), tree.get("a").unwrap());
+        assert_eq!(changed.get("top.txt"), tree.get("top.txt"));
+    }
+
+    #[test]
+    fn test_merkle_tree_tags_children() {
+        let digest = crate::HasherOptions::new().hash_reader(&b""[..]).unwrap();
+        let mut inner = Manifest::new(HashAlgorithm::Sha1);
+        inner.insert("y", digest.clone());
+        let inner = inner.merkle_tree().root_digest().clone();
+
+        // A file whose digest happens to equal a directory's digest still hashes differently
+        let mut as_file = Manifest::new(HashAlgorithm::Sha1);
+        as_file.insert("x", inner.clone());
+        let mut as_dir = Manifest::new(HashAlgorithm::Sha1);
+        as_dir.insert("x/y", digest);
+
+        assert_eq!(as_dir.merkle_tree().get("x").unwrap().digest(), &inner);
+        assert_ne!(
+            as_dir.merkle_tree().root_digest(),
+            as_file.merkle_tree().root_digest()
+        );
+    }
+}
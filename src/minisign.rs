@@ -0,0 +1,533 @@
+//! Ed25519 signatures in the format of the [minisign](https://jedisct1.github.io/minisign/)
+//! tool, for manifests and checksum files.
+//!
+//! A verified checksum file only proves integrity end to end if the checksum file itself is
+//! authentic. [`ChecksumFile::write_signed`] stores a detached `<file>.minisig` signature next
+//! to the file, and [`ChecksumFile::read_verified`] refuses to parse a file whose signature
+//! does not check out. Signatures and public keys interoperate with `minisign -V` and
+//! `minisign -S`, so either side can be the command-line tool.
+//!
+//! # Examples
+//! ```no_run
+//! use sha_file_hashing::minisign::{PublicKey, SecretKey};
+//! use sha_file_hashing::{ChecksumFile, DirOptions};
+//!
+//! // At release time
+//! let key = SecretKey::read("release.key").unwrap();
+//! let manifest = DirOptions::new().hash_dir("dist").unwrap();
+//! ChecksumFile::from(&manifest).write_signed("SHA1SUMS", &key).unwrap();
+//!
+//! // On the receiving side
+//! let public_key: PublicKey = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3"
+//!     .parse()
+//!     .unwrap();
+//! let sums = ChecksumFile::read_verified("SHA1SUMS", &public_key).unwrap();
+//! ```
+
+use crate::{ChecksumFile, SHAError};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use blake2::{Blake2b256, Blake2b512, Digest as _};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Signature algorithm of keys, and of signatures over the message itself.
+const LEGACY: &[u8; 2] = b"Ed";
+/// Signature algorithm of signatures over the BLAKE2b-512 hash of the message.
+const PREHASHED: &[u8; 2] = b"ED";
+const UNTRUSTED: &str = "untrusted comment: ";
+const TRUSTED: &str = "trusted comment: ";
+
+/// A minisign public key, as printed by `minisign -G` or stored in `minisign.pub`.
+///
+/// Parses from either the whole key file or just its base64 line, and
+/// [`Display`](fmt::Display)s as a key file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey {
+    key_id: [u8; 8],
+    key: VerifyingKey,
+}
+
+/// A minisign secret key.
+///
+/// Only unencrypted key files, as written by [`to_minisign`](SecretKey::to_minisign) or
+/// `minisign -G -W`, can be read; remove the password of an existing key with
+/// `minisign -C` first.
+#[derive(Clone)]
+pub struct SecretKey {
+    key_id: [u8; 8],
+    key: SigningKey,
+}
+
+impl PublicKey {
+    /// Reads a public key file such as `minisign.pub`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        text.parse()
+    }
+
+    /// Returns the key ID in hex, as minisign shows it in comments.
+    pub fn key_id(&self) -> String {
+        format_key_id(&self.key_id)
+    }
+
+    /// Returns the base64 form that `minisign -P` takes.
+    pub fn to_base64(&self) -> String {
+        let mut bytes = LEGACY.to_vec();
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(self.key.as_bytes());
+        STANDARD.encode(bytes)
+    }
+
+    /// Checks a `.minisig` signature over `data`, returning its trusted comment.
+    ///
+    /// Both prehashed signatures and the legacy ones of minisign before 0.8 are accepted.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidSignature`] if the signature is malformed, was made with
+    /// another key, or does not match `data` or the trusted comment.
+    pub fn verify(&self, data: &[u8], signature: &str) -> Result<String, SHAError> {
+        let signature = SignatureFile::parse(signature)?;
+        if signature.key_id != self.key_id {
+            return Err(invalid_signature(format!(
+                "made with key {}, expected {}",
+                format_key_id(&signature.key_id),
+                self.key_id()
+            )));
+        }
+        let hashed;
+        let message = match signature.prehashed {
+            true => {
+                hashed = Blake2b512::digest(data);
+                hashed.as_slice()
+            }
+            false => data,
+        };
+        self.key
+            .verify_strict(message, &signature.signature)
+            .map_err(|_| invalid_signature("does not match the signed data"))?;
+        let mut global = signature.signature.to_bytes().to_vec();
+        global.extend_from_slice(signature.trusted_comment.as_bytes());
+        self.key
+            .verify_strict(&global, &signature.global)
+            .map_err(|_| invalid_signature("does not match the trusted comment"))?;
+        Ok(signature.trusted_comment)
+    }
+
+    /// Checks the file at `path` against the signature in `<path>.minisig`, returning its
+    /// trusted comment.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::File`] if either file cannot be read, and fails like
+    /// [`verify`](PublicKey::verify) otherwise.
+    pub fn verify_file(&self, path: impl AsRef<Path>) -> Result<String, SHAError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        self.verify(&data, &read_signature(path)?)
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = SHAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED))
+            .unwrap_or_default();
+        let bytes = STANDARD
+            .decode(line)
+            .map_err(|_| invalid_key("public key is not base64"))?;
+        let Some((algorithm, rest)) = bytes.split_first_chunk::<2>() else {
+            return Err(invalid_key("public key is too short"));
+        };
+        if algorithm != LEGACY {
+            return Err(invalid_key("not an Ed25519 public key"));
+        }
+        let (key_id, key) = rest
+            .split_first_chunk::<8>()
+            .and_then(|(key_id, key)| Some((*key_id, <[u8; 32]>::try_from(key).ok()?)))
+            .ok_or_else(|| invalid_key("public key has the wrong length"))?;
+        let key = VerifyingKey::from_bytes(&key)
+            .map_err(|_| invalid_key("public key is not a valid Ed25519 point"))?;
+        Ok(Self { key_id, key })
+    }
+}
+
+/// Writes a public key file, as `minisign -G` does.
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}minisign public key {}", UNTRUSTED, self.key_id())?;
+        writeln!(f, "{}", self.to_base64())
+    }
+}
+
+impl SecretKey {
+    /// Generates a new key pair with a random key ID.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::IO`] if the operating system provides no randomness.
+    pub fn generate() -> Result<Self, SHAError> {
+        let mut seed = [0u8; 32];
+        let mut key_id = [0u8; 8];
+        getrandom::fill(&mut seed)
+            .and_then(|()| getrandom::fill(&mut key_id))
+            .map_err(std::io::Error::other)?;
+        Ok(Self {
+            key_id,
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Reads an unencrypted secret key file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        text.parse()
+    }
+
+    /// Returns the public half of the key pair.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            key_id: self.key_id,
+            key: self.key.verifying_key(),
+        }
+    }
+
+    /// Writes the key as an unencrypted minisign secret key file.
+    ///
+    /// Anyone with the file can sign in your name, so keep it out of the release tree.
+    pub fn to_minisign(&self) -> String {
+        let secret = self.secret_bytes();
+        let mut bytes = LEGACY.to_vec();
+        // No key derivation, so the salt and limits are unused
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(b"B2");
+        bytes.extend_from_slice(&[0; 48]);
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(&secret);
+        bytes.extend_from_slice(&checksum(&self.key_id, &secret));
+        format!(
+            "{}minisign unencrypted secret key\n{}\n",
+            UNTRUSTED,
+            STANDARD.encode(bytes)
+        )
+    }
+
+    /// Signs `data`, returning the contents of a `.minisig` file with `trusted_comment`
+    /// covered by the signature.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidSignature`] if `trusted_comment` contains a line break.
+    pub fn sign(&self, data: &[u8], trusted_comment: &str) -> Result<String, SHAError> {
+        if trusted_comment.contains(['\n', '\r']) {
+            return Err(invalid_signature("trusted comment must be a single line"));
+        }
+        let signature = self.key.sign(&Blake2b512::digest(data));
+        let mut global = signature.to_bytes().to_vec();
+        global.extend_from_slice(trusted_comment.as_bytes());
+        let global = self.key.sign(&global);
+
+        let mut line = PREHASHED.to_vec();
+        line.extend_from_slice(&self.key_id);
+        line.extend_from_slice(&signature.to_bytes());
+        Ok(format!(
+            "{}signature from minisign secret key {}\n{}\n{}{}\n{}\n",
+            UNTRUSTED,
+            format_key_id(&self.key_id),
+            STANDARD.encode(line),
+            TRUSTED,
+            trusted_comment,
+            STANDARD.encode(global.to_bytes())
+        ))
+    }
+
+    /// Signs the file at `path` into `<path>.minisig`, returning the signature's path.
+    ///
+    /// The trusted comment records the time and file name, as `minisign -S` does.
+    pub fn sign_file(&self, path: impl AsRef<Path>) -> Result<PathBuf, SHAError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        self.sign_to(path, &data)
+    }
+
+    /// Writes the signature of `data`, the contents of `path`, to `<path>.minisig`.
+    fn sign_to(&self, path: &Path, data: &[u8]) -> Result<PathBuf, SHAError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let name = name.replace(['\n', '\r'], " ");
+        let comment = format!("timestamp:{}\tfile:{}\thashed", timestamp, name);
+        let signature_path = signature_path(path);
+        fs::write(&signature_path, self.sign(data, &comment)?)
+            .map_err(|e| SHAError::from(e).at_path(&signature_path))?;
+        Ok(signature_path)
+    }
+
+    /// The 64-byte Ed25519 secret key as libsodium stores it: the seed, then the public key.
+    fn secret_bytes(&self) -> [u8; 64] {
+        let mut secret = [0; 64];
+        secret[..32].copy_from_slice(&self.key.to_bytes());
+        secret[32..].copy_from_slice(self.key.verifying_key().as_bytes());
+        secret
+    }
+}
+
+impl FromStr for SecretKey {
+    type Err = SHAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let line = s
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED))
+            .unwrap_or_default();
+        let bytes = STANDARD
+            .decode(line)
+            .map_err(|_| invalid_key("secret key is not base64"))?;
+        if bytes.len() != 158 || &bytes[..2] != LEGACY || &bytes[4..6] != b"B2" {
+            return Err(invalid_key("not a minisign Ed25519 secret key"));
+        }
+        if bytes[2..4] != [0, 0] {
+            return Err(invalid_key(
+                "encrypted secret keys are not supported; remove the password with `minisign -C`",
+            ));
+        }
+        let key_id: [u8; 8] = bytes[54..62].try_into().expect("8 bytes");
+        let secret: [u8; 64] = bytes[62..126].try_into().expect("64 bytes");
+        if checksum(&key_id, &secret)[..] != bytes[126..] {
+            return Err(invalid_key("secret key checksum does not match"));
+        }
+        let key = SigningKey::from_bytes(secret[..32].try_into().expect("32 bytes"));
+        if key.verifying_key().as_bytes()[..] != secret[32..] {
+            return Err(invalid_key("secret key does not match its public key"));
+        }
+        Ok(Self { key_id, key })
+    }
+}
+
+/// Shows only the key ID, so keys can be logged without leaking them.
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretKey")
+            .field("key_id", &format_key_id(&self.key_id))
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChecksumFile {
+    /// Reads the checksum file at `path` after checking it against the signature in
+    /// `<path>.minisig`.
+    ///
+    /// The bytes checked are the bytes parsed, so the file cannot change in between.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidSignature`] if the signature does not verify with `key`, and
+    /// otherwise fails like [`read`](ChecksumFile::read).
+    pub fn read_verified(path: impl AsRef<Path>, key: &PublicKey) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        key.verify(&data, &read_signature(path)?)?;
+        let text = String::from_utf8(data).map_err(|e| {
+            let e = std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+            SHAError::from(e).at_path(path)
+        })?;
+        text.parse()
+    }
+
+    /// Writes the checksum file to `path` and its signature to `<path>.minisig`.
+    pub fn write_signed(&self, path: impl AsRef<Path>, key: &SecretKey) -> Result<(), SHAError> {
+        let path = path.as_ref();
+        let data = self.to_string();
+        fs::write(path, &data).map_err(|e| SHAError::from(e).at_path(path))?;
+        key.sign_to(path, data.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The four lines of a `.minisig` file.
+struct SignatureFile {
+    prehashed: bool,
+    key_id: [u8; 8],
+    signature: Signature,
+    trusted_comment: String,
+    global: Signature,
+}
+
+impl SignatureFile {
+    fn parse(text: &str) -> Result<Self, SHAError> {
+        let mut lines = text.lines();
+        let malformed = || invalid_signature("not a minisign signature");
+        if !lines.next().is_some_and(|line| line.starts_with(UNTRUSTED)) {
+            return Err(malformed());
+        }
+        let line = STANDARD
+            .decode(lines.next().ok_or_else(malformed)?.trim())
+            .map_err(|_| malformed())?;
+        let trusted_comment = lines
+            .next()
+            .and_then(|line| line.strip_prefix(TRUSTED))
+            .ok_or_else(malformed)?;
+        let global = STANDARD
+            .decode(lines.next().ok_or_else(malformed)?.trim())
+            .map_err(|_| malformed())?;
+        if line.len() != 74 || global.len() != 64 {
+            return Err(malformed());
+        }
+        let prehashed = match &line[..2] {
+            algorithm if algorithm == PREHASHED => true,
+            algorithm if algorithm == LEGACY => false,
+            _ => return Err(invalid_signature("unknown signature algorithm")),
+        };
+        Ok(Self {
+            prehashed,
+            key_id: line[2..10].try_into().expect("8 bytes"),
+            signature: Signature::from_slice(&line[10..]).map_err(|_| malformed())?,
+            trusted_comment: trusted_comment.to_string(),
+            global: Signature::from_slice(&global).map_err(|_| malformed())?,
+        })
+    }
+}
+
+/// Returns `<path>.minisig`.
+fn signature_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".minisig");
+    PathBuf::from(name)
+}
+
+fn read_signature(path: &Path) -> Result<String, SHAError> {
+    let path = signature_path(path);
+    fs::read_to_string(&path).map_err(|e| SHAError::from(e).at_path(&path))
+}
+
+/// Checksum stored in secret key files, over the algorithm, key ID and secret key.
+fn checksum(key_id: &[u8; 8], secret: &[u8; 64]) -> [u8; 32] {
+    let mut hasher = Blake2b256::new();
+    hasher.update(LEGACY);
+    hasher.update(key_id);
+    hasher.update(secret);
+    hasher.finalize().into()
+}
+
+/// Formats a key ID the way minisign does, as a little-endian number in hex.
+fn format_key_id(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+fn invalid_key(message: &str) -> SHAError {
+    SHAError::InvalidKey(message.to_string())
+}
+
+fn invalid_signature(message: impl Into<String>) -> SHAError {
+    SHAError::InvalidSignature(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChecksumEntry, Hashable};
+    use tempfile::TempDir;
+
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+    #[test]
+    fn test_verify_minisign_signatures() {
+        let key: PublicKey = format!(
+            "untrusted comment: minisign public key E7620F1842B4E81F\n{}\n",
+            PUBLIC_KEY
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(key.key_id(), "E7620F1842B4E81F");
+        assert_eq!(key.to_base64(), PUBLIC_KEY);
+
+        // Signatures over b"test" made by minisign itself
+        let prehashed = "untrusted comment: signature from minisign secret key\n\
+            RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+            trusted comment: timestamp:1556193335\tfile:test\n\
+            y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==\n";
+        let legacy = "untrusted comment: signature from minisign secret key\r\n\
+            RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\r\n\
+            trusted comment: timestamp:1555779966\tfile:test\r\n\
+            QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==\r\n";
+        assert_eq!(
+            key.verify(b"test", prehashed).unwrap(),
+            "timestamp:1556193335\tfile:test"
+        );
+        assert_eq!(
+            key.verify(b"test", legacy).unwrap(),
+            "timestamp:1555779966\tfile:test"
+        );
+
+        for (data, signature) in [
+            (b"Test".as_slice(), prehashed.to_string()),
+            (b"test", prehashed.replace("1556193335", "1556193336")),
+            (b"test", prehashed.lines().take(3).collect()),
+        ] {
+            assert!(matches!(
+                key.verify(data, &signature),
+                Err(SHAError::InvalidSignature(_))
+            ));
+        }
+        let other = SecretKey::generate().unwrap().public_key();
+        assert!(other.verify(b"test", prehashed).is_err());
+    }
+
+    #[test]
+    fn test_sign_and_key_files() {
+        let key = SecretKey::generate().unwrap();
+        let signature = key.sign(b"data", "file:data").unwrap();
+        let public_key: PublicKey = key.public_key().to_string().parse().unwrap();
+        assert_eq!(public_key, key.public_key());
+        assert_eq!(public_key.verify(b"data", &signature).unwrap(), "file:data");
+        assert!(key.sign(b"data", "two\nlines").is_err());
+
+        let reloaded: SecretKey = key.to_minisign().parse().unwrap();
+        assert_eq!(reloaded.public_key(), public_key);
+        assert!(!format!("{:?}", key).contains(&STANDARD.encode(key.key.to_bytes())));
+
+        // A corrupted secret key fails its checksum
+        let mut bytes = STANDARD
+            .decode(key.to_minisign().lines().nth(1).unwrap())
+            .unwrap();
+        bytes[70] ^= 1;
+        assert!(matches!(
+            STANDARD.encode(&bytes).parse::<SecretKey>(),
+            Err(SHAError::InvalidKey(_))
+        ));
+        bytes[2..4].copy_from_slice(b"Sc");
+        assert!(STANDARD.encode(&bytes).parse::<SecretKey>().is_err());
+    }
+
+    #[test]
+    fn test_signed_checksum_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("SHA1SUMS");
+        let key = SecretKey::generate().unwrap();
+        let mut sums = ChecksumFile::new();
+        sums.push(ChecksumEntry::new("a".hash().unwrap(), "a.txt"));
+
+        sums.write_signed(&path, &key).unwrap();
+        let trusted = key.public_key().verify_file(&path).unwrap();
+        assert!(trusted.contains("\tfile:SHA1SUMS\t"), "{}", trusted);
+        let read = ChecksumFile::read_verified(&path, &key.public_key()).unwrap();
+        assert_eq!(read.to_string(), sums.to_string());
+
+        fs::write(&path, sums.to_string().replace("a.txt", "b.txt")).unwrap();
+        assert!(matches!(
+            ChecksumFile::read_verified(&path, &key.public_key()),
+            Err(SHAError::InvalidSignature(_))
+        ));
+        assert_eq!(key.sign_file(&path).unwrap(), signature_path(&path));
+        assert!(ChecksumFile::read_verified(&path, &key.public_key()).is_ok());
+    }
+}
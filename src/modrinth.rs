@@ -0,0 +1,287 @@
+//! Modrinth modpack indexes (`modrinth.index.json`, the manifest inside a `.mrpack`), enabled
+//! by the `json` feature.
+
+use crate::report::{FileOutcome, VerificationReport};
+use crate::{Digest, HashAlgorithm, SHAError, hash_file_from_path_multi};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// The file a `.mrpack` keeps its index in.
+pub const MODRINTH_INDEX_FILE: &str = "modrinth.index.json";
+
+/// A file a modpack downloads, with the hashes its index lists for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModrinthFile {
+    /// The path relative to the instance directory.
+    pub path: PathBuf,
+    /// The SHA-1 digest.
+    pub sha1: Digest,
+    /// The SHA-512 digest.
+    pub sha512: Digest,
+    /// The length in bytes, if listed.
+    pub file_size: Option<u64>,
+    /// The URLs the file can be downloaded from.
+    pub downloads: Vec<String>,
+}
+
+/// The index of a Modrinth modpack: its name and the files it downloads into an instance.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::ModrinthIndex;
+///
+/// let index = ModrinthIndex::read("pack/modrinth.index.json").unwrap();
+/// let report = index.verify("instances/pack");
+/// println!("{} of {} files OK", report.passed(), report.len());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModrinthIndex {
+    name: String,
+    version_id: String,
+    files: Vec<ModrinthFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexRepr {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    version_id: String,
+    files: Vec<FileRepr>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileRepr {
+    path: String,
+    hashes: HashesRepr,
+    #[serde(default)]
+    file_size: Option<u64>,
+    #[serde(default)]
+    downloads: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct HashesRepr {
+    sha1: String,
+    sha512: String,
+}
+
+impl ModrinthIndex {
+    /// Reads a `modrinth.index.json` file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        Self::from_json(&json)
+    }
+
+    /// Reads the index inside the `.mrpack` archive at `path`.
+    #[cfg(feature = "zip")]
+    pub fn read_mrpack(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        use std::io::{self, Read};
+
+        let path = path.as_ref();
+        let file = fs::File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        let mut archive = zip::ZipArchive::new(io::BufReader::new(file))
+            .map_err(|e| SHAError::from(io::Error::from(e)).at_path(path))?;
+        let mut json = String::new();
+        archive
+            .by_name(MODRINTH_INDEX_FILE)
+            .map_err(io::Error::from)
+            .and_then(|mut entry| entry.read_to_string(&mut json))
+            .map_err(|e| SHAError::from(e).at_path(path))?;
+        Self::from_json(&json)
+    }
+
+    /// Parses the contents of a `modrinth.index.json` file.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if the JSON is malformed, a hash has the wrong
+    /// length, or a path would leave the instance directory, which the format forbids.
+    pub fn from_json(json: &str) -> Result<Self, SHAError> {
+        let invalid = |e: String| SHAError::InvalidManifest(e);
+        let repr: IndexRepr = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+        let mut files = Vec::with_capacity(repr.files.len());
+        for file in repr.files {
+            let path = PathBuf::from(&file.path);
+            let mut components = path.components();
+            if file.path.is_empty()
+                || file.path.contains('\\')
+                || !components.all(|component| matches!(component, Component::Normal(_)))
+            {
+                return Err(invalid(format!("unsafe path {:?}", file.path)));
+            }
+            let digest = |hex: &str, algorithm| {
+                Digest::parse(hex, algorithm).map_err(|e| invalid(format!("{}: {}", file.path, e)))
+            };
+            files.push(ModrinthFile {
+                sha1: digest(&file.hashes.sha1, HashAlgorithm::Sha1)?,
+                sha512: digest(&file.hashes.sha512, HashAlgorithm::Sha512)?,
+                path,
+                file_size: file.file_size,
+                downloads: file.downloads,
+            });
+        }
+        Ok(Self {
+            name: repr.name,
+            version_id: repr.version_id,
+            files,
+        })
+    }
+
+    /// Returns the modpack's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the modpack's version.
+    pub fn version_id(&self) -> &str {
+        &self.version_id
+    }
+
+    /// Returns the files in the order the index lists them.
+    pub fn files(&self) -> &[ModrinthFile] {
+        &self.files
+    }
+
+    /// Checks every listed file under `instance_dir` against both its SHA-1 and its SHA-512,
+    /// reading each file once.
+    ///
+    /// A file matching one hash but not the other is a [`FileOutcome::Mismatch`] carrying the
+    /// hash that failed, SHA-512 first. Files from the pack's `overrides` are not listed in the
+    /// index and are not checked.
+    pub fn verify(&self, instance_dir: impl AsRef<Path>) -> VerificationReport {
+        let instance_dir = instance_dir.as_ref();
+        let algorithms = [HashAlgorithm::Sha1, HashAlgorithm::Sha512];
+        let mut report = VerificationReport::default();
+        for file in &self.files {
+            let outcome =
+                match hash_file_from_path_multi(instance_dir.join(&file.path), &algorithms) {
+                    Ok(mut actual) => {
+                        let sha512 = actual.remove(&HashAlgorithm::Sha512).expect("requested");
+                        let sha1 = actual.remove(&HashAlgorithm::Sha1).expect("requested");
+                        match (sha512 == file.sha512, sha1 == file.sha1) {
+                            (true, true) => FileOutcome::Ok,
+                            (false, _) => FileOutcome::Mismatch {
+                                expected: file.sha512.clone(),
+                                actual: sha512,
+                            },
+                            (true, false) => FileOutcome::Mismatch {
+                                expected: file.sha1.clone(),
+                                actual: sha1,
+                            },
+                        }
+                    }
+                    Err(e) => FileOutcome::from_error(e),
+                };
+            report.push(&file.path, outcome);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const SHA512: &str = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+
+    fn index_json(path: &str) -> String {
+        format!(
+            r#"{{
+                "formatVersion": 1,
+                "game": "minecraft",
+                "versionId": "1.2.0",
+                "name": "Example Pack",
+                "files": [
+                    {{
+                        "path": "{}",
+                        "hashes": {{"sha1": "{}", "sha512": "{}"}},
+                        "env": {{"client": "required", "server": "required"}},
+                        "downloads": ["https://cdn.modrinth.com/data/abc/versions/1/mod.jar"],
+                        "fileSize": 3
+                    }},
+                    {{
+                        "path": "mods/missing.jar",
+                        "hashes": {{"sha1": "{}", "sha512": "{}"}},
+                        "downloads": []
+                    }}
+                ],
+                "dependencies": {{"minecraft": "1.20.1"}}
+            }}"#,
+            path, SHA1, SHA512, SHA1, SHA512
+        )
+    }
+
+    #[test]
+    fn test_modrinth_index_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let instance = temp_dir.path();
+        fs::create_dir_all(instance.join("mods")).unwrap();
+        fs::write(instance.join("mods/mod.jar"), "abc").unwrap();
+
+        let index = ModrinthIndex::from_json(&index_json("mods/mod.jar")).unwrap();
+        assert_eq!(index.name(), "Example Pack");
+        assert_eq!(index.version_id(), "1.2.0");
+        assert_eq!(index.files()[0].file_size, Some(3));
+        assert_eq!(index.files()[0].downloads.len(), 1);
+
+        let report = index.verify(instance);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.missing(), 1);
+
+        fs::write(instance.join("mods/mod.jar"), "abd").unwrap();
+        let report = index.verify(instance);
+        let (path, outcome) = report.iter().next().unwrap();
+        assert_eq!(path, Path::new("mods/mod.jar"));
+        match outcome {
+            FileOutcome::Mismatch { expected, .. } => assert_eq!(*expected, SHA512),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_modrinth_index_errors() {
+        for path in ["../escape.jar", "/etc/passwd", "mods\\\\..\\\\x.jar", ""] {
+            assert!(
+                matches!(
+                    ModrinthIndex::from_json(&index_json(path)),
+                    Err(SHAError::InvalidManifest(_))
+                ),
+                "{}",
+                path
+            );
+        }
+        let short = index_json("mods/a.jar").replacen(SHA1, "a999", 1);
+        assert!(matches!(
+            ModrinthIndex::from_json(&short),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        assert!(ModrinthIndex::from_json("{}").is_err());
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_read_mrpack() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack.mrpack");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&pack).unwrap());
+        zip.start_file(MODRINTH_INDEX_FILE, SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(index_json("mods/mod.jar").as_bytes())
+            .unwrap();
+        zip.finish().unwrap();
+
+        let index = ModrinthIndex::read_mrpack(&pack).unwrap();
+        assert_eq!(index.files().len(), 2);
+        assert!(ModrinthIndex::read_mrpack(temp_dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! Self-describing multihash digests, as used by IPFS and libp2p.
+//!
+//! A multihash is the multicodec code of the algorithm and the digest length, each as an
+//! unsigned varint, followed by the digest bytes.
+
+use crate::{Digest, HashAlgorithm, SHAError};
+
+impl HashAlgorithm {
+    /// Returns the multicodec code identifying this algorithm in a multihash, or `None` if the
+    /// multicodec table has no entry for it.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::HashAlgorithm;
+    ///
+    /// assert_eq!(HashAlgorithm::Sha256.multihash_code(), Some(0x12));
+    /// assert_eq!(HashAlgorithm::from_multihash_code(0x12), Some(HashAlgorithm::Sha256));
+    /// ```
+    pub fn multihash_code(&self) -> Option<u64> {
+        match self {
+            HashAlgorithm::Md5 => Some(0xd5),
+            HashAlgorithm::Sha1 => Some(0x11),
+            HashAlgorithm::Sha224 => Some(0x1013),
+            HashAlgorithm::Sha256 => Some(0x12),
+            HashAlgorithm::Sha384 => Some(0x20),
+            HashAlgorithm::Sha512 => Some(0x13),
+            #[cfg(feature = "keccak")]
+            HashAlgorithm::Keccak256 => Some(0x1b),
+            #[cfg(feature = "shake")]
+            HashAlgorithm::Shake128 => Some(0x18),
+            #[cfg(feature = "shake")]
+            HashAlgorithm::Shake256 => Some(0x19),
+            #[cfg(feature = "sm3")]
+            HashAlgorithm::Sm3 => Some(0x534d),
+            _ => None,
+        }
+    }
+
+    /// Looks up the algorithm with the given multicodec code among those enabled in this build.
+    pub fn from_multihash_code(code: u64) -> Option<HashAlgorithm> {
+        HashAlgorithm::ALL
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.multihash_code() == Some(code))
+    }
+}
+
+impl Digest {
+    /// Encodes the digest as a multihash, naming `algorithm` as the one it was computed with.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::UnsupportedAlgorithm`] if `algorithm` has no multihash code.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{Digest, HashAlgorithm, Hashable};
+    ///
+    /// let digest = "test data".hash_with(HashAlgorithm::Sha256).unwrap();
+    /// let multihash = digest.to_multihash(HashAlgorithm::Sha256).unwrap();
+    /// assert_eq!(multihash[..2], [0x12, 0x20]);
+    /// assert_eq!(
+    ///     Digest::from_multihash(&multihash).unwrap(),
+    ///     (HashAlgorithm::Sha256, digest)
+    /// );
+    /// ```
+    pub fn to_multihash(&self, algorithm: HashAlgorithm) -> Result<Vec<u8>, SHAError> {
+        let code = algorithm.multihash_code().ok_or_else(|| {
+            SHAError::UnsupportedAlgorithm(format!("{} has no multihash code", algorithm))
+        })?;
+        let mut multihash = Vec::with_capacity(self.len() + 4);
+        write_varint(&mut multihash, code);
+        write_varint(&mut multihash, self.len() as u64);
+        multihash.extend_from_slice(self.as_bytes());
+        Ok(multihash)
+    }
+
+    /// Decodes a multihash into its algorithm and digest.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::UnsupportedAlgorithm`] for an unknown code, and
+    /// [`SHAError::InvalidDigest`] if the bytes are malformed or the digest is truncated, since
+    /// a truncated digest can never match a fresh one.
+    pub fn from_multihash(bytes: &[u8]) -> Result<(HashAlgorithm, Digest), SHAError> {
+        let (algorithm, digest, rest) = read_multihash(bytes)?;
+        if !rest.is_empty() {
+            return Err(SHAError::InvalidDigest(format!(
+                "{} trailing bytes after multihash",
+                rest.len()
+            )));
+        }
+        Ok((algorithm, digest))
+    }
+}
+
+/// Decodes the multihash at the start of `bytes`, returning what follows it.
+pub(crate) fn read_multihash(bytes: &[u8]) -> Result<(HashAlgorithm, Digest, &[u8]), SHAError> {
+    let (code, rest) = read_varint(bytes)?;
+    let algorithm = HashAlgorithm::from_multihash_code(code)
+        .ok_or_else(|| SHAError::UnsupportedAlgorithm(format!("multihash code 0x{:x}", code)))?;
+    let (len, rest) = read_varint(rest)?;
+    if len != algorithm.output_len() as u64 {
+        return Err(SHAError::InvalidDigest(format!(
+            "multihash declares {} bytes for {}, expected {}",
+            len,
+            algorithm,
+            algorithm.output_len()
+        )));
+    }
+    let Some((digest, rest)) = rest.split_at_checked(len as usize) else {
+        return Err(SHAError::InvalidDigest(
+            "multihash digest is cut short".into(),
+        ));
+    };
+    Ok((algorithm, Digest::from_bytes(digest), rest))
+}
+
+/// Appends `value` as an unsigned LEB128 varint.
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads a minimally encoded varint of at most 9 bytes, as the multiformats spec requires.
+pub(crate) fn read_varint(bytes: &[u8]) -> Result<(u64, &[u8]), SHAError> {
+    let mut value = 0;
+    for (index, &byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            if byte == 0 && index > 0 {
+                break;
+            }
+            return Ok((value, &bytes[index + 1..]));
+        }
+    }
+    Err(SHAError::InvalidDigest(
+        "malformed varint in multihash".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+
+    #[test]
+    fn test_multihash_round_trip() {
+        for algorithm in HashAlgorithm::ALL.iter().copied() {
+            let digest = "test data".hash_with(algorithm).unwrap();
+            match digest.to_multihash(algorithm) {
+                Ok(multihash) => assert_eq!(
+                    Digest::from_multihash(&multihash).unwrap(),
+                    (algorithm, digest)
+                ),
+                Err(e) => {
+                    assert!(algorithm.multihash_code().is_none());
+                    assert!(matches!(e, SHAError::UnsupportedAlgorithm(_)));
+                }
+            }
+        }
+
+        // A two-byte code
+        let digest = "".hash_with(HashAlgorithm::Sha224).unwrap();
+        assert_eq!(
+            digest.to_multihash(HashAlgorithm::Sha224).unwrap()[..3],
+            [0x93, 0x20, 0x1c]
+        );
+    }
+
+    #[test]
+    fn test_invalid_multihashes() {
+        let sha1 = "".hash().unwrap();
+        let multihash = sha1.to_multihash(HashAlgorithm::Sha1).unwrap();
+        assert!(Digest::from_multihash(&multihash[..10]).is_err());
+        assert!(Digest::from_multihash(&[multihash.as_slice(), &[0]].concat()).is_err());
+        assert!(Digest::from_multihash(&[0x11, 0x08, 0, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        assert!(matches!(
+            Digest::from_multihash(&[0x00, 0x00]),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+        // Not minimally encoded
+        assert!(Digest::from_multihash(&[0x91, 0x00, 0x14]).is_err());
+        assert!(Digest::from_multihash(&[0x80; 10]).is_err());
+        assert!(Digest::from_multihash(&[]).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Named information URIs, as specified by RFC 6920.
+
+use crate::{Digest, HashAlgorithm, SHAError};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// Algorithms with a full-length entry in the Named Information Hash Algorithm Registry.
+const NI_ALGORITHMS: [(HashAlgorithm, &str); 3] = [
+    (HashAlgorithm::Sha256, "sha-256"),
+    (HashAlgorithm::Sha384, "sha-384"),
+    (HashAlgorithm::Sha512, "sha-512"),
+];
+
+impl Digest {
+    /// Formats the digest as an `ni:///<algorithm>;<base64url>` URI, naming `algorithm` as the
+    /// one it was computed with.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::UnsupportedAlgorithm`] unless `algorithm` is SHA-256, SHA-384 or
+    /// SHA-512, and [`SHAError::InvalidDigest`] if the digest length does not match it.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{Digest, HashAlgorithm, Hashable};
+    ///
+    /// // The example from RFC 6920, section 8.1
+    /// let digest = "Hello World!".hash_with(HashAlgorithm::Sha256).unwrap();
+    /// let uri = digest.to_ni_uri(HashAlgorithm::Sha256).unwrap();
+    /// assert_eq!(uri, "ni:///sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk");
+    /// assert_eq!(Digest::parse_ni_uri(&uri).unwrap(), (HashAlgorithm::Sha256, digest));
+    /// ```
+    pub fn to_ni_uri(&self, algorithm: HashAlgorithm) -> Result<String, SHAError> {
+        let Some((_, name)) = NI_ALGORITHMS.iter().find(|(known, _)| *known == algorithm) else {
+            return Err(SHAError::UnsupportedAlgorithm(format!(
+                "{} has no named information identifier",
+                algorithm
+            )));
+        };
+        if self.len() != algorithm.output_len() {
+            return Err(SHAError::InvalidDigest(format!(
+                "a {} digest has {} bytes, found {}",
+                algorithm,
+                algorithm.output_len(),
+                self.len()
+            )));
+        }
+        Ok(format!(
+            "ni:///{};{}",
+            name,
+            URL_SAFE_NO_PAD.encode(self.as_bytes())
+        ))
+    }
+
+    /// Parses an `ni://` URI into its algorithm and digest.
+    ///
+    /// Any authority and query parameters, such as `?ct=text/plain`, are accepted and ignored.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidDigest`] if `uri` is not a well-formed `ni` URI or its value
+    /// has the wrong length, and [`SHAError::UnsupportedAlgorithm`] for an algorithm other
+    /// than SHA-256, SHA-384 or SHA-512, including truncated ones like `sha-256-128`.
+    pub fn parse_ni_uri(uri: &str) -> Result<(HashAlgorithm, Digest), SHAError> {
+        let invalid = || SHAError::InvalidDigest(format!("not a named information URI: {}", uri));
+        let rest = uri
+            .get(..5)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("ni://"))
+            .map(|_| &uri[5..])
+            .ok_or_else(invalid)?;
+        let (_authority, rest) = rest.split_once('/').ok_or_else(invalid)?;
+        let rest = rest.split_once('?').map_or(rest, |(path, _)| path);
+        let (name, value) = rest.split_once(';').ok_or_else(invalid)?;
+        let algorithm = NI_ALGORITHMS
+            .iter()
+            .find(|(_, known)| known.eq_ignore_ascii_case(name))
+            .map(|(algorithm, _)| *algorithm)
+            .ok_or_else(|| SHAError::UnsupportedAlgorithm(name.to_string()))?;
+        let bytes = URL_SAFE_NO_PAD.decode(value).map_err(|_| invalid())?;
+        if bytes.len() != algorithm.output_len() {
+            return Err(invalid());
+        }
+        Ok((algorithm, Digest::from_bytes(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+
+    #[test]
+    fn test_ni_uri_round_trip() {
+        for (algorithm, _) in NI_ALGORITHMS {
+            let digest = "test data".hash_with(algorithm).unwrap();
+            let uri = digest.to_ni_uri(algorithm).unwrap();
+            assert_eq!(Digest::parse_ni_uri(&uri).unwrap(), (algorithm, digest));
+        }
+        let sha1 = "test data".hash().unwrap();
+        assert!(matches!(
+            sha1.to_ni_uri(HashAlgorithm::Sha1),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+        assert!(matches!(
+            sha1.to_ni_uri(HashAlgorithm::Sha256),
+            Err(SHAError::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_ni_uri_forms() {
+        let expected = "Hello World!".hash_with(HashAlgorithm::Sha256).unwrap();
+        for uri in [
+            "ni://example.com/sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk?ct=text/plain",
+            "NI:///SHA-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk",
+        ] {
+            assert_eq!(Digest::parse_ni_uri(uri).unwrap().1, expected);
+        }
+        assert!(matches!(
+            Digest::parse_ni_uri("ni:///sha-256-128;f4OxZX_x_FO5LcGBSKHWXQ"),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+        for uri in [
+            "http:///sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk",
+            "ni:sha-256;f4OxZX_x_FO5LcGBSKHWXfwtSx-j1ncoSt3SABJtkGk",
+            "ni:///sha-256",
+            "ni:///sha-256;f4OxZX_x_FO5LcGBSKHWX",
+            "ni:///sha-256;f4OxZX+x/FO5LcGBSKHWXfwtSx+j1ncoSt3SABJtkGk",
+            "ni",
+        ] {
+            assert!(
+                matches!(Digest::parse_ni_uri(uri), Err(SHAError::InvalidDigest(_))),
+                "{}",
+                uri
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Nix's base32 encoding, used for store paths and fixed-output derivation hashes.
+//!
+//! Unlike RFC 4648 base32, the alphabet omits `e`, `o`, `t` and `u`, and the digest is read
+//! from its last bit backwards, so the output matches `nix-hash --to-base32` character for
+//! character.
+
+use crate::{Digest, HashAlgorithm, SHAError};
+
+const ALPHABET: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+/// Returns the number of base32 characters Nix uses for `len` bytes.
+fn encoded_len(len: usize) -> usize {
+    if len == 0 { 0 } else { (len * 8 - 1) / 5 + 1 }
+}
+
+impl Digest {
+    /// Encodes the digest in Nix's base32 alphabet.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{Digest, HashAlgorithm, Hashable};
+    ///
+    /// let digest = "".hash_with(HashAlgorithm::Sha256).unwrap();
+    /// let nix = digest.to_nix_base32();
+    /// assert_eq!(nix, "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73");
+    /// assert_eq!(Digest::parse_nix_base32(&nix, HashAlgorithm::Sha256).unwrap(), digest);
+    /// ```
+    pub fn to_nix_base32(&self) -> String {
+        let bytes = self.as_bytes();
+        (0..encoded_len(bytes.len()))
+            .rev()
+            .map(|n| {
+                let (i, j) = (n * 5 / 8, n * 5 % 8);
+                let low = bytes[i] >> j;
+                let high = bytes
+                    .get(i + 1)
+                    .map_or(0, |&next| next.unbounded_shl(8 - j as u32));
+                char::from(ALPHABET[usize::from((low | high) & 0x1f)])
+            })
+            .collect()
+    }
+
+    /// Parses a Nix base32 digest computed with `algorithm`.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidDigest`] if `s` has the wrong length for `algorithm`, contains
+    /// a character outside Nix's alphabet, or encodes bits beyond the digest.
+    pub fn parse_nix_base32(s: &str, algorithm: HashAlgorithm) -> Result<Self, SHAError> {
+        let len = algorithm.output_len();
+        if s.len() != encoded_len(len) {
+            return Err(SHAError::InvalidDigest(format!(
+                "a Nix base32 {} digest has {} characters, found {}",
+                algorithm,
+                encoded_len(len),
+                s.len()
+            )));
+        }
+        let mut bytes = vec![0u8; len];
+        for (n, c) in s.bytes().rev().enumerate() {
+            let Some(digit) = ALPHABET.iter().position(|&known| known == c) else {
+                return Err(SHAError::InvalidDigest(format!(
+                    "{:?} is not a Nix base32 character",
+                    char::from(c)
+                )));
+            };
+            let (i, j) = (n * 5 / 8, n * 5 % 8);
+            let digit = digit as u16;
+            bytes[i] |= (digit << j) as u8;
+            let carry = (digit << j >> 8) as u8;
+            match bytes.get_mut(i + 1) {
+                Some(next) => *next |= carry,
+                None if carry != 0 => {
+                    return Err(SHAError::InvalidDigest(format!(
+                        "Nix base32 digest {} has bits beyond its length",
+                        s
+                    )));
+                }
+                None => {}
+            }
+        }
+        Ok(Digest::from_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+
+    #[test]
+    fn test_nix_base32_round_trip() {
+        for algorithm in HashAlgorithm::ALL.iter().copied() {
+            let digest = "test data".hash_with(algorithm).unwrap();
+            let nix = digest.to_nix_base32();
+            assert_eq!(nix.len(), encoded_len(algorithm.output_len()));
+            assert_eq!(Digest::parse_nix_base32(&nix, algorithm).unwrap(), digest);
+        }
+    }
+
+    #[test]
+    fn test_parse_nix_base32_errors() {
+        let sha256 = "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73";
+        for invalid in [
+            &sha256[1..],
+            "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c7e",
+            "0MDQA9W1P6CMLI6976V4WI0SW9R4P5PRKJ7LZFD1877WK11C9C73",
+            // 52 characters hold 260 bits, and the top four must be zero
+            "zmdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73",
+        ] {
+            assert!(
+                matches!(
+                    Digest::parse_nix_base32(invalid, HashAlgorithm::Sha256),
+                    Err(SHAError::InvalidDigest(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
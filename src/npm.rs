@@ -0,0 +1,339 @@
+//! npm `package-lock.json` integrity values, enabled by the `json` feature.
+
+use crate::report::{FileOutcome, VerificationReport};
+use crate::{HashAlgorithm, HasherOptions, SHAError, sri};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// The algorithms npm and ssri accept in an `integrity` value, weakest first. Old lockfiles
+/// still carry `sha1-` values for packages published before npm 5.
+const NPM_ALGORITHMS: [HashAlgorithm; 4] = [
+    HashAlgorithm::Sha1,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Sha384,
+    HashAlgorithm::Sha512,
+];
+
+/// A package pinned by a `package-lock.json`, with the integrity of its tarball.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    /// The package name, such as `lodash` or `@babel/core`.
+    pub name: String,
+    /// The exact version.
+    pub version: String,
+    /// The URL the tarball was downloaded from, if the lockfile records it.
+    pub resolved: Option<String>,
+    /// The integrity value, such as `sha512-<base64>`, possibly listing several hashes.
+    pub integrity: String,
+}
+
+impl LockedPackage {
+    /// Returns the tarball's file name: the last segment of [`resolved`](Self::resolved), or
+    /// `<name>-<version>.tgz` without the scope as the registry names it.
+    pub fn file_name(&self) -> String {
+        let from_url = self.resolved.as_deref().and_then(|url| {
+            let url = url.split(['?', '#']).next().unwrap_or(url);
+            url.rsplit('/').next().filter(|name| !name.is_empty())
+        });
+        match from_url {
+            Some(name) => name.to_string(),
+            None => {
+                let name = self.name.rsplit('/').next().unwrap_or(&self.name);
+                format!("{}-{}.tgz", name, self.version)
+            }
+        }
+    }
+
+    /// Checks the tarball at `path` against the package's integrity, as `npm ci` does: only the
+    /// hashes of the strongest algorithm listed are checked, and any one matching is enough.
+    pub fn verify(&self, path: impl AsRef<Path>) -> Result<bool, SHAError> {
+        let path = path.as_ref();
+        let (algorithm, expected) = sri::strongest_of(&self.integrity, &NPM_ALGORITHMS)?;
+        let actual = HasherOptions::new()
+            .algorithm(algorithm)
+            .hash_file_from_path(path)
+            .map_err(|e| e.at_path(path))?;
+        Ok(sri::matches_any(&actual, &expected))
+    }
+}
+
+/// The packages of an npm `package-lock.json` (or `npm-shrinkwrap.json`) and their integrity
+/// values.
+///
+/// Linked, bundled and git dependencies have no integrity and are left out.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::PackageLock;
+///
+/// let lock = PackageLock::read("package-lock.json").unwrap();
+/// let report = lock.verify("/srv/npm-mirror/tarballs");
+/// println!("{} of {} tarballs OK", report.passed(), report.len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageLock {
+    packages: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockRepr {
+    #[serde(default)]
+    packages: Option<BTreeMap<String, PackageRepr>>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, DependencyRepr>,
+}
+
+#[derive(Deserialize)]
+struct PackageRepr {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    resolved: Option<String>,
+    #[serde(default)]
+    integrity: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DependencyRepr {
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    resolved: Option<String>,
+    #[serde(default)]
+    integrity: Option<String>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, DependencyRepr>,
+}
+
+impl PackageLock {
+    /// Reads a `package-lock.json` file.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        Self::from_json(&json)
+    }
+
+    /// Parses the contents of a lockfile of any version. Version 2 and 3 files are read from
+    /// their `packages` map and version 1 files from their nested `dependencies`.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if the JSON is malformed or an integrity value
+    /// holds no well-formed hash.
+    pub fn from_json(json: &str) -> Result<Self, SHAError> {
+        let repr: LockRepr =
+            serde_json::from_str(json).map_err(|e| SHAError::InvalidManifest(e.to_string()))?;
+        let mut packages = Vec::new();
+        match repr.packages {
+            Some(entries) => {
+                for (location, package) in entries {
+                    let Some(integrity) = package.integrity else {
+                        continue;
+                    };
+                    // `node_modules/a/node_modules/@scope/b` installs `@scope/b`; an
+                    // aliased package records its real name separately
+                    let name = package.name.unwrap_or_else(|| {
+                        location
+                            .rsplit_once("node_modules/")
+                            .map_or(location.as_str(), |(_, name)| name)
+                            .to_string()
+                    });
+                    packages.push(locked(
+                        name,
+                        package.version.unwrap_or_default(),
+                        package.resolved,
+                        integrity,
+                    )?);
+                }
+            }
+            None => flatten(repr.dependencies, &mut packages)?,
+        }
+        Ok(Self { packages })
+    }
+
+    /// Returns the packages, sorted by where they are installed.
+    pub fn packages(&self) -> &[LockedPackage] {
+        &self.packages
+    }
+
+    /// Checks the tarball of every package in `dir`, looked up by
+    /// [`LockedPackage::file_name`].
+    ///
+    /// Outcomes are reported under the tarball file names, and a tarball installed in several
+    /// places is checked once.
+    pub fn verify(&self, dir: impl AsRef<Path>) -> VerificationReport {
+        let dir = dir.as_ref();
+        let mut seen = BTreeSet::new();
+        let mut report = VerificationReport::default();
+        for package in &self.packages {
+            let file_name = package.file_name();
+            if !seen.insert(file_name.clone()) {
+                continue;
+            }
+            let (algorithm, expected) = sri::strongest_of(&package.integrity, &NPM_ALGORITHMS)
+                .expect("integrity checked when parsed");
+            let outcome = match HasherOptions::new()
+                .algorithm(algorithm)
+                .hash_file_from_path(dir.join(&file_name))
+            {
+                Ok(actual) if sri::matches_any(&actual, &expected) => FileOutcome::Ok,
+                Ok(actual) => FileOutcome::Mismatch {
+                    expected: expected[0].clone(),
+                    actual,
+                },
+                Err(e) => FileOutcome::from_error(e),
+            };
+            report.push(file_name, outcome);
+        }
+        report
+    }
+}
+
+fn locked(
+    name: String,
+    version: String,
+    resolved: Option<String>,
+    integrity: String,
+) -> Result<LockedPackage, SHAError> {
+    sri::strongest_of(&integrity, &NPM_ALGORITHMS)
+        .map_err(|e| SHAError::InvalidManifest(format!("{} {}: {}", name, version, e)))?;
+    Ok(LockedPackage {
+        name,
+        version,
+        resolved,
+        integrity,
+    })
+}
+
+fn flatten(
+    dependencies: BTreeMap<String, DependencyRepr>,
+    packages: &mut Vec<LockedPackage>,
+) -> Result<(), SHAError> {
+    for (name, dependency) in dependencies {
+        if let Some(integrity) = dependency.integrity {
+            packages.push(locked(
+                name,
+                dependency.version,
+                dependency.resolved,
+                integrity,
+            )?);
+        }
+        flatten(dependency.dependencies, packages)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // Integrity values of "abc"
+    const SHA512: &str = "sha512-3a81oZNherrMQXNJriBBMRLm+k6JqX6iCp7u5ktV05ohkpkqJ0/BqDa6PCOj/uu9RU1EI2Q86A4qmslPpUyknw==";
+    const SHA1: &str = "sha1-qZk+NkcGgWq6PiVxeFDCbJzQ2J0=";
+
+    #[test]
+    fn test_package_lock_verify() {
+        let lock = format!(
+            r#"{{
+                "name": "app",
+                "lockfileVersion": 3,
+                "packages": {{
+                    "": {{"name": "app", "version": "1.0.0"}},
+                    "node_modules/abc": {{
+                        "version": "1.0.0",
+                        "resolved": "https://registry.npmjs.org/abc/-/abc-1.0.0.tgz",
+                        "integrity": "{sha512}"
+                    }},
+                    "node_modules/x/node_modules/abc": {{
+                        "version": "1.0.0",
+                        "resolved": "https://registry.npmjs.org/abc/-/abc-1.0.0.tgz",
+                        "integrity": "{sha512}"
+                    }},
+                    "node_modules/@scope/old": {{
+                        "version": "0.1.0",
+                        "integrity": "{sha1}"
+                    }},
+                    "node_modules/local": {{"resolved": "packages/local", "link": true}}
+                }}
+            }}"#,
+            sha512 = SHA512,
+            sha1 = SHA1
+        );
+        let lock = PackageLock::from_json(&lock).unwrap();
+        let names: Vec<_> = lock.packages().iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["@scope/old", "abc", "abc"]);
+        assert_eq!(lock.packages()[0].file_name(), "old-0.1.0.tgz");
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("abc-1.0.0.tgz"), "abc").unwrap();
+        let report = lock.verify(temp_dir.path());
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.passed(), 1);
+        assert_eq!(report.missing(), 1);
+
+        fs::write(temp_dir.path().join("old-0.1.0.tgz"), "abd").unwrap();
+        assert_eq!(lock.verify(temp_dir.path()).mismatched(), 1);
+        assert!(
+            !lock.packages()[0]
+                .verify(temp_dir.path().join("old-0.1.0.tgz"))
+                .unwrap()
+        );
+        assert!(
+            lock.packages()[1]
+                .verify(temp_dir.path().join("abc-1.0.0.tgz"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_package_lock_v1() {
+        let lock = format!(
+            r#"{{
+                "lockfileVersion": 1,
+                "dependencies": {{
+                    "abc": {{
+                        "version": "1.0.0",
+                        "resolved": "https://registry.npmjs.org/abc/-/abc-1.0.0.tgz?cache=0",
+                        "integrity": "{sha1} {sha512}",
+                        "dependencies": {{
+                            "nested": {{"version": "2.0.0", "integrity": "{sha1}"}}
+                        }}
+                    }},
+                    "from-git": {{"version": "git+https://example.org/x.git#0123abc"}}
+                }}
+            }}"#,
+            sha512 = SHA512,
+            sha1 = SHA1
+        );
+        let lock = PackageLock::from_json(&lock).unwrap();
+        let names: Vec<_> = lock
+            .packages()
+            .iter()
+            .map(LockedPackage::file_name)
+            .collect();
+        assert_eq!(names, ["abc-1.0.0.tgz", "nested-2.0.0.tgz"]);
+
+        // Only the SHA-512 is checked when both are listed
+        let temp_dir = TempDir::new().unwrap();
+        let tarball = temp_dir.path().join("abc-1.0.0.tgz");
+        fs::write(&tarball, "abc").unwrap();
+        assert!(lock.packages()[0].verify(&tarball).unwrap());
+        let mut forged = lock.packages()[0].clone();
+        forged.integrity = format!("{} {}", SHA1, SHA512.replace("3a81", "3a82"));
+        assert!(!forged.verify(&tarball).unwrap());
+        // A malformed hash is skipped, leaving the SHA-1
+        forged.integrity = format!("{} sha512-AAAA", SHA1);
+        assert!(forged.verify(&tarball).unwrap());
+
+        let bad = r#"{"dependencies": {"a": {"version": "1.0.0", "integrity": "md5-AAAA"}}}"#;
+        assert!(matches!(
+            PackageLock::from_json(bad),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        assert!(PackageLock::from_json("[").is_err());
+    }
+}
@@ -0,0 +1,473 @@
+//! Container images: OCI image layouts and `docker save` archives, enabled by the `oci`
+//! feature.
+
+use crate::report::{FileOutcome, VerificationReport};
+use crate::{Digest, HashAlgorithm, Hasher, HasherOptions, SHAError};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+/// The file an OCI image layout lists its manifests in.
+const INDEX_FILE: &str = "index.json";
+/// The file a `docker save` archive lists its images in.
+const DOCKER_MANIFEST_FILE: &str = "manifest.json";
+/// Archive entries up to this size are kept in memory, so manifests and configs can be parsed
+/// once the archive has been read through.
+const MAX_DOCUMENT_LEN: u64 = 4 * 1024 * 1024;
+
+/// The media types of the blobs that list other blobs.
+const MANIFEST_MEDIA_TYPES: [&str; 4] = [
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+/// Checks every blob of the OCI image layout in `dir` against its digest.
+///
+/// Starting from `index.json`, each manifest, image index, config and layer the image refers
+/// to is hashed and reported under its path, such as `blobs/sha256/<hex>`. A directory a
+/// `docker save` archive was unpacked into is accepted as well; see [`verify_oci_archive`].
+///
+/// # Errors
+/// Returns [`SHAError::InvalidManifest`] if the layout has neither `index.json` nor
+/// `manifest.json`, or a manifest that matches its digest is malformed. Corrupt and missing
+/// blobs are reported, not returned as errors.
+///
+/// # Examples
+/// ```ignore
+/// use sha_file_hashing::verify_oci_layout;
+///
+/// let report = verify_oci_layout("images/app").unwrap();
+/// for (path, outcome) in report.iter().filter(|(_, outcome)| !outcome.is_ok()) {
+///     println!("corrupt blob {}: {:?}", path.display(), outcome);
+/// }
+/// ```
+pub fn verify_oci_layout(dir: impl AsRef<Path>) -> Result<VerificationReport, SHAError> {
+    verify(&DirBlobs(dir.as_ref()))
+}
+
+/// Checks every blob of the uncompressed image archive at `path` against its digest.
+///
+/// Archives holding an OCI image layout, as `docker save` writes since Docker 25 and
+/// `skopeo copy` to `oci-archive:` does, are checked from their `index.json`. Older `docker
+/// save` archives only have a `manifest.json`; their configs are checked against the digest in
+/// their file names and their `layer.tar` files against the `diff_ids` of the config.
+///
+/// The archive is read once. Gzipped archives must be decompressed first.
+///
+/// # Errors
+/// Fails as [`verify_oci_layout`] does, or if the archive cannot be read.
+pub fn verify_oci_archive(path: impl AsRef<Path>) -> Result<VerificationReport, SHAError> {
+    let path = path.as_ref();
+    let blobs = TarBlobs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    verify(&blobs)
+}
+
+/// The files of an image, wherever they are kept.
+trait Blobs {
+    fn contains(&self, name: &str) -> bool;
+    fn read(&self, name: &str) -> io::Result<Vec<u8>>;
+    fn hash(&self, name: &str, algorithm: HashAlgorithm) -> io::Result<Digest>;
+}
+
+struct DirBlobs<'a>(&'a Path);
+
+impl Blobs for DirBlobs<'_> {
+    fn contains(&self, name: &str) -> bool {
+        self.0.join(name).is_file()
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.0.join(name))
+    }
+
+    fn hash(&self, name: &str, algorithm: HashAlgorithm) -> io::Result<Digest> {
+        HasherOptions::new()
+            .algorithm(algorithm)
+            .hash_file_from_path(self.0.join(name))
+            .map_err(|e| match e {
+                SHAError::IO(e) | SHAError::File { source: e, .. } => e,
+                other => io::Error::other(other),
+            })
+    }
+}
+
+/// The entries of an archive, hashed as it was read.
+struct TarBlobs {
+    entries: HashMap<String, TarEntry>,
+}
+
+struct TarEntry {
+    algorithm: HashAlgorithm,
+    digest: Digest,
+    /// The contents, if small enough to be a manifest or config.
+    data: Option<Vec<u8>>,
+}
+
+impl TarBlobs {
+    fn read(path: &Path) -> io::Result<Self> {
+        let mut archive = tar::Archive::new(BufReader::new(File::open(path)?));
+        let mut entries = HashMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let name = name.trim_start_matches("./").to_string();
+            // Blobs are hashed with the algorithm their directory is named after
+            let algorithm = match name
+                .strip_prefix("blobs/")
+                .and_then(|rest| rest.split_once('/'))
+            {
+                Some(("sha512", _)) => HashAlgorithm::Sha512,
+                _ => HashAlgorithm::Sha256,
+            };
+            let mut hasher = Hasher::new(algorithm);
+            let (digest, data) = if entry.size() <= MAX_DOCUMENT_LEN {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                hasher.update(&data);
+                (hasher.finalize(), Some(data))
+            } else {
+                let digest = hasher.hash_reader(&mut entry).map_err(|e| match e {
+                    SHAError::IO(e) => e,
+                    other => io::Error::other(other),
+                })?;
+                (digest, None)
+            };
+            entries.insert(
+                name,
+                TarEntry {
+                    algorithm,
+                    digest,
+                    data,
+                },
+            );
+        }
+        Ok(Self { entries })
+    }
+
+    fn entry(&self, name: &str) -> io::Result<&TarEntry> {
+        self.entries.get(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in the archive", name),
+            )
+        })
+    }
+}
+
+impl Blobs for TarBlobs {
+    fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        self.entry(name)?.data.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is too large to be a manifest", name),
+            )
+        })
+    }
+
+    fn hash(&self, name: &str, algorithm: HashAlgorithm) -> io::Result<Digest> {
+        let entry = self.entry(name)?;
+        match &entry.data {
+            _ if entry.algorithm == algorithm => Ok(entry.digest.clone()),
+            Some(data) => {
+                let mut hasher = Hasher::new(algorithm);
+                hasher.update(data);
+                Ok(hasher.finalize())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} was not hashed with {}", name, algorithm),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DescriptorRepr {
+    #[serde(default)]
+    media_type: String,
+    digest: String,
+}
+
+/// An image index or manifest; which fields are present tells them apart.
+#[derive(Deserialize)]
+struct DocumentRepr {
+    #[serde(default)]
+    manifests: Vec<DescriptorRepr>,
+    #[serde(default)]
+    config: Option<DescriptorRepr>,
+    #[serde(default)]
+    layers: Vec<DescriptorRepr>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerImageRepr {
+    config: String,
+    #[serde(default)]
+    layers: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct DockerConfigRepr {
+    #[serde(default)]
+    rootfs: DockerRootfsRepr,
+}
+
+#[derive(Deserialize, Default)]
+struct DockerRootfsRepr {
+    #[serde(default)]
+    diff_ids: Vec<String>,
+}
+
+fn verify(blobs: &impl Blobs) -> Result<VerificationReport, SHAError> {
+    if blobs.contains(INDEX_FILE) {
+        verify_index(blobs)
+    } else if blobs.contains(DOCKER_MANIFEST_FILE) {
+        verify_docker_manifest(blobs)
+    } else {
+        Err(SHAError::InvalidManifest(format!(
+            "neither {} nor {} found",
+            INDEX_FILE, DOCKER_MANIFEST_FILE
+        )))
+    }
+}
+
+fn verify_index(blobs: &impl Blobs) -> Result<VerificationReport, SHAError> {
+    let index: DocumentRepr = parse(blobs, INDEX_FILE)?;
+    let mut queue = VecDeque::from(index.manifests);
+    let mut seen = BTreeSet::new();
+    let mut report = VerificationReport::default();
+    while let Some(descriptor) = queue.pop_front() {
+        if !seen.insert(descriptor.digest.clone()) {
+            continue;
+        }
+        let (name, algorithm, expected) = blob(&descriptor.digest)?;
+        let outcome = check(blobs, &name, algorithm, expected);
+        // Only a manifest that is intact can be trusted to name the blobs below it
+        if outcome.is_ok() && MANIFEST_MEDIA_TYPES.contains(&descriptor.media_type.as_str()) {
+            let document: DocumentRepr = parse(blobs, &name)?;
+            queue.extend(document.manifests);
+            queue.extend(document.config);
+            queue.extend(document.layers);
+        }
+        report.push(name, outcome);
+    }
+    Ok(report)
+}
+
+fn verify_docker_manifest(blobs: &impl Blobs) -> Result<VerificationReport, SHAError> {
+    let images: Vec<DockerImageRepr> = parse(blobs, DOCKER_MANIFEST_FILE)?;
+    let mut seen = BTreeSet::new();
+    let mut report = VerificationReport::default();
+    for image in images {
+        // Configs are named `<hex>.json`, or `blobs/sha256/<hex>` in newer archives
+        let config_digest = match image.config.strip_prefix("blobs/") {
+            Some(rest) => rest.replacen('/', ":", 1),
+            None => format!("sha256:{}", image.config.trim_end_matches(".json")),
+        };
+        let (_, algorithm, expected) = blob(&config_digest)?;
+        if seen.insert(image.config.clone()) {
+            report.push(
+                &image.config,
+                check(blobs, &image.config, algorithm, expected),
+            );
+        }
+        // A corrupt config still usually parses; its layers will then fail to match
+        let config: DockerConfigRepr = parse(blobs, &image.config).unwrap_or_default();
+        for (i, layer) in image.layers.iter().enumerate() {
+            if !seen.insert(layer.clone()) {
+                continue;
+            }
+            let outcome = match config.rootfs.diff_ids.get(i) {
+                Some(diff_id) => {
+                    let (_, algorithm, expected) = blob(diff_id)?;
+                    check(blobs, layer, algorithm, expected)
+                }
+                None => FileOutcome::IoError(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("no digest for this layer in {}", image.config),
+                )),
+            };
+            report.push(layer, outcome);
+        }
+    }
+    Ok(report)
+}
+
+/// Splits a digest such as `sha256:<hex>` into its blob path, algorithm and value.
+fn blob(digest: &str) -> Result<(String, HashAlgorithm, Digest), SHAError> {
+    let invalid = |message: &str| SHAError::InvalidManifest(format!("{}: {}", digest, message));
+    let (name, hex) = digest
+        .split_once(':')
+        .ok_or_else(|| invalid("not a digest"))?;
+    let algorithm = match name {
+        "sha256" => HashAlgorithm::Sha256,
+        "sha512" => HashAlgorithm::Sha512,
+        _ => return Err(invalid("unsupported algorithm")),
+    };
+    let value = Digest::parse(hex, algorithm).map_err(|e| invalid(&e.to_string()))?;
+    Ok((format!("blobs/{}/{}", name, hex), algorithm, value))
+}
+
+fn check(
+    blobs: &impl Blobs,
+    name: &str,
+    algorithm: HashAlgorithm,
+    expected: Digest,
+) -> FileOutcome {
+    match blobs.hash(name, algorithm) {
+        Ok(actual) if actual == expected => FileOutcome::Ok,
+        Ok(actual) => FileOutcome::Mismatch { expected, actual },
+        Err(e) => FileOutcome::from_io(e),
+    }
+}
+
+fn parse<T: DeserializeOwned>(blobs: &impl Blobs, name: &str) -> Result<T, SHAError> {
+    let data = blobs.read(name)?;
+    serde_json::from_slice(&data).map_err(|e| SHAError::InvalidManifest(format!("{}: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sha256(data: &[u8]) -> String {
+        let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+        hasher.update(data);
+        hasher.finalize().to_hex()
+    }
+
+    /// Writes an OCI layout with one image of one layer into `dir`, returning the layer's path.
+    fn write_layout(dir: &Path) -> String {
+        let layer = b"layer contents".as_slice();
+        let config = format!(
+            r#"{{"architecture":"amd64","os":"linux","rootfs":{{"type":"layers","diff_ids":["sha256:{}"]}}}}"#,
+            sha256(layer)
+        );
+        let manifest = format!(
+            r#"{{
+                "schemaVersion": 2,
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "config": {{"mediaType": "application/vnd.oci.image.config.v1+json", "digest": "sha256:{}", "size": {}}},
+                "layers": [{{"mediaType": "application/vnd.oci.image.layer.v1.tar", "digest": "sha256:{}", "size": {}}}]
+            }}"#,
+            sha256(config.as_bytes()),
+            config.len(),
+            sha256(layer),
+            layer.len()
+        );
+        let index = format!(
+            r#"{{"schemaVersion": 2, "manifests": [{{"mediaType": "application/vnd.oci.image.manifest.v1+json", "digest": "sha256:{}", "size": {}}}]}}"#,
+            sha256(manifest.as_bytes()),
+            manifest.len()
+        );
+        fs::create_dir_all(dir.join("blobs/sha256")).unwrap();
+        for blob in [layer, config.as_bytes(), manifest.as_bytes()] {
+            fs::write(dir.join("blobs/sha256").join(sha256(blob)), blob).unwrap();
+        }
+        fs::write(dir.join("oci-layout"), r#"{"imageLayoutVersion": "1.0.0"}"#).unwrap();
+        fs::write(dir.join(INDEX_FILE), index).unwrap();
+        format!("blobs/sha256/{}", sha256(layer))
+    }
+
+    fn tar_dir(dir: &Path, out: &Path) {
+        let mut builder = tar::Builder::new(File::create(out).unwrap());
+        builder.append_dir_all(".", dir).unwrap();
+        builder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_verify_oci_layout() {
+        let temp_dir = TempDir::new().unwrap();
+        let layout = temp_dir.path().join("layout");
+        let layer = write_layout(&layout);
+
+        let report = verify_oci_layout(&layout).unwrap();
+        assert_eq!(report.len(), 3);
+        assert!(report.is_ok());
+
+        let archive = temp_dir.path().join("image.tar");
+        tar_dir(&layout, &archive);
+        let report = verify_oci_archive(&archive).unwrap();
+        assert_eq!(report.len(), 3);
+        assert!(report.is_ok());
+
+        fs::write(layout.join(&layer), "tampered").unwrap();
+        let report = verify_oci_layout(&layout).unwrap();
+        assert_eq!(report.mismatched(), 1);
+        let (path, _) = report.iter().find(|(_, outcome)| !outcome.is_ok()).unwrap();
+        assert_eq!(path, Path::new(&layer));
+
+        fs::remove_file(layout.join(&layer)).unwrap();
+        tar_dir(&layout, &archive);
+        assert_eq!(verify_oci_archive(&archive).unwrap().missing(), 1);
+    }
+
+    #[test]
+    fn test_verify_docker_save_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("saved");
+        let layer = b"legacy layer".as_slice();
+        let config = format!(
+            r#"{{"rootfs":{{"diff_ids":["sha256:{}"]}}}}"#,
+            sha256(layer)
+        );
+        let config_name = format!("{}.json", sha256(config.as_bytes()));
+        fs::create_dir_all(dir.join("0123abcd")).unwrap();
+        fs::write(dir.join("0123abcd/layer.tar"), layer).unwrap();
+        fs::write(dir.join(&config_name), &config).unwrap();
+        fs::write(
+            dir.join(DOCKER_MANIFEST_FILE),
+            format!(
+                r#"[{{"Config":"{}","RepoTags":["app:latest"],"Layers":["0123abcd/layer.tar"]}}]"#,
+                config_name
+            ),
+        )
+        .unwrap();
+
+        let archive = temp_dir.path().join("app.tar");
+        tar_dir(&dir, &archive);
+        let report = verify_oci_archive(&archive).unwrap();
+        assert_eq!(report.len(), 2);
+        assert!(report.is_ok());
+
+        fs::write(dir.join("0123abcd/layer.tar"), "tampered").unwrap();
+        assert_eq!(verify_oci_layout(&dir).unwrap().mismatched(), 1);
+    }
+
+    #[test]
+    fn test_verify_oci_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(matches!(
+            verify_oci_layout(temp_dir.path()),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        fs::write(
+            temp_dir.path().join(INDEX_FILE),
+            r#"{"manifests": [{"digest": "md5:900150983cd24fb0d6963f7d28e17f72"}]}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_oci_layout(temp_dir.path()),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        assert!(matches!(
+            verify_oci_archive(temp_dir.path().join("missing.tar")),
+            Err(SHAError::File { .. })
+        ));
+    }
+}
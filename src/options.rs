@@ -0,0 +1,1102 @@
+use crate::direct::{self, Advice};
+use crate::output::split_declared;
+#[cfg(feature = "shake")]
+use crate::xof;
+use crate::{CancelToken, Digest, HashAlgorithm, Hasher, SHAError, pipeline, read_chunks_with};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+/// Size of the read buffer used when none is configured.
+///
+/// Large enough that syscall overhead is negligible on NVMe drives, while still small enough
+/// to stay cache-friendly and cheap to allocate per file.
+pub(crate) const DEFAULT_BUFFER_SIZE: usize = 128 * 1024;
+
+/// Default bounds for buffers sized from the file length.
+const ADAPTIVE_MIN: usize = 4 * 1024;
+const ADAPTIVE_MAX: usize = 4 * 1024 * 1024;
+
+/// How symbolic links are treated when hashing by path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SymlinkPolicy {
+    /// Hash the file the link points to. The default for single paths.
+    #[default]
+    Follow,
+    /// Leave links out. The default for directory walks; a single path that is a link fails
+    /// with [`SHAError::Symlink`], since there is nothing else to return.
+    Skip,
+    /// Hash the path the link points to instead of any file contents, so a manifest records
+    /// where links point without reading through them.
+    HashTargetPath,
+    /// Fail with [`SHAError::Symlink`].
+    Error,
+}
+
+/// Configuration shared by every file and reader hashing entry point.
+///
+/// The free functions such as [`hash_file_from_path_with_algorithm`](crate::hash_file_from_path_with_algorithm)
+/// are shorthands for a default `HasherOptions` with the algorithm set. Build one explicitly to
+/// tune how data is read without reaching for a dedicated function per knob.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashAlgorithm, HasherOptions};
+///
+/// let options = HasherOptions::new()
+///     .algorithm(HashAlgorithm::Sha256)
+///     .buffer_size(1 << 20);
+/// let digest = options.hash_file_from_path("disk.img").unwrap();
+/// assert!(options.validate_file_from_path("disk.img", digest.to_hex()).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HasherOptions {
+    algorithm: HashAlgorithm,
+    buffer_size: Option<usize>,
+    adaptive_range: (usize, usize),
+    cancel_token: Option<CancelToken>,
+    direct_io: bool,
+    sequential_hint: bool,
+    drop_cache: bool,
+    pipelined: bool,
+    symlinks: SymlinkPolicy,
+    #[cfg(feature = "mmap")]
+    mmap: bool,
+    #[cfg(feature = "shake")]
+    output_len: Option<usize>,
+}
+
+/// A file opened by [`HasherOptions`], remembering whether it bypasses the page cache.
+struct OpenFile {
+    file: File,
+    direct: bool,
+}
+
+impl Default for HasherOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: HashAlgorithm::default(),
+            buffer_size: None,
+            adaptive_range: (ADAPTIVE_MIN, ADAPTIVE_MAX),
+            cancel_token: None,
+            direct_io: false,
+            sequential_hint: false,
+            drop_cache: false,
+            pipelined: false,
+            symlinks: SymlinkPolicy::Follow,
+            #[cfg(feature = "mmap")]
+            mmap: false,
+            #[cfg(feature = "shake")]
+            output_len: None,
+        }
+    }
+}
+
+impl HasherOptions {
+    /// Creates options for SHA-1 with the default buffer size.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects the hash algorithm.
+    pub fn algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets a fixed size in bytes for each read, turning off adaptive sizing. A size of zero is
+    /// treated as one byte.
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = Some(buffer_size.max(1));
+        self
+    }
+
+    /// Sizes the read buffer of each file from its length, between `min` and `max` bytes.
+    ///
+    /// This is the default, with a range of 4KiB to 4MiB, so tiny files do not pay for a large
+    /// allocation and huge ones are read in few syscalls. The buffer is the file length
+    /// rounded up to a power of two, then clamped to the range. Readers of unknown length, and
+    /// files reporting a length of zero such as those in `/proc`, use a 128KiB buffer clamped
+    /// to the same range.
+    ///
+    /// Calling this after [`buffer_size`](Self::buffer_size) switches back to adaptive sizing.
+    /// `min` is raised to at least one byte, and `max` to at least `min`.
+    pub fn adaptive_buffer(mut self, min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        self.buffer_size = None;
+        self.adaptive_range = (min, max.max(min));
+        self
+    }
+
+    /// Makes hashing stop with [`SHAError::Cancelled`] once `token` is cancelled.
+    ///
+    /// The token is checked before each buffer is hashed, so a cancelled hash of a huge file
+    /// returns after at most one more read.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    /// Reads files by path without going through the operating system's page cache.
+    ///
+    /// Hashing terabytes during a scrub would otherwise evict everything else from the cache.
+    /// This uses `O_DIRECT` on Linux, `F_NOCACHE` on macOS and `FILE_FLAG_NO_BUFFERING` on
+    /// Windows; on other platforms, and on filesystems that reject unbuffered I/O such as
+    /// tmpfs, files are read normally. Reads are rounded up to a multiple of 4KiB.
+    ///
+    /// Only the path-based methods are affected; a [`File`] passed to
+    /// [`hash_file`](Self::hash_file) has already been opened. Takes precedence over
+    /// memory mapping.
+    pub fn direct_io(mut self, enabled: bool) -> Self {
+        self.direct_io = enabled;
+        self
+    }
+
+    /// Tells the kernel the file will be read front to back, so it reads ahead more
+    /// aggressively.
+    ///
+    /// Issues `posix_fadvise(POSIX_FADV_SEQUENTIAL)` before hashing on Linux, Android and
+    /// FreeBSD, and does nothing elsewhere.
+    pub fn sequential_hint(mut self, enabled: bool) -> Self {
+        self.sequential_hint = enabled;
+        self
+    }
+
+    /// Evicts the file's pages from the page cache once it has been hashed.
+    ///
+    /// Issues `posix_fadvise(POSIX_FADV_DONTNEED)` after hashing on Linux, Android and FreeBSD,
+    /// and does nothing elsewhere. Unlike [`direct_io`](Self::direct_io) the file still goes
+    /// through the cache, but a long scrub job no longer pushes everything else out of it.
+    pub fn drop_cache(mut self, enabled: bool) -> Self {
+        self.drop_cache = enabled;
+        self
+    }
+
+    /// Overlaps reading and hashing by reading each file on a second thread.
+    ///
+    /// Normally a read and the hashing of its buffer strictly alternate, so neither the disk
+    /// nor the CPU is saturated. With this enabled, a reader thread fills one buffer while the
+    /// previous one is hashed, which helps most when both take similar time, such as SHA-256
+    /// over a fast SSD. Costs one extra buffer and a short-lived thread per file.
+    ///
+    /// Applies to files only; [`hash_reader`](Self::hash_reader) and unbuffered
+    /// [`direct_io`](Self::direct_io) reads are not pipelined.
+    pub fn pipelined(mut self, enabled: bool) -> Self {
+        self.pipelined = enabled;
+        self
+    }
+
+    /// Sets how a path that is a symbolic link is hashed. Links are followed by default.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Hashes files by memory-mapping them instead of reading them into a buffer.
+    ///
+    /// Available with the `mmap` feature. Mapping avoids a `read` syscall and a copy per
+    /// buffer, which pays off for large files on fast storage; for small files the setup cost
+    /// usually outweighs it. Files that cannot be mapped, such as pipes, quietly fall back to
+    /// the read loop.
+    ///
+    /// The mapping reflects the file as it changes on disk. Hashing a file that another process
+    /// is writing may produce a digest of neither version, and truncating it mid-hash can kill
+    /// the process with `SIGBUS`, so only enable this for files that are not being modified.
+    #[cfg(feature = "mmap")]
+    pub fn mmap(mut self, enabled: bool) -> Self {
+        self.mmap = enabled;
+        self
+    }
+
+    /// Sets the digest length in bytes for the extendable-output algorithms,
+    /// [`HashAlgorithm::Shake128`] and [`HashAlgorithm::Shake256`].
+    ///
+    /// Available with the `shake` feature. Unless set, they produce 32 and 64 bytes, the sizes
+    /// most protocols use; other algorithms have a fixed length and ignore this. Expected
+    /// hashes given to the validation methods must be of this length. A length of zero is
+    /// treated as one byte.
+    #[cfg(feature = "shake")]
+    pub fn output_len(mut self, bytes: usize) -> Self {
+        self.output_len = Some(bytes.max(1));
+        self
+    }
+
+    /// Returns `true` if a configured [`CancelToken`] has been cancelled.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel_token
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// Returns the configured algorithm.
+    pub fn selected_algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the length in bytes of the digests these options produce.
+    pub fn selected_output_len(&self) -> usize {
+        #[cfg(feature = "shake")]
+        if let Some(len) = self.output_len
+            && xof::is_xof(self.algorithm)
+        {
+            return len;
+        }
+        self.algorithm.output_len()
+    }
+
+    /// Creates an incremental [`Hasher`] for the configured algorithm and output length.
+    pub fn hasher(&self) -> Hasher {
+        self.hasher_for(self.algorithm)
+    }
+
+    fn hasher_for(&self, algorithm: HashAlgorithm) -> Hasher {
+        #[cfg(feature = "shake")]
+        if let Some(state) = self.output_len.and_then(|len| xof::hasher(algorithm, len)) {
+            return Hasher::from_state(algorithm, state);
+        }
+        Hasher::new(algorithm)
+    }
+
+    fn multi_hashers(&self, algorithms: &[HashAlgorithm]) -> HashMap<HashAlgorithm, Hasher> {
+        algorithms
+            .iter()
+            .map(|algorithm| (*algorithm, self.hasher_for(*algorithm)))
+            .collect()
+    }
+
+    /// Returns the read buffer size in bytes used when the data length is unknown: the fixed
+    /// [`buffer_size`](Self::buffer_size) if one is set.
+    pub fn selected_buffer_size(&self) -> usize {
+        let (min, max) = self.adaptive_range;
+        self.buffer_size
+            .unwrap_or_else(|| DEFAULT_BUFFER_SIZE.clamp(min, max))
+    }
+
+    /// Returns the read buffer size for data of `len` bytes.
+    pub(crate) fn buffer_size_for(&self, len: u64) -> usize {
+        if self.buffer_size.is_some() || len == 0 {
+            return self.selected_buffer_size();
+        }
+        let (min, max) = self.adaptive_range;
+        usize::try_from(len)
+            .ok()
+            .and_then(usize::checked_next_power_of_two)
+            .unwrap_or(max)
+            .clamp(min, max)
+    }
+
+    fn buffer_size_for_file(&self, file: &File) -> usize {
+        if self.buffer_size.is_some() {
+            return self.selected_buffer_size();
+        }
+        file.metadata()
+            .map_or(self.selected_buffer_size(), |metadata| {
+                self.buffer_size_for(metadata.len())
+            })
+    }
+
+    /// Hashes everything read from `reader` until EOF.
+    pub fn hash_reader<R: Read>(&self, reader: R) -> Result<Digest, SHAError> {
+        let mut hasher = self.hasher();
+        self.read_reader(reader, self.selected_buffer_size(), |chunk| {
+            hasher.update(chunk)
+        })?;
+        Ok(hasher.finalize())
+    }
+
+    /// Hands everything read from `reader` to `update`, `buffer_size` bytes at a time, and
+    /// stops with [`SHAError::Cancelled`] once the cancel token fires.
+    pub(crate) fn read_reader<R: Read>(
+        &self,
+        reader: R,
+        buffer_size: usize,
+        mut update: impl FnMut(&[u8]),
+    ) -> Result<(), SHAError> {
+        let flow = read_chunks_with(reader, buffer_size, |chunk| {
+            if self.is_cancelled() {
+                return ControlFlow::Break(());
+            }
+            update(chunk);
+            ControlFlow::Continue(())
+        })?;
+        match flow {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(SHAError::Cancelled),
+        }
+    }
+
+    /// Hashes a `File` handle from its current position.
+    pub fn hash_file(&self, file: File) -> Result<Digest, SHAError> {
+        let mut hasher = self.hasher();
+        self.read_file(&file, |chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
+
+    /// Computes several hashes of a `File` handle in a single read pass, keyed by algorithm.
+    ///
+    /// The configured algorithm is ignored in favour of `algorithms`; every other setting
+    /// applies as it does to [`hash_file`](Self::hash_file).
+    pub fn hash_file_multi(
+        &self,
+        file: File,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<HashMap<HashAlgorithm, Digest>, SHAError> {
+        let mut hashers = self.multi_hashers(algorithms);
+        self.read_file(&file, |chunk| update_all(&mut hashers, chunk))?;
+        Ok(finalize_all(hashers))
+    }
+
+    /// Computes several hashes of the file at `path` in a single read pass.
+    ///
+    /// See [`hash_file_multi`](Self::hash_file_multi) for details.
+    pub fn hash_file_from_path_multi(
+        &self,
+        path: impl AsRef<Path>,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<HashMap<HashAlgorithm, Digest>, SHAError> {
+        let mut hashers = self.multi_hashers(algorithms);
+        self.read_path(path.as_ref(), |chunk| update_all(&mut hashers, chunk))?;
+        Ok(finalize_all(hashers))
+    }
+
+    /// Hashes a `File` handle with any hasher implementing [`digest::Digest`] instead of the
+    /// configured algorithm.
+    pub fn hash_file_with<D: digest::Digest>(&self, file: File) -> Result<Digest, SHAError> {
+        let mut hasher = D::new();
+        self.read_file(&file, |chunk| hasher.update(chunk))?;
+        Ok(Digest::from(hasher.finalize().as_slice()))
+    }
+
+    /// Hashes the file at `path` with any hasher implementing [`digest::Digest`] instead of the
+    /// configured algorithm.
+    pub fn hash_file_from_path_with<D: digest::Digest>(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<Digest, SHAError> {
+        let mut hasher = D::new();
+        self.read_path(path.as_ref(), |chunk| hasher.update(chunk))?;
+        Ok(Digest::from(hasher.finalize().as_slice()))
+    }
+
+    /// Hands the contents of `file` from its current position to `update`, applying the read
+    /// hints, buffer sizing, memory mapping and pipelining settings.
+    pub(crate) fn read_file(&self, file: &File, update: impl FnMut(&[u8])) -> Result<(), SHAError> {
+        if self.sequential_hint {
+            direct::advise(file, Advice::Sequential);
+        }
+        let result = self.read_file_contents(file, update);
+        if self.drop_cache {
+            direct::advise(file, Advice::DontNeed);
+        }
+        result
+    }
+
+    fn read_file_contents(&self, file: &File, update: impl FnMut(&[u8])) -> Result<(), SHAError> {
+        #[cfg(feature = "mmap")]
+        let mut update = update;
+        #[cfg(feature = "mmap")]
+        if self.mmap && self.read_mapped(file, &mut update)? {
+            return Ok(());
+        }
+        let buffer_size = self.buffer_size_for_file(file);
+        if self.pipelined {
+            return pipeline::read(self, file, buffer_size, update);
+        }
+        self.read_reader(file, buffer_size, update)
+    }
+
+    /// Hands `file` to `update` through a memory map, or returns `false` if it cannot be
+    /// mapped.
+    #[cfg(feature = "mmap")]
+    fn read_mapped(&self, file: &File, mut update: impl FnMut(&[u8])) -> Result<bool, SHAError> {
+        use std::io::Seek;
+
+        let mut handle = file;
+        let offset = handle.stream_position()?;
+        // SAFETY: the map is only read while this function runs; the caveats about concurrent
+        // modification are documented on `HasherOptions::mmap`.
+        let Ok(map) = (unsafe { memmap2::Mmap::map(file) }) else {
+            return Ok(false);
+        };
+        #[cfg(unix)]
+        let _ = map.advise(memmap2::Advice::Sequential);
+
+        let data = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| map.get(offset..))
+            .unwrap_or_default();
+        for chunk in data.chunks(self.selected_buffer_size()) {
+            if self.is_cancelled() {
+                return Err(SHAError::Cancelled);
+            }
+            update(chunk);
+        }
+        Ok(true)
+    }
+
+    /// Hashes the file at `path`.
+    pub fn hash_file_from_path(&self, path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+        let mut hasher = self.hasher();
+        self.read_path(path.as_ref(), |chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
+
+    /// Hands the contents of the file at `path` to `update`, or its link target if the
+    /// [`SymlinkPolicy`] hashes links by target path.
+    fn read_path(&self, path: &Path, mut update: impl FnMut(&[u8])) -> Result<(), SHAError> {
+        if self.hashes_link_target(path)? {
+            update(&link_target(path)?);
+            return Ok(());
+        }
+        if !path.exists() {
+            return Err(SHAError::IO(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "File not found",
+            )));
+        }
+        self.read_open(&self.open(path)?, update)
+    }
+
+    /// Applies the [`SymlinkPolicy`] to `path`, returning the digest if it settles the hash.
+    fn check_symlink(&self, path: &Path) -> Result<Option<Digest>, SHAError> {
+        if self.hashes_link_target(path)? {
+            self.hash_link_target(path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns `true` if `path` is a symbolic link to be hashed by its target path, or fails
+    /// if the [`SymlinkPolicy`] rejects it.
+    fn hashes_link_target(&self, path: &Path) -> Result<bool, SHAError> {
+        if self.symlinks == SymlinkPolicy::Follow
+            || !fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink())
+        {
+            return Ok(false);
+        }
+        match self.symlinks {
+            SymlinkPolicy::HashTargetPath => Ok(true),
+            _ => Err(SHAError::Symlink(path.to_path_buf())),
+        }
+    }
+
+    /// Hashes the target path stored in the symbolic link at `path`.
+    pub(crate) fn hash_link_target(&self, path: &Path) -> Result<Digest, SHAError> {
+        let mut hasher = self.hasher();
+        hasher.update(link_target(path)?);
+        Ok(hasher.finalize())
+    }
+
+    /// Hashes the file at `path`, following it if it is a symbolic link.
+    pub(crate) fn hash_resolved(&self, path: &Path) -> Result<Digest, SHAError> {
+        if !path.exists() {
+            Err(SHAError::IO(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "File not found",
+            )))
+        } else {
+            self.hash_open(self.open(path)?)
+        }
+    }
+
+    fn open(&self, path: &Path) -> std::io::Result<OpenFile> {
+        if self.direct_io
+            && let Some(file) = direct::open(path)?
+        {
+            return Ok(OpenFile { file, direct: true });
+        }
+        Ok(OpenFile {
+            file: File::open(path)?,
+            direct: false,
+        })
+    }
+
+    fn hash_open(&self, open: OpenFile) -> Result<Digest, SHAError> {
+        let mut hasher = self.hasher();
+        self.read_open(&open, |chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
+
+    fn read_open(&self, open: &OpenFile, update: impl FnMut(&[u8])) -> Result<(), SHAError> {
+        if open.direct {
+            direct::read(self, &open.file, update)
+        } else {
+            self.read_file(&open.file, update)
+        }
+    }
+
+    /// Validates a `File` handle against an expected hash (case-insensitive).
+    ///
+    /// A hash written as `<algorithm>:<hex>`, such as `sha256:<hex>`, is checked with the
+    /// algorithm it declares instead of the configured one. This applies to every validation
+    /// method.
+    pub fn validate_file(&self, file: File, hash: impl AsRef<str>) -> bool {
+        let hash = hash.as_ref();
+        self.for_expected(hash)
+            .and_then(|options| options.hash_file(file))
+            .and_then(|computed| computed.matches_hex(hash))
+            .unwrap_or(false)
+    }
+
+    /// Validates the file at `path` against an expected hash (case-insensitive).
+    pub fn validate_file_from_path(
+        &self,
+        path: impl AsRef<Path>,
+        hash: impl AsRef<str>,
+    ) -> Result<bool, SHAError> {
+        let options = self.for_expected(hash.as_ref())?;
+        options.validate_resolved(path.as_ref(), hash.as_ref())
+    }
+
+    fn validate_resolved(&self, path: &Path, hash: &str) -> Result<bool, SHAError> {
+        if let Some(digest) = self.check_symlink(path.as_ref())? {
+            return digest.matches_hex(hash);
+        }
+        let Ok(file) = self.open(path) else {
+            return Err(SHAError::IO(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "File not found",
+            )));
+        };
+        self.hash_open(file)?.matches_hex(hash)
+    }
+
+    /// Validates the file at `path`, returning [`SHAError::FailedValidation`] on a mismatch.
+    pub fn validate_or_err(
+        &self,
+        path: impl AsRef<Path>,
+        hash: impl AsRef<str>,
+    ) -> Result<(), SHAError> {
+        let (path, hash) = (path.as_ref(), hash.as_ref());
+        let options = self.for_expected(hash)?;
+        if let Some(digest) = options.check_symlink(path)? {
+            return digest.ensure_matches(hash, path);
+        }
+        let Ok(file) = options.open(path) else {
+            return Err(SHAError::IO(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "File not found",
+            )));
+        };
+        options.hash_open(file)?.ensure_matches(hash, path)
+    }
+
+    /// Switches to the algorithm `expected` declares with an `<algorithm>:` prefix, if any,
+    /// and checks that the hex has the length these options produce for it.
+    fn for_expected(&self, expected: &str) -> Result<Cow<'_, HasherOptions>, SHAError> {
+        let (algorithm, hex) = split_declared(expected, self.algorithm)?;
+        let options = if algorithm == self.algorithm {
+            Cow::Borrowed(self)
+        } else {
+            Cow::Owned(self.clone().algorithm(algorithm))
+        };
+        Digest::parse_sized(hex, algorithm, options.selected_output_len())?;
+        Ok(options)
+    }
+
+    /// Hashes every file in `paths`, collecting a result per path.
+    ///
+    /// A failure on one file does not stop the batch; its error is stored under its path and
+    /// the remaining files are still hashed. Repeated paths are hashed once.
+    pub fn hash_paths<P: AsRef<Path>>(
+        &self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> HashMap<PathBuf, Result<Digest, SHAError>> {
+        let mut results = HashMap::new();
+        for path in paths {
+            let path = path.as_ref();
+            if !results.contains_key(path) {
+                results.insert(path.to_path_buf(), self.hash_file_from_path(path));
+            }
+        }
+        results
+    }
+
+    /// Hashes every file in `paths` across a pool of `threads` worker threads.
+    ///
+    /// Available with the `rayon` feature. Results are the same as [`hash_paths`](Self::hash_paths);
+    /// only the files are hashed concurrently, each one still read sequentially. A `threads` of
+    /// zero uses one thread per CPU. The pool is created for this call and torn down afterwards,
+    /// so the global rayon pool is left untouched.
+    ///
+    /// # Errors
+    /// Fails only if the thread pool cannot be created.
+    #[cfg(feature = "rayon")]
+    pub fn hash_paths_parallel<P: AsRef<Path>>(
+        &self,
+        paths: impl IntoIterator<Item = P>,
+        threads: usize,
+    ) -> Result<HashMap<PathBuf, Result<Digest, SHAError>>, SHAError> {
+        use rayon::prelude::*;
+        use std::collections::HashSet;
+
+        let mut seen = HashSet::new();
+        let paths: Vec<PathBuf> = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .filter(|path| seen.insert(path.clone()))
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| SHAError::IO(std::io::Error::other(e)))?;
+        Ok(pool.install(|| {
+            paths
+                .into_par_iter()
+                .map(|path| {
+                    let result = self.hash_file_from_path(&path);
+                    (path, result)
+                })
+                .collect()
+        }))
+    }
+}
+
+/// Returns the target path stored in the symbolic link at `path` as bytes.
+fn link_target(path: &Path) -> std::io::Result<Vec<u8>> {
+    let target = fs::read_link(path)?;
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStringExt::into_vec(target.into_os_string());
+    #[cfg(not(unix))]
+    let bytes = target.to_string_lossy().into_owned().into_bytes();
+    Ok(bytes)
+}
+
+fn update_all(hashers: &mut HashMap<HashAlgorithm, Hasher>, chunk: &[u8]) {
+    for hasher in hashers.values_mut() {
+        hasher.update(chunk);
+    }
+}
+
+fn finalize_all(hashers: HashMap<HashAlgorithm, Hasher>) -> HashMap<HashAlgorithm, Digest> {
+    hashers
+        .into_iter()
+        .map(|(algorithm, hasher)| (algorithm, hasher.finalize()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_cache_hints_do_not_change_digest() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&[7u8; 50_000]).unwrap();
+        temp_file.flush().unwrap();
+
+        let options = HasherOptions::new().sequential_hint(true).drop_cache(true);
+        assert_eq!(
+            options.hash_file_from_path(temp_file.path()).unwrap(),
+            HasherOptions::new()
+                .hash_file_from_path(temp_file.path())
+                .unwrap()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let target = temp_dir.path().join("target.txt");
+        let link = temp_dir.path().join("link.txt");
+        std::fs::write(&target, b"test data").unwrap();
+        std::os::unix::fs::symlink("target.txt", &link).unwrap();
+
+        let follow = HasherOptions::new();
+        assert_eq!(
+            follow.hash_file_from_path(&link).unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+
+        let target_path = follow.clone().symlinks(SymlinkPolicy::HashTargetPath);
+        assert_eq!(
+            target_path.hash_file_from_path(&link).unwrap(),
+            follow.hash_reader(&b"target.txt"[..]).unwrap()
+        );
+        // Regular files are unaffected by the policy
+        assert_eq!(
+            target_path.hash_file_from_path(&target).unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+
+        for policy in [SymlinkPolicy::Skip, SymlinkPolicy::Error] {
+            let options = follow.clone().symlinks(policy);
+            assert!(matches!(
+                options.hash_file_from_path(&link),
+                Err(SHAError::Symlink(path)) if path == link
+            ));
+            assert!(matches!(
+                options.validate_file_from_path(&link, "0000000000000000000000000000000000000000"),
+                Err(SHAError::Symlink(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_adaptive_buffer_sizes() {
+        let options = HasherOptions::new();
+        assert_eq!(options.buffer_size_for(0), DEFAULT_BUFFER_SIZE);
+        assert_eq!(options.buffer_size_for(10), ADAPTIVE_MIN);
+        assert_eq!(options.buffer_size_for(100_000), 128 * 1024);
+        assert_eq!(options.buffer_size_for(u64::MAX), ADAPTIVE_MAX);
+
+        let narrow = HasherOptions::new().adaptive_buffer(64 * 1024, 256 * 1024);
+        assert_eq!(narrow.buffer_size_for(10), 64 * 1024);
+        assert_eq!(narrow.buffer_size_for(1 << 30), 256 * 1024);
+
+        let fixed = narrow.buffer_size(1000);
+        assert_eq!(fixed.buffer_size_for(1 << 30), 1000);
+        assert_eq!(fixed.adaptive_buffer(0, 0).buffer_size_for(1 << 30), 1);
+
+        // Adaptive sizing never changes the digest
+        let data = vec![3u8; 300_000];
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&data).unwrap();
+        temp_file.flush().unwrap();
+        assert_eq!(
+            options.hash_file_from_path(temp_file.path()).unwrap(),
+            options.hash_reader(&data[..]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pipelined_matches_read_loop() {
+        let options = HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha512)
+            .buffer_size(1000);
+        let pipelined = options.clone().pipelined(true);
+        for len in [0, 999, 1000, 54_321] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut temp_file = NamedTempFile::new().unwrap();
+            temp_file.write_all(&data).unwrap();
+            temp_file.flush().unwrap();
+            assert_eq!(
+                pipelined.hash_file_from_path(temp_file.path()).unwrap(),
+                options.hash_reader(&data[..]).unwrap(),
+                "length {}",
+                len
+            );
+        }
+
+        let token = CancelToken::new();
+        token.cancel();
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&[1u8; 10_000]).unwrap();
+        temp_file.flush().unwrap();
+        assert!(matches!(
+            pipelined
+                .cancel_token(token)
+                .hash_file_from_path(temp_file.path()),
+            Err(SHAError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_direct_io_matches_read_loop() {
+        let options = HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha256)
+            .buffer_size(5000);
+        let direct = options.clone().direct_io(true);
+        for len in [0, 1, 4096, 12_289] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            // Prefer a disk-backed directory, since tmpfs /tmp rejects O_DIRECT
+            let mut temp_file =
+                NamedTempFile::new_in(concat!(env!("CARGO_MANIFEST_DIR"), "/target"))
+                    .or_else(|_| NamedTempFile::new())
+                    .unwrap();
+            temp_file.write_all(&data).unwrap();
+            temp_file.flush().unwrap();
+            let expected = options.hash_reader(&data[..]).unwrap();
+            assert_eq!(
+                direct.hash_file_from_path(temp_file.path()).unwrap(),
+                expected,
+                "length {}",
+                len
+            );
+            assert!(
+                direct
+                    .validate_file_from_path(temp_file.path(), expected.to_hex())
+                    .unwrap()
+            );
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_matches_read_loop() {
+        use std::io::{Seek, SeekFrom};
+
+        let options = HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha256)
+            .buffer_size(4096);
+        let mapped = options.clone().mmap(true);
+        for len in [0, 1, 4096, 100_000] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let mut temp_file = NamedTempFile::new().unwrap();
+            temp_file.write_all(&data).unwrap();
+            temp_file.flush().unwrap();
+            assert_eq!(
+                mapped.hash_file_from_path(temp_file.path()).unwrap(),
+                options.hash_reader(&data[..]).unwrap(),
+                "length {}",
+                len
+            );
+        }
+
+        // Hashing starts from the current position, like the read loop
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"skip test data").unwrap();
+        temp_file.flush().unwrap();
+        let mut file = temp_file.reopen().unwrap();
+        file.seek(SeekFrom::Start(5)).unwrap();
+        assert_eq!(
+            mapped.hash_file(file).unwrap(),
+            options.hash_reader(&b"test data"[..]).unwrap()
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_hash_paths_parallel_matches_sequential() {
+        let files: Vec<NamedTempFile> = (0..16)
+            .map(|i| {
+                let mut temp_file = NamedTempFile::new().unwrap();
+                temp_file.write_all(&vec![i as u8; i * 1000]).unwrap();
+                temp_file.flush().unwrap();
+                temp_file
+            })
+            .collect();
+        let mut paths: Vec<&Path> = files.iter().map(|f| f.path()).collect();
+        paths.push(files[0].path());
+        paths.push(Path::new("nonexistent_file_12345.txt"));
+
+        let options = HasherOptions::new().algorithm(HashAlgorithm::Sha256);
+        let parallel = options.hash_paths_parallel(&paths, 4).unwrap();
+        let sequential = options.hash_paths(&paths);
+        assert_eq!(parallel.len(), 17);
+        for (path, result) in &sequential {
+            match (result, &parallel[path]) {
+                (Ok(expected), Ok(actual)) => assert_eq!(expected, actual),
+                (Err(_), Err(SHAError::IO(_))) => {}
+                _ => panic!("Mismatched result for {}", path.display()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_defaults_match_free_functions() {
+        let options = HasherOptions::new();
+        assert_eq!(options.selected_algorithm(), HashAlgorithm::Sha1);
+        assert_eq!(options.selected_buffer_size(), DEFAULT_BUFFER_SIZE);
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"Hello, World!").unwrap();
+        temp_file.flush().unwrap();
+        assert_eq!(
+            options.hash_file_from_path(temp_file.path()).unwrap(),
+            crate::hash_file_from_path(temp_file.path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_buffer_size_does_not_change_digest() {
+        let data = vec![b'q'; 10_000];
+        let expected = HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha256)
+            .hash_reader(Cursor::new(&data))
+            .unwrap();
+
+        for buffer_size in [0, 1, 7, 4096, 1 << 20] {
+            let options = HasherOptions::new()
+                .algorithm(HashAlgorithm::Sha256)
+                .buffer_size(buffer_size);
+            assert_eq!(options.hash_reader(Cursor::new(&data)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_cancel_token_stops_hashing() {
+        let token = CancelToken::new();
+        let options = HasherOptions::new()
+            .buffer_size(8192)
+            .cancel_token(token.clone());
+        let data = vec![0u8; 100_000];
+        assert!(options.hash_reader(Cursor::new(&data)).is_ok());
+
+        // Cancel from inside the stream, partway through the data
+        struct CancelAfter<'a>(Cursor<&'a [u8]>, CancelToken);
+        impl Read for CancelAfter<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.0.position() > 50_000 {
+                    self.1.cancel();
+                }
+                self.0.read(buf)
+            }
+        }
+        let reader = CancelAfter(Cursor::new(&data), token.clone());
+        assert!(matches!(
+            options.hash_reader(reader),
+            Err(SHAError::Cancelled)
+        ));
+        assert!(matches!(
+            options.hash_reader(Cursor::new(&data)),
+            Err(SHAError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_multi_and_generic_hashes_honor_options() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&vec![7u8; 100_000]).unwrap();
+        temp_file.flush().unwrap();
+        let algorithms = [HashAlgorithm::Sha1, HashAlgorithm::Sha256];
+        let expected = HasherOptions::new()
+            .hash_file_from_path_multi(temp_file.path(), &algorithms)
+            .unwrap();
+
+        let tuned = HasherOptions::new()
+            .buffer_size(4096)
+            .pipelined(true)
+            .direct_io(true);
+        assert_eq!(
+            tuned
+                .hash_file_from_path_multi(temp_file.path(), &algorithms)
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            tuned
+                .hash_file_from_path_with::<sha2::Sha256>(temp_file.path())
+                .unwrap(),
+            expected[&HashAlgorithm::Sha256]
+        );
+
+        let token = CancelToken::new();
+        token.cancel();
+        let cancelled = HasherOptions::new().cancel_token(token);
+        assert!(matches!(
+            cancelled.hash_file_multi(File::open(temp_file.path()).unwrap(), &algorithms),
+            Err(SHAError::Cancelled)
+        ));
+        assert!(matches!(
+            cancelled.hash_file_from_path_with::<sha2::Sha256>(temp_file.path()),
+            Err(SHAError::Cancelled)
+        ));
+    }
+
+    #[cfg(feature = "shake")]
+    #[test]
+    fn test_shake_default_lengths() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        assert_eq!(
+            HasherOptions::new()
+                .algorithm(HashAlgorithm::Shake128)
+                .hash_file_from_path(temp_file.path())
+                .unwrap(),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+        assert_eq!(
+            HasherOptions::new()
+                .algorithm(HashAlgorithm::Shake256)
+                .hash_file_from_path(temp_file.path())
+                .unwrap(),
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+             d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+        );
+    }
+
+    #[cfg(feature = "shake")]
+    #[test]
+    fn test_output_len_sets_shake_length() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let options = HasherOptions::new()
+            .algorithm(HashAlgorithm::Shake256)
+            .output_len(32);
+        assert_eq!(options.selected_output_len(), 32);
+
+        let short = options.hash_file_from_path(temp_file.path()).unwrap();
+        assert_eq!(
+            short,
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f"
+        );
+        assert_eq!(options.hash_reader(Cursor::new(b"")).unwrap(), short);
+        assert!(
+            options
+                .validate_file_from_path(temp_file.path(), short.to_hex().to_uppercase())
+                .unwrap()
+        );
+        assert!(
+            options
+                .validate_file_from_path(
+                    temp_file.path(),
+                    short.to_prefixed(HashAlgorithm::Shake256)
+                )
+                .unwrap()
+        );
+        assert!(short == *short.to_prefixed(HashAlgorithm::Shake256));
+        assert!(matches!(
+            HasherOptions::new()
+                .algorithm(HashAlgorithm::Shake256)
+                .validate_file_from_path(temp_file.path(), short.to_hex()),
+            Err(SHAError::InvalidDigest(_))
+        ));
+
+        // Fixed-size algorithms keep their length
+        let sha256 = options.clone().algorithm(HashAlgorithm::Sha256);
+        assert_eq!(sha256.selected_output_len(), 32);
+        let multi = options
+            .hash_file_from_path_multi(
+                temp_file.path(),
+                &[HashAlgorithm::Shake128, HashAlgorithm::Sha1],
+            )
+            .unwrap();
+        assert_eq!(multi[&HashAlgorithm::Shake128].len(), 32);
+        assert_eq!(multi[&HashAlgorithm::Sha1].len(), 20);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), b"").unwrap();
+        let manifest = crate::DirOptions::new()
+            .hasher(options.clone().output_len(16))
+            .hash_dir(dir.path())
+            .unwrap();
+        assert_eq!(
+            manifest.get("a.txt").unwrap(),
+            "46b9dd2b0ba88d13233b3feb743eeb24"
+        );
+    }
+
+    #[test]
+    fn test_hash_paths_collects_errors() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"test data").unwrap();
+        temp_file.flush().unwrap();
+        let missing = Path::new("nonexistent_file_12345.txt");
+
+        let results =
+            HasherOptions::new().hash_paths([temp_file.path(), missing, temp_file.path()]);
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[temp_file.path()].as_ref().unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+        assert!(matches!(results[missing], Err(SHAError::IO(_))));
+    }
+
+    #[test]
+    fn test_validate_missing_file() {
+        match HasherOptions::new().validate_file_from_path(
+            "nonexistent_file_12345.txt",
+            "0000000000000000000000000000000000000000",
+        ) {
+            Err(SHAError::IO(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("Expected IO error"),
+        }
+    }
+}
@@ -0,0 +1,442 @@
+use crate::{HashAlgorithm, SHAError};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+
+/// The raw bytes of a computed hash.
+///
+/// Every hashing API in the crate returns a `Digest` rather than a hex `String`, so results can
+/// be stored compactly (20 bytes for SHA-1, 32 for SHA-256) and compared without re-parsing.
+/// [`Display`](fmt::Display) and [`to_hex`](Digest::to_hex) produce the familiar lowercase hex
+/// form, and expected hashes can be parsed back with [`FromStr`] or, when the algorithm is
+/// known, with [`Digest::parse`].
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::Hashable;
+///
+/// let digest = b"Hello, World!".as_slice().hash().unwrap();
+/// assert_eq!(digest.len(), 20);
+/// assert_eq!(digest.to_hex(), "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+/// assert_eq!(format!("{}", digest), digest.to_hex());
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Digest(Box<[u8]>);
+
+impl Digest {
+    /// Parses a hex digest produced by `algorithm`.
+    ///
+    /// Unlike [`FromStr`], this also checks that the length matches the algorithm's output, so
+    /// a truncated or mistyped expected hash is rejected up front instead of failing validation
+    /// later.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidDigest`] if `s` is not hex or has the wrong length.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{Digest, HashAlgorithm};
+    ///
+    /// let expected = Digest::parse("DA39A3EE5E6B4B0D3255BFEF95601890AFD80709", HashAlgorithm::Sha1);
+    /// assert!(expected.is_ok());
+    /// assert!(Digest::parse("da39a3ee", HashAlgorithm::Sha1).is_err());
+    /// ```
+    pub fn parse(s: &str, algorithm: HashAlgorithm) -> Result<Self, SHAError> {
+        Self::parse_sized(s, algorithm, algorithm.output_len())
+    }
+
+    /// Like [`parse`](Self::parse), expecting `expected` bytes, such as a SHAKE length set with
+    /// [`HasherOptions::output_len`](crate::HasherOptions).
+    pub(crate) fn parse_sized(
+        s: &str,
+        algorithm: HashAlgorithm,
+        expected: usize,
+    ) -> Result<Self, SHAError> {
+        let digest: Digest = s.parse()?;
+        if digest.len() != expected {
+            return Err(SHAError::InvalidDigest(format!(
+                "expected {} hex characters for {}, found {}",
+                expected * 2,
+                algorithm,
+                s.len()
+            )));
+        }
+        Ok(digest)
+    }
+
+    /// Wraps raw digest bytes.
+    pub fn from_bytes(bytes: impl Into<Box<[u8]>>) -> Self {
+        Self(bytes.into())
+    }
+
+    /// Returns the raw digest bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consumes the digest, returning its bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0.into_vec()
+    }
+
+    /// Returns the digest as a lowercase hexadecimal string.
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    /// Returns the digest length in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Formats the digest as `<algorithm>:<hex>`, as container registries and OCI manifests
+    /// write digests.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{Digest, HashAlgorithm, Hashable};
+    ///
+    /// let digest = "test data".hash_with(HashAlgorithm::Sha256).unwrap();
+    /// let prefixed = digest.to_prefixed(HashAlgorithm::Sha256);
+    /// assert_eq!(
+    ///     prefixed,
+    ///     "sha256:916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9"
+    /// );
+    /// assert_eq!(
+    ///     Digest::parse_prefixed(&prefixed).unwrap(),
+    ///     (HashAlgorithm::Sha256, digest)
+    /// );
+    /// ```
+    pub fn to_prefixed(&self, algorithm: HashAlgorithm) -> String {
+        format!("{}:{}", algorithm, self)
+    }
+
+    /// Parses an `<algorithm>:<hex>` digest such as `sha256:<hex>`.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidDigest`] if there is no prefix or the hex does not fit the
+    /// algorithm, and [`SHAError::UnsupportedAlgorithm`] for an unknown algorithm.
+    pub fn parse_prefixed(s: &str) -> Result<(HashAlgorithm, Digest), SHAError> {
+        let Some((algorithm, hex)) = s.split_once(':') else {
+            return Err(SHAError::InvalidDigest(format!(
+                "expected <algorithm>:<hex>, found {:?}",
+                s
+            )));
+        };
+        let algorithm = algorithm.parse()?;
+        Ok((algorithm, Digest::parse(hex, algorithm)?))
+    }
+
+    /// Compares against an expected hex string, ignoring case and any `<algorithm>:` prefix.
+    ///
+    /// Text that is not hex, or hex of a different length than this digest, is reported as
+    /// [`SHAError::InvalidDigest`] rather than as a mismatch. The decoded bytes are compared in
+    /// constant time, since the expected hash may come from untrusted input.
+    pub(crate) fn matches_hex(&self, expected: &str) -> Result<bool, SHAError> {
+        let hex = expected.split_once(':').map_or(expected, |(_, hex)| hex);
+        let expected: Digest = hex.parse()?;
+        if expected.len() != self.len() {
+            return Err(SHAError::InvalidDigest(format!(
+                "expected {} hex characters, found {}",
+                self.len() * 2,
+                hex.len()
+            )));
+        }
+        Ok(self.0.ct_eq(&expected.0).into())
+    }
+
+    /// Like [`matches_hex`](Digest::matches_hex), but turns a mismatch into
+    /// [`SHAError::FailedValidation`] for `path`.
+    pub(crate) fn ensure_matches(self, expected: &str, path: &Path) -> Result<(), SHAError> {
+        if self.matches_hex(expected)? {
+            Ok(())
+        } else {
+            Err(SHAError::FailedValidation {
+                path: path.to_path_buf(),
+                expected: expected.to_string(),
+                actual: self,
+            })
+        }
+    }
+
+    /// Returns `true` for a zero-length digest, such as one built from no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Returns the algorithm an expected hash declares with an `<algorithm>:` prefix, or
+/// `fallback` for bare hex.
+///
+/// The hex is checked against that algorithm with [`Digest::parse`], so a malformed hash is
+/// rejected before any data is read.
+pub(crate) fn declared_algorithm(
+    expected: &str,
+    fallback: HashAlgorithm,
+) -> Result<HashAlgorithm, SHAError> {
+    let (algorithm, hex) = split_declared(expected, fallback)?;
+    Digest::parse(hex, algorithm)?;
+    Ok(algorithm)
+}
+
+/// Splits an expected hash into the algorithm its `<algorithm>:` prefix declares, or
+/// `fallback` for bare hex, and the hex itself.
+pub(crate) fn split_declared(
+    expected: &str,
+    fallback: HashAlgorithm,
+) -> Result<(HashAlgorithm, &str), SHAError> {
+    match expected.split_once(':') {
+        Some((algorithm, hex)) => Ok((algorithm.parse()?, hex)),
+        None => Ok((fallback, expected)),
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest({})", self)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = SHAError;
+
+    /// Parses a hex digest of any length, accepting upper- and lowercase digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.len().is_multiple_of(2) {
+            return Err(SHAError::InvalidDigest(format!(
+                "odd number of hex characters: {}",
+                s
+            )));
+        }
+        s.as_bytes()
+            .chunks(2)
+            .map(|pair| match (hex_value(pair[0]), hex_value(pair[1])) {
+                (Some(high), Some(low)) => Ok(high << 4 | low),
+                _ => Err(SHAError::InvalidDigest(format!(
+                    "invalid hex character in: {}",
+                    s
+                ))),
+            })
+            .collect::<Result<Vec<u8>, _>>()
+            .map(Digest::from)
+    }
+}
+
+/// Compares against a hex string, ignoring case; text that is not hex never matches.
+///
+/// An `<algorithm>:` prefix must name a known algorithm whose digests have this digest's
+/// length, so `md5:<hex>` never equals a SHA-1 digest. SHAKE digests can have any length.
+///
+/// ```
+/// use sha_file_hashing::Hashable;
+///
+/// let digest = "test data".hash().unwrap();
+/// assert!(digest == "F48DD853820860816C75D54D0F584DC863327A7C");
+/// ```
+impl PartialEq<str> for Digest {
+    fn eq(&self, other: &str) -> bool {
+        if let Some((algorithm, _)) = other.split_once(':') {
+            match algorithm.parse::<HashAlgorithm>() {
+                Ok(algorithm) if algorithm.output_len() == self.len() => {}
+                #[cfg(feature = "shake")]
+                Ok(algorithm) if crate::xof::is_xof(algorithm) => {}
+                _ => return false,
+            }
+        }
+        self.matches_hex(other).unwrap_or(false)
+    }
+}
+
+impl PartialEq<&str> for Digest {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<String> for Digest {
+    fn eq(&self, other: &String) -> bool {
+        *self == *other.as_str()
+    }
+}
+
+impl PartialEq<Digest> for str {
+    fn eq(&self, other: &Digest) -> bool {
+        *other == *self
+    }
+}
+
+impl PartialEq<Digest> for &str {
+    fn eq(&self, other: &Digest) -> bool {
+        *other == **self
+    }
+}
+
+impl PartialEq<Digest> for String {
+    fn eq(&self, other: &Digest) -> bool {
+        *other == *self.as_str()
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Digest {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl From<Box<[u8]>> for Digest {
+    fn from(bytes: Box<[u8]>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for Digest {
+    fn from(bytes: &[u8]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl From<Digest> for Vec<u8> {
+    fn from(digest: Digest) -> Self {
+        digest.into_bytes()
+    }
+}
+
+/// Formats digest bytes as a lowercase hexadecimal string.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_hex_and_bytes() {
+        let digest = Digest::from(vec![0x0a, 0xff, 0x00]);
+        assert_eq!(digest.as_bytes(), &[0x0a, 0xff, 0x00]);
+        assert_eq!(digest.to_hex(), "0aff00");
+        assert_eq!(digest.to_string(), "0aff00");
+        assert_eq!(format!("{:?}", digest), "Digest(0aff00)");
+        assert_eq!(digest.len(), 3);
+        assert!(!digest.is_empty());
+    }
+
+    #[test]
+    fn test_digest_equality() {
+        let a = Digest::from_bytes([1u8, 2, 3]);
+        let b = Digest::from(&[1u8, 2, 3][..]);
+        assert_eq!(a, b);
+        assert_ne!(a, Digest::from(vec![1, 2, 4]));
+        assert_eq!(Vec::from(a), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_digest_from_str() {
+        let digest: Digest = "0AfF00".parse().unwrap();
+        assert_eq!(digest.as_bytes(), &[0x0a, 0xff, 0x00]);
+        assert!(matches!(
+            "abc".parse::<Digest>(),
+            Err(SHAError::InvalidDigest(_))
+        ));
+        assert!(matches!(
+            "zz".parse::<Digest>(),
+            Err(SHAError::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_matches_hex() {
+        let digest = Digest::from(vec![0xde, 0xad, 0xbe, 0xef]);
+        assert!(digest.matches_hex("deadbeef").unwrap());
+        assert!(digest.matches_hex("DEADBEEF").unwrap());
+        assert!(!digest.matches_hex("deadbeee").unwrap());
+        assert!(matches!(
+            digest.matches_hex("deadbeef00"),
+            Err(SHAError::InvalidDigest(_))
+        ));
+        assert!(digest.matches_hex("deadbeeg").is_err());
+    }
+
+    #[test]
+    fn test_digest_eq_str() {
+        let digest = Digest::from(vec![0xab, 0xcd]);
+        assert_eq!(digest, "abcd");
+        assert_eq!(digest, "ABCD");
+        assert_eq!("AbCd", digest);
+        assert_eq!(digest, String::from("abcd"));
+        assert_ne!(digest, "abce");
+        assert_ne!(digest, "abcd00");
+        assert_ne!(digest, "not hex");
+
+        let sha1 = Digest::parse(
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709",
+            HashAlgorithm::Sha1,
+        )
+        .unwrap();
+        assert_eq!(sha1, "sha1:da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(sha1, "SHA1:DA39A3EE5E6B4B0D3255BFEF95601890AFD80709");
+        assert_ne!(sha1, "md5:da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_ne!(sha1, "garbage:da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    }
+
+    #[test]
+    fn test_digest_parse_checks_length() {
+        let sha1 = "da39a3ee5e6b4b0d3255bfef95601890afd80709";
+        assert_eq!(
+            Digest::parse(sha1, HashAlgorithm::Sha1).unwrap().to_hex(),
+            sha1
+        );
+        match Digest::parse(sha1, HashAlgorithm::Sha256) {
+            Err(SHAError::InvalidDigest(message)) => assert!(message.contains("sha256")),
+            _ => panic!("Expected InvalidDigest error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_prefixed() {
+        let (algorithm, digest) =
+            Digest::parse_prefixed("SHA-1:DA39A3EE5E6B4B0D3255BFEF95601890AFD80709").unwrap();
+        assert_eq!(algorithm, HashAlgorithm::Sha1);
+        assert_eq!(
+            digest.to_prefixed(algorithm),
+            "sha1:da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert!(matches!(
+            Digest::parse_prefixed("da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            Err(SHAError::InvalidDigest(_))
+        ));
+        assert!(matches!(
+            Digest::parse_prefixed("sha256:da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            Err(SHAError::InvalidDigest(_))
+        ));
+        assert!(matches!(
+            Digest::parse_prefixed("blake9:00"),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::dir::portable_path;
+use crate::{Manifest, SHAError};
+use std::path::{Path, PathBuf};
+
+/// How [`Manifest::rewrite_paths`] rewrites entry paths before a manifest is written.
+///
+/// Every writer, from [`ChecksumFile`](crate::ChecksumFile) to
+/// [`to_csv`](Manifest::to_csv), already separates path components with `/`, so a tree hashed
+/// on Windows and on Linux is written identically. These options cover the rest: dropping a
+/// leading directory, anchoring paths under another one, and splitting names imported from
+/// Windows tools on `\`.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::{HashAlgorithm, Hashable, Manifest, PathOptions};
+///
+/// let mut manifest = Manifest::new(HashAlgorithm::Sha256);
+/// manifest.insert("dist\\app.js", "code".hash_with(HashAlgorithm::Sha256).unwrap());
+///
+/// let options = PathOptions::new()
+///     .split_backslashes(true)
+///     .strip_prefix("dist")
+///     .prefix("/srv/www");
+/// let rewritten = manifest.rewrite_paths(&options).unwrap();
+/// assert!(rewritten.get("/srv/www/app.js").is_some());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    split_backslashes: bool,
+    strip_prefix: Option<PathBuf>,
+    prefix: Option<PathBuf>,
+    absolute: bool,
+}
+
+impl PathOptions {
+    /// Creates options that leave paths unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treats `\` in entry paths as a separator, as it is on Windows.
+    ///
+    /// On other platforms `\` is an ordinary file name character, so paths read from a
+    /// checksum file written on Windows arrive as single names like `dir\file.txt`.
+    pub fn split_backslashes(mut self, enabled: bool) -> Self {
+        self.split_backslashes = enabled;
+        self
+    }
+
+    /// Removes `prefix` from the start of every path.
+    ///
+    /// Entries outside `prefix` make [`rewrite_paths`](Manifest::rewrite_paths) fail.
+    pub fn strip_prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.strip_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Places every path under `prefix`, applied after [`strip_prefix`](PathOptions::strip_prefix).
+    ///
+    /// An absolute `prefix` makes every path absolute.
+    pub fn prefix(mut self, prefix: impl Into<PathBuf>) -> Self {
+        self.prefix = Some(prefix.into());
+        self.absolute = false;
+        self
+    }
+
+    /// Makes every path absolute by placing it under `base_dir`, which is itself made absolute
+    /// against the current directory when the paths are rewritten.
+    pub fn absolute(mut self, base_dir: impl Into<PathBuf>) -> Self {
+        self.prefix = Some(base_dir.into());
+        self.absolute = true;
+        self
+    }
+
+    fn rewrite(&self, path: &Path, prefix: Option<&Path>) -> Result<PathBuf, SHAError> {
+        let mut path = match self.split_backslashes {
+            true => PathBuf::from(portable_path(path).replace('\\', "/")),
+            false => path.to_path_buf(),
+        };
+        if let Some(strip) = &self.strip_prefix {
+            path = path
+                .strip_prefix(strip)
+                .map_err(|_| {
+                    SHAError::InvalidManifest(format!(
+                        "{} is not under {}",
+                        portable_path(&path),
+                        portable_path(strip)
+                    ))
+                })?
+                .to_path_buf();
+        }
+        Ok(match prefix {
+            Some(prefix) => prefix.join(path),
+            None => path,
+        })
+    }
+}
+
+impl Manifest {
+    /// Returns a copy of the manifest with every path rewritten by `options`, ready to be
+    /// written with rooted, stripped or normalized paths.
+    ///
+    /// Digests and [`FileStamp`](crate::FileStamp)s are kept. Paths that no longer point at the
+    /// hashed files cannot be checked with [`DirOptions::verify`](crate::DirOptions::verify)
+    /// against the original root, so rewrite a copy just before writing it.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if a path is outside the
+    /// [`strip_prefix`](PathOptions::strip_prefix) or two paths become the same, and
+    /// [`SHAError::File`] if an [`absolute`](PathOptions::absolute) base cannot be resolved.
+    pub fn rewrite_paths(&self, options: &PathOptions) -> Result<Manifest, SHAError> {
+        let prefix = match (&options.prefix, options.absolute) {
+            (Some(base), true) => {
+                Some(std::path::absolute(base).map_err(|e| SHAError::from(e).at_path(base))?)
+            }
+            (prefix, _) => prefix.clone(),
+        };
+        let mut rewritten = Manifest::new(self.algorithm());
+        for (path, digest) in self.iter() {
+            let new_path = options.rewrite(path, prefix.as_deref())?;
+            if rewritten.get(&new_path).is_some() {
+                return Err(SHAError::InvalidManifest(format!(
+                    "more than one entry becomes {}",
+                    portable_path(&new_path)
+                )));
+            }
+            rewritten.insert_with_stamp(new_path, digest.clone(), self.stamp(path).copied());
+        }
+        Ok(rewritten)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChecksumFile, HashAlgorithm, Hashable};
+
+    fn manifest(paths: &[&str]) -> Manifest {
+        let mut manifest = Manifest::new(HashAlgorithm::Md5);
+        for path in paths {
+            manifest.insert(*path, path.hash_with(HashAlgorithm::Md5).unwrap());
+        }
+        manifest
+    }
+
+    #[test]
+    fn test_rewrite_paths() {
+        let original = manifest(&["release/a.txt", "release/docs/b.txt"]);
+        let stripped = original
+            .rewrite_paths(&PathOptions::new().strip_prefix("release"))
+            .unwrap();
+        let paths: Vec<_> = stripped
+            .iter()
+            .map(|(path, _)| portable_path(path))
+            .collect();
+        assert_eq!(paths, ["a.txt", "docs/b.txt"]);
+        assert_eq!(stripped.get("a.txt"), original.get("release/a.txt"));
+
+        let rooted = original
+            .rewrite_paths(&PathOptions::new().strip_prefix("release").prefix("/srv"))
+            .unwrap();
+        let written = ChecksumFile::from(&rooted).to_string();
+        assert!(written.contains("  /srv/docs/b.txt\n"), "{}", written);
+
+        let absolute = original
+            .rewrite_paths(&PathOptions::new().absolute("."))
+            .unwrap();
+        assert!(absolute.iter().all(|(path, _)| path.is_absolute()));
+        // An empty base cannot be made absolute
+        assert!(matches!(
+            original.rewrite_paths(&PathOptions::new().absolute("")),
+            Err(SHAError::File { .. })
+        ));
+
+        assert!(matches!(
+            original.rewrite_paths(&PathOptions::new().strip_prefix("other")),
+            Err(SHAError::InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_backslashes() {
+        let windows = manifest(&["dir\\file.txt", "dir/other.txt"]);
+        let options = PathOptions::new().split_backslashes(true);
+        let split = windows.rewrite_paths(&options).unwrap();
+        assert!(split.get(Path::new("dir").join("file.txt")).is_some());
+
+        // Two spellings of one path collide
+        let windows = manifest(&["dir\\file.txt", "dir/file.txt"]);
+        assert!(matches!(
+            windows.rewrite_paths(&options),
+            Err(SHAError::InvalidManifest(_))
+        ));
+    }
+}
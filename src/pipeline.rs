@@ -0,0 +1,69 @@
+use crate::{HasherOptions, SHAError};
+use std::fs::File;
+use std::io::{self, Read};
+use std::sync::mpsc;
+use std::thread;
+
+/// Number of buffers circulating between the reader and the hasher: one being filled while the
+/// other is hashed.
+const BUFFERS: usize = 2;
+
+/// Hands `file` from its current position to `update`, reading on a separate thread.
+///
+/// A scoped reader thread fills buffers and sends them to the calling thread, which hands
+/// each one to `update` and hands it back for refilling. With two buffers the disk and the CPU are both
+/// kept busy instead of taking turns, and memory use stays at two buffers.
+pub(crate) fn read(
+    options: &HasherOptions,
+    file: &File,
+    buffer_size: usize,
+    mut update: impl FnMut(&[u8]),
+) -> Result<(), SHAError> {
+    let (full_sender, full_receiver) = mpsc::channel::<(Vec<u8>, usize)>();
+    let (empty_sender, empty_receiver) = mpsc::channel::<Vec<u8>>();
+    for _ in 0..BUFFERS {
+        let _ = empty_sender.send(vec![0u8; buffer_size]);
+    }
+
+    thread::scope(|scope| {
+        let reader = scope.spawn(move || -> io::Result<()> {
+            let mut file = file;
+            // Ends once the hasher stops handing buffers back
+            for mut buffer in empty_receiver {
+                let n = loop {
+                    match file.read(&mut buffer) {
+                        Ok(n) => break n,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e),
+                    }
+                };
+                if n == 0 || full_sender.send((buffer, n)).is_err() {
+                    return Ok(());
+                }
+            }
+            Ok(())
+        });
+
+        let mut cancelled = false;
+        // Ends once the reader hits EOF or an error and drops its sender
+        for (buffer, n) in full_receiver {
+            if options.is_cancelled() {
+                cancelled = true;
+                break;
+            }
+            update(&buffer[..n]);
+            let _ = empty_sender.send(buffer);
+        }
+        // Unblocks the reader if hashing stopped early
+        drop(empty_sender);
+
+        match reader.join() {
+            Ok(result) => result?,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+        if cancelled {
+            return Err(SHAError::Cancelled);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,185 @@
+use crate::{Digest, HasherOptions, SHAError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, TryRecvError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+type Job = (PathBuf, mpsc::Sender<Result<Digest, SHAError>>);
+
+/// A fixed set of worker threads that hash files submitted over a channel.
+///
+/// Spawning a thread per file costs more than hashing a tiny file, so applications that hash
+/// thousands of small files should create one pool up front and [`submit`](HashPool::submit)
+/// paths as they are discovered. Workers pull jobs from a shared queue, so the disk always has
+/// up to one read per worker in flight.
+///
+/// Dropping the pool finishes every job already submitted, then joins the workers.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashPool, HasherOptions};
+///
+/// let pool = HashPool::new(8, HasherOptions::new()).unwrap();
+/// let jobs: Vec<_> = ["a.txt", "b.txt", "c.txt"]
+///     .into_iter()
+///     .map(|path| pool.submit(path))
+///     .collect();
+/// for job in jobs {
+///     let path = job.path().to_path_buf();
+///     println!("{}: {:?}", path.display(), job.wait());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HashPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl HashPool {
+    /// Starts `threads` workers hashing with `options`. A `threads` of zero starts one worker
+    /// per CPU.
+    ///
+    /// # Errors
+    /// Fails if the operating system refuses to spawn a thread.
+    pub fn new(threads: usize, options: HasherOptions) -> io::Result<Self> {
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, usize::from),
+            n => n,
+        };
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads)
+            .map(|index| {
+                let receiver = Arc::clone(&receiver);
+                let options = options.clone();
+                thread::Builder::new()
+                    .name(format!("sha-hash-{}", index))
+                    .spawn(move || {
+                        loop {
+                            // Hold the lock only while taking a job, never while hashing
+                            let job = receiver
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .recv();
+                            let Ok((path, reply)) = job else {
+                                return;
+                            };
+                            let _ = reply.send(options.hash_file_from_path(&path));
+                        }
+                    })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+        })
+    }
+
+    /// Returns the number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queues the file at `path` for hashing and returns a handle to its result.
+    ///
+    /// This never blocks; jobs are queued without limit and run in submission order.
+    pub fn submit(&self, path: impl AsRef<Path>) -> HashJob {
+        let path = path.as_ref().to_path_buf();
+        let (reply, result) = mpsc::channel();
+        if let Some(sender) = &self.sender {
+            // Workers only exit once the sender is dropped, so the queue is open here
+            let _ = sender.send((path.clone(), reply));
+        }
+        HashJob { path, result }
+    }
+}
+
+impl Drop for HashPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The pending result of a file submitted to a [`HashPool`].
+#[derive(Debug)]
+pub struct HashJob {
+    path: PathBuf,
+    result: mpsc::Receiver<Result<Digest, SHAError>>,
+}
+
+impl HashJob {
+    /// Returns the path this job hashes.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Blocks until the file has been hashed.
+    pub fn wait(self) -> Result<Digest, SHAError> {
+        self.result.recv().unwrap_or_else(|_| Err(worker_lost()))
+    }
+
+    /// Returns the result if the file has been hashed, without blocking.
+    ///
+    /// The result can only be taken once; calling this again after it returned `Some` reports
+    /// an I/O error.
+    pub fn try_wait(&self) -> Option<Result<Digest, SHAError>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(worker_lost())),
+        }
+    }
+}
+
+fn worker_lost() -> SHAError {
+    SHAError::IO(io::Error::other("hash worker exited without a result"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_pool_hashes_submitted_files() {
+        let files: Vec<NamedTempFile> = (0..50)
+            .map(|i| {
+                let mut temp_file = NamedTempFile::new().unwrap();
+                write!(temp_file, "file {}", i).unwrap();
+                temp_file.flush().unwrap();
+                temp_file
+            })
+            .collect();
+
+        let pool = HashPool::new(4, HasherOptions::new()).unwrap();
+        assert_eq!(pool.threads(), 4);
+        let jobs: Vec<HashJob> = files.iter().map(|f| pool.submit(f.path())).collect();
+        let missing = pool.submit("nonexistent_file_12345.txt");
+
+        for job in jobs {
+            let expected = crate::hash_file_from_path(job.path()).unwrap();
+            assert_eq!(job.wait().unwrap(), expected);
+        }
+        assert!(matches!(missing.wait(), Err(SHAError::IO(_))));
+    }
+
+    #[test]
+    fn test_drop_finishes_queued_jobs() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"test data").unwrap();
+        temp_file.flush().unwrap();
+
+        let pool = HashPool::new(1, HasherOptions::new()).unwrap();
+        let job = pool.submit(temp_file.path());
+        drop(pool);
+        assert_eq!(
+            job.try_wait().unwrap().unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use crate::dir::is_contained;
+use crate::{Digest, HasherOptions, Manifest, SHAError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The result of checking one file against its expected digest.
+#[derive(Debug)]
+pub enum FileOutcome {
+    /// The file matched.
+    Ok,
+    /// The file was read but its digest differs.
+    Mismatch {
+        /// The digest listed for the file.
+        expected: Digest,
+        /// The digest the file has now.
+        actual: Digest,
+    },
+    /// The file does not exist.
+    Missing,
+    /// The file exists but could not be read.
+    IoError(io::Error),
+}
+
+impl FileOutcome {
+    /// Returns `true` for [`FileOutcome::Ok`].
+    pub fn is_ok(&self) -> bool {
+        matches!(self, FileOutcome::Ok)
+    }
+
+    /// Classifies an I/O error, treating a file that no longer exists as missing.
+    pub(crate) fn from_io(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::NotFound => FileOutcome::Missing,
+            _ => FileOutcome::IoError(e),
+        }
+    }
+
+    /// Classifies a hashing error, wrapping errors that did not come from I/O.
+    pub(crate) fn from_error(e: SHAError) -> Self {
+        match e {
+            SHAError::IO(e) | SHAError::File { source: e, .. } => FileOutcome::from_io(e),
+            other => FileOutcome::IoError(io::Error::other(other)),
+        }
+    }
+}
+
+/// Per-file outcomes of checking a list of files, in the order they were listed.
+///
+/// Every file is checked even after a failure, so one report shows everything that is wrong.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{FileOutcome, SfvFile};
+///
+/// let report = SfvFile::read("release.sfv").unwrap().verify(".");
+/// for (path, outcome) in report.iter() {
+///     if !outcome.is_ok() {
+///         println!("{}: {:?}", path.display(), outcome);
+///     }
+/// }
+/// println!("{} of {} files OK", report.passed(), report.len());
+/// ```
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    files: Vec<(PathBuf, FileOutcome)>,
+}
+
+impl VerificationReport {
+    /// Iterates over every checked file and its outcome.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &FileOutcome)> {
+        self.files
+            .iter()
+            .map(|(path, outcome)| (path.as_path(), outcome))
+    }
+
+    /// Returns the number of files checked.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if no files were checked.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns `true` if every file matched.
+    pub fn is_ok(&self) -> bool {
+        self.files.iter().all(|(_, outcome)| outcome.is_ok())
+    }
+
+    /// Returns the number of files that matched.
+    pub fn passed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Ok))
+    }
+
+    /// Returns the number of files whose digest differs.
+    pub fn mismatched(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Mismatch { .. }))
+    }
+
+    /// Returns the number of files that do not exist.
+    pub fn missing(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Missing))
+    }
+
+    /// Returns the number of files that could not be read.
+    pub fn io_errors(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::IoError(_)))
+    }
+
+    pub(crate) fn push(&mut self, path: impl Into<PathBuf>, outcome: FileOutcome) {
+        self.files.push((path.into(), outcome));
+    }
+
+    pub(crate) fn extend(&mut self, other: VerificationReport) {
+        self.files.extend(other.files);
+    }
+
+    fn count(&self, matches: impl Fn(&FileOutcome) -> bool) -> usize {
+        self.files
+            .iter()
+            .filter(|(_, outcome)| matches(outcome))
+            .count()
+    }
+}
+
+/// Checks every file in `manifest` against the files under `base_dir`, hashing each with the
+/// manifest's algorithm.
+///
+/// Unlike [`DirOptions::verify`](crate::DirOptions::verify), only the listed files are looked
+/// at, so files added under `base_dir` since the manifest was made are not reported. Paths
+/// that are absolute or contain `..` are reported as [`FileOutcome::IoError`] without being
+/// opened, so a manifest from elsewhere cannot probe files outside `base_dir`.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{DirOptions, verify_manifest};
+///
+/// let manifest = DirOptions::new().hash_dir("release").unwrap();
+/// let report = verify_manifest(&manifest, "/srv/deploy/release");
+/// println!("{} passed, {} missing", report.passed(), report.missing());
+/// ```
+pub fn verify_manifest(manifest: &Manifest, base_dir: impl AsRef<Path>) -> VerificationReport {
+    let base_dir = base_dir.as_ref();
+    let options = HasherOptions::new().algorithm(manifest.algorithm());
+    let mut report = VerificationReport::default();
+    for (path, expected) in manifest.iter() {
+        if !is_contained(path) {
+            let outside = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "path is outside the base directory",
+            );
+            report.push(path, FileOutcome::IoError(outside));
+            continue;
+        }
+        let outcome = match options.hash_file_from_path(base_dir.join(path)) {
+            Ok(actual) if actual == *expected => FileOutcome::Ok,
+            Ok(actual) => FileOutcome::Mismatch {
+                expected: expected.clone(),
+                actual,
+            },
+            Err(e) => FileOutcome::from_error(e),
+        };
+        report.push(path, outcome);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DirOptions;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_verify_manifest_outcomes() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), b"test data").unwrap();
+        fs::write(temp_dir.path().join("b.txt"), b"Hello, World!").unwrap();
+        fs::write(temp_dir.path().join("c.txt"), b"").unwrap();
+        let manifest = DirOptions::new().hash_dir(temp_dir.path()).unwrap();
+        assert!(verify_manifest(&manifest, temp_dir.path()).is_ok());
+
+        fs::write(temp_dir.path().join("b.txt"), b"Goodbye").unwrap();
+        fs::remove_file(temp_dir.path().join("c.txt")).unwrap();
+        let report = verify_manifest(&manifest, temp_dir.path());
+        assert_eq!(report.len(), 3);
+        assert_eq!(
+            (report.passed(), report.mismatched(), report.missing()),
+            (1, 1, 1)
+        );
+        assert!(!report.is_ok());
+        let (path, outcome) = report.iter().nth(1).unwrap();
+        assert_eq!(path, Path::new("b.txt"));
+        match outcome {
+            FileOutcome::Mismatch { expected, actual } => {
+                assert_eq!(expected, manifest.get("b.txt").unwrap());
+                assert_eq!(actual.to_hex(), "b5b29c53e3c71cb9c6581ab053d7758fab8ca24d");
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+
+        // A directory in place of a file cannot be read
+        fs::create_dir(temp_dir.path().join("c.txt")).unwrap();
+        let report = verify_manifest(&manifest, temp_dir.path());
+        assert_eq!(report.io_errors(), 1);
+    }
+
+    #[test]
+    fn test_verify_manifest_stays_in_base_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().join("base");
+        fs::create_dir(&base).unwrap();
+        let outside = temp_dir.path().join("secret.txt");
+        fs::write(&outside, b"test data").unwrap();
+
+        let digest = HasherOptions::new().hash_file_from_path(&outside).unwrap();
+        let mut manifest = Manifest::new(crate::HashAlgorithm::Sha1);
+        manifest.insert("../secret.txt", digest.clone());
+        manifest.insert(&outside, digest);
+        let report = verify_manifest(&manifest, &base);
+        assert_eq!((report.passed(), report.io_errors()), (0, 2));
+    }
+}
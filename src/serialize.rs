@@ -0,0 +1,293 @@
+//! Serde support for digests, algorithms and manifests, enabled by the `serde` feature.
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+use crate::SHAError;
+use crate::dir::{is_contained, portable_path};
+use crate::{Digest, FileStamp, HashAlgorithm, Manifest};
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+impl Serialize for HashAlgorithm {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for HashAlgorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Digests are written as lowercase hex strings.
+impl Serialize for Digest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+/// Accepts hex of any length; a [`Manifest`] additionally checks it against its algorithm.
+impl<'de> Deserialize<'de> for Digest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        hex.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestRepr {
+    algorithm: HashAlgorithm,
+    files: Vec<EntryRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryRepr {
+    path: String,
+    digest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<SystemTime>,
+}
+
+/// A manifest is written as its algorithm and a list of files, each with a `/`-separated
+/// relative path (lossily converted to UTF-8), a hex digest and, when known, the size and
+/// modification time recorded when it was hashed:
+///
+/// ```json
+/// {
+///   "algorithm": "sha256",
+///   "files": [
+///     {
+///       "path": "bin/tool",
+///       "digest": "916f0027a575074ce72a331777c3478d6513f786a591bd892da1a577bf2335f9",
+///       "size": 9,
+///       "modified": { "secs_since_epoch": 1700000000, "nanos_since_epoch": 0 }
+///     }
+///   ]
+/// }
+/// ```
+impl Serialize for Manifest {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let files = self
+            .iter()
+            .map(|(path, digest)| {
+                let stamp = self.stamp(path);
+                EntryRepr {
+                    path: portable_path(path),
+                    digest: digest.to_hex(),
+                    size: stamp.map(|stamp| stamp.len),
+                    // Serde cannot represent times before the epoch
+                    modified: stamp
+                        .map(|stamp| stamp.modified)
+                        .filter(|modified| *modified >= SystemTime::UNIX_EPOCH),
+                }
+            })
+            .collect();
+        let repr = ManifestRepr {
+            algorithm: self.algorithm(),
+            files,
+        };
+        repr.serialize(serializer)
+    }
+}
+
+/// Rejects digests whose length does not match the manifest's algorithm, and paths that are
+/// absolute or contain `..`, which would reach outside the directory the manifest is checked
+/// against. A stamp is only restored when both `size` and `modified` are present.
+impl<'de> Deserialize<'de> for Manifest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ManifestRepr::deserialize(deserializer)?;
+        let mut manifest = Manifest::new(repr.algorithm);
+        for entry in repr.files {
+            let path = PathBuf::from(entry.path);
+            if !is_contained(&path) {
+                return Err(de::Error::custom(format!(
+                    "path {} is outside the manifest root",
+                    path.display()
+                )));
+            }
+            let digest = Digest::parse(&entry.digest, repr.algorithm).map_err(de::Error::custom)?;
+            let stamp = match (entry.size, entry.modified) {
+                (Some(len), Some(modified)) => Some(FileStamp { len, modified }),
+                _ => None,
+            };
+            manifest.insert_with_stamp(path, digest, stamp);
+        }
+        Ok(manifest)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Manifest {
+    /// Renders the manifest as pretty-printed JSON, one object per file with its path, digest,
+    /// size and modification time.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use sha_file_hashing::{DirOptions, Manifest};
+    ///
+    /// let manifest = DirOptions::new().hash_dir("release").unwrap();
+    /// std::fs::write("release.json", manifest.to_json()).unwrap();
+    ///
+    /// let json = std::fs::read_to_string("release.json").unwrap();
+    /// assert_eq!(Manifest::from_json(&json).unwrap(), manifest);
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("manifests always serialize to JSON")
+    }
+
+    /// Parses a manifest written by [`to_json`](Manifest::to_json) or another producer of the
+    /// same format.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if `json` is malformed, names an unknown algorithm,
+    /// holds a digest of the wrong length, or has a path that is absolute or contains `..`.
+    pub fn from_json(json: &str) -> Result<Self, SHAError> {
+        serde_json::from_str(json).map_err(|e| SHAError::InvalidManifest(e.to_string()))
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Manifest {
+    /// Renders the manifest as TOML, with one `[[files]]` table per file.
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("manifests always serialize to TOML")
+    }
+
+    /// Parses a manifest written by [`to_toml`](Manifest::to_toml).
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if `toml` is malformed, names an unknown algorithm,
+    /// holds a digest of the wrong length, or has a path that is absolute or contains `..`.
+    pub fn from_toml(toml: &str) -> Result<Self, SHAError> {
+        toml::from_str(toml).map_err(|e| SHAError::InvalidManifest(e.to_string()))
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Manifest {
+    /// Renders the manifest as YAML.
+    ///
+    /// To embed expected hashes in a larger YAML document, such as a deployment descriptor,
+    /// use a `Manifest` field in a `Deserialize` type instead.
+    pub fn to_yaml(&self) -> String {
+        serde_yaml_ng::to_string(self).expect("manifests always serialize to YAML")
+    }
+
+    /// Parses a manifest written by [`to_yaml`](Manifest::to_yaml).
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidManifest`] if `yaml` is malformed, names an unknown algorithm,
+    /// holds a digest of the wrong length, or has a path that is absolute or contains `..`.
+    pub fn from_yaml(yaml: &str) -> Result<Self, SHAError> {
+        serde_yaml_ng::from_str(yaml).map_err(|e| SHAError::InvalidManifest(e.to_string()))
+    }
+}
+
+#[cfg(all(test, any(feature = "json", feature = "toml", feature = "yaml")))]
+mod tests {
+    use super::*;
+    use crate::DirOptions;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn sample() -> (TempDir, Manifest) {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        fs::write(temp_dir.path().join("a.txt"), b"test data").unwrap();
+        fs::write(temp_dir.path().join("sub").join("b.txt"), b"Hello, World!").unwrap();
+        let manifest = DirOptions::new().hash_dir(temp_dir.path()).unwrap();
+        (temp_dir, manifest)
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_manifest_json_round_trip() {
+        let (_temp_dir, manifest) = sample();
+        let json = manifest.to_json();
+        assert!(json.contains("\"path\": \"sub/b.txt\""));
+        assert!(json.contains("\"digest\": \"0a0a9f2a6772942557ab5355d76af442f8f65e01\""));
+
+        let parsed = Manifest::from_json(&json).unwrap();
+        assert_eq!(parsed, manifest);
+        assert_eq!(parsed.stamp("a.txt"), manifest.stamp("a.txt"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_manifest_json_errors() {
+        let json = r#"{"algorithm": "sha256", "files": [{"path": "a", "digest": "00ff"}]}"#;
+        assert!(matches!(
+            Manifest::from_json(json),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        let json = r#"{"algorithm": "nope", "files": []}"#;
+        assert!(Manifest::from_json(json).is_err());
+        for path in ["/etc/passwd", "../secret", "a/../../b"] {
+            let json = format!(
+                r#"{{"algorithm": "md5", "files": [
+                    {{"path": "{}", "digest": "d41d8cd98f00b204e9800998ecf8427e"}}
+                ]}}"#,
+                path
+            );
+            assert!(matches!(
+                Manifest::from_json(&json),
+                Err(SHAError::InvalidManifest(_))
+            ));
+        }
+
+        let json = r#"{"algorithm": "md5", "files": [
+            {"path": "a", "digest": "d41d8cd98f00b204e9800998ecf8427e"}
+        ]}"#;
+        let manifest = Manifest::from_json(json).unwrap();
+        assert_eq!(manifest.algorithm(), HashAlgorithm::Md5);
+        assert!(manifest.stamp("a").is_none());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_manifest_toml_round_trip() {
+        let (_temp_dir, manifest) = sample();
+        let toml = manifest.to_toml();
+        assert!(toml.starts_with("algorithm = \"sha1\"\n"));
+        assert!(toml.contains("[[files]]\npath = \"a.txt\"\n"));
+        assert_eq!(Manifest::from_toml(&toml).unwrap(), manifest);
+        assert!(matches!(
+            Manifest::from_toml("algorithm = 1"),
+            Err(SHAError::InvalidManifest(_))
+        ));
+        let escaping = "algorithm = \"md5\"\n\n[[files]]\npath = \"../app.bin\"\n\
+                        digest = \"d41d8cd98f00b204e9800998ecf8427e\"\n";
+        assert!(matches!(
+            Manifest::from_toml(escaping),
+            Err(SHAError::InvalidManifest(_))
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_manifest_yaml_round_trip() {
+        let (_temp_dir, manifest) = sample();
+        let yaml = manifest.to_yaml();
+        assert!(yaml.contains("- path: sub/b.txt\n"));
+        assert_eq!(Manifest::from_yaml(&yaml).unwrap(), manifest);
+
+        let descriptor =
+            "algorithm: md5\nfiles:\n- path: app.bin\n  digest: d41d8cd98f00b204e9800998ecf8427e\n";
+        let expected = Manifest::from_yaml(descriptor).unwrap();
+        assert_eq!(
+            expected.get("app.bin"),
+            Some(&"d41d8cd98f00b204e9800998ecf8427e".parse().unwrap())
+        );
+        let escaping = descriptor.replace("app.bin", "/etc/app.bin");
+        assert!(matches!(
+            Manifest::from_yaml(&escaping),
+            Err(SHAError::InvalidManifest(_))
+        ));
+    }
+}
@@ -0,0 +1,438 @@
+use crate::report::{FileOutcome, VerificationReport};
+use crate::{Digest, HasherOptions, SHAError, read_chunks};
+use crc::{CRC_32_ISO_HDLC, Crc};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+static CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A file and its CRC-32 listed in an [`SfvFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfvEntry {
+    /// The file, exactly as written in the SFV file.
+    pub path: PathBuf,
+    /// The expected CRC-32 (ISO-HDLC, as used by zip and PNG).
+    pub crc: u32,
+}
+
+/// The size and modification time of a file, from a `; <size> <time> <date> <name>` comment.
+///
+/// Tools such as `cksfv` write one of these per file in the header. Times are read and written
+/// as UTC with one second resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfvInfo {
+    /// The file, exactly as written in the comment.
+    pub path: PathBuf,
+    /// The file length in bytes.
+    pub len: u64,
+    /// The last modification time.
+    pub modified: SystemTime,
+}
+
+/// A line of an [`SfvFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SfvLine {
+    /// A file and its CRC-32.
+    Entry(SfvEntry),
+    /// A comment recording a file's size and modification time.
+    Info(SfvInfo),
+    /// Any other line starting with `;`, stored without the `;`.
+    Comment(String),
+}
+
+/// A Simple File Verification (`.sfv`) list of CRC-32 checksums.
+///
+/// Entry lines are a file name, whitespace and an eight-digit hex CRC-32. Lines starting with
+/// `;` are comments, and comments in the `cksfv` size and timestamp layout are parsed into
+/// [`SfvInfo`]. All lines are kept in order, so a file can be read, amended and written back.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::SfvFile;
+///
+/// let mut sfv = SfvFile::new();
+/// sfv.push_comment(" Generated by my-tool");
+/// sfv.add_file("release", "disc1.iso").unwrap();
+/// sfv.write_to(std::fs::File::create("release/release.sfv").unwrap()).unwrap();
+///
+/// let report = SfvFile::read("release/release.sfv").unwrap().verify("release");
+/// assert!(report.is_ok());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SfvFile {
+    lines: Vec<SfvLine>,
+}
+
+impl SfvFile {
+    /// Creates an empty SFV file.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads and parses the SFV file at `path`.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::File`] if the file cannot be read, or
+    /// [`SHAError::InvalidChecksumLine`] for the first line that is not an entry or a comment.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        text.parse()
+    }
+
+    /// Returns every line in file order.
+    pub fn lines(&self) -> &[SfvLine] {
+        &self.lines
+    }
+
+    /// Iterates over the entries, skipping comments.
+    pub fn entries(&self) -> impl Iterator<Item = &SfvEntry> {
+        self.lines.iter().filter_map(|line| match line {
+            SfvLine::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Returns the size and modification time recorded for `path`, if any.
+    pub fn info(&self, path: impl AsRef<Path>) -> Option<&SfvInfo> {
+        self.lines.iter().find_map(|line| match line {
+            SfvLine::Info(info) if info.path == path.as_ref() => Some(info),
+            _ => None,
+        })
+    }
+
+    /// Appends an entry.
+    pub fn push(&mut self, entry: SfvEntry) {
+        self.lines.push(SfvLine::Entry(entry));
+    }
+
+    /// Appends a comment line. `text` is written after a `;`.
+    pub fn push_comment(&mut self, text: impl Into<String>) {
+        self.lines.push(SfvLine::Comment(text.into()));
+    }
+
+    /// Computes the CRC-32 of `base/path` and appends an entry for `path`, along with an
+    /// [`SfvInfo`] comment placed before the entries, as `cksfv` lays them out.
+    pub fn add_file(
+        &mut self,
+        base: impl AsRef<Path>,
+        path: impl Into<PathBuf>,
+    ) -> Result<(), SHAError> {
+        let path = path.into();
+        let full = base.as_ref().join(&path);
+        let file = File::open(&full).map_err(|e| SHAError::from(e).at_path(&full))?;
+        let metadata = file
+            .metadata()
+            .map_err(|e| SHAError::from(e).at_path(&full))?;
+        let crc = crc32(file).map_err(|e| e.at_path(&full))?;
+
+        if let Ok(modified) = metadata.modified() {
+            let header_end = self
+                .lines
+                .iter()
+                .position(|line| matches!(line, SfvLine::Entry(_)))
+                .unwrap_or(self.lines.len());
+            let info = SfvInfo {
+                path: path.clone(),
+                len: metadata.len(),
+                modified,
+            };
+            self.lines.insert(header_end, SfvLine::Info(info));
+        }
+        self.push(SfvEntry { path, crc });
+        Ok(())
+    }
+
+    /// Checks every entry against the file at its path under `base`.
+    ///
+    /// A listed file that does not exist is reported as [`FileOutcome::Missing`] and one that
+    /// cannot be read as [`FileOutcome::IoError`]; either way the remaining files are still
+    /// checked. Digests in the report are the four big-endian CRC bytes.
+    pub fn verify(&self, base: impl AsRef<Path>) -> VerificationReport {
+        let base = base.as_ref();
+        let mut report = VerificationReport::default();
+        for entry in self.entries() {
+            let crc = File::open(base.join(&entry.path))
+                .map_err(SHAError::from)
+                .and_then(crc32);
+            let outcome = match crc {
+                Ok(actual) if actual == entry.crc => FileOutcome::Ok,
+                Ok(actual) => FileOutcome::Mismatch {
+                    expected: crc_digest(entry.crc),
+                    actual: crc_digest(actual),
+                },
+                Err(e) => FileOutcome::from_error(e),
+            };
+            report.push(&entry.path, outcome);
+        }
+        report
+    }
+
+    /// Writes the SFV file to `writer`, one `\n`-terminated line per entry or comment.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{}", self)
+    }
+}
+
+/// Computes the CRC-32 of everything `reader` yields.
+fn crc32(reader: impl Read) -> Result<u32, SHAError> {
+    let mut digest = CRC32.digest();
+    read_chunks(&HasherOptions::new(), reader, |chunk| digest.update(chunk))?;
+    Ok(digest.finalize())
+}
+
+fn crc_digest(crc: u32) -> Digest {
+    Digest::from_bytes(crc.to_be_bytes())
+}
+
+impl FromStr for SfvFile {
+    type Err = SHAError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut file = SfvFile::new();
+        for (index, line) in s.lines().enumerate() {
+            if let Some(comment) = line.strip_prefix(';') {
+                file.lines.push(match parse_info(comment) {
+                    Some(info) => SfvLine::Info(info),
+                    None => SfvLine::Comment(comment.to_string()),
+                });
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = parse_entry(line).ok_or_else(|| SHAError::InvalidChecksumLine {
+                line: index + 1,
+                text: line.to_string(),
+            })?;
+            file.push(entry);
+        }
+        Ok(file)
+    }
+}
+
+/// Parses `<name> <crc>`, where the name may contain spaces.
+fn parse_entry(line: &str) -> Option<SfvEntry> {
+    let line = line.trim_end();
+    let (path, crc) = line.rsplit_once([' ', '\t'])?;
+    let path = path.trim_end();
+    if crc.len() != 8 || path.is_empty() {
+        return None;
+    }
+    Some(SfvEntry {
+        path: PathBuf::from(path),
+        crc: u32::from_str_radix(crc, 16).ok()?,
+    })
+}
+
+/// Splits off the first whitespace-separated word of `s`.
+fn next_word(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace)?;
+    Some((&s[..end], &s[end..]))
+}
+
+/// Parses `<size> <hh>:<mm>.<ss> <yyyy>-<mm>-<dd> <name>`.
+fn parse_info(comment: &str) -> Option<SfvInfo> {
+    let (len, rest) = next_word(comment)?;
+    let (time, rest) = next_word(rest)?;
+    let (date, rest) = next_word(rest)?;
+    let name = rest.trim_start();
+    if name.is_empty() {
+        return None;
+    }
+
+    let (hour, rest) = time.split_once(':')?;
+    let (minute, second) = rest.split_once('.')?;
+    let mut date = date.splitn(3, '-');
+    let (year, month, day) = (date.next()?, date.next()?, date.next()?);
+    let fields = [hour, minute, second, month, day];
+    if fields.iter().any(|field| field.len() != 2) || year.len() != 4 {
+        return None;
+    }
+    let (hour, minute, second): (u64, u64, u64) = (
+        hour.parse().ok()?,
+        minute.parse().ok()?,
+        second.parse().ok()?,
+    );
+    let (month, day): (u32, u32) = (month.parse().ok()?, day.parse().ok()?);
+    if hour > 23 || minute > 59 || second > 59 || !(1..=12).contains(&month) || day > 31 {
+        return None;
+    }
+
+    let days = days_from_civil(year.parse().ok()?, month, day);
+    let seconds = u64::try_from(days).ok()? * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second;
+    Some(SfvInfo {
+        path: PathBuf::from(name),
+        len: len.parse().ok()?,
+        modified: UNIX_EPOCH.checked_add(Duration::from_secs(seconds))?,
+    })
+}
+
+impl fmt::Display for SfvFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                SfvLine::Entry(entry) => {
+                    writeln!(f, "{} {:08X}", entry.path.to_string_lossy(), entry.crc)?
+                }
+                SfvLine::Info(info) => {
+                    let seconds = info
+                        .modified
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_secs());
+                    let (year, month, day) = civil_from_days((seconds / SECONDS_PER_DAY) as i64);
+                    let time = seconds % SECONDS_PER_DAY;
+                    writeln!(
+                        f,
+                        "; {:>12}  {:02}:{:02}.{:02} {:04}-{:02}-{:02} {}",
+                        info.len,
+                        time / 3600,
+                        time % 3600 / 60,
+                        time % 60,
+                        year,
+                        month,
+                        day,
+                        info.path.to_string_lossy()
+                    )?
+                }
+                SfvLine::Comment(text) => writeln!(f, ";{}", text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of days from 1970-01-01 to the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The inverse of [`days_from_civil`].
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SFV: &str = "\
+; Generated by cksfv v1.3.14
+;
+;            9  10:57.52 2009-04-06 test file.txt
+test file.txt D308AEB2
+";
+
+    #[test]
+    fn test_parse_sfv() {
+        let sfv: SfvFile = SFV.parse().unwrap();
+        assert_eq!(sfv.lines().len(), 4);
+        assert_eq!(
+            sfv.lines()[0],
+            SfvLine::Comment(" Generated by cksfv v1.3.14".to_string())
+        );
+
+        let entry = sfv.entries().next().unwrap();
+        assert_eq!(entry.path, Path::new("test file.txt"));
+        assert_eq!(entry.crc, 0xD308_AEB2);
+
+        let info = sfv.info("test file.txt").unwrap();
+        assert_eq!(info.len, 9);
+        // 2009-04-06T10:57:52Z
+        assert_eq!(
+            info.modified,
+            UNIX_EPOCH + Duration::from_secs(1_239_015_472)
+        );
+
+        assert_eq!(sfv.to_string(), SFV);
+    }
+
+    #[test]
+    fn test_sfv_add_and_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::write(base.join("a.txt"), b"test data").unwrap();
+        fs::write(base.join("b.txt"), b"Hello, World!").unwrap();
+
+        let mut sfv = SfvFile::new();
+        sfv.push_comment(" test");
+        sfv.add_file(base, "a.txt").unwrap();
+        sfv.add_file(base, "b.txt").unwrap();
+        assert!(matches!(sfv.lines()[1], SfvLine::Info(_)));
+        assert!(matches!(sfv.lines()[2], SfvLine::Info(_)));
+        assert_eq!(sfv.info("b.txt").unwrap().len, 13);
+
+        // Round-trips through text, apart from sub-second times
+        let reparsed: SfvFile = sfv.to_string().parse().unwrap();
+        let entries: Vec<_> = reparsed.entries().cloned().collect();
+        assert_eq!(entries, sfv.entries().cloned().collect::<Vec<_>>());
+        assert_eq!(entries[0].crc, 0xD308_AEB2);
+
+        assert!(reparsed.verify(base).is_ok());
+
+        fs::write(base.join("a.txt"), b"changed").unwrap();
+        fs::remove_file(base.join("b.txt")).unwrap();
+        let report = reparsed.verify(base);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.mismatched(), 1);
+        assert_eq!(report.missing(), 1);
+        assert!(!report.is_ok());
+        match report.iter().next().unwrap() {
+            (path, FileOutcome::Mismatch { expected, .. }) => {
+                assert_eq!(path, Path::new("a.txt"));
+                assert_eq!(*expected, "d308aeb2");
+            }
+            other => panic!("Expected a mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_invalid_sfv_lines() {
+        for text in [
+            "file.txt",
+            "file.txt D308AE",
+            "file.txt D308AEBZ",
+            " D308AEB2",
+        ] {
+            assert!(text.parse::<SfvFile>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_civil_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        for days in [-1, 0, 59, 11_016, 11_017, 19_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! Maven-style sidecar checksum files: `<artifact>.sha1`, `<artifact>.md5` and so on, each
+//! holding the bare hex digest of the file beside it.
+
+use crate::{Digest, HashAlgorithm, HasherOptions, SHAError};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Returns the sidecar path for `artifact`, `<artifact>.<algorithm>` such as `app.jar.sha1`.
+pub fn sidecar_path(artifact: impl AsRef<Path>, algorithm: HashAlgorithm) -> PathBuf {
+    let mut name = OsString::from(artifact.as_ref().as_os_str());
+    name.push(".");
+    name.push(algorithm.name());
+    PathBuf::from(name)
+}
+
+/// Hashes `artifact` and writes its sidecar, returning the sidecar's path.
+///
+/// The sidecar holds the lowercase hex digest alone, with no file name or trailing newline,
+/// as Maven deploys them.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::{HashAlgorithm, write_sidecar};
+///
+/// for algorithm in [HashAlgorithm::Md5, HashAlgorithm::Sha1] {
+///     write_sidecar("target/app-1.0.jar", algorithm).unwrap();
+/// }
+/// ```
+pub fn write_sidecar(
+    artifact: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<PathBuf, SHAError> {
+    let artifact = artifact.as_ref();
+    let digest = HasherOptions::new()
+        .algorithm(algorithm)
+        .hash_file_from_path(artifact)
+        .map_err(|e| e.at_path(artifact))?;
+    let path = sidecar_path(artifact, algorithm);
+    fs::write(&path, digest.to_hex()).map_err(|e| SHAError::from(e).at_path(&path))?;
+    Ok(path)
+}
+
+/// Reads the digest from the sidecar of `artifact`.
+///
+/// Besides the bare hex Maven writes, the formats other tools left in real repositories are
+/// accepted: any case, a trailing newline or file name (`<hex>  app.jar`), the BSD
+/// `SHA1 (app.jar) = <hex>` form, and the `app.jar: DA39 A3EE ...` blocks of
+/// `gpg --print-md`, which may span lines.
+///
+/// # Errors
+/// Returns [`SHAError::InvalidDigest`] if no digest of the right length is found.
+pub fn read_sidecar(
+    artifact: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<Digest, SHAError> {
+    let path = sidecar_path(artifact, algorithm);
+    let text = fs::read_to_string(&path).map_err(|e| SHAError::from(e).at_path(&path))?;
+    parse_sidecar(&text, algorithm).ok_or_else(|| {
+        SHAError::InvalidDigest(format!("no {} digest in {}", algorithm, path.display()))
+    })
+}
+
+/// Checks `artifact` against the digest in its sidecar.
+///
+/// # Errors
+/// Fails if either file cannot be read or the sidecar holds no digest; see [`read_sidecar`].
+pub fn verify_sidecar(
+    artifact: impl AsRef<Path>,
+    algorithm: HashAlgorithm,
+) -> Result<bool, SHAError> {
+    let artifact = artifact.as_ref();
+    let expected = read_sidecar(artifact, algorithm)?;
+    let actual = HasherOptions::new()
+        .algorithm(algorithm)
+        .hash_file_from_path(artifact)
+        .map_err(|e| e.at_path(artifact))?;
+    Ok(actual == expected)
+}
+
+fn parse_sidecar(text: &str, algorithm: HashAlgorithm) -> Option<Digest> {
+    let text = text.trim_start_matches('\u{feff}').trim();
+    let parse = |hex: &str| Digest::parse(hex, algorithm).ok();
+    // BSD style, `SHA1 (name) = <hex>`
+    if let Some((_, hex)) = text.rsplit_once(" = ") {
+        return parse(hex.trim());
+    }
+    // `<hex>` or `<hex>  name`, as Maven and sha1sum write
+    if let Some(digest) = text.split_whitespace().next().and_then(parse) {
+        return Some(digest);
+    }
+    // `name: DA39 A3EE ...` from gpg --print-md, grouped and possibly wrapped
+    let groups = text.rsplit_once(": ").map_or(text, |(_, groups)| groups);
+    let hex: String = groups.split_whitespace().collect();
+    parse(&hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+
+    #[test]
+    fn test_write_and_verify_sidecar() {
+        let temp_dir = TempDir::new().unwrap();
+        let artifact = temp_dir.path().join("app-1.0.jar");
+        fs::write(&artifact, "abc").unwrap();
+
+        let path = write_sidecar(&artifact, HashAlgorithm::Sha1).unwrap();
+        assert_eq!(path, temp_dir.path().join("app-1.0.jar.sha1"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), SHA1);
+        write_sidecar(&artifact, HashAlgorithm::Md5).unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("app-1.0.jar.md5")).unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert!(verify_sidecar(&artifact, HashAlgorithm::Sha1).unwrap());
+        assert!(verify_sidecar(&artifact, HashAlgorithm::Md5).unwrap());
+
+        fs::write(&artifact, "abd").unwrap();
+        assert!(!verify_sidecar(&artifact, HashAlgorithm::Sha1).unwrap());
+        assert!(matches!(
+            verify_sidecar(&artifact, HashAlgorithm::Sha256),
+            Err(SHAError::File { .. })
+        ));
+        fs::write(sidecar_path(&artifact, HashAlgorithm::Sha1), "not a hash").unwrap();
+        assert!(matches!(
+            read_sidecar(&artifact, HashAlgorithm::Sha1),
+            Err(SHAError::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_sidecar_formats() {
+        for text in [
+            SHA1.to_string(),
+            format!("{}\n", SHA1.to_uppercase()),
+            format!("\u{feff}{}  app-1.0.jar\r\n", SHA1),
+            format!("SHA1 (app-1.0.jar) = {}\n", SHA1),
+            "app-1.0.jar: A999 3E36 4706 816A BA3E  2571 7850 C26C 9CD0 D89D\n".to_string(),
+            "app-1.0.jar: A999 3E36 4706 816A BA3E\n             2571 7850 C26C 9CD0 D89D\n"
+                .to_string(),
+        ] {
+            assert_eq!(
+                parse_sidecar(&text, HashAlgorithm::Sha1).unwrap(),
+                SHA1,
+                "{:?}",
+                text
+            );
+        }
+        assert!(parse_sidecar("a999", HashAlgorithm::Sha1).is_none());
+        assert!(parse_sidecar(SHA1, HashAlgorithm::Md5).is_none());
+    }
+}
@@ -0,0 +1,73 @@
+//! SM3 (GB/T 32905-2016) digests.
+//!
+//! Available with the `sm3` feature. The functions mirror the crate-level SHA-1 API and
+//! return the 256-bit digest as a [`Digest`].
+//!
+//! ```no_run
+//! use sha_file_hashing::sm3;
+//!
+//! let digest = sm3::hash_file_from_path("tender.zip").unwrap();
+//! assert!(sm3::validate_file_from_path("tender.zip", digest.to_hex()).unwrap());
+//! ```
+
+use crate::{Digest, HashAlgorithm, SHAError, hash_file_with_algorithm};
+use std::fs::File;
+use std::path::Path;
+
+pub use sm3::Sm3;
+
+/// Computes the SM3 digest of a `File` handle.
+pub fn hash_file(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Sm3)
+}
+
+/// Computes the SM3 digest of the file at `path`.
+pub fn hash_file_from_path(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file(File::open(path.as_ref())?)
+}
+
+/// Validates a `File` handle against an expected SM3 digest (case-insensitive).
+pub fn validate_file(file: File, hash: impl AsRef<str>) -> bool {
+    hash_file(file)
+        .and_then(|computed| computed.matches_hex(hash.as_ref()))
+        .unwrap_or(false)
+}
+
+/// Validates the file at `path` against an expected SM3 digest (case-insensitive).
+pub fn validate_file_from_path(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    hash_file_from_path(path)?.matches_hex(hash.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_sm3_known_value() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"abc").unwrap();
+        temp_file.flush().unwrap();
+
+        assert_eq!(
+            hash_file_from_path(temp_file.path()).unwrap(),
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+        );
+    }
+
+    #[test]
+    fn test_sm3_validate() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"abc").unwrap();
+        temp_file.flush().unwrap();
+
+        let expected = "66C7F0F462EEEDD9D1F2D46BDC10E4E24167C4875CF2F7A2297DA02B8F4BA8E0";
+        assert!(validate_file_from_path(temp_file.path(), expected).unwrap());
+        let file = File::open(temp_file.path()).unwrap();
+        assert!(!validate_file(file, "00"));
+    }
+}
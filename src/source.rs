@@ -0,0 +1,94 @@
+use crate::output::declared_algorithm;
+use crate::{Digest, HashAlgorithm, Hashable, SHAError, hash_reader_with_algorithm};
+use std::cell::RefCell;
+use std::io::Read;
+
+/// Adapts any [`Read`] source into a [`Hashable`] value.
+///
+/// Generic code written against `Hashable` can then accept sockets, stdin, archive entries or
+/// decompressors alongside paths and files.
+///
+/// Reading is destructive: hashing drains the reader to EOF, so a second call only sees whatever
+/// arrived afterwards (usually nothing). Create a new `HashSource` for each pass, or use
+/// [`hash_file_multi`](crate::hash_file_multi)-style single-pass APIs when several digests
+/// are needed.
+///
+/// # Examples
+/// ```
+/// use sha_file_hashing::{HashSource, Hashable};
+/// use std::io::Cursor;
+///
+/// fn digest_of(input: &impl Hashable) -> String {
+///     input.hash().unwrap().to_hex()
+/// }
+///
+/// let source = HashSource::new(Cursor::new(b"Hello, World!".to_vec()));
+/// assert_eq!(digest_of(&source), "0a0a9f2a6772942557ab5355d76af442f8f65e01");
+/// ```
+#[derive(Debug)]
+pub struct HashSource<R: Read> {
+    reader: RefCell<R>,
+}
+
+impl<R: Read> HashSource<R> {
+    /// Wraps `reader` so it can be hashed through the [`Hashable`] trait.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: RefCell::new(reader),
+        }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R: Read> From<R> for HashSource<R> {
+    fn from(reader: R) -> Self {
+        Self::new(reader)
+    }
+}
+
+impl<R: Read> Hashable for HashSource<R> {
+    fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Digest, SHAError> {
+        hash_reader_with_algorithm(&mut *self.reader.borrow_mut(), algorithm)
+    }
+
+    fn validate_with(
+        &self,
+        hash: impl AsRef<str>,
+        algorithm: HashAlgorithm,
+    ) -> Result<bool, SHAError> {
+        let hash = hash.as_ref();
+        self.hash_with(declared_algorithm(hash, algorithm)?)?
+            .matches_hex(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_hash_source_hashes_reader() {
+        let source = HashSource::new(Cursor::new(b"test data".to_vec()));
+        assert!(
+            source
+                .validate("f48dd853820860816c75d54d0f584dc863327a7c")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_source_drains_reader() {
+        let source = HashSource::from(Cursor::new(b"test data".to_vec()));
+        let first = source.hash().unwrap();
+        let second = source.hash().unwrap();
+
+        assert_eq!(first, "f48dd853820860816c75d54d0f584dc863327a7c");
+        assert_eq!(second, "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(source.into_inner().position(), 9);
+    }
+}
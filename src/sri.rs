@@ -0,0 +1,157 @@
+//! Subresource Integrity values, as used in `integrity` attributes of `<script>` and `<link>`.
+
+use crate::{Digest, HashAlgorithm, SHAError};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use subtle::ConstantTimeEq;
+
+/// The algorithms SRI allows, weakest first.
+const SRI_ALGORITHMS: [HashAlgorithm; 3] = [
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Sha384,
+    HashAlgorithm::Sha512,
+];
+
+impl Digest {
+    /// Formats the digest as an SRI value such as `sha384-<base64>`, naming `algorithm` as the
+    /// one it was computed with.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::UnsupportedAlgorithm`] unless `algorithm` is SHA-256, SHA-384 or
+    /// SHA-512, the only ones browsers accept, and [`SHAError::InvalidDigest`] if the digest
+    /// length does not match it.
+    ///
+    /// # Examples
+    /// ```
+    /// use sha_file_hashing::{HashAlgorithm, Hashable};
+    ///
+    /// let digest = "alert('Hello, world.');".hash_with(HashAlgorithm::Sha384).unwrap();
+    /// assert_eq!(
+    ///     digest.to_sri(HashAlgorithm::Sha384).unwrap(),
+    ///     "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO"
+    /// );
+    /// ```
+    pub fn to_sri(&self, algorithm: HashAlgorithm) -> Result<String, SHAError> {
+        check_algorithm(algorithm)?;
+        if self.len() != algorithm.output_len() {
+            return Err(SHAError::InvalidDigest(format!(
+                "a {} digest has {} bytes, found {}",
+                algorithm,
+                algorithm.output_len(),
+                self.len()
+            )));
+        }
+        Ok(format!(
+            "{}-{}",
+            algorithm,
+            STANDARD.encode(self.as_bytes())
+        ))
+    }
+}
+
+pub(crate) fn check_algorithm(algorithm: HashAlgorithm) -> Result<(), SHAError> {
+    if SRI_ALGORITHMS.contains(&algorithm) {
+        Ok(())
+    } else {
+        Err(SHAError::UnsupportedAlgorithm(format!(
+            "{} cannot be used for subresource integrity",
+            algorithm
+        )))
+    }
+}
+
+/// Parses an `integrity` attribute into its strongest algorithm and the digests given for it.
+///
+/// As in browsers, the value may hold several space-separated hashes, each possibly followed by
+/// `?options`, and entries with unknown algorithms or bad base64 are skipped.
+pub(crate) fn strongest(integrity: &str) -> Result<(HashAlgorithm, Vec<Digest>), SHAError> {
+    strongest_of(integrity, &SRI_ALGORITHMS)
+}
+
+/// Like [`strongest`], accepting the given `algorithms`, listed weakest first.
+pub(crate) fn strongest_of(
+    integrity: &str,
+    algorithms: &[HashAlgorithm],
+) -> Result<(HashAlgorithm, Vec<Digest>), SHAError> {
+    let mut best: Option<(HashAlgorithm, Vec<Digest>)> = None;
+    for token in integrity.split_ascii_whitespace() {
+        let Some((algorithm, value)) = token.split_once('-') else {
+            continue;
+        };
+        let Some(algorithm) = algorithms
+            .iter()
+            .copied()
+            .find(|candidate| candidate.name() == algorithm)
+        else {
+            continue;
+        };
+        let value = value.split_once('?').map_or(value, |(value, _)| value);
+        let Ok(bytes) = STANDARD.decode(value) else {
+            continue;
+        };
+        if bytes.len() != algorithm.output_len() {
+            continue;
+        }
+        let rank = |algorithm| algorithms.iter().position(|known| *known == algorithm);
+        match &mut best {
+            Some((current, digests)) if *current == algorithm => {
+                digests.push(Digest::from_bytes(bytes))
+            }
+            Some((current, _)) if rank(*current) > rank(algorithm) => {}
+            _ => best = Some((algorithm, vec![Digest::from_bytes(bytes)])),
+        }
+    }
+    best.ok_or_else(|| {
+        SHAError::InvalidDigest(format!("no usable integrity metadata in {:?}", integrity))
+    })
+}
+
+/// Returns `true` if `actual` equals any of `expected`, comparing in constant time.
+pub(crate) fn matches_any(actual: &Digest, expected: &[Digest]) -> bool {
+    expected
+        .iter()
+        .any(|digest| bool::from(actual.as_bytes().ct_eq(digest.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+
+    const SCRIPT: &str = "alert('Hello, world.');";
+    const SHA384: &str = "sha384-H8BRh8j48O9oYatfu5AZzq6A9RINhZO5H16dQZngK7T62em8MUt1FLm52t+eX6xO";
+
+    #[test]
+    fn test_to_sri() {
+        assert_eq!(SCRIPT.to_sri().unwrap(), SHA384);
+        assert_eq!(
+            "".to_sri_with(HashAlgorithm::Sha256).unwrap(),
+            "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+        );
+        assert!(matches!(
+            SCRIPT.to_sri_with(HashAlgorithm::Sha1),
+            Err(SHAError::UnsupportedAlgorithm(_))
+        ));
+        let sha1 = SCRIPT.hash().unwrap();
+        assert!(matches!(
+            sha1.to_sri(HashAlgorithm::Sha384),
+            Err(SHAError::InvalidDigest(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_sri() {
+        assert!(SCRIPT.validate_sri(SHA384).unwrap());
+        assert!(!"tampered".validate_sri(SHA384).unwrap());
+
+        // The strongest algorithm wins, and any of its hashes may match
+        let sha256 = "".to_sri_with(HashAlgorithm::Sha256).unwrap();
+        let other = "other".to_sri_with(HashAlgorithm::Sha384).unwrap();
+        let integrity = format!("{} {}?ct=x md5-abc {}", sha256, other, SHA384);
+        assert!(SCRIPT.validate_sri(&integrity).unwrap());
+        assert!(!"".validate_sri(&integrity).unwrap());
+
+        assert!(SCRIPT.validate_sri("sha1-abc md5-x sha384-!!!").is_err());
+        assert!(SCRIPT.validate_sri("").is_err());
+    }
+}
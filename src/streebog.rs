@@ -0,0 +1,92 @@
+//! GOST R 34.11-2012 (Streebog) digests.
+//!
+//! Available with the `streebog` feature. Both the 256-bit and 512-bit variants are provided
+//! and the functions mirror the crate-level SHA-1 API, returning [`Digest`] values.
+//!
+//! ```no_run
+//! use sha_file_hashing::streebog;
+//!
+//! let digest = streebog::hash_file_from_path_256("contract.pdf").unwrap();
+//! assert!(streebog::validate_file_from_path_256("contract.pdf", digest.to_hex()).unwrap());
+//! ```
+
+use crate::{Digest, HashAlgorithm, SHAError, hash_file_with_algorithm};
+use std::fs::File;
+use std::path::Path;
+
+pub use streebog::{Streebog256, Streebog512};
+
+/// Computes the Streebog-256 digest of a `File` handle.
+pub fn hash_file_256(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Streebog256)
+}
+
+/// Computes the Streebog-512 digest of a `File` handle.
+pub fn hash_file_512(file: File) -> Result<Digest, SHAError> {
+    hash_file_with_algorithm(file, HashAlgorithm::Streebog512)
+}
+
+/// Computes the Streebog-256 digest of the file at `path`.
+pub fn hash_file_from_path_256(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file_256(File::open(path.as_ref())?)
+}
+
+/// Computes the Streebog-512 digest of the file at `path`.
+pub fn hash_file_from_path_512(path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+    hash_file_512(File::open(path.as_ref())?)
+}
+
+/// Validates the file at `path` against an expected Streebog-256 digest (case-insensitive).
+pub fn validate_file_from_path_256(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    hash_file_from_path_256(path)?.matches_hex(hash.as_ref())
+}
+
+/// Validates the file at `path` against an expected Streebog-512 digest (case-insensitive).
+pub fn validate_file_from_path_512(
+    path: impl AsRef<Path>,
+    hash: impl AsRef<str>,
+) -> Result<bool, SHAError> {
+    hash_file_from_path_512(path)?.matches_hex(hash.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_streebog_empty() {
+        let temp_file = NamedTempFile::new().unwrap();
+
+        assert_eq!(
+            hash_file_from_path_256(temp_file.path()).unwrap(),
+            "3f539a213e97c802cc229d474c6aa32a825a360b2a933a949fd925208d9ce1bb"
+        );
+        assert_eq!(
+            hash_file_from_path_512(temp_file.path()).unwrap(),
+            "8e945da209aa869f0455928529bcae4679e9873ab707b55315f56ceb98bef0a7\
+             362f715528356ee83cda5f2aac4c6ad2ba3a715c1bcd81cb8e9f90bf4c1c1a8a"
+        );
+    }
+
+    #[test]
+    fn test_streebog_validate() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"streebog test").unwrap();
+        temp_file.flush().unwrap();
+
+        let hash = hash_file_from_path_256(temp_file.path()).unwrap();
+        assert_eq!(hash.to_hex().len(), 64);
+        assert!(
+            validate_file_from_path_256(temp_file.path(), hash.to_hex().to_uppercase()).unwrap()
+        );
+        assert!(matches!(
+            validate_file_from_path_512(temp_file.path(), hash.to_hex()),
+            Err(SHAError::InvalidDigest(_))
+        ));
+    }
+}
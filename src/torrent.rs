@@ -0,0 +1,1024 @@
+//! BitTorrent metainfo: the piece hashes of a file or file set, and the infohash that
+//! identifies a torrent to trackers and peers.
+//!
+//! Version 1 torrents lay the files end to end and hash them into SHA-1 pieces. Version 2
+//! torrents ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html)) hash each file on its own
+//! into a SHA-256 merkle tree over 16 KiB blocks, keeping the tree's root in the info dictionary
+//! and the layer with one node per piece in the `piece layers` of the `.torrent` file.
+
+use crate::bencode::{self, Value};
+use crate::{Digest, DirOptions, HashAlgorithm, Hasher, HasherOptions, SHAError, read_chunks};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// The BitTorrent v2 block size, which is also the smallest piece length clients accept.
+const BLOCK_SIZE: u64 = 16 * 1024;
+
+/// The metainfo format of a torrent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TorrentVersion {
+    /// SHA-1 pieces that span file boundaries, understood by every client.
+    #[default]
+    V1,
+    /// Per-file SHA-256 merkle trees, as defined by BEP 52.
+    ///
+    /// Hybrid torrents, which carry v1 pieces as well, are read as v2.
+    V2,
+}
+
+/// How [`TorrentOptions::hash_file`] and [`hash_dir`](TorrentOptions::hash_dir) build a
+/// torrent.
+///
+/// # Examples
+/// ```no_run
+/// use sha_file_hashing::TorrentOptions;
+///
+/// let info = TorrentOptions::new()
+///     .piece_length(1024 * 1024)
+///     .hash_dir("release")
+///     .unwrap();
+/// println!("magnet:?xt=urn:btih:{}", info.infohash());
+/// std::fs::write(
+///     "release.torrent",
+///     info.to_torrent(Some("udp://tracker.example.org:1337")),
+/// )
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentOptions {
+    piece_length: u64,
+    private: bool,
+    version: TorrentVersion,
+}
+
+impl Default for TorrentOptions {
+    fn default() -> Self {
+        Self {
+            piece_length: 256 * 1024,
+            private: false,
+            version: TorrentVersion::V1,
+        }
+    }
+}
+
+impl TorrentOptions {
+    /// Creates options for a public v1 torrent with 256 KiB pieces.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the piece length in bytes, which must be a power of two of at least 16 KiB.
+    ///
+    /// Larger pieces make a smaller `.torrent` file at the cost of more data re-downloaded per
+    /// corrupt piece; common choices keep the piece count between one and a few thousand.
+    pub fn piece_length(mut self, piece_length: u64) -> Self {
+        self.piece_length = piece_length;
+        self
+    }
+
+    /// Marks the torrent private, so clients only find peers through its trackers.
+    ///
+    /// The flag is part of the info dictionary, so it changes the infohash.
+    pub fn private(mut self, enabled: bool) -> Self {
+        self.private = enabled;
+        self
+    }
+
+    /// Selects the metainfo format, v1 by default.
+    pub fn version(mut self, version: TorrentVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Hashes the file at `path` into a single-file torrent named after it.
+    pub fn hash_file(&self, path: impl AsRef<Path>) -> Result<TorrentInfo, SHAError> {
+        let path = path.as_ref();
+        let length = fs::metadata(path)
+            .map_err(|e| SHAError::from(e).at_path(path))?
+            .len();
+        let name = torrent_name(path)?;
+        let file = TorrentFile {
+            path: PathBuf::from(&name),
+            length,
+            pieces_root: None,
+        };
+        self.build(name, vec![(path.to_path_buf(), file)], true)
+    }
+
+    /// Hashes every file under `root` into a multi-file torrent named after the directory.
+    ///
+    /// Files are listed in path order, compared component by component as a v2 file tree
+    /// orders them, hidden files included.
+    pub fn hash_dir(&self, root: impl AsRef<Path>) -> Result<TorrentInfo, SHAError> {
+        let root = root.as_ref();
+        let mut files = Vec::new();
+        for relative in DirOptions::new().hash_ignore(false).list_files(root)? {
+            let path = root.join(&relative);
+            let length = fs::metadata(&path)
+                .map_err(|e| SHAError::from(e).at_path(&path))?
+                .len();
+            let file = TorrentFile {
+                path: relative,
+                length,
+                pieces_root: None,
+            };
+            files.push((path, file));
+        }
+        files.sort_by(|(_, a), (_, b)| a.path.cmp(&b.path));
+        self.build(torrent_name(root)?, files, false)
+    }
+
+    /// Hashes `files`, each paired with the path to read it from, into an info dictionary.
+    fn build(
+        &self,
+        name: String,
+        mut files: Vec<(PathBuf, TorrentFile)>,
+        single_file: bool,
+    ) -> Result<TorrentInfo, SHAError> {
+        if !self.piece_length.is_power_of_two() || self.piece_length < BLOCK_SIZE {
+            return Err(SHAError::InvalidTorrent(format!(
+                "piece length {} is not a power of two of at least 16 KiB",
+                self.piece_length
+            )));
+        }
+        let mut pieces = Vec::new();
+        let mut piece_layers = BTreeMap::new();
+        match self.version {
+            TorrentVersion::V1 => {
+                let lengths = files.iter().map(|(path, file)| (path.clone(), file.length));
+                pieces = hash_pieces(self.piece_length, lengths)?;
+            }
+            TorrentVersion::V2 => {
+                for (path, file) in &mut files {
+                    let (root, layer) = hash_merkle(self.piece_length, path, file.length)?;
+                    if let Some(root) = &root
+                        && !layer.is_empty()
+                    {
+                        piece_layers.insert(root.clone(), layer);
+                    }
+                    file.pieces_root = root;
+                }
+            }
+        }
+        Ok(TorrentInfo {
+            name,
+            piece_length: self.piece_length,
+            version: self.version,
+            pieces,
+            piece_layers,
+            files: files.into_iter().map(|(_, file)| file).collect(),
+            single_file,
+            private: self.private,
+            raw: None,
+        })
+    }
+}
+
+/// A file of a torrent and its length in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentFile {
+    /// The path relative to the torrent's directory, or the file name of a single-file torrent.
+    pub path: PathBuf,
+    /// The length in bytes.
+    pub length: u64,
+    /// The root of the file's v2 merkle tree, or `None` in a v1 torrent and for empty files.
+    pub pieces_root: Option<Digest>,
+}
+
+/// The info dictionary of a torrent: its name, files and piece hashes.
+///
+/// Built by [`TorrentOptions`], or read from a `.torrent` file with
+/// [`read`](TorrentInfo::read) to check downloaded data with [`verify`](TorrentInfo::verify).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TorrentInfo {
+    name: String,
+    piece_length: u64,
+    version: TorrentVersion,
+    pieces: Vec<Digest>,
+    /// The v2 piece layers of the files longer than one piece, by pieces root.
+    piece_layers: BTreeMap<Digest, Vec<Digest>>,
+    files: Vec<TorrentFile>,
+    single_file: bool,
+    private: bool,
+    /// The info dictionary as read, so the infohash covers keys this crate does not know.
+    raw: Option<Vec<u8>>,
+}
+
+impl TorrentInfo {
+    /// Reads the `.torrent` file at `path`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, SHAError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        Self::from_torrent(&data)
+    }
+
+    /// Parses the contents of a `.torrent` file.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::InvalidTorrent`] if the data is not bencoded metainfo with a valid
+    /// info dictionary, a file path would leave the torrent's directory, or a v2 piece layer is
+    /// missing or does not match its file's root.
+    pub fn from_torrent(data: &[u8]) -> Result<Self, SHAError> {
+        let metainfo = bencode::decode_dict(data).map_err(SHAError::InvalidTorrent)?;
+        let (info, range) = metainfo
+            .get(b"info".as_slice())
+            .ok_or_else(|| invalid("no info dictionary"))?;
+        let info = info
+            .dict()
+            .ok_or_else(|| invalid("info is not a dictionary"))?;
+        let raw = data[range.clone()].to_vec();
+        if info.get(b"meta version".as_slice()).and_then(Value::int) == Some(2) {
+            let layers = metainfo
+                .get(b"piece layers".as_slice())
+                .map(|(layers, _)| layers);
+            return Self::from_v2(info, layers, raw);
+        }
+        let field = |key: &str| info.get(key.as_bytes());
+        let name = field("name")
+            .and_then(Value::bytes)
+            .and_then(|name| String::from_utf8(name.to_vec()).ok())
+            .ok_or_else(|| invalid("missing or non-UTF-8 name"))?;
+        let piece_length = field("piece length")
+            .and_then(Value::int)
+            .and_then(|n| u64::try_from(n).ok())
+            .filter(|n| *n > 0)
+            .ok_or_else(|| invalid("missing or invalid piece length"))?;
+        let pieces = field("pieces")
+            .and_then(Value::bytes)
+            .filter(|pieces| pieces.len() % 20 == 0)
+            .ok_or_else(|| invalid("missing v1 piece hashes"))?;
+        let pieces: Vec<Digest> = pieces.chunks(20).map(Digest::from).collect();
+        check_component(name.as_bytes())?;
+
+        let (single_file, files) = match (field("length"), field("files")) {
+            (Some(length), None) => {
+                let file = TorrentFile {
+                    path: PathBuf::from(&name),
+                    length: length_of(length)?,
+                    pieces_root: None,
+                };
+                (true, vec![file])
+            }
+            (None, Some(files)) => {
+                let files = files.list().ok_or_else(|| invalid("files is not a list"))?;
+                (
+                    false,
+                    files.iter().map(parse_file).collect::<Result<_, _>>()?,
+                )
+            }
+            _ => return Err(invalid("info must have exactly one of length and files")),
+        };
+        let total: u64 = files.iter().map(|file: &TorrentFile| file.length).sum();
+        if pieces.len() as u64 != total.div_ceil(piece_length) {
+            return Err(invalid("the piece count does not match the total length"));
+        }
+        Ok(Self {
+            name,
+            piece_length,
+            version: TorrentVersion::V1,
+            pieces,
+            piece_layers: BTreeMap::new(),
+            files,
+            single_file,
+            private: field("private").and_then(Value::int) == Some(1),
+            raw: Some(raw),
+        })
+    }
+
+    /// Parses a v2 info dictionary and the `piece layers` beside it.
+    fn from_v2(
+        info: &BTreeMap<Vec<u8>, Value>,
+        layers: Option<&Value>,
+        raw: Vec<u8>,
+    ) -> Result<Self, SHAError> {
+        let field = |key: &str| info.get(key.as_bytes());
+        let name = field("name")
+            .and_then(Value::bytes)
+            .and_then(|name| String::from_utf8(name.to_vec()).ok())
+            .ok_or_else(|| invalid("missing or non-UTF-8 name"))?;
+        let piece_length = field("piece length")
+            .and_then(Value::int)
+            .and_then(|n| u64::try_from(n).ok())
+            .filter(|n| n.is_power_of_two() && *n >= BLOCK_SIZE)
+            .ok_or_else(|| invalid("missing or invalid piece length"))?;
+        let tree = field("file tree").ok_or_else(|| invalid("missing file tree"))?;
+        let mut files = Vec::new();
+        parse_file_tree(tree, Path::new(""), &mut files)?;
+        let single_file = files.len() == 1 && files[0].path == Path::new(&name);
+
+        let no_layers = BTreeMap::new();
+        let layers = match layers {
+            Some(layers) => layers
+                .dict()
+                .ok_or_else(|| invalid("piece layers is not a dictionary"))?,
+            None => &no_layers,
+        };
+        let pad = pad_hash(piece_length);
+        let mut piece_layers = BTreeMap::new();
+        for file in files.iter().filter(|file| file.length > piece_length) {
+            let Some(root) = &file.pieces_root else {
+                continue;
+            };
+            let layer: Vec<Digest> = layers
+                .get(root.as_bytes())
+                .and_then(Value::bytes)
+                .filter(|layer| layer.len() % 32 == 0)
+                .ok_or_else(|| invalid("missing piece layer"))?
+                .chunks(32)
+                .map(Digest::from)
+                .collect();
+            if layer.len() as u64 != file.length.div_ceil(piece_length)
+                || merkle_root(layer.clone(), &pad, 1) != *root
+            {
+                return Err(invalid("a piece layer does not match its pieces root"));
+            }
+            piece_layers.insert(root.clone(), layer);
+        }
+        Ok(Self {
+            name,
+            piece_length,
+            version: TorrentVersion::V2,
+            pieces: Vec::new(),
+            piece_layers,
+            files,
+            single_file,
+            private: field("private").and_then(Value::int) == Some(1),
+            raw: Some(raw),
+        })
+    }
+
+    /// Returns the suggested name of the file, or of the directory holding the files.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the piece length in bytes.
+    pub fn piece_length(&self) -> u64 {
+        self.piece_length
+    }
+
+    /// Returns the metainfo format.
+    pub fn version(&self) -> TorrentVersion {
+        self.version
+    }
+
+    /// Returns the SHA-1 digest of each piece of a v1 torrent, in order, and nothing for v2.
+    pub fn pieces(&self) -> &[Digest] {
+        &self.pieces
+    }
+
+    /// Returns the v2 piece layer of the file with the given
+    /// [`pieces_root`](TorrentFile::pieces_root): the merkle root of each of its pieces.
+    ///
+    /// Files no longer than one piece have no layer, since their root covers their only piece.
+    pub fn piece_layer(&self, pieces_root: &Digest) -> Option<&[Digest]> {
+        self.piece_layers.get(pieces_root).map(Vec::as_slice)
+    }
+
+    /// Returns the files in the order their data is laid out in pieces.
+    pub fn files(&self) -> &[TorrentFile] {
+        &self.files
+    }
+
+    /// Returns the total length of all files in bytes.
+    pub fn total_length(&self) -> u64 {
+        self.files.iter().map(|file| file.length).sum()
+    }
+
+    /// Returns whether the torrent is marked private.
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Returns the bencoded info dictionary, exactly as read for a parsed torrent.
+    pub fn to_bencode(&self) -> Vec<u8> {
+        if let Some(raw) = &self.raw {
+            return raw.clone();
+        }
+        if self.version == TorrentVersion::V2 {
+            return self.to_bencode_v2();
+        }
+        let mut pieces = Vec::with_capacity(self.pieces.len() * 20);
+        self.pieces
+            .iter()
+            .for_each(|piece| pieces.extend_from_slice(piece.as_bytes()));
+        let mut info = vec![
+            ("name", Value::Bytes(self.name.clone().into_bytes())),
+            ("piece length", Value::Int(self.piece_length as i64)),
+            ("pieces", Value::Bytes(pieces)),
+        ];
+        if self.private {
+            info.push(("private", Value::Int(1)));
+        }
+        match self.single_file {
+            true => info.push(("length", Value::Int(self.total_length() as i64))),
+            false => info.push((
+                "files",
+                Value::List(self.files.iter().map(file_value).collect()),
+            )),
+        }
+        let mut out = Vec::new();
+        bencode::dict(info).encode(&mut out);
+        out
+    }
+
+    fn to_bencode_v2(&self) -> Vec<u8> {
+        let mut tree = BTreeMap::new();
+        for file in &self.files {
+            let mut entry = vec![("length", Value::Int(file.length as i64))];
+            if let Some(root) = &file.pieces_root {
+                entry.push(("pieces root", Value::Bytes(root.as_bytes().to_vec())));
+            }
+            let names: Vec<_> = file.path.components().map(component_bytes).collect();
+            insert_file(
+                &mut tree,
+                &names,
+                bencode::dict([("", bencode::dict(entry))]),
+            );
+        }
+        let mut info = vec![
+            ("file tree", Value::Dict(tree)),
+            ("meta version", Value::Int(2)),
+            ("name", Value::Bytes(self.name.clone().into_bytes())),
+            ("piece length", Value::Int(self.piece_length as i64)),
+        ];
+        if self.private {
+            info.push(("private", Value::Int(1)));
+        }
+        let mut out = Vec::new();
+        bencode::dict(info).encode(&mut out);
+        out
+    }
+
+    /// Returns the infohash: the digest of the bencoded info dictionary, as used in magnet
+    /// links and by trackers.
+    ///
+    /// It is a SHA-1 digest for v1 torrents and a SHA-256 digest for v2, which magnet links
+    /// carry as `urn:btmh:1220<hex>`.
+    pub fn infohash(&self) -> Digest {
+        let algorithm = match self.version {
+            TorrentVersion::V1 => HashAlgorithm::Sha1,
+            TorrentVersion::V2 => HashAlgorithm::Sha256,
+        };
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(self.to_bencode());
+        hasher.finalize()
+    }
+
+    /// Returns the contents of a `.torrent` file for this info dictionary, with an optional
+    /// tracker `announce` URL and, for v2, the piece layers.
+    pub fn to_torrent(&self, announce: Option<&str>) -> Vec<u8> {
+        let mut out = b"d".to_vec();
+        if let Some(announce) = announce {
+            Value::Bytes(b"announce".to_vec()).encode(&mut out);
+            Value::Bytes(announce.as_bytes().to_vec()).encode(&mut out);
+        }
+        Value::Bytes(b"info".to_vec()).encode(&mut out);
+        out.extend_from_slice(&self.to_bencode());
+        if self.version == TorrentVersion::V2 {
+            let layers = self.piece_layers.iter().map(|(root, layer)| {
+                let hashes = layer.iter().flat_map(|hash| hash.as_bytes().to_vec());
+                (root.as_bytes().to_vec(), Value::Bytes(hashes.collect()))
+            });
+            Value::Bytes(b"piece layers".to_vec()).encode(&mut out);
+            Value::Dict(layers.collect()).encode(&mut out);
+        }
+        out.push(b'e');
+        out
+    }
+
+    /// Hashes the downloaded data at `path` and returns the indexes of the pieces that do not
+    /// match; an empty list means the download is complete and intact.
+    ///
+    /// `path` is the file itself for a single-file torrent, and the directory holding the files,
+    /// usually named [`name`](TorrentInfo::name), otherwise. v2 pieces never span files, so they
+    /// are numbered file by file in [`files`](TorrentInfo::files) order.
+    ///
+    /// # Errors
+    /// Returns [`SHAError::File`] if a file is missing or shorter than the torrent says.
+    pub fn verify(&self, path: impl AsRef<Path>) -> Result<Vec<usize>, SHAError> {
+        let path = path.as_ref();
+        if self.version == TorrentVersion::V2 {
+            return self.verify_v2(path);
+        }
+        let pieces = match self.single_file {
+            true => hash_pieces(
+                self.piece_length,
+                [(path.to_path_buf(), self.total_length())],
+            )?,
+            false => hash_pieces(
+                self.piece_length,
+                self.files
+                    .iter()
+                    .map(|file| (path.join(&file.path), file.length)),
+            )?,
+        };
+        let bad = pieces.iter().zip(&self.pieces).enumerate();
+        Ok(bad
+            .filter(|(_, (found, expected))| found != expected)
+            .map(|(index, _)| index)
+            .collect())
+    }
+
+    fn verify_v2(&self, path: &Path) -> Result<Vec<usize>, SHAError> {
+        let mut bad = Vec::new();
+        let mut first = 0;
+        for file in &self.files {
+            let file_path = match self.single_file {
+                true => path.to_path_buf(),
+                false => path.join(&file.path),
+            };
+            let count = file.length.div_ceil(self.piece_length) as usize;
+            let (root, layer) = hash_merkle(self.piece_length, &file_path, file.length)?;
+            let expected = file
+                .pieces_root
+                .as_ref()
+                .and_then(|root| self.piece_layer(root));
+            match expected {
+                Some(expected) => bad.extend(
+                    (0..count)
+                        .filter(|piece| layer.get(*piece) != expected.get(*piece))
+                        .map(|piece| first + piece),
+                ),
+                None if root != file.pieces_root => bad.push(first),
+                None => {}
+            }
+            first += count;
+        }
+        Ok(bad)
+    }
+}
+
+/// Hashes the first `length` bytes of each file, laid end to end, into pieces of
+/// `piece_length` bytes.
+fn hash_pieces(
+    piece_length: u64,
+    files: impl IntoIterator<Item = (PathBuf, u64)>,
+) -> Result<Vec<Digest>, SHAError> {
+    let mut pieces = Vec::new();
+    let mut hasher = Hasher::new(HashAlgorithm::Sha1);
+    let mut filled = 0u64;
+    for (path, length) in files {
+        let reader = File::open(&path).map_err(|e| SHAError::from(e).at_path(&path))?;
+        let mut read = 0u64;
+        read_chunks(&HasherOptions::new(), reader.take(length), |mut chunk| {
+            read += chunk.len() as u64;
+            while !chunk.is_empty() {
+                let take = chunk.len().min((piece_length - filled) as usize);
+                hasher.update(&chunk[..take]);
+                filled += take as u64;
+                if filled == piece_length {
+                    pieces.push(hasher.finalize_reset());
+                    filled = 0;
+                }
+                chunk = &chunk[take..];
+            }
+        })
+        .map_err(|e| e.at_path(&path))?;
+        if read != length {
+            let e = io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file is shorter than expected",
+            );
+            return Err(SHAError::from(e).at_path(&path));
+        }
+    }
+    if filled > 0 {
+        pieces.push(hasher.finalize());
+    }
+    Ok(pieces)
+}
+
+/// Hashes the first `length` bytes of the file at `path` into its v2 pieces root and, if the
+/// file is longer than one piece, its piece layer.
+fn hash_merkle(
+    piece_length: u64,
+    path: &Path,
+    length: u64,
+) -> Result<(Option<Digest>, Vec<Digest>), SHAError> {
+    if length == 0 {
+        return Ok((None, Vec::new()));
+    }
+    let reader = File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+    let mut blocks = Vec::with_capacity(length.div_ceil(BLOCK_SIZE) as usize);
+    let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+    let mut filled = 0u64;
+    let mut read = 0u64;
+    read_chunks(&HasherOptions::new(), reader.take(length), |mut chunk| {
+        read += chunk.len() as u64;
+        while !chunk.is_empty() {
+            let take = chunk.len().min((BLOCK_SIZE - filled) as usize);
+            hasher.update(&chunk[..take]);
+            filled += take as u64;
+            if filled == BLOCK_SIZE {
+                blocks.push(hasher.finalize_reset());
+                filled = 0;
+            }
+            chunk = &chunk[take..];
+        }
+    })
+    .map_err(|e| e.at_path(path))?;
+    if read != length {
+        let e = io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "file is shorter than expected",
+        );
+        return Err(SHAError::from(e).at_path(path));
+    }
+    if filled > 0 {
+        blocks.push(hasher.finalize());
+    }
+
+    // Leaves past the end of the file are zeros, not the hash of zeros
+    let zero = Digest::from(&[0u8; 32][..]);
+    let per_piece = (piece_length / BLOCK_SIZE) as usize;
+    let layer = match length > piece_length {
+        true => blocks
+            .chunks(per_piece)
+            .map(|piece| merkle_root(piece.to_vec(), &zero, per_piece))
+            .collect(),
+        false => Vec::new(),
+    };
+    Ok((Some(merkle_root(blocks, &zero, 1)), layer))
+}
+
+/// Returns the root of a merkle tree over `nodes`, padded with `pad` to a power of two of at
+/// least `width` nodes.
+fn merkle_root(mut nodes: Vec<Digest>, pad: &Digest, width: usize) -> Digest {
+    nodes.resize(nodes.len().max(width).next_power_of_two(), pad.clone());
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    nodes.pop().expect("a tree has at least one node")
+}
+
+fn hash_pair(left: &Digest, right: &Digest) -> Digest {
+    let mut hasher = Hasher::new(HashAlgorithm::Sha256);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// Returns the node a piece layer is padded with: the root of a piece of zero leaves.
+fn pad_hash(piece_length: u64) -> Digest {
+    let mut pad = Digest::from(&[0u8; 32][..]);
+    for _ in 0..(piece_length / BLOCK_SIZE).trailing_zeros() {
+        pad = hash_pair(&pad, &pad);
+    }
+    pad
+}
+
+/// Adds a file's entry to a v2 file tree, creating the directories `names` leads through.
+fn insert_file(tree: &mut BTreeMap<Vec<u8>, Value>, names: &[Vec<u8>], entry: Value) {
+    let Some((name, rest)) = names.split_first() else {
+        return;
+    };
+    if rest.is_empty() {
+        tree.insert(name.clone(), entry);
+        return;
+    }
+    let dir = tree
+        .entry(name.clone())
+        .or_insert_with(|| Value::Dict(BTreeMap::new()));
+    if let Value::Dict(dir) = dir {
+        insert_file(dir, rest, entry);
+    }
+}
+
+/// Collects the files of a v2 file tree in key order, which is the order their pieces follow.
+fn parse_file_tree(
+    tree: &Value,
+    prefix: &Path,
+    files: &mut Vec<TorrentFile>,
+) -> Result<(), SHAError> {
+    let tree = tree
+        .dict()
+        .ok_or_else(|| invalid("file tree is not a dictionary"))?;
+    for (name, node) in tree {
+        check_component(name)?;
+        let path = prefix.join(String::from_utf8_lossy(name).as_ref());
+        let Some(file) = node.dict().and_then(|node| node.get(b"".as_slice())) else {
+            parse_file_tree(node, &path, files)?;
+            continue;
+        };
+        let file = file
+            .dict()
+            .ok_or_else(|| invalid("file is not a dictionary"))?;
+        let length = length_of(
+            file.get(b"length".as_slice())
+                .ok_or_else(|| invalid("file without length"))?,
+        )?;
+        let pieces_root = match file.get(b"pieces root".as_slice()) {
+            Some(root) => Some(
+                root.bytes()
+                    .filter(|root| root.len() == 32)
+                    .map(Digest::from)
+                    .ok_or_else(|| invalid("invalid pieces root"))?,
+            ),
+            None if length > 0 => return Err(invalid("file without pieces root")),
+            None => None,
+        };
+        files.push(TorrentFile {
+            path,
+            length,
+            pieces_root,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the last component of `path`, made absolute first so `.` is named too.
+fn torrent_name(path: &Path) -> Result<String, SHAError> {
+    let path = std::path::absolute(path)?;
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| invalid("the path has no name to give the torrent"))
+}
+
+fn component_bytes(component: Component<'_>) -> Vec<u8> {
+    component.as_os_str().to_string_lossy().as_bytes().to_vec()
+}
+
+fn file_value(file: &TorrentFile) -> Value {
+    let path = file
+        .path
+        .components()
+        .map(|component| Value::Bytes(component_bytes(component)))
+        .collect();
+    bencode::dict([
+        ("length", Value::Int(file.length as i64)),
+        ("path", Value::List(path)),
+    ])
+}
+
+fn parse_file(value: &Value) -> Result<TorrentFile, SHAError> {
+    let file = value
+        .dict()
+        .ok_or_else(|| invalid("file is not a dictionary"))?;
+    let length = length_of(
+        file.get(b"length".as_slice())
+            .ok_or_else(|| invalid("file without length"))?,
+    )?;
+    let components = file
+        .get(b"path".as_slice())
+        .and_then(Value::list)
+        .filter(|components| !components.is_empty())
+        .ok_or_else(|| invalid("file without path"))?;
+    let mut path = PathBuf::new();
+    for component in components {
+        let component = component.bytes().ok_or_else(|| invalid("invalid path"))?;
+        check_component(component)?;
+        path.push(String::from_utf8_lossy(component).as_ref());
+    }
+    Ok(TorrentFile {
+        path,
+        length,
+        pieces_root: None,
+    })
+}
+
+fn length_of(value: &Value) -> Result<u64, SHAError> {
+    value
+        .int()
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or_else(|| invalid("invalid length"))
+}
+
+/// Rejects a name that is not a single normal path component, so paths stay inside the torrent.
+fn check_component(name: &[u8]) -> Result<(), SHAError> {
+    let name = String::from_utf8_lossy(name);
+    let mut components = Path::new(name.as_ref()).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains(['/', '\\']) => Ok(()),
+        _ => Err(invalid(&format!("unsafe path component {:?}", name))),
+    }
+}
+
+fn invalid(message: &str) -> SHAError {
+    SHAError::InvalidTorrent(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::portable_path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_single_file_torrent() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        let data: Vec<u8> = (0..40_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &data).unwrap();
+
+        let info = TorrentOptions::new()
+            .piece_length(16 * 1024)
+            .hash_file(&path)
+            .unwrap();
+        assert_eq!(info.name(), "data.bin");
+        assert_eq!(info.total_length(), 40_000);
+        assert_eq!(info.pieces().len(), 3);
+        let mut last = Hasher::new(HashAlgorithm::Sha1);
+        last.update(&data[32 * 1024..]);
+        assert_eq!(info.pieces()[2], last.finalize());
+        // Computed independently with Python's hashlib and a separate bencoder
+        assert_eq!(info.infohash(), "7c4ef20d2477b790e7a166a2c52d3f81e5e4f42a");
+
+        let read = TorrentInfo::from_torrent(&info.to_torrent(Some("udp://t.example:1"))).unwrap();
+        assert_eq!(read.infohash(), info.infohash());
+        assert_eq!(read.files(), info.files());
+        assert!(read.verify(&path).unwrap().is_empty());
+
+        let mut corrupt = data.clone();
+        corrupt[20_000] ^= 1;
+        fs::write(&path, &corrupt).unwrap();
+        assert_eq!(read.verify(&path).unwrap(), [1]);
+        fs::write(&path, &data[..100]).unwrap();
+        assert!(matches!(read.verify(&path), Err(SHAError::File { .. })));
+
+        assert!(matches!(
+            TorrentOptions::new().piece_length(1000).hash_file(&path),
+            Err(SHAError::InvalidTorrent(_))
+        ));
+    }
+
+    #[test]
+    fn test_multi_file_torrent_keeps_ignored_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("album");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(".hashignore"), "*.log\n").unwrap();
+        fs::write(root.join("rip.log"), b"log").unwrap();
+
+        let info = TorrentOptions::new().hash_dir(&root).unwrap();
+        let paths: Vec<_> = info
+            .files()
+            .iter()
+            .map(|f| portable_path(&f.path))
+            .collect();
+        assert_eq!(paths, [".hashignore", "rip.log"]);
+    }
+
+    #[test]
+    fn test_multi_file_torrent() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("album");
+        fs::create_dir_all(root.join("disc 2")).unwrap();
+        fs::write(root.join("cover.jpg"), vec![1u8; 10_000]).unwrap();
+        fs::write(root.join("disc 2/track.flac"), vec![2u8; 30_000]).unwrap();
+
+        let info = TorrentOptions::new()
+            .piece_length(16 * 1024)
+            .private(true)
+            .hash_dir(&root)
+            .unwrap();
+        assert_eq!(info.name(), "album");
+        let paths: Vec<_> = info
+            .files()
+            .iter()
+            .map(|f| portable_path(&f.path))
+            .collect();
+        assert_eq!(paths, ["cover.jpg", "disc 2/track.flac"]);
+        assert_eq!(info.pieces().len(), 3);
+        assert!(info.is_private());
+        assert_eq!(info.infohash(), "bcc4cb0d1d82e0432383f3e44b98656e79f81de5");
+
+        let read = TorrentInfo::from_torrent(&info.to_torrent(None)).unwrap();
+        assert_eq!(
+            read,
+            TorrentInfo {
+                raw: read.raw.clone(),
+                ..info
+            }
+        );
+        assert!(read.verify(&root).unwrap().is_empty());
+        fs::write(root.join("cover.jpg"), vec![9u8; 10_000]).unwrap();
+        assert_eq!(read.verify(&root).unwrap(), [0]);
+    }
+
+    #[test]
+    fn test_v2_single_file_torrent() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        let data: Vec<u8> = (0..40_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &data).unwrap();
+
+        let info = TorrentOptions::new()
+            .piece_length(16 * 1024)
+            .version(TorrentVersion::V2)
+            .hash_file(&path)
+            .unwrap();
+        assert_eq!(info.version(), TorrentVersion::V2);
+        assert!(info.pieces().is_empty());
+        let root = info.files()[0].pieces_root.clone().unwrap();
+        // Computed independently with Python's hashlib and a separate bencoder
+        assert_eq!(
+            root,
+            "ab671631a9fa97a1fdac651fff6c68773b9acf0735b9c7f6ecdd54cbf1bf5dc2"
+        );
+        assert_eq!(
+            info.infohash(),
+            "b52e36398307d8ca203a5656310869ed6aeabdd609d25f4963e9dce403399671"
+        );
+        let mut last = Hasher::new(HashAlgorithm::Sha256);
+        last.update(&data[32 * 1024..]);
+        assert_eq!(info.piece_layer(&root).unwrap()[2], last.finalize());
+
+        let read = TorrentInfo::from_torrent(&info.to_torrent(None)).unwrap();
+        assert_eq!(read.infohash(), info.infohash());
+        assert_eq!(read.files(), info.files());
+        assert!(read.verify(&path).unwrap().is_empty());
+        let mut corrupt = data.clone();
+        corrupt[39_999] ^= 1;
+        fs::write(&path, &corrupt).unwrap();
+        assert_eq!(read.verify(&path).unwrap(), [2]);
+    }
+
+    #[test]
+    fn test_v2_multi_file_torrent() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("pack");
+        fs::create_dir_all(root.join("docs")).unwrap();
+        let big: Vec<u8> = (0..70_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(root.join("big.bin"), &big).unwrap();
+        fs::write(root.join("docs/a.txt"), vec![3u8; 10_000]).unwrap();
+        fs::write(root.join("empty"), "").unwrap();
+
+        let info = TorrentOptions::new()
+            .piece_length(32 * 1024)
+            .private(true)
+            .version(TorrentVersion::V2)
+            .hash_dir(&root)
+            .unwrap();
+        let paths: Vec<_> = info
+            .files()
+            .iter()
+            .map(|f| portable_path(&f.path))
+            .collect();
+        assert_eq!(paths, ["big.bin", "docs/a.txt", "empty"]);
+        assert_eq!(info.files()[2].pieces_root, None);
+        let big_root = info.files()[0].pieces_root.clone().unwrap();
+        assert_eq!(
+            big_root,
+            "83deabd1fe1301daff7f0f151ac57676bb0de039ffc91faa63b02a3e0105bcd8"
+        );
+        assert_eq!(info.piece_layer(&big_root).unwrap().len(), 3);
+        let small_root = info.files()[1].pieces_root.clone().unwrap();
+        assert_eq!(info.piece_layer(&small_root), None);
+        assert_eq!(
+            info.infohash(),
+            "9d49bc94cb506922c516d707958014d44b1ff802ef88e0179ae7d0866dc841da"
+        );
+
+        let torrent = info.to_torrent(Some("udp://t.example:1"));
+        let read = TorrentInfo::from_torrent(&torrent).unwrap();
+        assert_eq!(
+            read,
+            TorrentInfo {
+                raw: read.raw.clone(),
+                ..info
+            }
+        );
+        assert!(read.verify(&root).unwrap().is_empty());
+        fs::write(root.join("docs/a.txt"), vec![4u8; 10_000]).unwrap();
+        let mut corrupt = big.clone();
+        corrupt[40_000] ^= 1;
+        fs::write(root.join("big.bin"), &corrupt).unwrap();
+        assert_eq!(read.verify(&root).unwrap(), [1, 3]);
+
+        // A piece layer that does not add up to its root
+        let layer_start = torrent.len() - 2 - 3 * 32;
+        let mut tampered = torrent.clone();
+        tampered[layer_start] ^= 1;
+        assert!(matches!(
+            TorrentInfo::from_torrent(&tampered),
+            Err(SHAError::InvalidTorrent(_))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_torrents() {
+        let unsafe_path = b"d4:infod5:filesld6:lengthi1e4:pathl2:..1:xeee4:name1:x12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        for data in [
+            &b"d4:infoi1ee"[..],
+            b"d3:fooi1ee",
+            b"d4:infod4:name1:x12:piece lengthi16384e6:pieces3:abcee",
+            b"d4:infod6:lengthi1e4:name1:x12:piece lengthi16384e6:pieces0:ee",
+            unsafe_path,
+        ] {
+            assert!(
+                matches!(
+                    TorrentInfo::from_torrent(data),
+                    Err(SHAError::InvalidTorrent(_))
+                ),
+                "{}",
+                String::from_utf8_lossy(data)
+            );
+        }
+    }
+}
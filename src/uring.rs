@@ -0,0 +1,282 @@
+//! io_uring file reading for Linux.
+//!
+//! Available with the `uring` feature on Linux. [`UringHasher`] keeps several reads of the
+//! same file in flight at once, so fast NVMe devices see a deep queue instead of one
+//! `read(2)` at a time. Reads may complete in any order, but data is always fed to the hasher
+//! in file order, so digests are identical to the rest of the crate.
+//!
+//! ```no_run
+//! use sha_file_hashing::HasherOptions;
+//! use sha_file_hashing::uring::UringHasher;
+//!
+//! let mut hasher = UringHasher::new(HasherOptions::new(), 16).unwrap();
+//! for (path, result) in hasher.hash_paths(["a.iso", "b.iso"]) {
+//!     println!("{:?}: {:?}", path, result);
+//! }
+//! ```
+
+use crate::{Digest, HasherOptions, SHAError};
+use io_uring::{IoUring, opcode, types};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// A reusable io_uring instance that hashes files with several reads in flight.
+///
+/// The ring and its read buffers are allocated once and reused for every file, which also
+/// makes this a good fit for large batches.
+pub struct UringHasher {
+    ring: IoUring,
+    options: HasherOptions,
+    slots: Vec<Slot>,
+}
+
+/// One read buffer and the state of the read currently using it.
+struct Slot {
+    buffer: Vec<u8>,
+    offset: u64,
+    filled: usize,
+    done: bool,
+}
+
+impl UringHasher {
+    /// Creates a hasher keeping up to `queue_depth` reads of
+    /// [`buffer_size`](HasherOptions::buffer_size) bytes in flight.
+    ///
+    /// # Errors
+    /// Fails if the kernel does not support io_uring or refuses to create a ring, for example
+    /// inside a restrictive container.
+    pub fn new(options: HasherOptions, queue_depth: u32) -> io::Result<Self> {
+        let queue_depth = queue_depth.max(1);
+        let slots = (0..queue_depth)
+            .map(|_| Slot {
+                buffer: vec![0u8; options.selected_buffer_size()],
+                offset: 0,
+                filled: 0,
+                done: false,
+            })
+            .collect();
+        Ok(Self {
+            ring: IoUring::new(queue_depth)?,
+            options,
+            slots,
+        })
+    }
+
+    /// Hashes the file at `path`.
+    pub fn hash_file_from_path(&mut self, path: impl AsRef<Path>) -> Result<Digest, SHAError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| SHAError::from(e).at_path(path))?;
+        self.hash_file(&file).map_err(|e| e.at_path(path))
+    }
+
+    /// Hashes every file in `paths`, collecting a result per path.
+    ///
+    /// See [`HasherOptions::hash_paths`] for the error and duplicate handling.
+    pub fn hash_paths<P: AsRef<Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> HashMap<PathBuf, Result<Digest, SHAError>> {
+        let mut results = HashMap::new();
+        for path in paths {
+            let path = path.as_ref();
+            if !results.contains_key(path) {
+                results.insert(path.to_path_buf(), self.hash_file_from_path(path));
+            }
+        }
+        results
+    }
+
+    /// Hashes an open file from offset zero.
+    pub fn hash_file(&mut self, file: &File) -> Result<Digest, SHAError> {
+        let mut hasher = self.options.hasher();
+        let result = self.read_in_order(file, |chunk| hasher.update(chunk));
+        // Every submitted read must have completed before the buffers can be touched again or
+        // the ring reused, so drain even after an error.
+        self.drain();
+        result?;
+        Ok(hasher.finalize())
+    }
+
+    fn read_in_order(
+        &mut self,
+        file: &File,
+        mut update: impl FnMut(&[u8]),
+    ) -> Result<(), SHAError> {
+        let fd = types::Fd(file.as_raw_fd());
+        let chunk = self.options.selected_buffer_size() as u64;
+        let mut order = VecDeque::with_capacity(self.slots.len());
+        let mut next_offset = 0;
+
+        for index in 0..self.slots.len() {
+            self.start(index, fd, next_offset)?;
+            order.push_back(index);
+            next_offset += chunk;
+        }
+
+        loop {
+            self.submit_and_wait()?;
+            let completions: Vec<_> = self
+                .ring
+                .completion()
+                .map(|cqe| (cqe.user_data() as usize, cqe.result()))
+                .collect();
+
+            // Record every completion before acting on errors, so `drain` knows exactly which
+            // reads are still outstanding
+            let mut error = None;
+            for (index, result) in completions {
+                let slot = &mut self.slots[index];
+                if result < 0 {
+                    slot.done = true;
+                    error.get_or_insert(io::Error::from_raw_os_error(-result));
+                    continue;
+                }
+                slot.filled += result as usize;
+                if result == 0 || slot.filled == slot.buffer.len() {
+                    slot.done = true;
+                } else if let Err(e) = self.resume(index, fd) {
+                    // Short read: ask for the rest of this chunk before it can be hashed
+                    error.get_or_insert(e);
+                }
+            }
+            if let Some(error) = error {
+                return Err(error.into());
+            }
+
+            while let Some(&index) = order.front() {
+                let slot = &self.slots[index];
+                if !slot.done {
+                    break;
+                }
+                if self.options.is_cancelled() {
+                    return Err(SHAError::Cancelled);
+                }
+                update(&slot.buffer[..slot.filled]);
+                if slot.filled < slot.buffer.len() {
+                    return Ok(());
+                }
+                order.pop_front();
+                self.start(index, fd, next_offset)?;
+                order.push_back(index);
+                next_offset += chunk;
+            }
+        }
+    }
+
+    /// Submits a fresh read filling slot `index` from `offset`.
+    fn start(&mut self, index: usize, fd: types::Fd, offset: u64) -> io::Result<()> {
+        let slot = &mut self.slots[index];
+        slot.offset = offset;
+        slot.filled = 0;
+        slot.done = false;
+        self.resume(index, fd)
+    }
+
+    /// Submits a read for the unfilled remainder of slot `index`.
+    fn resume(&mut self, index: usize, fd: types::Fd) -> io::Result<()> {
+        let slot = &mut self.slots[index];
+        let remaining = &mut slot.buffer[slot.filled..];
+        let entry = opcode::Read::new(fd, remaining.as_mut_ptr(), remaining.len() as u32)
+            .offset(slot.offset + slot.filled as u64)
+            .build()
+            .user_data(index as u64);
+        // SAFETY: the buffer belongs to `self.slots`, which is neither resized nor dropped
+        // while reads are in flight; `hash_file` drains every outstanding read before
+        // returning, whatever error stopped it.
+        let pushed = unsafe { self.ring.submission().push(&entry) };
+        pushed.map_err(|_| {
+            slot.done = true;
+            io::Error::other("io_uring submission queue is full")
+        })
+    }
+
+    /// Submits queued reads and waits for at least one to complete, retrying when a signal
+    /// interrupts the wait.
+    fn submit_and_wait(&mut self) -> io::Result<()> {
+        loop {
+            match self.ring.submit_and_wait(1) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                result => return result.map(drop),
+            }
+        }
+    }
+
+    /// Waits for all outstanding reads to complete and discards their results.
+    ///
+    /// This cannot fail: returning while a read is in flight would let the kernel write into
+    /// a buffer after it is reused or freed. Completions are posted whether or not waiting
+    /// succeeds, so an error only means polling the completion queue again.
+    fn drain(&mut self) {
+        let mut in_flight = self.slots.iter().filter(|slot| !slot.done).count();
+        while in_flight > 0 {
+            if self.submit_and_wait().is_err() {
+                std::thread::yield_now();
+            }
+            for cqe in self.ring.completion() {
+                let slot = &mut self.slots[cqe.user_data() as usize];
+                if !slot.done {
+                    slot.done = true;
+                    in_flight -= 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HashAlgorithm;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_temp(data: &[u8]) -> NamedTempFile {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(data).unwrap();
+        temp_file.flush().unwrap();
+        temp_file
+    }
+
+    #[test]
+    fn test_uring_matches_buffered_reader() {
+        let options = HasherOptions::new()
+            .algorithm(HashAlgorithm::Sha256)
+            .buffer_size(4096);
+        let mut hasher = UringHasher::new(options.clone(), 4).unwrap();
+
+        // Sizes around chunk and queue boundaries, including empty and exact multiples
+        for len in [0, 1, 4095, 4096, 4097, 4096 * 4, 4096 * 9 + 17] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let temp_file = write_temp(&data);
+            assert_eq!(
+                hasher.hash_file_from_path(temp_file.path()).unwrap(),
+                options.hash_file_from_path(temp_file.path()).unwrap(),
+                "length {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn test_uring_hash_paths() {
+        let temp_file = write_temp(b"test data");
+        let missing = Path::new("nonexistent_file_12345.txt");
+
+        let mut hasher = UringHasher::new(HasherOptions::new(), 2).unwrap();
+        let results = hasher.hash_paths([temp_file.path(), missing]);
+        assert_eq!(
+            *results[temp_file.path()].as_ref().unwrap(),
+            "f48dd853820860816c75d54d0f584dc863327a7c"
+        );
+        match &results[missing] {
+            Err(SHAError::File { path, source }) => {
+                assert_eq!(path, missing);
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("Expected File error, got {:?}", other),
+        }
+    }
+}
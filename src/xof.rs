@@ -0,0 +1,75 @@
+use crate::HashAlgorithm;
+use digest::{
+    DynDigest, ExtendableOutput, ExtendableOutputReset, InvalidBufferSize, Reset, Update,
+};
+use shake::{Shake128, Shake256};
+
+/// Returns `true` if `algorithm` is an extendable-output function, whose digest length is
+/// chosen by the caller.
+pub(crate) fn is_xof(algorithm: HashAlgorithm) -> bool {
+    matches!(algorithm, HashAlgorithm::Shake128 | HashAlgorithm::Shake256)
+}
+
+/// Creates a hasher for `algorithm` producing `len` bytes, or `None` if it has a fixed output
+/// length.
+pub(crate) fn hasher(algorithm: HashAlgorithm, len: usize) -> Option<Box<dyn DynDigest>> {
+    match algorithm {
+        HashAlgorithm::Shake128 => Some(Box::new(Xof::<Shake128>::new(len))),
+        HashAlgorithm::Shake256 => Some(Box::new(Xof::<Shake256>::new(len))),
+        _ => None,
+    }
+}
+
+/// Adapts an extendable-output function to [`DynDigest`] with an output length fixed at
+/// runtime, where `digest`'s own wrapper fixes it at compile time.
+#[derive(Clone)]
+struct Xof<X> {
+    state: X,
+    len: usize,
+}
+
+impl<X: Default> Xof<X> {
+    fn new(len: usize) -> Self {
+        Self {
+            state: X::default(),
+            len,
+        }
+    }
+}
+
+impl<X> DynDigest for Xof<X>
+where
+    X: Clone + Update + ExtendableOutput + ExtendableOutputReset + Reset + 'static,
+{
+    fn update(&mut self, data: &[u8]) {
+        Update::update(&mut self.state, data);
+    }
+
+    fn finalize_into(self, buf: &mut [u8]) -> Result<(), InvalidBufferSize> {
+        if buf.len() != self.len {
+            return Err(InvalidBufferSize);
+        }
+        self.state.finalize_xof_into(buf);
+        Ok(())
+    }
+
+    fn finalize_into_reset(&mut self, out: &mut [u8]) -> Result<(), InvalidBufferSize> {
+        if out.len() != self.len {
+            return Err(InvalidBufferSize);
+        }
+        self.state.finalize_xof_reset_into(out);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        Reset::reset(&mut self.state);
+    }
+
+    fn output_size(&self) -> usize {
+        self.len
+    }
+
+    fn box_clone(&self) -> Box<dyn DynDigest> {
+        Box::new(self.clone())
+    }
+}
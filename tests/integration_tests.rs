@@ -1,4 +1,7 @@
-use sha_file_hashing::{hash_file_from_path, validate_file_from_path, Hashable, SHAError};
+use sha_file_hashing::{
+    hash_file_from_path, hash_file_from_path_with_algorithm, validate_file_from_path, HashAlgorithm,
+    Hashable, SHAError,
+};
 use std::fs::File;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -35,13 +38,13 @@ fn test_hash_empty_file() {
 #[test]
 fn test_hash_large_file() {
     let temp_dir = TempDir::new().unwrap();
-    // Create a file larger than the buffer size (8192 bytes)
-    let content = vec![b'A'; 20000];
+    // Create a file larger than the default buffer size (128KiB)
+    let content = vec![b'A'; 300_000];
     let file_path = create_temp_file(&temp_dir, "large.txt", &content);
 
     let hash = hash_file_from_path(&file_path).unwrap();
     assert!(!hash.is_empty());
-    assert_eq!(hash.len(), 40); // SHA-1 produces 40 hex characters
+    assert_eq!(hash.to_hex().len(), 40); // SHA-1 produces 40 hex characters
 }
 
 #[test]
@@ -106,9 +109,9 @@ fn test_path_hashable_trait() {
 
     let hash = path.hash().unwrap();
     assert!(!hash.is_empty());
-    assert_eq!(hash.len(), 40);
+    assert_eq!(hash.to_hex().len(), 40);
 
-    let is_valid = path.validate(&hash).unwrap();
+    let is_valid = path.validate(hash.to_hex()).unwrap();
     assert!(is_valid);
 }
 
@@ -119,9 +122,9 @@ fn test_pathbuf_hashable_trait() {
 
     let hash = file_path.hash().unwrap();
     assert!(!hash.is_empty());
-    assert_eq!(hash.len(), 40);
+    assert_eq!(hash.to_hex().len(), 40);
 
-    let is_valid = file_path.validate(&hash).unwrap();
+    let is_valid = file_path.validate(hash.to_hex()).unwrap();
     assert!(is_valid);
 }
 
@@ -133,10 +136,10 @@ fn test_file_hashable_trait() {
     let file = File::open(&file_path).unwrap();
     let hash = file.hash().unwrap();
     assert!(!hash.is_empty());
-    assert_eq!(hash.len(), 40);
+    assert_eq!(hash.to_hex().len(), 40);
 
     let file = File::open(&file_path).unwrap();
-    let is_valid = file.validate(&hash).unwrap();
+    let is_valid = file.validate(hash.to_hex()).unwrap();
     assert!(is_valid);
 }
 
@@ -149,7 +152,7 @@ fn test_binary_file() {
     let hash = hash_file_from_path(&file_path).unwrap();
     assert!(!hash.is_empty());
 
-    let is_valid = validate_file_from_path(&file_path, &hash).unwrap();
+    let is_valid = validate_file_from_path(&file_path, hash.to_hex()).unwrap();
     assert!(is_valid);
 }
 
@@ -178,3 +181,25 @@ fn test_different_files_different_hashes() {
 
     assert_ne!(hash1, hash2);
 }
+
+#[test]
+fn test_hash_with_runtime_algorithm() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = create_temp_file(&temp_dir, "test.txt", b"Hello, World!");
+
+    let algorithm: HashAlgorithm = "sha256".parse().unwrap();
+    let hash = hash_file_from_path_with_algorithm(&file_path, algorithm).unwrap();
+    assert_eq!(
+        hash,
+        "dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f"
+    );
+
+    // The trait defaults to SHA-1 but accepts any algorithm
+    assert!(file_path.validate_with(hash.to_hex(), algorithm).unwrap());
+    // A SHA-256 hash is rejected by SHA-1 validation instead of silently failing it
+    assert!(matches!(
+        file_path.validate(hash.to_hex()),
+        Err(SHAError::InvalidDigest(_))
+    ));
+    assert_eq!(file_path.hash_with(HashAlgorithm::Sha1).unwrap(), file_path.hash().unwrap());
+}